@@ -0,0 +1,235 @@
+//! Configuration and API clients shared by command handlers
+
+use anyhow::Result;
+use r2pilot_core::{
+    is_valid_bucket_name, ClientOptions, CloudflareClient, ConfigFile, Error, R2Client, Timings,
+    TlsOptions,
+};
+use std::sync::{Arc, OnceLock};
+
+/// Settings overridden from global command-line flags
+#[derive(Debug, Default)]
+pub struct ConfigOverrides {
+    /// S3 endpoint used instead of `cloudflare.endpoint`
+    pub endpoint: Option<String>,
+    /// Upload bandwidth cap used instead of `advanced.max_upload_rate`
+    pub max_rate: Option<String>,
+}
+
+/// Load the configuration file and apply command-line overrides
+pub fn load_config(overrides: &ConfigOverrides) -> r2pilot_core::Result<ConfigFile> {
+    let mut config = r2pilot_core::load_config()?;
+
+    if let Some(endpoint) = &overrides.endpoint {
+        config.cloudflare.endpoint = endpoint.clone();
+    }
+    if let Some(max_rate) = &overrides.max_rate {
+        config
+            .advanced
+            .get_or_insert_with(Default::default)
+            .max_upload_rate = Some(max_rate.clone());
+    }
+
+    Ok(config)
+}
+
+/// Everything a command needs to reach R2, built on first use
+///
+/// The configuration is only read when a handler asks for it, so commands
+/// such as `cors schema` or `urls inspect` work without one.
+#[derive(Default)]
+pub struct Context {
+    overrides: ConfigOverrides,
+    config: OnceLock<ConfigFile>,
+    cloudflare: OnceLock<CloudflareClient>,
+    /// Where R2 clients record request durations (`--timings`)
+    timings: Option<Arc<Timings>>,
+}
+
+impl Context {
+    pub fn new(overrides: ConfigOverrides) -> Self {
+        Self {
+            overrides,
+            ..Default::default()
+        }
+    }
+
+    /// Record the duration of every S3 request made by the R2 clients
+    pub fn with_timings(mut self, timings: Arc<Timings>) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// The loaded configuration, with command-line overrides applied
+    pub fn config(&self) -> Result<&ConfigFile> {
+        if let Some(config) = self.config.get() {
+            return Ok(config);
+        }
+        let config = load_config(&self.overrides)?;
+        Ok(self.config.get_or_init(|| config))
+    }
+
+    /// Reload the configuration from disk, e.g. after it was edited
+    pub fn reload_config(&self) -> Result<ConfigFile> {
+        Ok(load_config(&self.overrides)?)
+    }
+
+    /// Cloudflare API client in the configured jurisdiction; fails if no API
+    /// token is configured
+    pub fn cloudflare(&self) -> Result<&CloudflareClient> {
+        if let Some(client) = self.cloudflare.get() {
+            return Ok(client);
+        }
+
+        let config = self.config()?;
+        let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
+            Error::Config(
+                "API Token required for this command.\n\
+                 Add 'api_token' to your configuration.\n\
+                 Get an API Token from: https://dash.cloudflare.com/profile/api-tokens"
+                    .to_string(),
+            )
+        })?;
+        let danger_accept_invalid_certs =
+            config.cloudflare.danger_accept_invalid_certs == Some(true);
+        if danger_accept_invalid_certs {
+            eprintln!(
+                "  ⚠️  TLS certificate verification is disabled for the Cloudflare API \
+                 (danger_accept_invalid_certs)"
+            );
+        }
+        let client = CloudflareClient::new_with_tls(
+            api_token,
+            config.cloudflare.account_id.clone(),
+            config.cloudflare.user_agent.as_deref(),
+            &TlsOptions {
+                ca_bundle: ca_bundle(config)?,
+                danger_accept_invalid_certs,
+            },
+        )?
+        .with_jurisdiction(config.cloudflare.jurisdiction.as_deref())?;
+
+        Ok(self.cloudflare.get_or_init(|| client))
+    }
+
+    /// Whether R2 access keys are configured (needed for S3 operations)
+    pub fn has_access_keys(&self) -> Result<bool> {
+        let cloudflare = &self.config()?.cloudflare;
+        Ok(cloudflare.access_key_id.is_some() && cloudflare.secret_access_key.is_some())
+    }
+
+    /// S3 client for `bucket`; fails if no access keys are configured
+    pub async fn r2_client(&self, bucket: &str) -> Result<R2Client> {
+        let config = self.config()?;
+        let (Some(access_key_id), Some(secret_access_key)) = (
+            config.cloudflare.access_key_id.clone(),
+            config.cloudflare.secret_access_key.clone(),
+        ) else {
+            return Err(missing_access_keys(&config.cloudflare.account_id).into());
+        };
+
+        Ok(R2Client::new_with_options(
+            config.cloudflare.endpoint.clone(),
+            access_key_id,
+            secret_access_key,
+            bucket.to_string(),
+            ClientOptions {
+                timings: self.timings.clone(),
+                ..client_options(config)?
+            },
+        )
+        .await?)
+    }
+
+    /// Resolve the bucket a command should target
+    ///
+    /// Prefers the explicit `--bucket` value, then the configured default bucket.
+    /// If neither is usable and a terminal is attached, offers a picker listing the
+    /// account's buckets (requires an API token).
+    pub async fn resolve_bucket(&self, bucket: Option<&str>) -> Result<String> {
+        use dialoguer::{theme::ColorfulTheme, Select};
+        use std::io::IsTerminal;
+
+        if let Some(bucket) = bucket {
+            return Ok(bucket.to_string());
+        }
+
+        let config = self.config()?;
+        if is_valid_bucket_name(&config.r2.default_bucket) {
+            return Ok(config.r2.default_bucket.clone());
+        }
+
+        let no_bucket = || {
+            Error::Config(
+                "No bucket specified and no usable default bucket configured.\n\
+                 Use --bucket <name> or set 'default_bucket' in your configuration."
+                    .to_string(),
+            )
+        };
+
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return Err(no_bucket().into());
+        }
+        if config.cloudflare.api_token.is_none() {
+            return Err(no_bucket().into());
+        }
+
+        let buckets = self.cloudflare()?.list_buckets().await?;
+
+        if buckets.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No buckets found (create one with 'r2pilot buckets create <name>')"
+            ));
+        }
+
+        let names: Vec<&str> = buckets.iter().map(|b| b.name.as_str()).collect();
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select a bucket")
+            .items(&names)
+            .default(0)
+            .interact()?;
+
+        Ok(names[selection].to_string())
+    }
+}
+
+/// Error for a command that needs S3 access keys when none are configured
+pub fn missing_access_keys(account_id: &str) -> Error {
+    Error::Config(format!(
+        "R2 Access Keys required for this command.\n\
+         Configure access_key_id and secret_access_key (run 'r2pilot init').\n\
+         Get your Access Keys from: https://dash.cloudflare.com/{}/r2/api-tokens",
+        account_id
+    ))
+}
+
+/// R2 client settings from the configuration
+fn client_options(config: &ConfigFile) -> Result<ClientOptions> {
+    let max_rate = config
+        .advanced
+        .as_ref()
+        .and_then(|a| a.max_upload_rate.as_deref())
+        .map(r2pilot_core::parse_rate)
+        .transpose()?;
+    if let Some(rate) = max_rate {
+        tracing::debug!(bytes_per_sec = rate, "upload bandwidth capped");
+    }
+
+    Ok(ClientOptions {
+        force_path_style: config.cloudflare.force_path_style.unwrap_or(false),
+        user_agent: config.cloudflare.user_agent.clone(),
+        max_rate,
+        ca_bundle: ca_bundle(config)?,
+        timings: None,
+    })
+}
+
+/// Contents of the configured `ca_bundle`, if any
+fn ca_bundle(config: &ConfigFile) -> Result<Option<Vec<u8>>> {
+    Ok(config
+        .cloudflare
+        .ca_bundle
+        .as_deref()
+        .map(r2pilot_core::load_ca_bundle)
+        .transpose()?)
+}
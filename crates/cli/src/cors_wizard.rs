@@ -2,7 +2,7 @@
 
 use anyhow::Result;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect};
-use r2pilot_core::{BucketCorsConfig, CorsRule};
+use r2pilot_core::{split_comma_list, BucketCorsConfig, CorsRule};
 
 /// Run the interactive CORS configuration wizard
 pub async fn run_cors_wizard() -> Result<BucketCorsConfig> {
@@ -24,7 +24,7 @@ pub async fn run_cors_wizard() -> Result<BucketCorsConfig> {
             .with_prompt("Enter allowed origins (comma-separated, e.g., https://example.com,https://app.example.com)")
             .allow_empty(false)
             .interact()?;
-        input.split(',').map(|s| s.trim().to_string()).collect()
+        split_comma_list(&input)
     };
 
     // Select allowed methods
@@ -51,15 +51,11 @@ pub async fn run_cors_wizard() -> Result<BucketCorsConfig> {
             )
             .allow_empty(true)
             .interact()?;
-        if input.is_empty() {
+        let headers = split_comma_list(&input);
+        if headers.is_empty() {
             None
         } else {
-            Some(
-                input
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect::<Vec<_>>(),
-            )
+            Some(headers)
         }
     };
 
@@ -73,6 +69,7 @@ pub async fn run_cors_wizard() -> Result<BucketCorsConfig> {
 
     // Build rule
     let rule = CorsRule {
+        id: None,
         allowed_origins,
         allowed_methods,
         allowed_headers,
@@ -104,6 +101,18 @@ pub async fn load_cors_from_file(file_path: &str) -> Result<BucketCorsConfig> {
     Ok(config)
 }
 
+/// Load a single CORS rule from JSON file
+pub async fn load_cors_rule_from_file(file_path: &str) -> Result<CorsRule> {
+    let content = tokio::fs::read_to_string(file_path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file_path, e))?;
+
+    let rule: CorsRule = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse CORS rule: {}", e))?;
+
+    Ok(rule)
+}
+
 /// Save CORS config to JSON file
 #[allow(dead_code)]
 pub async fn save_cors_to_file(file_path: &str, config: &BucketCorsConfig) -> Result<()> {
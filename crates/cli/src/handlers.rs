@@ -1,33 +1,160 @@
 //! Command handlers for r2pilot CLI
 
-use crate::wizard::run_init_wizard;
+use crate::context::Context;
+use crate::wizard::{run_init, InitOptions};
 use anyhow::Result;
 use clap::Command;
 use clap_complete::{generate, Shell as ClapShell};
 use r2pilot_core::{
-    generate_presigned_url, get_config_path, load_config, validate_config, MultipartUploadConfig,
-    PresignedMethod, PresignedUrlConfig, R2Client,
+    generate_presigned_url_signed, get_config_path, is_compressed_format, parse_presigned_url,
+    rewrite_presigned_host, validate_config, Compression, ConfigFile, MultipartUploadConfig,
+    PresignedMethod, PresignedUrlConfig, R2Operations, ResponseHeaders, StorageClass,
+    UploadOptions,
 };
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::OnceLock;
 use tabled::{Table, Tabled};
 
+static DISPLAY_UTC: OnceLock<bool> = OnceLock::new();
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+
+/// Display timestamps in UTC instead of the local timezone
+pub fn set_display_utc(utc: bool) {
+    let _ = DISPLAY_UTC.set(utc);
+}
+
+/// How much commands print besides their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only results (URLs, listings, JSON) and errors, for scripts (`--quiet`)
+    Quiet,
+    #[default]
+    Normal,
+}
+
+/// Set how much commands print
+pub fn set_verbosity(verbosity: Verbosity) {
+    let _ = VERBOSITY.set(verbosity);
+}
+
+fn is_quiet() -> bool {
+    VERBOSITY.get().copied().unwrap_or_default() == Verbosity::Quiet
+}
+
+/// `println!` for progress lines, statuses and summaries, dropped by `--quiet`
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// `println!` for failures and warnings, moved to stderr by `--quiet`
+macro_rules! problem {
+    ($($arg:tt)*) => {
+        if is_quiet() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
 /// Handle init command
-pub async fn handle_init() -> Result<()> {
-    run_init_wizard().await
+pub async fn handle_init(options: InitOptions) -> Result<()> {
+    run_init(options).await
+}
+
+/// Flags for `config` commands
+#[derive(Debug, Default)]
+pub struct ConfigOptions {
+    /// Print the full account ID
+    pub reveal: bool,
+    /// Print secrets in plain text
+    pub reveal_secrets: bool,
+    /// Export format (toml, json)
+    pub format: Option<String>,
+    /// Include secrets in the export
+    pub with_secrets: bool,
+    /// File to import
+    pub file: Option<String>,
+    /// Skip the confirmation before replacing the configuration
+    pub yes: bool,
 }
 
 /// Handle config commands
-pub async fn handle_config(action: &str) -> Result<()> {
+pub async fn handle_config(ctx: &Context, action: &str, options: ConfigOptions) -> Result<()> {
     match action {
+        "export" => {
+            let format = match &options.format {
+                Some(format) => format.parse()?,
+                None => r2pilot_core::ConfigFormat::Toml,
+            };
+            let config = ctx.config()?;
+
+            print!(
+                "{}",
+                r2pilot_core::export_config(config, format, options.with_secrets)?
+            );
+            if !options.with_secrets {
+                eprintln!("Secrets left out; use --with-secrets to include them");
+            }
+
+            Ok(())
+        }
+        "import" => {
+            let file = options
+                .file
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("File required"))?;
+            let format = match &options.format {
+                Some(format) => format.parse()?,
+                None => r2pilot_core::ConfigFormat::from_path(Path::new(file)),
+            };
+            let content = std::fs::read_to_string(file)
+                .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", file, e))?;
+
+            if r2pilot_core::config_exists()
+                && !options.yes
+                && !confirm("Replace the current configuration?")?
+            {
+                status!("❌ Import cancelled");
+                return Ok(());
+            }
+
+            status!("Importing configuration from {}...", file);
+            let config = r2pilot_core::import_config(&content, format)?;
+            status!("  ✅ Configuration valid");
+            status!("  ✅ Saved to {}", get_config_path()?.display());
+            print_config_warnings(&config);
+
+            Ok(())
+        }
         "show" => {
+            let reveal_secrets =
+                options.reveal_secrets && confirm("Print secrets in plain text to the terminal?")?;
+
             println!("Current configuration:");
             println!();
 
-            let config = load_config()?;
+            let config = ctx.config()?;
 
             println!("Cloudflare:");
-            println!("  Account ID: {}", &config.cloudflare.account_id[..8]);
+            if options.reveal || reveal_secrets {
+                println!("  Account ID: {}", config.cloudflare.account_id);
+            } else {
+                let short: String = config.cloudflare.account_id.chars().take(8).collect();
+                println!("  Account ID: {}", short);
+            }
             println!("  Endpoint: {}", config.cloudflare.endpoint);
+            if let Some(jurisdiction) = &config.cloudflare.jurisdiction {
+                println!("  Jurisdiction: {}", jurisdiction);
+            }
+            if let Some(path) = &config.cloudflare.api_token_file {
+                println!("  API Token file: {}", path.display());
+            }
             println!(
                 "  Auth: {}",
                 if config.cloudflare.api_token.is_some() {
@@ -36,6 +163,21 @@ pub async fn handle_config(action: &str) -> Result<()> {
                     "Access Keys"
                 }
             );
+            let secrets = [
+                ("API Token", &config.cloudflare.api_token),
+                ("Access Key ID", &config.cloudflare.access_key_id),
+                ("Secret Access Key", &config.cloudflare.secret_access_key),
+            ];
+            for (label, secret) in secrets {
+                if let Some(secret) = secret {
+                    let shown = if reveal_secrets {
+                        secret.as_str()
+                    } else {
+                        "****"
+                    };
+                    println!("  {}: {}", label, shown);
+                }
+            }
             println!();
             println!("R2:");
             println!("  Default bucket: {}", config.r2.default_bucket);
@@ -45,55 +187,30 @@ pub async fn handle_config(action: &str) -> Result<()> {
             Ok(())
         }
         "validate" => {
-            println!("Validating credentials...");
+            status!("Validating credentials...");
 
-            let config = load_config()?;
+            let config = ctx.config()?;
 
             // Validate config format
-            validate_config(&config)?;
-            println!("  ✅ Valid configuration format");
+            validate_config(config)?;
+            status!("  ✅ Valid configuration format");
+            print_config_warnings(config);
 
-            // Get R2 credentials
-            let (access_key_id, secret_access_key) =
-                if let Some(_token) = &config.cloudflare.api_token {
-                    return Err(anyhow::anyhow!(
-                        "Validation via API Token not yet implemented.\n\
-                     Use Access Keys in your configuration.\n\
-                     Get your Access Keys from: https://dash.cloudflare.com/{}/r2/api-tokens",
-                        config.cloudflare.account_id
-                    ));
-                } else {
-                    (
-                        config.cloudflare.access_key_id.clone().ok_or_else(|| {
-                            anyhow::anyhow!("Access Key ID not configured (run 'r2pilot init')")
-                        })?,
-                        config.cloudflare.secret_access_key.clone().ok_or_else(|| {
-                            anyhow::anyhow!("Secret Access Key not configured (run 'r2pilot init')")
-                        })?,
-                    )
-                };
-
-            println!("  Testing R2 connection...");
-            let r2_client = R2Client::new(
-                config.cloudflare.endpoint.clone(),
-                access_key_id,
-                secret_access_key,
-                config.r2.default_bucket.clone(),
-            )
-            .await?;
+            status!("  Testing R2 connection...");
+            let r2_client = ctx.r2_client(&config.r2.default_bucket).await?;
 
             // Try to list objects as a connection test
             let _objects = r2_client.list_objects(None).await?;
 
-            println!("  ✅ Valid configuration!");
-            println!("  ✅ R2 connection successful!");
+            status!("  ✅ Valid configuration!");
+            status!("  ✅ R2 connection successful!");
 
             Ok(())
         }
         "edit" => {
-            println!("Opening editor...");
-            println!("  File: ~/.config/r2pilot/config.toml");
-            println!();
+            status!("Opening editor...");
+            status!("  File: ~/.config/r2pilot/config.toml");
+            status!();
 
             let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
             let config_path = get_config_path()?;
@@ -103,52 +220,81 @@ pub async fn handle_config(action: &str) -> Result<()> {
                 .status()?;
 
             if status.success() {
-                println!("  ✅ Configuration edited");
+                status!("  ✅ Configuration edited");
 
                 // Validate after edit
-                let config = load_config()?;
+                let config = ctx.reload_config()?;
                 validate_config(&config)?;
-                println!("  ✅ Configuration valid");
+                status!("  ✅ Configuration valid");
+                print_config_warnings(&config);
             } else {
-                println!("  ⚠️  Editor exited with error");
+                status!("  ⚠️  Editor exited with error");
             }
 
             Ok(())
         }
         _ => {
             println!("Unknown action: {}", action);
-            println!("Available actions: show, edit, validate");
+            println!("Available actions: show, edit, validate, export, import");
             Ok(())
         }
     }
 }
 
-/// Handle tokens commands
-pub async fn handle_tokens(action: &str, token_id: Option<&str>) -> Result<()> {
-    use r2pilot_core::{CloudflareClient, R2TokenBuilder};
+/// Flags for `tokens create`
+#[derive(Debug, Default)]
+pub struct TokenOptions {
+    /// Token name
+    pub name: Option<String>,
+    /// Access level
+    pub permission: Option<r2pilot_core::TokenPermission>,
+    /// Allowed IPs/CIDRs
+    pub ip: Option<Vec<String>>,
+    /// Days until the token expires
+    pub expires_days: Option<u32>,
+    /// Print the created token as JSON
+    pub json: bool,
+    /// Listing format (table, json, csv)
+    pub output: String,
+}
 
-    let config = load_config()?;
+impl TokenOptions {
+    /// Whether any creation flag was given (skips the interactive prompts)
+    fn is_scripted(&self) -> bool {
+        self.name.is_some()
+            || self.permission.is_some()
+            || self.ip.is_some()
+            || self.expires_days.is_some()
+            || self.json
+    }
+}
 
-    // Get API token for Cloudflare API access
-    let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-        anyhow::anyhow!(
-            "API Token required to manage tokens.\n\
-         Add 'api_token' to your configuration.\n\
-         Get an API Token from: https://dash.cloudflare.com/profile/api-tokens"
-        )
-    })?;
+/// Handle tokens commands
+pub async fn handle_tokens(
+    ctx: &Context,
+    action: &str,
+    token_id: Option<&str>,
+    options: TokenOptions,
+) -> Result<()> {
+    use r2pilot_core::R2TokenBuilder;
 
-    let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
+    let cf_client = ctx.cloudflare()?;
 
     match action {
         "list" => {
-            println!("Listing Cloudflare API Tokens...");
-            println!();
+            if is_machine_output(&options.output) {
+                let tokens = cf_client.list_tokens().await?;
+                let rows: Vec<TokenView> = tokens.iter().map(TokenView::from).collect();
+                return print_listing(&rows, &options.output);
+            }
+
+            status!("Listing Cloudflare API Tokens...");
+            status!();
 
             let tokens = cf_client.list_tokens().await?;
 
             if tokens.is_empty() {
-                println!("  No tokens found");
+                status!("  No tokens found");
             } else {
                 #[derive(Tabled)]
                 struct TokenRow {
@@ -180,31 +326,70 @@ pub async fn handle_tokens(action: &str, token_id: Option<&str>) -> Result<()> {
             Ok(())
         }
         "create" => {
-            // Interactive prompt for token creation
-            use dialoguer::{theme::ColorfulTheme, Input};
+            let default_name = format!("r2pilot-{}", chrono::Utc::now().format("%Y%m%d"));
 
-            let theme = ColorfulTheme::default();
+            let name = if options.is_scripted() {
+                options.name.clone().unwrap_or(default_name)
+            } else {
+                // Interactive prompt for token creation
+                use dialoguer::{theme::ColorfulTheme, Input};
 
-            let name = Input::with_theme(&theme)
-                .with_prompt("Token name")
-                .default(format!("r2pilot-{}", chrono::Utc::now().format("%Y%m%d")))
-                .interact()?;
+                let theme = ColorfulTheme::default();
 
-            println!();
-            println!("Creating token '{}'...", name);
+                Input::with_theme(&theme)
+                    .with_prompt("Token name")
+                    .default(default_name)
+                    .interact()?
+            };
 
-            let builder = R2TokenBuilder::new(name.clone(), config.cloudflare.account_id.clone());
+            let mut builder =
+                R2TokenBuilder::new(name.clone(), ctx.config()?.cloudflare.account_id.clone())
+                    .permission(options.permission.unwrap_or_default());
+            if let Some(ips) = options.ip.clone() {
+                builder = builder.ip_whitelist(ips);
+            }
+            if let Some(days) = options.expires_days {
+                builder = builder.expires_in_days(days);
+            }
             let params = builder.build();
 
+            if options.json {
+                let token = cf_client.create_token(params).await?;
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "id": token.id,
+                        "name": token.name,
+                        "status": token.status,
+                        "expires_on": token.expires_on,
+                        "value": token.value,
+                    }))?
+                );
+                return Ok(());
+            }
+
+            status!();
+            status!("Creating token '{}'...", name);
+
             let token = cf_client.create_token(params).await?;
 
-            println!("  ✅ Token created: {}", token.name);
-            println!();
-            println!("  IMPORTANT: Copy this token now, it won't be shown again!");
-            println!("  Status: {}", format_status(&token.status));
-            println!();
-            println!("  ⚠️  Save this token in your configuration:");
-            println!("     api_token = \"<your_token>\"");
+            status!("  ✅ Token created: {}", token.name);
+            status!();
+            status!("  IMPORTANT: Copy this token now, it won't be shown again!");
+            if let Some(value) = &token.value {
+                if is_quiet() {
+                    println!("{}", value);
+                } else {
+                    println!("  Token: {}", value);
+                }
+            }
+            status!("  Status: {}", format_status(&token.status));
+            if let Some(expires_on) = &token.expires_on {
+                status!("  Expires: {}", format_date(expires_on));
+            }
+            status!();
+            status!("  ⚠️  Save this token in your configuration:");
+            status!("     api_token = \"<your_token>\"");
 
             Ok(())
         }
@@ -213,18 +398,92 @@ pub async fn handle_tokens(action: &str, token_id: Option<&str>) -> Result<()> {
                 anyhow::anyhow!("Token ID required (use 'tokens list' to see IDs)")
             })?;
 
-            println!("⚠️  Warning: you are about to revoke token '{}'", id);
-            println!("  This action is IRREVERSIBLE!");
+            status!("⚠️  Warning: you are about to revoke token '{}'", id);
+            status!("  This action is IRREVERSIBLE!");
 
             cf_client.revoke_token(id).await?;
 
-            println!("  ✅ Token revoked: {}", id);
+            status!("  ✅ Token revoked: {}", id);
+
+            Ok(())
+        }
+        "verify" => {
+            // The verify endpoint only covers the configured token and doesn't
+            // return permissions, so R2 access needs a token lookup as well
+            let (name, verification, grants_r2) = match token_id {
+                Some(id) => {
+                    let token = cf_client.get_token(id).await?;
+                    (
+                        Some(token.name.clone()),
+                        token.verification(),
+                        Some(token.grants_r2()),
+                    )
+                }
+                None => {
+                    let verification = cf_client.verify_token().await?;
+                    match cf_client.get_token(&verification.id).await {
+                        Ok(token) => (
+                            Some(token.name.clone()),
+                            verification,
+                            Some(token.grants_r2()),
+                        ),
+                        Err(_) => (None, verification, None),
+                    }
+                }
+            };
+            let active = verification.is_active();
+            let valid_for_r2 = grants_r2.map(|grants| active && grants);
+
+            if options.output == "json" {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "id": verification.id,
+                        "name": name,
+                        "status": verification.status,
+                        "not_before": verification.not_before,
+                        "expires_on": verification.expires_on,
+                        "active": active,
+                        "valid_for_r2": valid_for_r2,
+                    }))?
+                );
+                return Ok(());
+            }
+
+            status!("Verifying token {}...", verification.id);
+            status!();
+            if let Some(name) = &name {
+                println!("  Name: {}", name);
+            }
+            println!("  Status: {}", format_status(&verification.status));
+            if let Some(not_before) = &verification.not_before {
+                println!("  Not before: {}", format_date(not_before));
+            }
+            println!(
+                "  Expires: {}",
+                verification
+                    .expires_on
+                    .as_deref()
+                    .map(format_date)
+                    .unwrap_or_else(|| "Never".to_string())
+            );
+            println!();
+            match valid_for_r2 {
+                Some(true) => println!("  ✅ Valid for R2 operations"),
+                Some(false) if !active => println!("  ❌ Token is not active"),
+                Some(false) => println!("  ❌ Token has no R2 permissions"),
+                None if active => {
+                    println!("  ⚠️  Token is active, but its permissions can't be read");
+                    println!("     (it needs the 'API Tokens Read' permission to inspect itself)");
+                }
+                None => println!("  ❌ Token is not active"),
+            }
 
             Ok(())
         }
         _ => {
             println!("Unknown action: {}", action);
-            println!("Available actions: list, create, revoke");
+            println!("Available actions: list, create, revoke, verify");
             Ok(())
         }
     }
@@ -249,38 +508,79 @@ fn format_id(id: &str) -> String {
     }
 }
 
-/// Handle buckets commands
-pub async fn handle_buckets(action: &str, name: Option<&str>) -> Result<()> {
-    use r2pilot_core::CloudflareClient;
+/// Optional flags for buckets commands
+#[derive(Debug, Default)]
+pub struct BucketOptions {
+    /// Apply a public-read CORS configuration after creation
+    pub with_cors: bool,
+    /// Enable static website hosting after creation
+    pub with_website: bool,
+    /// Set the local description
+    pub description: Option<String>,
+    /// Tags to add or overwrite
+    pub set_tags: Vec<(String, String)>,
+    /// Tag keys to remove
+    pub remove_tags: Vec<String>,
+    /// Remove the description and all tags
+    pub clear: bool,
+    /// Listing format (table, json, csv)
+    pub output: String,
+    /// Jurisdiction for a new bucket (overrides the configured one)
+    pub jurisdiction: Option<String>,
+    /// Treat an existing bucket as success
+    pub if_not_exists: bool,
+    /// Name the bucket is renamed to
+    pub new_name: Option<String>,
+    /// Objects copied at once (overrides max_concurrent_uploads)
+    pub concurrency: Option<usize>,
+    /// Skip the confirmation before deleting the old bucket
+    pub yes: bool,
+}
 
-    let config = load_config()?;
+/// Handle buckets commands
+pub async fn handle_buckets(
+    ctx: &Context,
+    action: &str,
+    name: Option<&str>,
+    options: BucketOptions,
+) -> Result<()> {
+    let config = ctx.config()?;
 
     match action {
         "list" => {
-            // List buckets requires Cloudflare API token
-            let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "API Token required to list buckets.\n\
-                 Add 'api_token' to your configuration.\n\
-                 Get an API Token from: https://dash.cloudflare.com/{}/r2/api-tokens",
-                    config.cloudflare.account_id
-                )
-            })?;
+            let cf_client = ctx.cloudflare()?;
 
-            println!("Listing R2 buckets...");
-            println!();
+            if is_machine_output(&options.output) {
+                let buckets = cf_client.list_buckets().await?;
+                let rows: Vec<BucketView> = buckets
+                    .iter()
+                    .map(|b| BucketView {
+                        name: b.name.clone(),
+                        location: b.location.clone(),
+                        creation_date: b.creation_date.clone(),
+                        description: config
+                            .buckets
+                            .get(&b.name)
+                            .and_then(|a| a.description.clone()),
+                    })
+                    .collect();
+                return print_listing(&rows, &options.output);
+            }
+
+            status!("Listing R2 buckets...");
+            status!();
 
-            let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
             let buckets = cf_client.list_buckets().await?;
 
             if buckets.is_empty() {
-                println!("  No buckets found");
+                status!("  No buckets found");
             } else {
                 #[derive(Tabled)]
                 struct BucketRow {
                     name: String,
                     location: String,
                     created: String,
+                    description: String,
                 }
 
                 let rows: Vec<BucketRow> = buckets
@@ -289,35 +589,129 @@ pub async fn handle_buckets(action: &str, name: Option<&str>) -> Result<()> {
                         name: b.name.clone(),
                         location: b.location.clone(),
                         created: format_date(&b.creation_date),
+                        description: config
+                            .buckets
+                            .get(&b.name)
+                            .and_then(|a| a.description.clone())
+                            .unwrap_or_default(),
                     })
                     .collect();
 
                 println!("{}", Table::new(rows));
             }
-            println!();
-            println!("Default bucket: {}", config.r2.default_bucket);
+            status!();
+            status!("Default bucket: {}", config.r2.default_bucket);
 
             Ok(())
         }
         "create" => {
             let bucket_name = name.ok_or_else(|| anyhow::anyhow!("Bucket name required"))?;
 
-            let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "API Token required to create buckets.\n\
-                 Add 'api_token' to your configuration.\n\
-                 Get an API Token from: https://dash.cloudflare.com/{}/r2/api-tokens",
-                    config.cloudflare.account_id
-                )
-            })?;
+            let jurisdiction = options
+                .jurisdiction
+                .as_deref()
+                .or(config.cloudflare.jurisdiction.as_deref());
+            // Setup and rollback must reach the bucket in its jurisdiction too
+            let cf_client = &ctx.cloudflare()?.clone().with_jurisdiction(jurisdiction)?;
+
+            let already_exists = || {
+                status!(
+                    "  ⏭️  Bucket '{}' already exists, nothing to do",
+                    bucket_name
+                );
+                Ok(())
+            };
+
+            if options.if_not_exists {
+                match cf_client.get_bucket(bucket_name).await {
+                    Ok(_) => return already_exists(),
+                    Err(r2pilot_core::Error::NotFound(_)) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            status!("Creating bucket '{}'...", bucket_name);
+
+            let bucket = match cf_client
+                .create_bucket(bucket_name, "eu")
+                .await
+            {
+                Ok(bucket) => bucket,
+                Err(r2pilot_core::Error::AlreadyExists(_)) if options.if_not_exists => {
+                    return already_exists()
+                }
+                Err(e @ r2pilot_core::Error::AlreadyExists(_)) => {
+                    return Err(anyhow::Error::from(e).context(format!(
+                        "Cannot create bucket '{}'; re-run with --if-not-exists to skip existing buckets",
+                        bucket_name
+                    )))
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            status!("  ✅ Bucket created: {}", bucket.name);
+            status!("  Location: {}", bucket.location);
+            if let Some(jurisdiction) = jurisdiction {
+                status!("  Jurisdiction: {}", jurisdiction);
+            }
+
+            if let Err(e) = configure_new_bucket(cf_client, bucket_name, &options).await {
+                // The bucket is brand new: don't leave it half configured
+                status!("  ❌ Setup failed, deleting bucket '{}'...", bucket_name);
+                match cf_client.delete_bucket(bucket_name).await {
+                    Ok(()) => status!("  ✅ Bucket deleted"),
+                    Err(delete_err) => status!(
+                        "  ⚠️  Could not delete bucket '{}': {}",
+                        bucket_name,
+                        delete_err
+                    ),
+                }
+                return Err(e);
+            }
+
+            Ok(())
+        }
+        "tag" => {
+            let bucket_name = name.ok_or_else(|| anyhow::anyhow!("Bucket name required"))?;
+
+            let modifies = options.clear
+                || options.description.is_some()
+                || !options.set_tags.is_empty()
+                || !options.remove_tags.is_empty();
 
-            println!("Creating bucket '{}'...", bucket_name);
+            if modifies {
+                // Edit the file as stored, without command-line overrides
+                let mut stored = r2pilot_core::load_config()?;
+                let mut annotation = stored.buckets.remove(bucket_name).unwrap_or_default();
+
+                if options.clear {
+                    annotation = Default::default();
+                }
+                if let Some(description) = &options.description {
+                    annotation.description = (!description.is_empty()).then(|| description.clone());
+                }
+                for key in &options.remove_tags {
+                    annotation.tags.remove(key);
+                }
+                for (key, value) in &options.set_tags {
+                    annotation.tags.insert(key.clone(), value.clone());
+                }
 
-            let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
-            let bucket = cf_client.create_bucket(bucket_name, "eu").await?;
+                if !annotation.is_empty() {
+                    stored
+                        .buckets
+                        .insert(bucket_name.to_string(), annotation.clone());
+                }
+                r2pilot_core::save_config(&stored)?;
 
-            println!("  ✅ Bucket created: {}", bucket.name);
-            println!("  Location: {}", bucket.location);
+                status!("  ✅ Annotations updated for '{}'", bucket_name);
+                print_bucket_annotation(&annotation);
+            } else {
+                println!("Annotations for '{}':", bucket_name);
+                print_bucket_annotation(
+                    &config.buckets.get(bucket_name).cloned().unwrap_or_default(),
+                );
+            }
 
             Ok(())
         }
@@ -333,186 +727,779 @@ pub async fn handle_buckets(action: &str, name: Option<&str>) -> Result<()> {
                 ));
             }
 
-            let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "API Token required to delete buckets.\n\
-                 Add 'api_token' to your configuration."
-                )
-            })?;
+            let cf_client = ctx.cloudflare()?;
 
-            println!(
+            status!(
                 "⚠️  Warning: you are about to delete bucket '{}'",
                 bucket_name
             );
-            println!("  This action is IRREVERSIBLE!");
+            status!("  This action is IRREVERSIBLE!");
 
-            let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
             cf_client.delete_bucket(bucket_name).await?;
 
-            println!("  ✅ Bucket deleted: {}", bucket_name);
+            status!("  ✅ Bucket deleted: {}", bucket_name);
 
             Ok(())
         }
-        "info" | "ls" => {
-            // Get R2 credentials for S3 API access
-            let (access_key_id, secret_access_key) =
-                if let Some(_token) = &config.cloudflare.api_token {
-                    return Err(anyhow::anyhow!(
-                        "The '{}' command requires R2 Access Keys.\n\
-                     Configure access_key_id and secret_access_key to use S3 operations.",
-                        action
-                    ));
-                } else {
-                    (
-                        config.cloudflare.access_key_id.clone().ok_or_else(|| {
-                            anyhow::anyhow!("Access Key ID not configured (run 'r2pilot init')")
-                        })?,
-                        config.cloudflare.secret_access_key.clone().ok_or_else(|| {
-                            anyhow::anyhow!("Secret Access Key not configured (run 'r2pilot init')")
-                        })?,
-                    )
-                };
+        "rename" => {
+            let old_name = name.ok_or_else(|| anyhow::anyhow!("Bucket name required"))?;
+            let new_name = options
+                .new_name
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("New bucket name required"))?;
+
+            if old_name == new_name {
+                return Err(anyhow::anyhow!("The new name is the current name"));
+            }
+            // The old bucket is deleted at the end, like `buckets delete`
+            if old_name == config.r2.default_bucket {
+                return Err(anyhow::anyhow!(
+                    "Cannot rename default bucket '{}'.\n\
+                     Change the default bucket in your configuration first.",
+                    old_name
+                ));
+            }
+
+            let cf_client = ctx.cloudflare()?;
+            let source = ctx.r2_client(old_name).await?;
+            let concurrency = options.concurrency.unwrap_or_else(|| {
+                config
+                    .advanced
+                    .as_ref()
+                    .map(|a| a.max_concurrent_uploads)
+                    .unwrap_or(5)
+            });
+
+            // R2 can't rename: the new bucket gets a copy of every object
+            let old_bucket = cf_client.get_bucket(old_name).await?;
+            // The copy must stay where the data lives; the S3 endpoint and the
+            // API client only reach the configured jurisdiction
+            let configured = cf_client.jurisdiction().unwrap_or("default");
+            let source_jurisdiction = old_bucket.jurisdiction.as_deref().unwrap_or(configured);
+            if source_jurisdiction != configured {
+                return Err(anyhow::anyhow!(
+                    "'{}' is in the '{}' jurisdiction but the configuration targets '{}'.\n\
+                     Rename it with a configuration for its jurisdiction (r2pilot init --jurisdiction {})",
+                    old_name,
+                    source_jurisdiction,
+                    configured,
+                    source_jurisdiction
+                ));
+            }
+            status!(
+                "Renaming '{}' to '{}' (copies every object, billed as operations)...",
+                old_name,
+                new_name
+            );
 
-            let bucket = name.unwrap_or(&config.r2.default_bucket);
+            let location = old_bucket.location.to_lowercase();
+            let new_bucket = cf_client.create_bucket(new_name, &location).await?;
+            status!(
+                "  ✅ Bucket created: {} ({})",
+                new_bucket.name,
+                new_bucket.location
+            );
 
-            let r2_client = R2Client::new(
-                config.cloudflare.endpoint.clone(),
-                access_key_id,
-                secret_access_key,
-                bucket.to_string(),
-            )
-            .await?;
+            let dest = ctx.r2_client(new_name).await?;
+            let copy = match r2pilot_core::copy_bucket(&source, &dest, concurrency).await {
+                Ok(copy) => copy,
+                Err(e) => {
+                    problem!(
+                        "  ❌ Copy failed, '{}' and '{}' are both kept (delete '{}' to retry)",
+                        old_name,
+                        new_name,
+                        new_name
+                    );
+                    return Err(e.into());
+                }
+            };
+            status!(
+                "  ✅ Copied {} object(s) ({}), counts verified",
+                copy.objects,
+                format_bytes(copy.bytes as i64)
+            );
 
-            if action == "info" {
-                println!("Bucket '{}' information...", bucket);
+            if !options.yes
+                && !confirm(&format!(
+                    "Delete '{}' and its {} object(s)?",
+                    old_name, copy.objects
+                ))?
+            {
+                status!("  ⏭️  Kept '{}', both buckets now exist", old_name);
+                return Ok(());
+            }
 
-                // Try to list objects as a connection test
-                let objects = r2_client.list_objects(None).await?;
+            // Only what was copied: objects written since then stay, and keep
+            // the old bucket from being deleted
+            source.delete_objects(copy.keys).await?;
+            cf_client.delete_bucket(old_name).await.map_err(|e| {
+                anyhow::Error::from(e).context(format!(
+                    "Could not delete '{}'; objects written to it after the copy are kept there",
+                    old_name
+                ))
+            })?;
+            status!("  ✅ Bucket deleted: {}", old_name);
+            status!("  ✅ Renamed '{}' to '{}'", old_name, new_name);
 
-                println!("  Name: {}", bucket);
+            Ok(())
+        }
+        "info" => {
+            let bucket = &ctx.resolve_bucket(name).await?;
+            status!("Bucket '{}' information...", bucket);
+            println!("  Name: {}", bucket);
+
+            // Object count needs S3 credentials, bucket settings the API token
+            if ctx.has_access_keys()? {
+                let r2_client = ctx.r2_client(bucket).await?;
+                let objects = r2_client.list_objects(None).await?;
                 println!("  Objects: {}", objects.len());
+                println!(
+                    "  Incomplete uploads: {}",
+                    incomplete_uploads_summary(&r2_client, config).await
+                );
             } else {
-                println!("Bucket '{}' contents...", bucket);
+                println!("  Objects: - (requires R2 Access Keys)");
+                println!("  Incomplete uploads: - (requires R2 Access Keys)");
+            }
 
-                let objects = r2_client.list_objects(None).await?;
+            if config.cloudflare.api_token.is_none() {
+                println!("  CORS, lifecycle, website: - (requires an API Token)");
+                return Ok(());
+            }
 
-                if objects.is_empty() {
-                    println!("  Empty bucket");
-                } else {
-                    #[derive(Tabled)]
-                    struct ObjectRow {
-                        key: String,
-                        size: String,
-                    }
+            let cf_client = ctx.cloudflare()?;
+            let (cors, lifecycle, website) = tokio::join!(
+                cf_client.get_bucket_cors(bucket),
+                cf_client.get_bucket_lifecycle(bucket),
+                cf_client.get_bucket_website(bucket),
+            );
 
-                    let rows: Vec<ObjectRow> = objects
+            println!(
+                "  CORS: {}",
+                setting_summary(cors, |cors| {
+                    (!cors.rules.is_empty()).then(|| format!("{} rule(s)", cors.rules.len()))
+                })
+            );
+            println!(
+                "  Lifecycle: {}",
+                setting_summary(lifecycle, |lifecycle| {
+                    let enabled = lifecycle
+                        .rules
                         .iter()
-                        .map(|o| ObjectRow {
-                            key: o.key.clone(),
-                            size: format_bytes(o.size),
-                        })
-                        .collect();
+                        .filter(|r| r.status == "Enabled")
+                        .count();
+                    (!lifecycle.rules.is_empty()).then(|| {
+                        format!(
+                            "{} rule(s) ({} enabled, {} disabled)",
+                            lifecycle.rules.len(),
+                            enabled,
+                            lifecycle.rules.len() - enabled
+                        )
+                    })
+                })
+            );
+            println!(
+                "  Website: {}",
+                setting_summary(website, |website| {
+                    let index = website.index_document.as_ref().map(|d| d.suffix.as_str());
+                    let error = website.error_document.as_ref().map(|d| d.key.as_str());
+                    (index.is_some() || error.is_some()).then(|| {
+                        format!(
+                            "enabled (index: {}, error: {})",
+                            index.unwrap_or("-"),
+                            error.unwrap_or("-")
+                        )
+                    })
+                })
+            );
+
+            Ok(())
+        }
+        "ls" => {
+            let bucket = &ctx.resolve_bucket(name).await?;
+            let r2_client = ctx.r2_client(bucket).await?;
+            let json = options.output == "json";
+
+            if !json {
+                status!("Bucket '{}' contents...", bucket);
+            }
+
+            // Totals come from the full listing: the metrics API is account-wide
+            let objects = r2_client.list_objects(None).await?;
+            let total_size: i64 = objects.iter().map(|o| o.size).sum();
+
+            if json {
+                let rows: Vec<ObjectView> = objects.iter().map(ObjectView::from).collect();
+                let json = serde_json::json!({
+                    "bucket": bucket,
+                    "objects": rows,
+                    "summary": {
+                        "objects": objects.len(),
+                        "total_size": total_size,
+                    },
+                });
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
 
-                    println!();
-                    println!("{}", Table::new(rows));
+            if objects.is_empty() {
+                status!("  Empty bucket");
+            } else {
+                #[derive(Tabled)]
+                struct ObjectRow {
+                    key: String,
+                    size: String,
                 }
+
+                let rows: Vec<ObjectRow> = objects
+                    .iter()
+                    .map(|o| ObjectRow {
+                        key: o.key.clone(),
+                        size: format_bytes(o.size),
+                    })
+                    .collect();
+
+                status!();
+                println!("{}", Table::new(rows));
+                status!(
+                    "  {} object(s), {} total",
+                    objects.len(),
+                    format_bytes(total_size)
+                );
             }
 
             Ok(())
         }
         _ => {
             println!("Unknown action: {}", action);
-            println!("Available actions: list, create, delete, info, ls");
+            println!("Available actions: list, create, delete, rename, info, ls, tag");
             Ok(())
         }
     }
 }
 
+/// Summarize an optional bucket setting for `buckets info`
+///
+/// `summarize` returns `None` for an empty configuration; like a 404, that
+/// reads as "not configured". Other errors are shown instead of failing.
+fn setting_summary<T>(
+    result: r2pilot_core::Result<T>,
+    summarize: impl FnOnce(&T) -> Option<String>,
+) -> String {
+    match result {
+        Ok(value) => summarize(&value).unwrap_or_else(|| "not configured".to_string()),
+        Err(r2pilot_core::Error::NotFound(_)) => "not configured".to_string(),
+        Err(e) => format!("unavailable ({})", e),
+    }
+}
+
 /// Format ISO date string to readable format
 fn format_date(iso_date: &str) -> String {
     match chrono::DateTime::parse_from_rfc3339(iso_date) {
-        Ok(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        Ok(dt) => format_datetime(dt.with_timezone(&chrono::Utc)),
         Err(_) => iso_date.to_string(),
     }
 }
 
-/// Handle files commands
-pub async fn handle_files(
-    action: &str,
-    file: Option<&str>,
-    key: Option<&str>,
-    bucket: Option<&str>,
-    prefix: Option<&str>,
-    _progress: bool,
-    multipart: bool,
-) -> Result<()> {
-    let config = load_config()?;
+/// Count, reserved size and age of the multipart uploads left in a bucket
+async fn incomplete_uploads_summary(
+    r2_client: &r2pilot_core::R2Client,
+    config: &ConfigFile,
+) -> String {
+    let uploads = match r2_client.list_multipart_uploads().await {
+        Ok(uploads) if uploads.is_empty() => return "none".to_string(),
+        Ok(uploads) => uploads,
+        Err(e) => return format!("- ({})", e),
+    };
 
-    // Get R2 credentials
-    let (access_key_id, secret_access_key) = if let Some(_token) = &config.cloudflare.api_token {
-        return Err(anyhow::anyhow!(
-            "API Token not supported yet.\n\
-             Use Access Keys in your configuration.\n\
-             Get your Access Keys from: https://dash.cloudflare.com/{}/r2/api-tokens",
-            config.cloudflare.account_id
-        ));
-    } else {
-        (
-            config.cloudflare.access_key_id.clone().unwrap(),
-            config.cloudflare.secret_access_key.clone().unwrap(),
-        )
+    let concurrency = config
+        .advanced
+        .as_ref()
+        .map(|a| a.max_concurrent_uploads)
+        .unwrap_or(5);
+    let size = match r2pilot_core::multipart_uploads_size(r2_client, &uploads, concurrency).await {
+        Ok(size) => format!("{} reserved", format_bytes(size as i64)),
+        Err(e) => format!("size unavailable: {}", e),
     };
+    let oldest = uploads
+        .iter()
+        .filter_map(|u| u.initiated)
+        .min_by_key(|dt| (dt.secs(), dt.subsec_nanos()))
+        .and_then(|dt| chrono::DateTime::from_timestamp(dt.secs(), dt.subsec_nanos()))
+        .map(|dt| format!(", oldest started {}", format_datetime(dt)))
+        .unwrap_or_default();
+
+    format!(
+        "{} ({}{}) - abort them with `r2pilot lifecycle set --preset cleanup-incomplete-uploads`",
+        uploads.len(),
+        size,
+        oldest
+    )
+}
 
-    let bucket = bucket.unwrap_or(&config.r2.default_bucket);
+/// Format an object's last-modified time ("-" when unknown)
+fn format_last_modified(object: &r2pilot_core::ObjectInfo) -> String {
+    object
+        .last_modified
+        .and_then(|dt| chrono::DateTime::from_timestamp(dt.secs(), dt.subsec_nanos()))
+        .map(format_datetime)
+        .unwrap_or_else(|| "-".to_string())
+}
 
-    let r2_client = R2Client::new(
-        config.cloudflare.endpoint.clone(),
-        access_key_id,
-        secret_access_key,
-        bucket.to_string(),
-    )
-    .await?;
+/// Format a timestamp in the local timezone, or UTC with `--utc`
+fn format_datetime(dt: chrono::DateTime<chrono::Utc>) -> String {
+    if DISPLAY_UTC.get().copied().unwrap_or(false) {
+        dt.format("%Y-%m-%d %H:%M UTC").to_string()
+    } else {
+        dt.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
 
-    match action {
-        "upload" => {
-            let file = file.ok_or_else(|| anyhow::anyhow!("Source file required"))?;
-            let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
+/// Print the non-fatal issues found in a valid configuration
+fn print_config_warnings(config: &ConfigFile) {
+    for warning in r2pilot_core::config_warnings(config) {
+        problem!("  ⚠️  {}", warning);
+    }
+}
 
-            let path = Path::new(file);
-            if !path.exists() {
-                return Err(anyhow::anyhow!("File not found: {}", file));
-            }
+/// Optional flags for files commands
+#[derive(Debug, Default)]
+pub struct FileOptions {
+    /// Show a progress bar for multipart uploads
+    pub progress: bool,
+    /// Force multipart upload
+    pub multipart: bool,
+    /// Shared link expiration in seconds (uses the configured default)
+    pub expires: Option<u64>,
+    /// Compress before upload (gzip, zstd)
+    pub compress: Option<String>,
+    /// Skip confirmation prompts
+    pub yes: bool,
+    /// Skip if the destination already exists
+    pub no_clobber: bool,
+    /// Declared size of a stdin upload
+    pub content_length: Option<u64>,
+    /// Compare same-size files by checksum when syncing
+    pub checksum: bool,
+    /// Size/age filters for listings
+    pub filter: r2pilot_core::ObjectFilter,
+    /// Output format (table, json)
+    pub output: String,
+    /// Parallel transfers (overrides max_concurrent_uploads)
+    pub concurrency: Option<usize>,
+    /// Show last-modified times in listings
+    pub long: bool,
+    /// List every key under the prefix instead of one folder level
+    pub recursive: bool,
+    /// HEAD each listed object to show its Content-Type
+    pub with_content_type: bool,
+    /// Delete remote objects missing locally when syncing
+    pub delete: bool,
+    /// Content-Disposition for uploads (overrides the configured default)
+    pub content_disposition: Option<String>,
+    /// Upload only if the current ETag matches
+    pub if_match: Option<String>,
+    /// Upload only if no object matches (`*`: only if absent)
+    pub if_none_match: Option<String>,
+    /// Poll until the object exists, for at most this long (`files stat --wait`)
+    pub wait: Option<std::time::Duration>,
+    /// Correct the Content-Type of unchanged objects when syncing
+    pub fix_content_type: bool,
+    /// Follow symlinks when walking a directory (sync, diff, glob uploads)
+    pub follow_symlinks: bool,
+    /// Stop a listing after this many keys (`files ls`)
+    pub max_keys: Option<usize>,
+    /// Start a listing after this key (`files ls`)
+    pub after: Option<String>,
+    /// Key uploads by content hash, under the prefix (`files upload`)
+    pub content_addressed: bool,
+    /// Skip files a previous failed glob upload already completed
+    pub resume: bool,
+    /// Target storage class (`files set-class`)
+    pub storage_class: Option<String>,
+    /// Destination bucket of a copy (`files copy`)
+    pub dst_bucket: Option<String>,
+    /// Content-Type replacing the object's (`files copy`, `files set-meta`)
+    pub content_type: Option<String>,
+    /// Cache-Control replacing the object's (`files set-meta`)
+    pub cache_control: Option<String>,
+    /// User metadata added to the object's (`files set-meta`)
+    pub metadata: Vec<(String, String)>,
+    /// File listing the keys to delete, one per line
+    pub keys_file: Option<String>,
+    /// Print the upload plan and exit
+    pub plan_only: bool,
+    /// Extra headers stored with uploaded objects
+    pub headers: Vec<(String, String)>,
+    /// Decode the object's Content-Encoding when downloading
+    pub decompress: bool,
+    /// Host shared links point at instead of the R2 endpoint
+    pub custom_host: Option<String>,
+    /// Go through the local download cache
+    pub cache: bool,
+}
 
-            let file_size = path.metadata()?.len();
+impl FileOptions {
+    /// Concurrency for this run: the flag if given, else the configured value
+    fn effective_concurrency(&self, config: &ConfigFile) -> usize {
+        let concurrency = self.concurrency.unwrap_or_else(|| {
+            config
+                .advanced
+                .as_ref()
+                .map(|a| a.max_concurrent_uploads)
+                .unwrap_or(5)
+        });
+        tracing::debug!(concurrency, "effective concurrency");
+        concurrency
+    }
 
-            println!("Uploading {} -> {}...", file, key);
-            println!("  Size: {}", format_bytes(file_size as i64));
+    /// Apply the `--header` values on top of `upload_options`
+    fn with_headers(&self, mut upload_options: UploadOptions) -> Result<UploadOptions> {
+        for (name, value) in &self.headers {
+            upload_options.set_header(name, value)?;
+        }
+        Ok(upload_options)
+    }
+
+    /// Content-Disposition for uploads: the flag if given, else the configured default
+    fn effective_content_disposition(&self, config: &ConfigFile) -> Option<String> {
+        self.content_disposition.clone().or_else(|| {
+            config
+                .upload
+                .as_ref()
+                .and_then(|u| u.default_content_disposition.clone())
+        })
+    }
+}
+
+/// The configured `[deploy.rules]`, compiled for multi-file uploads
+fn deploy_rules(config: &ConfigFile) -> Result<r2pilot_core::DeployRules> {
+    match &config.deploy {
+        Some(deploy) => Ok(r2pilot_core::DeployRules::new(&deploy.rules)?),
+        None => Ok(Default::default()),
+    }
+}
+
+/// Print a bucket's local description and tags
+fn print_bucket_annotation(annotation: &r2pilot_core::BucketAnnotation) {
+    if annotation.is_empty() {
+        println!("  No description or tags");
+        return;
+    }
+    if let Some(description) = &annotation.description {
+        println!("  Description: {}", description);
+    }
+    for (key, value) in &annotation.tags {
+        println!("  {} = {}", key, value);
+    }
+}
+
+/// Apply the optional CORS/website setup to a freshly created bucket
+async fn configure_new_bucket(
+    cf_client: &r2pilot_core::CloudflareClient,
+    bucket_name: &str,
+    options: &BucketOptions,
+) -> Result<()> {
+    use r2pilot_core::{BucketCorsConfig, ErrorDocument, IndexDocument, WebsiteConfiguration};
+
+    if options.with_cors {
+        status!("  Applying public-read CORS configuration...");
+        cf_client
+            .put_bucket_cors(bucket_name, &BucketCorsConfig::public_read())
+            .await?;
+        status!("  ✅ CORS configured (GET, HEAD from *)");
+    }
+
+    if options.with_website {
+        status!("  Enabling static hosting...");
+        let website_config = WebsiteConfiguration {
+            index_document: Some(IndexDocument {
+                suffix: "index.html".to_string(),
+            }),
+            error_document: Some(ErrorDocument {
+                key: "404.html".to_string(),
+            }),
+        };
+        cf_client
+            .put_bucket_website(bucket_name, &website_config)
+            .await?;
+        status!("  ✅ Static hosting enabled (index.html, 404.html)");
+    }
+
+    Ok(())
+}
+
+/// Handle files commands
+pub async fn handle_files(
+    ctx: &Context,
+    action: &str,
+    file: Option<&str>,
+    key: Option<&str>,
+    bucket: Option<&str>,
+    prefix: Option<&str>,
+    options: FileOptions,
+) -> Result<()> {
+    let bucket = ctx.resolve_bucket(bucket).await?;
+    let r2_client = ctx.r2_client(&bucket).await?;
+
+    run_files(
+        &r2_client,
+        ctx.config()?,
+        action,
+        file,
+        key,
+        prefix,
+        options,
+    )
+    .await
+}
+
+/// Run a files command against any R2 backend
+async fn run_files(
+    r2_client: &dyn R2Operations,
+    config: &ConfigFile,
+    action: &str,
+    file: Option<&str>,
+    key: Option<&str>,
+    prefix: Option<&str>,
+    options: FileOptions,
+) -> Result<()> {
+    match action {
+        "upload" => {
+            let file = file.ok_or_else(|| anyhow::anyhow!("Source file required"))?;
+
+            let hashed_key;
+            let key = if options.content_addressed {
+                if file == "-" || r2pilot_core::is_glob_pattern(file) || !Path::new(file).is_file()
+                {
+                    return Err(anyhow::anyhow!(
+                        "--content-addressed needs a single local file (not stdin or a glob)"
+                    ));
+                }
+
+                hashed_key =
+                    r2pilot_core::content_addressed_key(Path::new(file), prefix.unwrap_or(""))
+                        .await?;
+                if is_quiet() {
+                    println!("{}", hashed_key);
+                } else {
+                    println!("  Key: {}", hashed_key);
+                }
+
+                // Same key, same content: nothing to upload again
+                if r2_client.object_exists(&hashed_key).await? {
+                    status!("  ⏭️  Already stored, skipping");
+                    return Ok(());
+                }
+                hashed_key.as_str()
+            } else {
+                key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?
+            };
+
+            let conditional = options.if_match.is_some() || options.if_none_match.is_some();
+
+            if r2pilot_core::is_glob_pattern(file) {
+                return upload_glob(r2_client, config, file, key, &options).await;
+            }
+            if options.resume {
+                return Err(anyhow::anyhow!("--resume only applies to glob uploads"));
+            }
+            if options.plan_only && file == "-" {
+                return Err(anyhow::anyhow!(
+                    "--plan-only needs a file (the size of stdin isn't known in advance)"
+                ));
+            }
+
+            if options.no_clobber && r2_client.object_exists(key).await? {
+                status!("  ⏭️  {} already exists, skipping", key);
+                return Ok(());
+            }
+
+            let advanced = config.advanced.clone().unwrap_or_default();
+            let mut multipart_config = MultipartUploadConfig {
+                chunk_size: advanced.multipart_chunk_size_mb * 1024 * 1024,
+                concurrent_parts: options.effective_concurrency(config),
+                max_retries: advanced.max_retries,
+                retry_delay: std::time::Duration::from_millis(advanced.retry_delay),
+                content_disposition: options.effective_content_disposition(config),
+                ..Default::default()
+            };
+
+            if file == "-" {
+                if options.compress.is_some() || conditional || !options.headers.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "--compress, --if-match, --if-none-match and --header are not supported when uploading from stdin"
+                    ));
+                }
+
+                let content_type = mime_guess::from_path(key)
+                    .first_or_octet_stream()
+                    .to_string();
+
+                status!("Uploading stdin -> {}...", key);
+                match options.content_length {
+                    Some(length) => status!("  Size: {}", format_bytes(length as i64)),
+                    None => {
+                        status!("  Size unknown, using multipart upload...");
+                        multipart_config.cancel = cancel_on_ctrl_c();
+                    }
+                }
+
+                let mut progress_bar = None;
+                if options.progress && options.content_length.is_none() {
+                    let (tx, handle) = upload_progress_bar()?;
+                    multipart_config.progress = Some(tx);
+                    progress_bar = Some(handle);
+                }
+
+                let uploaded = r2_client
+                    .upload_stream(
+                        key,
+                        Box::new(tokio::io::stdin()),
+                        &content_type,
+                        options.content_length,
+                        multipart_config,
+                    )
+                    .await;
+                if let Some(handle) = progress_bar {
+                    let _ = handle.await;
+                }
+                uploaded?;
+
+                status!("  ✅ Upload complete");
+                return Ok(());
+            }
+
+            let path = Path::new(file);
+            if !path.exists() {
+                return Err(anyhow::anyhow!("File not found: {}", file));
+            }
+
+            let file_size = path.metadata()?.len();
+
+            // The plan is the result of --plan-only, printed even with --quiet
+            if options.plan_only {
+                println!("Upload plan for {} -> {}:", file, key);
+                println!("  Size: {}", format_bytes(file_size as i64));
+            } else {
+                status!("Uploading {} -> {}...", file, key);
+                status!("  Size: {}", format_bytes(file_size as i64));
+            }
 
             // Detect content type
             let content_type = mime_guess::from_path(path)
                 .first_or_octet_stream()
                 .to_string();
 
-            // Check if multipart upload is needed or requested
-            let use_multipart = multipart || r2pilot_core::requires_multipart_upload(file_size);
+            // Check if multipart upload is needed or requested; a file below
+            // the minimum part size can't use it (an empty one has no part)
+            let too_small = file_size < r2pilot_core::MIN_PART_SIZE as u64;
+            if options.multipart && too_small {
+                status!(
+                    "  ⚠️  {} is below the {} minimum part size, using a single PUT instead of --multipart",
+                    format_bytes(file_size as i64),
+                    format_bytes(r2pilot_core::MIN_PART_SIZE as i64)
+                );
+            }
+            let use_multipart = !too_small
+                && (options.multipart || r2pilot_core::requires_multipart_upload(file_size));
+
+            let compression = options
+                .compress
+                .as_deref()
+                .map(str::parse::<Compression>)
+                .transpose()?;
+
+            if (conditional || !options.headers.is_empty()) && use_multipart {
+                return Err(anyhow::anyhow!(
+                    "--if-match, --if-none-match and --header are not supported with multipart uploads (file too large or --multipart set)"
+                ));
+            }
+
+            if use_multipart && (options.plan_only || !is_quiet()) {
+                print_multipart_plan(&r2pilot_core::plan_multipart(file_size, &multipart_config));
+            }
+            if options.plan_only {
+                if !use_multipart {
+                    println!(
+                        "  Plan: single PUT request (use --multipart to force a multipart upload)"
+                    );
+                }
+                return Ok(());
+            }
+
+            if let Some(compression) = compression {
+                if use_multipart {
+                    return Err(anyhow::anyhow!(
+                        "--compress is not supported with multipart uploads (file too large or --multipart set)"
+                    ));
+                }
+                if is_compressed_format(path) {
+                    status!(
+                        "  ⚠️  {} is already a compressed format, {} compression is wasteful",
+                        file,
+                        compression
+                    );
+                }
+                status!("  Compressing with {}...", compression);
+            }
 
             if use_multipart {
-                println!("  Using multipart upload...");
+                multipart_config.cancel = cancel_on_ctrl_c();
 
-                let advanced = config.advanced.unwrap_or_default();
-                let multipart_config = MultipartUploadConfig {
-                    chunk_size: advanced.multipart_chunk_size_mb * 1024 * 1024,
-                    concurrent_parts: advanced.max_concurrent_uploads,
-                };
+                let mut progress_bar = None;
+                if options.progress {
+                    let (tx, handle) = upload_progress_bar()?;
+                    multipart_config.progress = Some(tx);
+                    progress_bar = Some(handle);
+                }
 
-                r2_client
+                let uploaded = r2_client
                     .upload_file_multipart(key, path, &content_type, multipart_config)
-                    .await?;
+                    .await;
+                if let Some(handle) = progress_bar {
+                    let _ = handle.await;
+                }
+                uploaded?;
             } else {
-                r2_client.upload_file(key, path, &content_type).await?;
+                let upload_options = options.with_headers(UploadOptions {
+                    compression,
+                    content_disposition: options.effective_content_disposition(config),
+                    if_match: options.if_match.clone(),
+                    if_none_match: options.if_none_match.clone(),
+                    ..Default::default()
+                })?;
+                let uploaded = r2_client
+                    .upload_file_with_options(key, path, &content_type, &upload_options)
+                    .await;
+                if let Err(r2pilot_core::Error::PreconditionFailed(_)) = &uploaded {
+                    match &options.if_match {
+                        Some(etag) => {
+                            problem!("  ❌ {} no longer has ETag {}, not overwritten", key, etag)
+                        }
+                        None => problem!("  ❌ {} already exists, not overwritten", key),
+                    }
+                }
+                uploaded?;
             }
 
-            println!("  ✅ Upload complete");
+            if let Some(compression) = compression {
+                // Make sure the stored object advertises its encoding
+                let metadata = r2_client.head_object(key).await?;
+                if metadata.content_encoding.as_deref() == Some(compression.content_encoding()) {
+                    status!("  Content-Encoding: {}", compression);
+                } else {
+                    status!(
+                        "  ⚠️  Stored object has Content-Encoding {:?}, expected {}",
+                        metadata.content_encoding,
+                        compression
+                    );
+                }
+            }
+
+            status!("  ✅ Upload complete");
 
             Ok(())
         }
@@ -520,28 +1507,449 @@ pub async fn handle_files(
             let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
             let dest = file.ok_or_else(|| anyhow::anyhow!("Destination required"))?;
 
-            println!("Downloading {} -> {}...", key, dest);
-            r2_client.download_file(key, Path::new(dest)).await?;
-            println!("  ✅ Download complete");
+            if options.no_clobber && Path::new(dest).exists() {
+                status!("  ⏭️  {} already exists, skipping", dest);
+                return Ok(());
+            }
+
+            status!("Downloading {} -> {}...", key, dest);
+            let advanced = config.advanced.clone().unwrap_or_default();
+
+            if options.decompress {
+                let (size, encoding) = r2pilot_core::download_file_decompressed(
+                    r2_client,
+                    key,
+                    Path::new(dest),
+                    advanced.max_retries,
+                    std::time::Duration::from_millis(advanced.retry_delay),
+                )
+                .await?;
+                match encoding {
+                    Some(encoding) => status!(
+                        "  ✅ Download complete ({}, {} decompressed)",
+                        format_bytes(size as i64),
+                        encoding
+                    ),
+                    None => status!(
+                        "  ✅ Download complete ({}, not compressed)",
+                        format_bytes(size as i64)
+                    ),
+                }
+                return Ok(());
+            }
+
+            if options.cache {
+                let cache = r2pilot_core::DownloadCache::new(
+                    r2pilot_core::DownloadCache::default_dir()?,
+                    advanced.download_cache_max_bytes()?,
+                );
+                let (size, status) = cache
+                    .download(
+                        r2_client,
+                        key,
+                        Path::new(dest),
+                        advanced.max_retries,
+                        std::time::Duration::from_millis(advanced.retry_delay),
+                    )
+                    .await?;
+                let source = match status {
+                    r2pilot_core::CacheStatus::Hit => "from cache",
+                    r2pilot_core::CacheStatus::Miss => "cached",
+                };
+                status!(
+                    "  ✅ Download complete ({}, {})",
+                    format_bytes(size as i64),
+                    source
+                );
+                return Ok(());
+            }
+
+            let size = r2pilot_core::download_file_verified(
+                r2_client,
+                key,
+                Path::new(dest),
+                advanced.max_retries,
+                std::time::Duration::from_millis(advanced.retry_delay),
+            )
+            .await?;
+            status!("  ✅ Download complete ({})", format_bytes(size as i64));
 
             Ok(())
         }
         "delete" => {
+            if let Some(keys_file) = &options.keys_file {
+                return delete_listed_keys(r2_client, keys_file, options.yes).await;
+            }
             let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
 
-            println!("Deleting {}...", key);
+            status!("Deleting {}...", key);
             r2_client.delete_object(key).await?;
-            println!("  ✅ File deleted");
+            status!("  ✅ File deleted");
+
+            Ok(())
+        }
+        "rm" => {
+            let pattern = key.ok_or_else(|| anyhow::anyhow!("Pattern required"))?;
+
+            // A plain "prefix/" deletes everything under it
+            let pattern = if !r2pilot_core::has_glob_wildcards(pattern) && pattern.ends_with('/') {
+                format!("{}**", pattern)
+            } else {
+                pattern.to_string()
+            };
+            let glob = r2pilot_core::KeyGlob::new(&pattern)?;
+
+            let objects = r2pilot_core::find_objects(r2_client, &glob).await?;
+            let total_size: i64 = objects.iter().map(|o| o.size).sum();
+            status!(
+                "Found {} object(s) matching {} ({})",
+                objects.len(),
+                pattern,
+                format_bytes(total_size)
+            );
+
+            if objects.is_empty() {
+                return Ok(());
+            }
+
+            const PREVIEW: usize = 10;
+            for object in objects.iter().take(PREVIEW) {
+                status!("  - {}", object.key);
+            }
+            if objects.len() > PREVIEW {
+                status!("  ... and {} more", objects.len() - PREVIEW);
+            }
+
+            if !options.yes {
+                let threshold = config
+                    .advanced
+                    .as_ref()
+                    .map(|a| a.rm_confirm_threshold)
+                    .unwrap_or(100);
+                check_rm_threshold(objects.len(), threshold)?;
+                if !confirm(&format!("Delete {} object(s)?", objects.len()))? {
+                    status!("❌ Delete cancelled");
+                    return Ok(());
+                }
+            }
+
+            let count = objects.len();
+            r2_client
+                .delete_objects(objects.into_iter().map(|o| o.key).collect())
+                .await?;
+            status!("  ✅ Deleted {} object(s)", count);
+
+            Ok(())
+        }
+        "stat" => {
+            let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
+
+            let metadata = match options.wait {
+                Some(timeout) => {
+                    if options.output != "json" {
+                        status!("Waiting for {} (timeout: {}s)...", key, timeout.as_secs());
+                    }
+                    r2pilot_core::wait_for_object(r2_client, key, timeout).await?
+                }
+                None => r2_client.head_object(key).await?,
+            };
+            let modified = metadata
+                .last_modified
+                .and_then(|dt| chrono::DateTime::from_timestamp(dt.secs(), dt.subsec_nanos()));
+
+            if options.output == "json" {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "key": metadata.key,
+                        "size": metadata.size,
+                        "content_type": metadata.content_type,
+                        "content_encoding": metadata.content_encoding,
+                        "content_disposition": metadata.content_disposition,
+                        "last_modified": modified.map(|dt| dt.to_rfc3339()),
+                        "etag": metadata.etag,
+                        "storage_class": metadata.storage_class.as_str(),
+                    }))?
+                );
+                return Ok(());
+            }
+
+            println!("  Key:            {}", metadata.key);
+            println!(
+                "  Size:           {} ({} bytes)",
+                format_bytes(metadata.size),
+                metadata.size
+            );
+            println!("  Content-Type:   {}", metadata.content_type);
+            if let Some(encoding) = &metadata.content_encoding {
+                println!("  Encoding:       {}", encoding);
+            }
+            if let Some(disposition) = &metadata.content_disposition {
+                println!("  Disposition:    {}", disposition);
+            }
+            println!(
+                "  Last modified:  {}",
+                modified
+                    .map(format_datetime)
+                    .unwrap_or_else(|| "-".to_string())
+            );
+            println!("  ETag:           {}", metadata.etag);
+            match metadata.storage_class {
+                StorageClass::Standard => println!("  Storage class:  {}", metadata.storage_class),
+                StorageClass::InfrequentAccess => println!(
+                    "  Storage class:  {} (downloads incur a retrieval fee)",
+                    metadata.storage_class
+                ),
+            }
+
+            Ok(())
+        }
+        "set-class" => {
+            let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
+            let class = options
+                .storage_class
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("Storage class required"))?
+                .parse::<StorageClass>()?;
+
+            status!("Moving {} to {}...", key, class);
+            r2_client.change_storage_class(key, class).await?;
+            status!("  ✅ Storage class set to {}", class);
+
+            Ok(())
+        }
+        "set-meta" => {
+            let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
+            let update = r2pilot_core::MetadataUpdate {
+                content_type: options.content_type.clone(),
+                cache_control: options.cache_control.clone(),
+                content_disposition: options.content_disposition.clone(),
+                metadata: options.metadata.iter().cloned().collect(),
+                ..Default::default()
+            };
+            if update.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Nothing to change (use --content-type, --cache-control, --content-disposition or --metadata)"
+                ));
+            }
+
+            status!(
+                "Updating metadata of {} (rewrites the object in place)...",
+                key
+            );
+            r2_client.update_metadata(key, &update).await?;
+            status!("  ✅ Metadata updated");
+            for (name, value) in [
+                ("Content-Type", &update.content_type),
+                ("Cache-Control", &update.cache_control),
+                ("Content-Disposition", &update.content_disposition),
+            ] {
+                if let Some(value) = value {
+                    status!("  {}: {}", name, value);
+                }
+            }
+            for (name, value) in &options.metadata {
+                status!("  x-amz-meta-{}: {}", name, value);
+            }
+
+            Ok(())
+        }
+        "restore" => {
+            let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
+
+            status!("Restoring {}...", key);
+            let version_id = r2_client.restore_object(key).await?;
+            status!("  ✅ Delete marker {} removed, object restored", version_id);
 
             Ok(())
         }
         "ls" => {
-            println!("Listing files (prefix: {:?})...", prefix);
+            let machine = is_machine_output(&options.output);
+            if !machine {
+                status!("Listing files (prefix: {:?})...", prefix);
+            }
 
-            let objects = r2_client.list_objects(prefix).await?;
+            // Without --recursive, keys below the next "/" are folded into folders
+            let delimiter = (!options.recursive).then_some("/");
+            let paged = options.max_keys.is_some() || options.after.is_some();
+            let (objects, prefixes, next_after) = if paged {
+                let page = r2_client
+                    .list_objects_paged(
+                        prefix,
+                        delimiter,
+                        options.after.as_deref(),
+                        options.max_keys.unwrap_or(usize::MAX),
+                    )
+                    .await?;
+                (page.objects, page.common_prefixes, page.next_after)
+            } else if let Some(delimiter) = delimiter {
+                let (objects, prefixes) =
+                    r2_client.list_objects_delimited(prefix, delimiter).await?;
+                (objects, prefixes, None)
+            } else {
+                (r2_client.list_objects(prefix).await?, Vec::new(), None)
+            };
+            let objects = options.filter.apply(objects);
+
+            // Listings don't include Content-Type, so it costs a HEAD per object
+            let content_types: HashMap<String, String> = if options.with_content_type {
+                let warning = format!(
+                    "⚠️  --with-content-type sends one HEAD request per object ({} requests)",
+                    objects.len()
+                );
+                if machine {
+                    eprintln!("{}", warning);
+                } else {
+                    status!("  {}", warning);
+                }
+
+                let keys = objects.iter().map(|o| o.key.clone()).collect();
+                r2pilot_core::head_objects(r2_client, keys, options.effective_concurrency(config))
+                    .await
+                    .into_iter()
+                    .filter_map(|(key, result)| Some((key, result.ok()?.content_type)))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+
+            if machine {
+                let rows: Vec<ObjectView> = prefixes
+                    .iter()
+                    .map(|p| ObjectView::prefix(p))
+                    .chain(objects.iter().map(|o| ObjectView {
+                        content_type: content_types.get(&o.key).cloned(),
+                        ..ObjectView::from(o)
+                    }))
+                    .collect();
+                if !paged {
+                    return print_listing(&rows, &options.output);
+                }
+                // Scripts read the cursor of the next page along with the rows
+                if options.output == "json" {
+                    let page = serde_json::json!({ "objects": rows, "next_after": next_after });
+                    println!("{}", serde_json::to_string_pretty(&page)?);
+                    return Ok(());
+                }
+                print_listing(&rows, &options.output)?;
+                if let Some(next_after) = &next_after {
+                    eprintln!("Next page: --after {}", shell_quote(next_after));
+                }
+                return Ok(());
+            }
+
+            if objects.is_empty() && prefixes.is_empty() {
+                status!("  No files found");
+            } else if options.with_content_type {
+                #[derive(Tabled)]
+                struct TypedObjectRow {
+                    key: String,
+                    size: String,
+                    modified: String,
+                    content_type: String,
+                }
+
+                let rows: Vec<TypedObjectRow> = prefixes
+                    .iter()
+                    .map(|p| TypedObjectRow {
+                        key: format!("PRE {}", p),
+                        size: String::new(),
+                        modified: String::new(),
+                        content_type: String::new(),
+                    })
+                    .chain(objects.iter().map(|o| {
+                        TypedObjectRow {
+                            key: o.key.clone(),
+                            size: format_bytes(o.size),
+                            modified: format_last_modified(o),
+                            content_type: content_types
+                                .get(&o.key)
+                                .cloned()
+                                .unwrap_or_else(|| "?".to_string()),
+                        }
+                    }))
+                    .collect();
+
+                status!();
+                println!("{}", Table::new(rows));
+            } else if options.long {
+                #[derive(Tabled)]
+                struct LongObjectRow {
+                    key: String,
+                    size: String,
+                    modified: String,
+                }
+
+                let rows: Vec<LongObjectRow> = prefixes
+                    .iter()
+                    .map(|p| LongObjectRow {
+                        key: format!("PRE {}", p),
+                        size: String::new(),
+                        modified: String::new(),
+                    })
+                    .chain(objects.iter().map(|o| LongObjectRow {
+                        key: o.key.clone(),
+                        size: format_bytes(o.size),
+                        modified: format_last_modified(o),
+                    }))
+                    .collect();
+
+                status!();
+                println!("{}", Table::new(rows));
+            } else {
+                #[derive(Tabled)]
+                struct ObjectRow {
+                    key: String,
+                    size: String,
+                }
+
+                let rows: Vec<ObjectRow> = prefixes
+                    .iter()
+                    .map(|p| ObjectRow {
+                        key: format!("PRE {}", p),
+                        size: String::new(),
+                    })
+                    .chain(objects.iter().map(|o| ObjectRow {
+                        key: o.key.clone(),
+                        size: format_bytes(o.size),
+                    }))
+                    .collect();
+
+                status!();
+                println!("{}", Table::new(rows));
+            }
+            if let Some(next_after) = &next_after {
+                status!(
+                    "  More keys follow; next page: --after {}",
+                    shell_quote(next_after)
+                );
+            }
+
+            Ok(())
+        }
+        "find" => {
+            let pattern = key.ok_or_else(|| anyhow::anyhow!("Pattern required"))?;
+            let glob = r2pilot_core::KeyGlob::new(pattern)?;
+
+            let objects = r2pilot_core::find_objects(r2_client, &glob).await?;
+
+            if options.output == "json" {
+                let keys: Vec<serde_json::Value> = objects
+                    .iter()
+                    .map(|o| serde_json::json!({ "key": o.key, "size": o.size }))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&keys)?);
+                return Ok(());
+            }
+
+            status!(
+                "Finding files matching {} (scanning prefix: {:?})...",
+                pattern,
+                glob.prefix()
+            );
 
             if objects.is_empty() {
-                println!("  No files found");
+                status!("  No files found");
             } else {
                 #[derive(Tabled)]
                 struct ObjectRow {
@@ -549,103 +1957,773 @@ pub async fn handle_files(
                     size: String,
                 }
 
-                let rows: Vec<ObjectRow> = objects
-                    .iter()
-                    .map(|o| ObjectRow {
-                        key: o.key.clone(),
-                        size: format_bytes(o.size),
-                    })
-                    .collect();
+                let rows: Vec<ObjectRow> = objects
+                    .iter()
+                    .map(|o| ObjectRow {
+                        key: o.key.clone(),
+                        size: format_bytes(o.size),
+                    })
+                    .collect();
+
+                status!();
+                println!("{}", Table::new(rows));
+                status!("  {} matching file(s)", objects.len());
+            }
+
+            Ok(())
+        }
+        "share" => {
+            let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
+            let expires = options.expires.unwrap_or(config.r2.default_expiration);
+
+            if expires > 604800 {
+                return Err(anyhow::anyhow!(
+                    "Expiration cannot exceed 7 days (604800 seconds)"
+                ));
+            }
+
+            status!("Sharing {} (expires: {})...", key, format_expiry(expires));
+
+            let mut presigned_config = PresignedUrlConfig::new(
+                PresignedMethod::Get,
+                key.to_string(),
+                std::time::Duration::from_secs(expires),
+            );
+            if let Some(host) = &options.custom_host {
+                presigned_config = presigned_config.with_custom_host(host.clone());
+            }
+            // SigV4 from the configured access keys, R2 rejects unsigned links
+            let url = generate_presigned_url_signed(
+                &config.cloudflare,
+                &config.r2.region,
+                r2_client.bucket(),
+                key,
+                presigned_config,
+            )?;
+
+            if is_quiet() {
+                println!("{}", url);
+                copy_to_clipboard(&url);
+                return Ok(());
+            }
+
+            println!();
+            println!("  ✅ URL generated:");
+            println!("  {}", url);
+            println!();
+
+            if copy_to_clipboard(&url) {
+                println!("  📋 Copied to clipboard");
+            } else {
+                println!("  ⚠️  Clipboard unavailable, copy the URL above");
+            }
+
+            Ok(())
+        }
+        "copy" => {
+            let source = prefix.ok_or_else(|| anyhow::anyhow!("Source key required"))?;
+            let dest = key.ok_or_else(|| anyhow::anyhow!("Destination key required"))?;
+            let source_bucket = r2_client.bucket();
+            let dest_bucket = options.dst_bucket.as_deref().unwrap_or(source_bucket);
+
+            if dest_bucket == source_bucket && dest == source && options.content_type.is_none() {
+                return Err(anyhow::anyhow!(
+                    "Source and destination are the same object (add --content-type to change its type)"
+                ));
+            }
+
+            status!(
+                "Copying {}/{} -> {}/{}...",
+                source_bucket,
+                source,
+                dest_bucket,
+                dest
+            );
+            r2_client
+                .copy_object_to(source, dest_bucket, dest, options.content_type.as_deref())
+                .await?;
+            status!("  ✅ Copied to {}/{}", dest_bucket, dest);
+            if let Some(content_type) = &options.content_type {
+                status!("  Content-Type: {}", content_type);
+            }
+
+            Ok(())
+        }
+        "mv-prefix" => {
+            use r2pilot_core::{move_objects, plan_prefix_move, MoveStatus};
+
+            let from = prefix.ok_or_else(|| anyhow::anyhow!("Source prefix required"))?;
+            let to = key.ok_or_else(|| anyhow::anyhow!("Destination prefix required"))?;
+
+            if from.is_empty() || from == to {
+                return Err(anyhow::anyhow!(
+                    "Source prefix must be non-empty and differ from the destination"
+                ));
+            }
+
+            let concurrency = options.effective_concurrency(config);
+            let keys: Vec<String> =
+                r2pilot_core::list_objects_concurrent(r2_client, Some(from), concurrency)
+                    .await?
+                    .into_iter()
+                    .map(|o| o.key)
+                    .collect();
+            let plan = plan_prefix_move(&keys, from, to);
+
+            if plan.is_empty() {
+                status!("  No objects found under '{}'", from);
+                return Ok(());
+            }
+
+            status!(
+                "Moving {} object(s) from '{}' to '{}'...",
+                plan.len(),
+                from,
+                to
+            );
+
+            if !options.yes && !confirm(&format!("Move {} object(s)?", plan.len()))? {
+                status!("❌ Move cancelled");
+                return Ok(());
+            }
+
+            let outcomes = move_objects(r2_client, plan, concurrency).await;
+
+            let mut moved = 0;
+            let mut copy_failed = 0;
+            let mut delete_failed = 0;
+            for outcome in &outcomes {
+                match &outcome.status {
+                    MoveStatus::Moved => {
+                        moved += 1;
+                        status!("  ✅ {} -> {}", outcome.source, outcome.dest);
+                    }
+                    MoveStatus::CopyFailed(e) => {
+                        copy_failed += 1;
+                        problem!("  ❌ {} (not copied, original kept): {}", outcome.source, e);
+                    }
+                    MoveStatus::DeleteFailed(e) => {
+                        delete_failed += 1;
+                        problem!(
+                            "  ⚠️  {} -> {} (copied, original NOT deleted): {}",
+                            outcome.source,
+                            outcome.dest,
+                            e
+                        );
+                    }
+                }
+            }
+
+            status!();
+            status!("  Moved: {}/{}", moved, outcomes.len());
+            if copy_failed + delete_failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} copy failure(s) (originals kept), {} delete failure(s) (objects exist under both prefixes)",
+                    copy_failed,
+                    delete_failed
+                ));
+            }
+
+            Ok(())
+        }
+        "diff" => {
+            use r2pilot_core::{compute_sync_plan, SyncOptions};
+
+            let dir = sync_source_dir(file)?;
+            let prefix = prefix.unwrap_or("");
+
+            let sync_options = SyncOptions {
+                checksum: options.checksum,
+                concurrency: options.effective_concurrency(config),
+                delete: options.delete,
+                follow_symlinks: options.follow_symlinks,
+                ..Default::default()
+            };
+            let plan = compute_sync_plan(r2_client, dir, prefix, &sync_options).await?;
+
+            if options.output == "json" {
+                let json = serde_json::json!({
+                    "upload": plan
+                        .uploads
+                        .iter()
+                        .map(|u| serde_json::json!({
+                            "key": u.key,
+                            "path": u.local.path,
+                            "size": u.local.size,
+                            "reason": u.reason.as_str(),
+                        }))
+                        .collect::<Vec<_>>(),
+                    "delete": plan.deletes,
+                    "unchanged": plan.unchanged,
+                    "failed": plan
+                        .failed
+                        .iter()
+                        .map(|(key, e)| serde_json::json!({ "key": key, "error": e.to_string() }))
+                        .collect::<Vec<_>>(),
+                });
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                return Ok(());
+            }
+
+            status!(
+                "Comparing {} with {}/{}...",
+                dir.display(),
+                r2_client.bucket(),
+                prefix
+            );
+            for upload in &plan.uploads {
+                println!("  + {} ({})", upload.key, upload.reason.as_str());
+            }
+            for key in &plan.deletes {
+                println!("  - {}", key);
+            }
+            for (key, e) in &plan.failed {
+                problem!("  ❌ {}: {}", key, e);
+            }
+
+            status!();
+            status!(
+                "  To upload: {}, to delete: {}, unchanged: {}, failed: {}",
+                plan.uploads.len(),
+                plan.deletes.len(),
+                plan.unchanged.len(),
+                plan.failed.len()
+            );
+            if !plan.has_changes() && plan.failed.is_empty() {
+                status!("  ✅ Already in sync");
+            }
+
+            Ok(())
+        }
+        "sync" => {
+            use r2pilot_core::{compute_sync_plan, execute_sync_plan, SyncOptions, SyncStatus};
+
+            let dir = sync_source_dir(file)?;
+            let prefix = prefix.unwrap_or("");
+
+            status!(
+                "Syncing {} -> {}/{} ({})...",
+                dir.display(),
+                r2_client.bucket(),
+                prefix,
+                if options.checksum {
+                    "size + checksum"
+                } else {
+                    "size only"
+                }
+            );
+
+            let sync_options = SyncOptions {
+                checksum: options.checksum,
+                concurrency: options.effective_concurrency(config),
+                cancel: cancel_on_ctrl_c(),
+                delete: options.delete,
+                upload: UploadOptions {
+                    content_disposition: options.effective_content_disposition(config),
+                    deploy_rules: deploy_rules(config)?,
+                    ..Default::default()
+                },
+                fix_content_type: options.fix_content_type,
+                follow_symlinks: options.follow_symlinks,
+            };
+            let mut plan = compute_sync_plan(r2_client, dir, prefix, &sync_options).await?;
+
+            if !plan.deletes.is_empty() && !options.yes {
+                for key in &plan.deletes {
+                    status!("  - {}", key);
+                }
+                if !confirm(&format!(
+                    "Delete {} remote object(s) missing locally?",
+                    plan.deletes.len()
+                ))? {
+                    status!("  ⏭️  Skipping deletions");
+                    plan.deletes.clear();
+                }
+            }
+
+            let mut outcomes = execute_sync_plan(r2_client, plan, &sync_options).await;
+            outcomes.sort_by(|a, b| a.key.cmp(&b.key));
+
+            let mut uploaded = 0;
+            let mut unchanged = 0;
+            let mut deleted = 0;
+            let mut retyped = 0;
+            let mut failed = 0;
+            for outcome in &outcomes {
+                match &outcome.status {
+                    SyncStatus::Uploaded(reason) => {
+                        uploaded += 1;
+                        status!("  ✅ {} ({})", outcome.key, reason.as_str());
+                    }
+                    SyncStatus::Unchanged => unchanged += 1,
+                    SyncStatus::Deleted => {
+                        deleted += 1;
+                        status!("  ✅ {} (deleted)", outcome.key);
+                    }
+                    SyncStatus::ContentTypeFixed { from, to } => {
+                        retyped += 1;
+                        status!("  ✅ {} (content type: {} -> {})", outcome.key, from, to);
+                    }
+                    SyncStatus::Failed(e) => {
+                        failed += 1;
+                        problem!("  ❌ {}: {}", outcome.key, e);
+                    }
+                }
+            }
+
+            status!();
+            if sync_options.delete {
+                status!(
+                    "  Uploaded: {}, deleted: {}, unchanged: {}, failed: {}",
+                    uploaded,
+                    deleted,
+                    unchanged,
+                    failed
+                );
+            } else {
+                status!(
+                    "  Uploaded: {}, unchanged: {}, failed: {}",
+                    uploaded,
+                    unchanged,
+                    failed
+                );
+            }
+            if sync_options.fix_content_type {
+                status!("  Content types corrected: {}", retyped);
+            }
+            if sync_options.cancel.is_cancelled() {
+                return Err(r2pilot_core::Error::Cancelled.into());
+            }
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{} file(s) failed to sync", failed));
+            }
+
+            Ok(())
+        }
+        _ => {
+            println!("Unknown action: {}", action);
+            println!(
+                "Available actions: upload, download, delete, stat, rm, set-class, set-meta, restore, ls, find, share, copy, mv-prefix, diff, sync"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Check the local directory given to `diff`/`sync`
+fn sync_source_dir(dir: Option<&str>) -> Result<&Path> {
+    let dir = Path::new(dir.ok_or_else(|| anyhow::anyhow!("Local directory required"))?);
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!("Not a directory: {}", dir.display()));
+    }
+    Ok(dir)
+}
+
+/// Cancel the returned token on Ctrl-C so long operations can clean up
+///
+/// A second Ctrl-C exits immediately.
+fn cancel_on_ctrl_c() -> r2pilot_core::CancellationToken {
+    let token = r2pilot_core::CancellationToken::new();
+    let cancel = token.clone();
+
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!();
+        eprintln!("  ⚠️  Cancelling, cleaning up (press Ctrl-C again to force quit)...");
+        cancel.cancel();
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+
+    token
+}
+
+/// Drive a progress bar from multipart upload events
+///
+/// The bar finishes once every sender is dropped, i.e. when the upload returns;
+/// await the handle afterwards so it's cleared before the next line prints.
+fn upload_progress_bar() -> Result<(
+    tokio::sync::mpsc::Sender<r2pilot_core::MultipartUploadProgress>,
+    tokio::task::JoinHandle<()>,
+)> {
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    let sized = ProgressStyle::with_template(
+        "  [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({msg}, {bytes_per_sec})",
+    )?
+    .progress_chars("=> ");
+    let streamed =
+        ProgressStyle::with_template("  {spinner:.green} {bytes} ({msg}, {bytes_per_sec})")?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<r2pilot_core::MultipartUploadProgress>(64);
+    let handle = tokio::spawn(async move {
+        let bar = ProgressBar::hidden();
+        while let Some(event) = rx.recv().await {
+            if bar.is_hidden() && !is_quiet() {
+                bar.set_draw_target(indicatif::ProgressDrawTarget::stderr());
+                if event.total_bytes > 0 {
+                    bar.set_style(sized.clone());
+                    bar.set_length(event.total_bytes);
+                } else {
+                    bar.set_style(streamed.clone());
+                }
+            }
+            bar.set_position(event.uploaded_bytes);
+            bar.set_message(if event.total_parts > 0 {
+                format!("part {}/{}", event.completed_parts, event.total_parts)
+            } else {
+                format!("{} part(s)", event.completed_parts)
+            });
+        }
+        bar.finish_and_clear();
+    });
+
+    Ok((tx, handle))
+}
+
+/// Ask the user for confirmation (defaults to no)
+fn confirm(prompt: &str) -> Result<bool> {
+    use dialoguer::{theme::ColorfulTheme, Confirm};
+
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
+/// Copy text to the system clipboard, returning false if no clipboard is available
+fn copy_to_clipboard(text: &str) -> bool {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text.to_string()))
+        .is_ok()
+}
 
-                println!();
-                println!("{}", Table::new(rows));
-            }
+/// An expiry in seconds, followed by its human form (`7200s (2h)`)
+fn format_expiry(seconds: u64) -> String {
+    format!(
+        "{}s ({})",
+        seconds,
+        r2pilot_core::format_duration(std::time::Duration::from_secs(seconds))
+    )
+}
 
-            Ok(())
-        }
-        _ => {
-            println!("Unknown action: {}", action);
-            println!("Available actions: upload, download, delete, ls");
-            Ok(())
-        }
+/// Refuse to prompt for more than `threshold` deletions: those need `--yes`
+fn check_rm_threshold(matches: usize, threshold: usize) -> Result<()> {
+    if matches > threshold {
+        return Err(anyhow::anyhow!(
+            "{} objects match, more than rm_confirm_threshold ({}); re-run with --yes to delete them",
+            matches,
+            threshold
+        ));
     }
+    Ok(())
+}
+
+/// The `Expires` date R2 should send, `age` after `now`
+fn response_expires_at(
+    now: chrono::DateTime<chrono::Utc>,
+    age: std::time::Duration,
+) -> r2pilot_core::Result<chrono::DateTime<chrono::Utc>> {
+    chrono::TimeDelta::from_std(age)
+        .ok()
+        .and_then(|age| now.checked_add_signed(age))
+        .ok_or_else(|| {
+            r2pilot_core::Error::InvalidInput(format!(
+                "--response-expires {} is too far in the future",
+                r2pilot_core::format_duration(age)
+            ))
+        })
+}
+
+/// Flags for `urls` commands
+#[derive(Debug, Default)]
+pub struct UrlOptions {
+    /// Bucket the URL is for, the default bucket otherwise
+    pub bucket: Option<String>,
+    /// HTTP method (get, put, delete, head)
+    pub method: String,
+    /// Expiration in seconds
+    pub expires: u64,
+    /// Content type (for PUT requests)
+    pub content_type: Option<String>,
+    /// Output format (table, json)
+    pub output: String,
+    /// Also print the headers to send and a ready-to-run curl command
+    pub curl: bool,
+    /// Host the URL points at instead of the R2 endpoint (e.g. a CDN domain)
+    pub custom_host: Option<String>,
+    /// Cache-Control R2 sets on the response to a GET
+    pub response_cache_control: Option<String>,
+    /// Expires R2 sets on the response to a GET, relative to now
+    pub response_expires: Option<std::time::Duration>,
 }
 
 /// Handle URLs commands
 pub async fn handle_urls(
+    ctx: &Context,
     action: &str,
     key: Option<&str>,
-    method: &str,
-    expires: u64,
-    content_type: Option<&str>,
-    output: &str,
+    options: UrlOptions,
 ) -> Result<()> {
-    if action != "generate" {
-        println!("Unknown action: {}", action);
-        println!("Available actions: generate");
-        return Ok(());
+    let method = options.method.as_str();
+    let expires = options.expires;
+    let content_type = options.content_type.as_deref();
+    let output = options.output.as_str();
+
+    match action {
+        "generate" => {}
+        "inspect" => {
+            let url = key.ok_or_else(|| anyhow::anyhow!("URL required"))?;
+            return inspect_url(url, output);
+        }
+        _ => {
+            println!("Unknown action: {}", action);
+            println!("Available actions: generate, inspect");
+            return Ok(());
+        }
     }
 
     let key = key.ok_or_else(|| anyhow::anyhow!("R2 key required"))?;
-    let config = load_config()?;
+    let config = ctx.config()?;
 
     // Parse method
     let presigned_method = match method.to_lowercase().as_str() {
         "get" => PresignedMethod::Get,
         "put" => PresignedMethod::Put,
         "delete" => PresignedMethod::Delete,
+        "head" => PresignedMethod::Head,
         _ => {
             return Err(anyhow::anyhow!(
-                "Invalid method: {}. Valid methods: get, put, delete",
+                "Invalid method: {}. Valid methods: get, put, delete, head",
                 method
             ))
         }
     };
 
-    println!(
-        "Generating signed URL for {} (method: {}, expires: {}s)...",
-        key, presigned_method, expires
-    );
-
-    // Build presigned URL config
-    let mut presigned_config = PresignedUrlConfig::new(
+    status!(
+        "Generating signed URL for {} (method: {}, expires: {})...",
+        key,
         presigned_method,
-        key.to_string(),
-        std::time::Duration::from_secs(expires),
+        format_expiry(expires)
     );
 
-    // Set content type if provided (for PUT requests)
-    if let Some(ct) = content_type {
-        presigned_config = presigned_config.with_content_type(ct.to_string());
+    let expires_in = std::time::Duration::from_secs(expires);
+
+    let response_expires_at = options
+        .response_expires
+        .map(|age| response_expires_at(chrono::Utc::now(), age))
+        .transpose()?;
+    let response = ResponseHeaders {
+        cache_control: options.response_cache_control.clone(),
+        expires: response_expires_at,
+    };
+    // SigV4 needs the S3 access keys, an API token cannot sign
+    if !ctx.has_access_keys()? {
+        return Err(r2pilot_core::Error::Config(
+            "Signed URLs need R2 access keys (access_key_id and secret_access_key), an API token cannot sign them (r2pilot config init)"
+                .to_string(),
+        )
+        .into());
+    }
+    if response != ResponseHeaders::default() && presigned_method != PresignedMethod::Get {
+        return Err(anyhow::anyhow!(
+            "--response-cache-control and --response-expires only apply to GET URLs"
+        ));
     }
+    let bucket = ctx.resolve_bucket(options.bucket.as_deref()).await?;
+
+    let url = match presigned_method {
+        // GET and PUT are signed by the SDK presigner of a bucket-scoped client
+        PresignedMethod::Get | PresignedMethod::Put => {
+            let client = ctx.r2_client(&bucket).await?;
+            let url = match presigned_method {
+                PresignedMethod::Put => client.presign_put(key, expires_in, content_type).await?,
+                _ => client.presign_get(key, expires_in, &response).await?,
+            };
+            // Signed for the endpoint host, only the URL's host changes
+            match &options.custom_host {
+                Some(host) => rewrite_presigned_host(&url, host)?,
+                None => url,
+            }
+        }
+        // R2Client only presigns GET and PUT, the core library signs the rest
+        PresignedMethod::Delete | PresignedMethod::Head => {
+            let mut presigned_config =
+                PresignedUrlConfig::new(presigned_method, key.to_string(), expires_in);
+            if let Some(host) = &options.custom_host {
+                presigned_config = presigned_config.with_custom_host(host.clone());
+            }
 
-    // Generate presigned URL using the presigned module
-    let url = generate_presigned_url(
-        &config.cloudflare.endpoint,
-        &config.r2.default_bucket,
-        key,
-        presigned_config,
-    )?;
+            // Full SigV4 signature from the configured access keys
+            generate_presigned_url_signed(
+                &config.cloudflare,
+                &config.r2.region,
+                &bucket,
+                key,
+                presigned_config,
+            )?
+        }
+    };
+
+    // A signed Content-Type must be sent exactly as given
+    let headers: Vec<(&str, &str)> = match (presigned_method, content_type) {
+        (PresignedMethod::Put, Some(ct)) => vec![("Content-Type", ct)],
+        _ => Vec::new(),
+    };
+    let curl = options
+        .curl
+        .then(|| curl_command(presigned_method, &url, key, &headers));
 
     match output {
         "json" => {
+            let mut json = serde_json::json!({
+                "key": key,
+                "url": url,
+                "expires_in": expires,
+                "expires_at": chrono::Utc::now() + chrono::Duration::seconds(expires as i64)
+            });
+            if let Some(cache_control) = &response.cache_control {
+                json["response_cache_control"] = cache_control.as_str().into();
+            }
+            if let Some(at) = response_expires_at {
+                json["response_expires"] = serde_json::json!(at);
+            }
+            if let Some(curl) = &curl {
+                json["method"] = presigned_method.as_str().into();
+                json["headers"] = headers
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), serde_json::Value::from(*value)))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into();
+                json["curl"] = curl.as_str().into();
+            }
+            status!();
+            println!("{}", json);
+        }
+        _ if is_quiet() => {
+            println!("{}", url);
+            if let Some(curl) = &curl {
+                println!("{}", curl);
+            }
+        }
+        _ => {
+            println!();
+            println!("  ✅ URL generated:");
+            println!("  {}", url);
             println!();
+            println!("  Expires in: {}", format_expiry(expires));
+            if let Some(cache_control) = &response.cache_control {
+                println!("  Response Cache-Control: {}", cache_control);
+            }
+            if let Some(at) = response_expires_at {
+                println!("  Response Expires: {}", format_datetime(at));
+            }
+
+            if let Some(curl) = &curl {
+                println!();
+                if headers.is_empty() {
+                    println!("  Required headers: none");
+                } else {
+                    println!("  Required headers:");
+                    for (name, value) in &headers {
+                        println!("    {}: {}", name, value);
+                    }
+                }
+                println!();
+                println!("  Test it with:");
+                println!("  {}", curl);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A curl command that sends `method` to a presigned URL
+///
+/// GET saves and PUT uploads a local file named after the last non-empty key
+/// segment, or `download` for a key made only of slashes.
+fn curl_command(method: PresignedMethod, url: &str, key: &str, headers: &[(&str, &str)]) -> String {
+    let name = key
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download");
+    let file = shell_quote(name);
+    let mut args = vec!["curl".to_string()];
+
+    match method {
+        PresignedMethod::Get => args.push(format!("-o {}", file)),
+        PresignedMethod::Put => args.push("-X PUT".to_string()),
+        PresignedMethod::Delete => args.push("-X DELETE".to_string()),
+        PresignedMethod::Head => args.push("-I".to_string()),
+    }
+    for (name, value) in headers {
+        args.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", name, value))
+        ));
+    }
+    if method == PresignedMethod::Put {
+        args.push(format!("--upload-file {}", file));
+    }
+    args.push(shell_quote(url));
+
+    args.join(" ")
+}
+
+/// Quote a string for a POSIX shell
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Decode a presigned URL and report its details
+fn inspect_url(url: &str, output: &str) -> Result<()> {
+    let info = parse_presigned_url(url)?;
+    let expired = info.is_expired();
+    let method = info
+        .method
+        .map(|m| m.to_string())
+        .unwrap_or_else(|| "unknown (bound by the signature)".to_string());
+
+    match output {
+        "json" => {
             println!(
                 "{}",
                 serde_json::json!({
-                    "key": key,
-                    "url": url,
-                    "expires_in": expires,
-                    "expires_at": chrono::Utc::now() + chrono::Duration::seconds(expires as i64)
+                    "method": info.method.map(|m| m.to_string()),
+                    "bucket": info.bucket,
+                    "key": info.key,
+                    "signed_at": info.signed_at,
+                    "expires_at": info.expires_at,
+                    "expired": expired,
                 })
             );
         }
         _ => {
-            println!();
-            println!("  ✅ URL generated:");
-            println!("  {}", url);
-            println!();
-            println!("  Expires in: {}s", expires);
+            println!("Presigned URL:");
+            println!("  Method: {}", method);
+            println!("  Bucket: {}", info.bucket);
+            println!("  Key: {}", info.key);
+            if let Some(signed_at) = info.signed_at {
+                println!("  Signed: {}", signed_at.format("%Y-%m-%d %H:%M:%S UTC"));
+            }
+            println!(
+                "  Expires: {}",
+                info.expires_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+
+            if expired {
+                println!("  ❌ Expired");
+            } else {
+                let remaining = info.expires_at - chrono::Utc::now();
+                println!("  ✅ Valid for {}s", remaining.num_seconds());
+            }
         }
     }
 
@@ -653,7 +2731,7 @@ pub async fn handle_urls(
 }
 
 /// Handle doctor commands
-pub async fn handle_doctor(action: &str) -> Result<()> {
+pub async fn handle_doctor(ctx: &Context, action: &str) -> Result<()> {
     match action {
         "check" => {
             println!("Checking r2pilot installation...");
@@ -666,57 +2744,647 @@ pub async fn handle_doctor(action: &str) -> Result<()> {
             if config_path.exists() {
                 println!("  ✅ Configuration found");
 
-                let config = load_config()?;
-                validate_config(&config)?;
+                let config = ctx.config()?;
+                validate_config(config)?;
                 println!("  ✅ Configuration valid");
+                print_config_warnings(config);
+
+                if config.cloudflare.api_token.is_some() {
+                    check_token_permissions(ctx.cloudflare()?).await;
+                }
             } else {
                 println!("  ⚠️  Configuration not found (run 'r2pilot init')");
             }
 
-            Ok(())
+            Ok(())
+        }
+        "fix" => fix_config(),
+        "test-connection" => {
+            status!("Testing R2 connection...");
+
+            let config = ctx.config()?;
+
+            status!("  Testing R2 connection...");
+            let r2_client = ctx.r2_client(&config.r2.default_bucket).await?;
+
+            let _objects = r2_client.list_objects(None).await?;
+            status!("  ✅ R2 connection OK");
+
+            status!();
+            status!("  ✅ All connections are working!");
+
+            Ok(())
+        }
+        _ => {
+            println!("Unknown action: {}", action);
+            println!("Available actions: check, fix, test-connection");
+            Ok(())
+        }
+    }
+}
+
+/// Report the API token's permission groups and the commands they don't allow
+///
+/// Problems are warnings rather than errors, so `doctor check` always
+/// finishes; the point is to explain a 403 before the user runs into it.
+async fn check_token_permissions(cf_client: &r2pilot_core::CloudflareClient) {
+    let verification = match cf_client.verify_token().await {
+        Ok(verification) => verification,
+        Err(e) => {
+            println!("  ❌ API token check failed: {}", e);
+            return;
+        }
+    };
+    if !verification.is_active() {
+        println!(
+            "  ❌ API token is not active (status: {})",
+            verification.status
+        );
+        return;
+    }
+    println!("  ✅ API token active");
+
+    // The verify endpoint doesn't return permissions
+    let token = match cf_client.get_token(&verification.id).await {
+        Ok(token) => token,
+        Err(_) => {
+            println!("  ⚠️  The API token's permissions can't be read");
+            println!("     (it needs the 'API Tokens Read' permission to inspect itself)");
+            return;
+        }
+    };
+
+    let groups = token.permission_group_names();
+    if groups.is_empty() {
+        println!("  Permission groups: none");
+    } else {
+        println!("  Permission groups: {}", groups.join(", "));
+    }
+
+    match token.r2_access() {
+        Some(r2pilot_core::TokenPermission::Edit) => {
+            println!("  ✅ R2 edit permission: bucket and settings commands are allowed")
+        }
+        Some(r2pilot_core::TokenPermission::Read) => {
+            println!("  ⚠️  R2 read-only: buckets create/delete and cors, lifecycle and website changes need R2 edit permission (Workers R2 Storage Write)");
+        }
+        None => {
+            println!("  ⚠️  No R2 permission: buckets, cors, lifecycle, website and audit will be refused (403)");
+        }
+    }
+    if !groups.contains(&"API Tokens Write") {
+        println!("  ⚠️  tokens create and tokens revoke need the 'API Tokens Write' permission");
+    }
+}
+
+/// Detect common configuration problems and repair them, asking before each fix
+fn fix_config() -> Result<()> {
+    println!("Looking for configuration issues...");
+
+    let config_path = get_config_path()?;
+    if !config_path.exists() {
+        println!("  ⚠️  Configuration not found (run 'r2pilot init')");
+        return Ok(());
+    }
+
+    // Fixes edit the file as stored, without command-line overrides
+    let mut issues = 0;
+    let mut fixed = 0;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&config_path)?.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            issues += 1;
+            println!(
+                "  ⚠️  {} is readable by other users (mode {:o}, expected 600)",
+                config_path.display(),
+                mode
+            );
+            if confirm("Restrict it to your user (chmod 600)?")? {
+                r2pilot_core::restrict_permissions(&config_path)?;
+                println!("  ✅ Permissions set to 600");
+                fixed += 1;
+            }
+        }
+    }
+
+    let content = std::fs::read_to_string(&config_path)?;
+    let missing = r2pilot_core::missing_config_defaults(&content)?;
+    if !missing.is_empty() {
+        issues += 1;
+        println!("  ⚠️  Missing [r2] settings: {}", missing.join(", "));
+        if confirm("Write the default values? (comments in the file are not kept)")? {
+            r2pilot_core::save_config(&r2pilot_core::load_config()?)?;
+            println!("  ✅ Defaults written");
+            fixed += 1;
+            revalidate_config();
+        }
+    }
+
+    let mut stored = r2pilot_core::load_config()?;
+    if let Some(endpoint) = r2pilot_core::expected_endpoint(&stored) {
+        issues += 1;
+        println!(
+            "  ⚠️  Endpoint {} does not match account ID {}",
+            stored.cloudflare.endpoint, stored.cloudflare.account_id
+        );
+        if confirm(&format!("Use {} instead?", endpoint))? {
+            stored.cloudflare.endpoint = endpoint;
+            r2pilot_core::save_config(&stored)?;
+            println!("  ✅ Endpoint updated");
+            fixed += 1;
+            revalidate_config();
+        }
+    }
+
+    let inline_secrets =
+        r2pilot_core::Credentials::inline(&std::fs::read_to_string(&config_path)?)?;
+    let git_root = config_path.parent().and_then(r2pilot_core::find_git_root);
+    if let (Some(git_root), false) = (git_root, inline_secrets.is_empty()) {
+        issues += 1;
+        println!(
+            "  ⚠️  {} holds secrets and is inside the git repository {}",
+            config_path.display(),
+            git_root.display()
+        );
+        let credentials_path = r2pilot_core::get_credentials_path()?;
+        if confirm(&format!(
+            "Move them to {} and ignore that file in git?",
+            credentials_path.display()
+        ))? {
+            move_secrets_to_credentials(inline_secrets, &git_root, &credentials_path)?;
+            println!("  ✅ Secrets moved to {}", credentials_path.display());
+            println!("  ⚠️  If config.toml was already committed, rotate these credentials: they remain in the history");
+            fixed += 1;
+            revalidate_config();
+        }
+    }
+
+    println!();
+    if issues == 0 {
+        println!("  ✅ No issues found");
+    } else {
+        println!("  {} issue(s) found, {} fixed", issues, fixed);
+    }
+
+    Ok(())
+}
+
+/// Move secrets from config.toml to credentials.toml, ignored by git
+fn move_secrets_to_credentials(
+    secrets: r2pilot_core::Credentials,
+    git_root: &Path,
+    credentials_path: &Path,
+) -> Result<()> {
+    use std::io::Write;
+
+    // Secrets already in credentials.toml stay unless config.toml overrode them
+    let mut credentials = r2pilot_core::load_credentials()?.unwrap_or_default();
+    for (field, value) in [
+        (&mut credentials.api_token, secrets.api_token),
+        (&mut credentials.access_key_id, secrets.access_key_id),
+        (
+            &mut credentials.secret_access_key,
+            secrets.secret_access_key,
+        ),
+    ] {
+        if value.is_some() {
+            *field = value;
+        }
+    }
+    r2pilot_core::save_credentials(&credentials)?;
+
+    // Saving now leaves out everything credentials.toml holds
+    r2pilot_core::save_config(&r2pilot_core::load_config()?)?;
+
+    let entry = credentials_path
+        .strip_prefix(git_root)
+        .unwrap_or(credentials_path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let gitignore = git_root.join(".gitignore");
+    let ignored = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if !ignored.lines().any(|line| line.trim() == entry) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&gitignore)?;
+        if !ignored.is_empty() && !ignored.ends_with('\n') {
+            writeln!(file)?;
+        }
+        writeln!(file, "{}", entry)?;
+        println!("  ✅ Added {} to {}", entry, gitignore.display());
+    }
+
+    Ok(())
+}
+
+/// Re-check the stored configuration after a fix
+fn revalidate_config() {
+    match r2pilot_core::load_config().map(|config| validate_config(&config).map(|()| config)) {
+        Ok(Ok(config)) => {
+            println!("  ✅ Configuration valid");
+            print_config_warnings(&config);
+        }
+        Ok(Err(e)) | Err(e) => println!("  ❌ Configuration still invalid: {}", e),
+    }
+}
+
+/// Upload every file matching a local glob under the `dest` prefix
+async fn upload_glob(
+    r2_client: &dyn R2Operations,
+    config: &ConfigFile,
+    pattern: &str,
+    dest: &str,
+    options: &FileOptions,
+) -> Result<()> {
+    if options.compress.is_some()
+        || options.multipart
+        || options.if_match.is_some()
+        || options.if_none_match.is_some()
+        || options.plan_only
+    {
+        return Err(anyhow::anyhow!(
+            "--compress, --multipart, --if-match, --if-none-match and --plan-only are not supported with glob uploads"
+        ));
+    }
+
+    let mut files = r2pilot_core::expand_local_glob(pattern, options.follow_symlinks)?;
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No files match {}", pattern));
+    }
+
+    if options.no_clobber {
+        let existing: std::collections::HashSet<String> = r2_client
+            .list_objects((!dest.is_empty()).then_some(dest))
+            .await?
+            .into_iter()
+            .map(|o| o.key)
+            .collect();
+        files.retain(|f| {
+            let key = r2pilot_core::sync_key(dest, &f.relative_path);
+            let exists = existing.contains(&key);
+            if exists {
+                status!("  ⏭️  {} already exists, skipping", key);
+            }
+            !exists
+        });
+        if files.is_empty() {
+            status!("  Nothing to upload");
+            return Ok(());
+        }
+    }
+
+    // Completed keys are recorded as they finish so a failed run can be resumed
+    let source = std::env::current_dir()?.join(pattern);
+    let manifest_path =
+        r2pilot_core::manifest_path(r2_client.bucket(), &source.to_string_lossy(), dest)?;
+    let mut resumed = 0;
+    let mut manifest = if options.resume {
+        let manifest = r2pilot_core::UploadManifest::open(manifest_path)?;
+        let (pending, done) =
+            r2pilot_core::partition_resumable(r2_client, files, dest, &manifest).await?;
+        resumed = done.len();
+        files = pending;
+        if manifest.is_empty() {
+            status!("  No previous upload to resume, starting fresh");
+        } else {
+            status!("  Resuming: {} file(s) already uploaded", resumed);
+        }
+        manifest
+    } else {
+        r2pilot_core::UploadManifest::create(manifest_path)?
+    };
+
+    let total_size: u64 = files.iter().map(|f| f.size).sum();
+    status!(
+        "Uploading {} file(s) matching {} -> {} ({})...",
+        files.len(),
+        pattern,
+        dest,
+        format_bytes(total_size as i64)
+    );
+
+    let upload_options = options.with_headers(UploadOptions {
+        content_disposition: options.effective_content_disposition(config),
+        deploy_rules: deploy_rules(config)?,
+        ..Default::default()
+    })?;
+    let concurrency = options.effective_concurrency(config);
+    let cancel = cancel_on_ctrl_c();
+    let mut outcomes = r2pilot_core::upload_local_files_resumable(
+        r2_client,
+        files,
+        dest,
+        &upload_options,
+        concurrency,
+        &cancel,
+        &mut manifest,
+    )
+    .await;
+    outcomes.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let mut failed = 0;
+    for outcome in &outcomes {
+        match &outcome.result {
+            Ok(()) => status!("  ✅ {} -> {}", outcome.path.display(), outcome.key),
+            Err(e) => {
+                failed += 1;
+                problem!("  ❌ {}: {}", outcome.key, e);
+            }
+        }
+    }
+
+    status!();
+    if options.resume {
+        status!(
+            "  Uploaded: {}, resumed: {}, failed: {}",
+            outcomes.len() - failed,
+            resumed,
+            failed
+        );
+    } else {
+        status!(
+            "  Uploaded: {}, failed: {}",
+            outcomes.len() - failed,
+            failed
+        );
+    }
+
+    if cancel.is_cancelled() || failed > 0 {
+        status!(
+            "  Re-run with --resume to skip the {} file(s) already uploaded",
+            manifest.len()
+        );
+    }
+    if cancel.is_cancelled() {
+        return Err(r2pilot_core::Error::Cancelled.into());
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} file(s) failed to upload", failed));
+    }
+
+    manifest.remove()?;
+    Ok(())
+}
+
+/// Print how a multipart upload will split the file
+fn print_multipart_plan(plan: &r2pilot_core::MultipartPlan) {
+    println!("  Using multipart upload:");
+    println!("    Part size:   {}", format_bytes(plan.chunk_size as i64));
+    println!("    Parts:       {}", plan.total_parts);
+    println!("    Concurrency: {}", plan.concurrency);
+}
+
+/// Delete the keys listed in `keys_file`, reporting each one
+async fn delete_listed_keys(
+    r2_client: &dyn R2Operations,
+    keys_file: &str,
+    yes: bool,
+) -> Result<()> {
+    let content = std::fs::read_to_string(keys_file)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", keys_file, e))?;
+    let keys = r2pilot_core::parse_key_list(&content);
+
+    status!("Found {} key(s) in {}", keys.len(), keys_file);
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    if !yes && !confirm(&format!("Delete {} object(s)?", keys.len()))? {
+        status!("❌ Delete cancelled");
+        return Ok(());
+    }
+
+    let outcomes = r2_client.try_delete_objects(keys).await?;
+
+    let mut failed = 0;
+    for outcome in &outcomes {
+        match &outcome.result {
+            Ok(()) => status!("  ✅ {}", outcome.key),
+            Err(e) => {
+                failed += 1;
+                problem!("  ❌ {}: {}", outcome.key, e);
+            }
+        }
+    }
+
+    status!();
+    status!("  Deleted: {}/{}", outcomes.len() - failed, outcomes.len());
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} key(s) could not be deleted", failed));
+    }
+
+    Ok(())
+}
+
+/// A listing row shared by the JSON and CSV outputs
+trait ListingRow: serde::Serialize {
+    /// CSV columns, named after the serialized fields
+    const COLUMNS: &'static [&'static str];
+}
+
+/// Object row for `files ls`
+///
+/// Common prefixes ("folders") of a non-recursive listing use the same row,
+/// with `type` set to `prefix`.
+#[derive(serde::Serialize)]
+struct ObjectView {
+    key: String,
+    size: i64,
+    last_modified: Option<String>,
+    etag: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Only filled in by `--with-content-type`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+}
+
+impl ListingRow for ObjectView {
+    const COLUMNS: &'static [&'static str] = &[
+        "key",
+        "size",
+        "last_modified",
+        "etag",
+        "type",
+        "content_type",
+    ];
+}
+
+impl ObjectView {
+    fn prefix(prefix: &str) -> Self {
+        Self {
+            key: prefix.to_string(),
+            size: 0,
+            last_modified: None,
+            etag: String::new(),
+            kind: "prefix",
+            content_type: None,
+        }
+    }
+}
+
+impl From<&r2pilot_core::ObjectInfo> for ObjectView {
+    fn from(object: &r2pilot_core::ObjectInfo) -> Self {
+        Self {
+            key: object.key.clone(),
+            size: object.size,
+            last_modified: object
+                .last_modified
+                .and_then(|dt| chrono::DateTime::from_timestamp(dt.secs(), dt.subsec_nanos()))
+                .map(|dt| dt.to_rfc3339()),
+            etag: object.etag.trim_matches('"').to_string(),
+            kind: "object",
+            content_type: None,
+        }
+    }
+}
+
+/// Bucket row for `buckets list`
+#[derive(serde::Serialize)]
+struct BucketView {
+    name: String,
+    location: String,
+    creation_date: String,
+    description: Option<String>,
+}
+
+impl ListingRow for BucketView {
+    const COLUMNS: &'static [&'static str] = &["name", "location", "creation_date", "description"];
+}
+
+/// Token row for `tokens list`
+#[derive(serde::Serialize)]
+struct TokenView {
+    id: String,
+    name: String,
+    status: String,
+    issued_on: String,
+    expires_on: Option<String>,
+}
+
+impl ListingRow for TokenView {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "status", "issued_on", "expires_on"];
+}
+
+impl From<&r2pilot_core::ApiToken> for TokenView {
+    fn from(token: &r2pilot_core::ApiToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            status: token.status.clone(),
+            issued_on: token.issued_on.clone(),
+            expires_on: token.expires_on.clone(),
         }
-        "test-connection" => {
-            println!("Testing R2 connection...");
+    }
+}
 
-            let config = load_config()?;
+/// Whether a listing should print JSON or CSV instead of a table
+fn is_machine_output(output: &str) -> bool {
+    matches!(output, "json" | "csv")
+}
 
-            // Get R2 credentials
-            let (access_key_id, secret_access_key) =
-                if let Some(_token) = &config.cloudflare.api_token {
-                    return Err(anyhow::anyhow!(
-                        "API Token not supported yet.\n\
-                     Use Access Keys in your configuration."
-                    ));
-                } else {
-                    println!("  Using configured Access Keys");
-                    (
-                        config.cloudflare.access_key_id.clone().unwrap(),
-                        config.cloudflare.secret_access_key.clone().unwrap(),
-                    )
-                };
+/// Print listing rows as pretty JSON or CSV
+fn print_listing<T: ListingRow>(rows: &[T], output: &str) -> Result<()> {
+    if output == "csv" {
+        print!("{}", render_csv(rows)?);
+    } else {
+        println!("{}", serde_json::to_string_pretty(rows)?);
+    }
+    Ok(())
+}
 
-            println!("  Testing R2 connection...");
-            let r2_client = R2Client::new(
-                config.cloudflare.endpoint.clone(),
-                access_key_id,
-                secret_access_key,
-                config.r2.default_bucket.clone(),
-            )
-            .await?;
+/// Render rows as RFC 4180 CSV, with a header row
+///
+/// Missing values are left empty; fields containing commas, quotes or line
+/// breaks are quoted.
+fn render_csv<T: ListingRow>(rows: &[T]) -> Result<String> {
+    let mut csv = String::new();
+    push_csv_record(&mut csv, T::COLUMNS.iter().map(|c| c.to_string()));
+
+    for row in rows {
+        let value = serde_json::to_value(row)?;
+        push_csv_record(
+            &mut csv,
+            T::COLUMNS.iter().map(|column| match &value[*column] {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }),
+        );
+    }
 
-            let _objects = r2_client.list_objects(None).await?;
-            println!("  ✅ R2 connection OK");
+    Ok(csv)
+}
 
-            println!();
-            println!("  ✅ All connections are working!");
+fn push_csv_record(csv: &mut String, fields: impl Iterator<Item = String>) {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
 
-            Ok(())
-        }
-        _ => {
-            println!("Unknown action: {}", action);
-            println!("Available actions: check, test-connection");
-            Ok(())
+/// Print the request durations recorded for `--timings` to stderr
+///
+/// One line per phase (list, upload, part, complete, ...) with per-request
+/// min/avg/max, and the throughput over the phase's wall time for phases
+/// that moved data: concurrent parts overlap, so it is not bytes / total.
+pub fn print_timings(timings: &r2pilot_core::Timings) {
+    let phases = timings.summary();
+
+    eprintln!();
+    eprintln!(
+        "⏱️  Timings (command: {})",
+        format_elapsed(timings.elapsed())
+    );
+    if phases.is_empty() {
+        eprintln!("  No S3 requests");
+        return;
+    }
+
+    eprintln!(
+        "  {:<10} {:>8} {:>9} {:>9} {:>9} {:>9}",
+        "phase", "requests", "total", "min", "avg", "max"
+    );
+    for phase in &phases {
+        let mut line = format!(
+            "  {:<10} {:>8} {:>9} {:>9} {:>9} {:>9}",
+            phase.phase,
+            phase.requests,
+            format_elapsed(phase.total),
+            format_elapsed(phase.min),
+            format_elapsed(phase.average()),
+            format_elapsed(phase.max)
+        );
+        if let Some(rate) = phase.throughput() {
+            line.push_str(&format!(
+                "  {} in {} ({}/s)",
+                format_bytes(phase.bytes as i64),
+                format_elapsed(phase.wall),
+                format_bytes(rate as i64)
+            ));
         }
+        eprintln!("{}", line);
+    }
+}
+
+/// A duration as milliseconds below a second, seconds above (`850ms`, `2.41s`)
+fn format_elapsed(duration: std::time::Duration) -> String {
+    if duration < std::time::Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
     }
 }
 
@@ -752,53 +3420,53 @@ pub async fn handle_completion(shell: &str, cmd: &mut Command) -> Result<()> {
         }
     };
 
-    println!("Generating completion for {:?}...", clap_shell);
-    println!();
+    status!("Generating completion for {:?}...", clap_shell);
+    status!();
 
     // Generate completion script
     generate(clap_shell, cmd, "r2pilot", &mut io::stdout());
 
-    println!();
-    println!("✅ Completion generated!");
-    println!();
-    println!("Installation instructions:");
+    status!();
+    status!("✅ Completion generated!");
+    status!();
+    status!("Installation instructions:");
 
     match shell {
         "bash" => {
-            println!("  # Add to your ~/.bashrc:");
-            println!("  source <(r2pilot completion bash)");
-            println!();
-            println!("  # Or for permanent installation:");
-            println!(
+            status!("  # Add to your ~/.bashrc:");
+            status!("  source <(r2pilot completion bash)");
+            status!();
+            status!("  # Or for permanent installation:");
+            status!(
                 "  r2pilot completion bash > ~/.local/share/bash-completion/completions/r2pilot"
             );
         }
         "zsh" => {
-            println!("  # Add to your ~/.zshrc:");
-            println!("  source <(r2pilot completion zsh)");
-            println!();
-            println!("  # Or for permanent installation:");
-            println!("  r2pilot completion zsh > ~/.zsh/completion/_r2pilot");
-            println!("  # then add to ~/.zshrc:");
-            println!("  fpath=(~/.zsh/completion $fpath)");
-            println!("  autoload -U compinit && compinit");
+            status!("  # Add to your ~/.zshrc:");
+            status!("  source <(r2pilot completion zsh)");
+            status!();
+            status!("  # Or for permanent installation:");
+            status!("  r2pilot completion zsh > ~/.zsh/completion/_r2pilot");
+            status!("  # then add to ~/.zshrc:");
+            status!("  fpath=(~/.zsh/completion $fpath)");
+            status!("  autoload -U compinit && compinit");
         }
         "fish" => {
-            println!("  # Add to ~/.config/fish/completions/r2pilot.fish:");
-            println!("  r2pilot completion fish > ~/.config/fish/completions/r2pilot.fish");
+            status!("  # Add to ~/.config/fish/completions/r2pilot.fish:");
+            status!("  r2pilot completion fish > ~/.config/fish/completions/r2pilot.fish");
         }
         "elvish" => {
-            println!("  # Add to ~/.elvish/rc.elv:");
-            println!("  r2pilot completion elvish > ~/.elvish/lib/r2pilot.elv");
-            println!("  # then add to rc.elv:");
-            println!("  use ~/.elvish/lib/r2pilot");
+            status!("  # Add to ~/.elvish/rc.elv:");
+            status!("  r2pilot completion elvish > ~/.elvish/lib/r2pilot.elv");
+            status!("  # then add to rc.elv:");
+            status!("  use ~/.elvish/lib/r2pilot");
         }
         "powershell" | "pwsh" => {
-            println!("  # Run in PowerShell:");
-            println!("  r2pilot completion powershell | Out-String | Invoke-Expression");
-            println!();
-            println!("  # Or add to your PowerShell Profile:");
-            println!("  r2pilot completion powershell > $PROFILE");
+            status!("  # Run in PowerShell:");
+            status!("  r2pilot completion powershell | Out-String | Invoke-Expression");
+            status!();
+            status!("  # Or add to your PowerShell Profile:");
+            status!("  r2pilot completion powershell > $PROFILE");
         }
         _ => {}
     }
@@ -808,198 +3476,453 @@ pub async fn handle_completion(shell: &str, cmd: &mut Command) -> Result<()> {
 
 /// Handle CORS commands
 pub async fn handle_cors(
+    ctx: &Context,
     action: &str,
     bucket: Option<&str>,
-    file: Option<&str>,
-    interactive: bool,
+    options: CorsOptions,
 ) -> Result<()> {
     use crate::cors_wizard;
-    use r2pilot_core::CloudflareClient;
 
-    let config = load_config()?;
+    let CorsOptions {
+        file,
+        interactive,
+        rule_id,
+        preflight,
+        preset,
+        origin,
+        all,
+    } = options;
+    let file = file.as_deref();
+    let rule_id = rule_id.as_deref();
+
+    // Needs no configuration
+    if action == "schema" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&r2pilot_core::cors_schema())?
+        );
+        return Ok(());
+    }
+
+    let cf_client = ctx.cloudflare()?;
 
-    // Get API token for Cloudflare API access
-    let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-        anyhow::anyhow!(
-            "API Token required for CORS management.\n\
-         Add 'api_token' to your configuration.\n\
-         Get an API Token from: https://dash.cloudflare.com/profile/api-tokens"
-        )
-    })?;
+    if action == "get" && all {
+        status!("Getting CORS configuration for all buckets...");
+
+        for bucket in cf_client.list_buckets().await? {
+            println!();
+            println!("Bucket '{}':", bucket.name);
+            match cf_client.get_bucket_cors(&bucket.name).await {
+                Ok(cors_config) if !cors_config.rules.is_empty() => print_cors_rules(&cors_config),
+                Ok(_) | Err(r2pilot_core::Error::NotFound(_)) => {
+                    println!("  No CORS configuration")
+                }
+                Err(e) => println!("  ⚠️  Couldn't read CORS configuration: {}", e),
+            }
+        }
+
+        return Ok(());
+    }
 
-    let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
-    let bucket_name = bucket.unwrap_or(&config.r2.default_bucket);
+    let bucket_name = &ctx.resolve_bucket(bucket).await?;
 
     match action {
         "get" => {
-            println!("Getting CORS configuration for '{}'...", bucket_name);
+            status!("Getting CORS configuration for '{}'...", bucket_name);
 
             let cors_config = cf_client.get_bucket_cors(bucket_name).await?;
 
-            println!();
-            println!("CORS Rules:");
-            for (i, rule) in cors_config.rules.iter().enumerate() {
-                println!("  Rule {}:", i + 1);
-                println!("    Allowed Origins: {:?}", rule.allowed_origins);
-                println!("    Allowed Methods: {:?}", rule.allowed_methods);
-                println!("    Allowed Headers: {:?}", rule.allowed_headers);
-                println!("    Max Age: {:?}", rule.max_age_seconds);
-            }
+            status!();
+            print_cors_rules(&cors_config);
 
             Ok(())
         }
         "set" => {
+            if let Some(preset) = preset {
+                let origin = match origin {
+                    Some(origin) => Some(origin),
+                    None if preset.needs_origin() => Some(prompt_cors_origin()?),
+                    None => None,
+                };
+                let cors_config = preset.config(origin.as_deref())?;
+
+                status!("Applying CORS preset to '{}'...", bucket_name);
+
+                cf_client.put_bucket_cors(bucket_name, &cors_config).await?;
+
+                status!("  ✅ CORS configuration set");
+                status!();
+                print_cors_rules(&cors_config);
+
+                return Ok(());
+            }
+
             let cors_config = if interactive {
                 cors_wizard::run_cors_wizard().await?
             } else if let Some(file_path) = file {
                 cors_wizard::load_cors_from_file(file_path).await?
             } else {
                 return Err(anyhow::anyhow!(
-                    "Either --interactive or --file must be specified"
+                    "Either --interactive, --file or --preset must be specified"
                 ));
             };
 
-            println!("Setting CORS configuration for '{}'...", bucket_name);
+            status!("Setting CORS configuration for '{}'...", bucket_name);
 
             cf_client.put_bucket_cors(bucket_name, &cors_config).await?;
 
-            println!("  ✅ CORS configuration set");
+            status!("  ✅ CORS configuration set");
 
             Ok(())
         }
         "delete" => {
-            println!("Deleting CORS configuration for '{}'...", bucket_name);
+            status!("Deleting CORS configuration for '{}'...", bucket_name);
 
             cf_client.delete_bucket_cors(bucket_name).await?;
 
-            println!("  ✅ CORS configuration deleted");
+            status!("  ✅ CORS configuration deleted");
+
+            Ok(())
+        }
+        "add" => {
+            let id = rule_id.ok_or_else(|| anyhow::anyhow!("Rule ID required"))?;
+
+            let mut rule = if interactive {
+                cors_wizard::run_cors_wizard()
+                    .await?
+                    .rules
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("No CORS rule created"))?
+            } else if let Some(file_path) = file {
+                cors_wizard::load_cors_rule_from_file(file_path).await?
+            } else {
+                return Err(anyhow::anyhow!(
+                    "Either --interactive or --file must be specified"
+                ));
+            };
+            rule.id = Some(id.to_string());
+
+            status!("Adding CORS rule '{}' to '{}'...", id, bucket_name);
+
+            cf_client.add_cors_rule(bucket_name, rule).await?;
+
+            status!("  ✅ CORS rule added");
+
+            Ok(())
+        }
+        "rm" => {
+            let id = rule_id.ok_or_else(|| anyhow::anyhow!("Rule ID required"))?;
+
+            status!("Removing CORS rule '{}' from '{}'...", id, bucket_name);
+
+            cf_client.remove_cors_rule(bucket_name, id).await?;
+
+            status!("  ✅ CORS rule removed");
 
             Ok(())
         }
+        "test" => {
+            let preflight =
+                preflight.ok_or_else(|| anyhow::anyhow!("Origin and method required"))?;
+            let headers: Vec<&str> = preflight.headers.iter().map(String::as_str).collect();
+
+            status!("Testing CORS preflight for '{}'...", bucket_name);
+            status!("  Origin:  {}", preflight.origin);
+            status!("  Method:  {}", preflight.method.to_uppercase());
+            if !headers.is_empty() {
+                status!("  Headers: {}", headers.join(", "));
+            }
+
+            let cors_config = cf_client.get_bucket_cors(bucket_name).await?;
+
+            match cors_config.matches(&preflight.origin, &preflight.method, &headers) {
+                Some(rule) => {
+                    let index = cors_config
+                        .rules
+                        .iter()
+                        .position(|r| std::ptr::eq(r, rule))
+                        .unwrap_or_default();
+                    match &rule.id {
+                        Some(id) => println!("  ✅ Allowed by rule {} ({})", index + 1, id),
+                        None => println!("  ✅ Allowed by rule {}", index + 1),
+                    }
+                    Ok(())
+                }
+                None => {
+                    println!("  ❌ Not allowed: no rule matches this origin, method and headers");
+                    Err(anyhow::anyhow!("CORS preflight would be rejected"))
+                }
+            }
+        }
         _ => {
             println!("Unknown action: {}", action);
-            println!("Available actions: get, set, delete");
+            println!("Available actions: get, set, delete, add, rm, schema, test");
             Ok(())
         }
     }
 }
 
+/// Print CORS rules as shown by `cors get`
+fn print_cors_rules(cors_config: &r2pilot_core::BucketCorsConfig) {
+    println!("CORS Rules:");
+    for (i, rule) in cors_config.rules.iter().enumerate() {
+        match &rule.id {
+            Some(id) => println!("  Rule {} ({}):", i + 1, id),
+            None => println!("  Rule {}:", i + 1),
+        }
+        println!("    Allowed Origins: {:?}", rule.allowed_origins);
+        println!("    Allowed Methods: {:?}", rule.allowed_methods);
+        println!("    Allowed Headers: {:?}", rule.allowed_headers);
+        println!("    Max Age: {:?}", rule.max_age_seconds);
+    }
+}
+
+/// Ask for the origin a CORS preset should allow
+fn prompt_cors_origin() -> Result<String> {
+    use dialoguer::{theme::ColorfulTheme, Input};
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "This preset needs an origin; pass --origin <url>"
+        ));
+    }
+
+    Ok(Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Allowed origin (e.g. https://app.example.com)")
+        .interact_text()?)
+}
+
+/// Optional flags for cors commands
+#[derive(Debug, Default)]
+pub struct CorsOptions {
+    /// JSON file with the rules (`set`) or a single rule (`add`)
+    pub file: Option<String>,
+    /// Build the rules with the wizard
+    pub interactive: bool,
+    /// Rule ID for `add` and `rm`
+    pub rule_id: Option<String>,
+    /// Preflight simulated by `test`
+    pub preflight: Option<CorsPreflight>,
+    /// Preset applied by `set`
+    pub preset: Option<r2pilot_core::CorsPreset>,
+    /// Origin for presets restricted to one
+    pub origin: Option<String>,
+    /// Show every bucket (`get`)
+    pub all: bool,
+}
+
+/// Simulated browser preflight for `cors test`
+#[derive(Debug)]
+pub struct CorsPreflight {
+    pub origin: String,
+    pub method: String,
+    pub headers: Vec<String>,
+}
+
 /// Handle Lifecycle commands
 pub async fn handle_lifecycle(
+    ctx: &Context,
     action: &str,
     bucket: Option<&str>,
-    file: Option<&str>,
-    interactive: bool,
+    options: LifecycleOptions,
 ) -> Result<()> {
     use crate::lifecycle_wizard;
-    use r2pilot_core::CloudflareClient;
 
-    let config = load_config()?;
+    let file = options.file.as_deref();
+    let interactive = options.interactive;
 
-    // Get API token for Cloudflare API access
-    let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-        anyhow::anyhow!(
-            "API Token required for Lifecycle management.\n\
-         Add 'api_token' to your configuration.\n\
-         Get an API Token from: https://dash.cloudflare.com/profile/api-tokens"
-        )
-    })?;
+    // Needs no configuration
+    if action == "schema" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&r2pilot_core::lifecycle_schema())?
+        );
+        return Ok(());
+    }
+
+    let cf_client = ctx.cloudflare()?;
+
+    if action == "get" && options.all {
+        status!("Getting Lifecycle rules for all buckets...");
+
+        for bucket in cf_client.list_buckets().await? {
+            println!();
+            println!("Bucket '{}':", bucket.name);
+            match cf_client.get_bucket_lifecycle(&bucket.name).await {
+                Ok(lifecycle_config) if !lifecycle_config.rules.is_empty() => {
+                    print_lifecycle_rules(&lifecycle_config)
+                }
+                Ok(_) | Err(r2pilot_core::Error::NotFound(_)) => println!("  No lifecycle rules"),
+                Err(e) => println!("  ⚠️  Couldn't read lifecycle rules: {}", e),
+            }
+        }
+
+        return Ok(());
+    }
 
-    let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
-    let bucket_name = bucket.unwrap_or(&config.r2.default_bucket);
+    let bucket_name = &ctx.resolve_bucket(bucket).await?;
 
     match action {
         "get" => {
-            println!("Getting Lifecycle rules for '{}'...", bucket_name);
+            status!("Getting Lifecycle rules for '{}'...", bucket_name);
 
             let lifecycle_config = cf_client.get_bucket_lifecycle(bucket_name).await?;
 
-            println!();
-            println!("Lifecycle Rules:");
-            for (i, rule) in lifecycle_config.rules.iter().enumerate() {
-                println!("  Rule {} ({})", i + 1, rule.id);
-                println!("    Status: {}", rule.status);
-                if let Some(prefix) = &rule.filter.prefix {
-                    println!("    Filter Prefix: {}", prefix);
-                }
-                if let Some(expiration) = &rule.expiration {
-                    println!(
-                        "    Expiration: {} days",
-                        expiration.days.as_ref().unwrap_or(&0)
-                    );
-                }
-            }
+            status!();
+            print_lifecycle_rules(&lifecycle_config);
 
             Ok(())
         }
         "set" => {
+            if let Some(preset) = options.preset {
+                let lifecycle_config = preset.config(options.days)?;
+
+                status!("Applying Lifecycle preset to '{}'...", bucket_name);
+
+                cf_client
+                    .put_bucket_lifecycle(bucket_name, &lifecycle_config)
+                    .await?;
+
+                status!("  ✅ Lifecycle rules set");
+                status!("  Note: Rules may take time to apply");
+                status!();
+                print_lifecycle_rules(&lifecycle_config);
+
+                return Ok(());
+            }
+
             let lifecycle_config = if interactive {
                 lifecycle_wizard::run_lifecycle_wizard().await?
             } else if let Some(file_path) = file {
                 lifecycle_wizard::load_lifecycle_from_file(file_path).await?
             } else {
                 return Err(anyhow::anyhow!(
-                    "Either --interactive or --file must be specified"
+                    "Either --interactive, --file or --preset must be specified"
                 ));
             };
 
-            println!("Setting Lifecycle rules for '{}'...", bucket_name);
+            status!("Setting Lifecycle rules for '{}'...", bucket_name);
 
             cf_client
                 .put_bucket_lifecycle(bucket_name, &lifecycle_config)
                 .await?;
 
-            println!("  ✅ Lifecycle rules set");
-            println!("  Note: Rules may take time to apply");
+            status!("  ✅ Lifecycle rules set");
+            status!("  Note: Rules may take time to apply");
 
             Ok(())
         }
         "delete" => {
-            println!("Deleting Lifecycle rules for '{}'...", bucket_name);
+            status!("Deleting Lifecycle rules for '{}'...", bucket_name);
 
             cf_client.delete_bucket_lifecycle(bucket_name).await?;
 
-            println!("  ✅ Lifecycle rules deleted");
+            status!("  ✅ Lifecycle rules deleted");
 
             Ok(())
         }
         _ => {
             println!("Unknown action: {}", action);
-            println!("Available actions: get, set, delete");
+            println!("Available actions: get, set, delete, schema");
             Ok(())
         }
     }
 }
 
+/// Print lifecycle rules as shown by `lifecycle get`
+fn print_lifecycle_rules(lifecycle_config: &r2pilot_core::LifecycleConfiguration) {
+    println!("Lifecycle Rules:");
+    for (i, rule) in lifecycle_config.rules.iter().enumerate() {
+        println!("  Rule {} ({})", i + 1, rule.id);
+        println!("    Status: {}", rule.status);
+        if let Some(prefix) = &rule.filter.prefix {
+            println!("    Filter Prefix: {}", prefix);
+        }
+        if let Some(expiration) = &rule.expiration {
+            if let Some(days) = expiration.days {
+                println!("    Expiration: {} days", days);
+            }
+            if let Some(date) = &expiration.date {
+                println!("    Expiration: on {}", date);
+            }
+        }
+        if let Some(noncurrent) = &rule.noncurrent_version_expiration {
+            println!(
+                "    Noncurrent versions: deleted after {} days",
+                noncurrent.noncurrent_days
+            );
+        }
+        if let Some(abort) = &rule.abort_incomplete_multipart_upload {
+            println!(
+                "    Incomplete uploads: aborted after {} days",
+                abort.days_after_initiation
+            );
+        }
+    }
+}
+
+/// Optional flags for lifecycle commands
+#[derive(Debug, Default)]
+pub struct LifecycleOptions {
+    /// JSON file with the rules
+    pub file: Option<String>,
+    /// Build the rules with the wizard
+    pub interactive: bool,
+    /// Preset applied by `set`
+    pub preset: Option<r2pilot_core::LifecyclePreset>,
+    /// Day count overriding the preset's default
+    pub days: Option<u32>,
+    /// Show every bucket (`get`)
+    pub all: bool,
+}
+
 /// Handle Website commands
 pub async fn handle_website(
+    ctx: &Context,
     action: &str,
     bucket: Option<&str>,
     index: Option<&str>,
     error: Option<&str>,
+    all: bool,
 ) -> Result<()> {
-    use r2pilot_core::{CloudflareClient, ErrorDocument, IndexDocument, WebsiteConfiguration};
+    use r2pilot_core::{ErrorDocument, IndexDocument, WebsiteConfiguration};
 
-    let config = load_config()?;
+    let config = ctx.config()?;
+    let cf_client = ctx.cloudflare()?;
 
-    // Get API token for Cloudflare API access
-    let api_token = config.cloudflare.api_token.clone().ok_or_else(|| {
-        anyhow::anyhow!(
-            "API Token required for Website management.\n\
-         Add 'api_token' to your configuration.\n\
-         Get an API Token from: https://dash.cloudflare.com/profile/api-tokens"
-        )
-    })?;
+    if action == "get" && all {
+        status!("Getting website configuration for all buckets...");
+
+        for bucket in cf_client.list_buckets().await? {
+            println!();
+            println!("Bucket '{}':", bucket.name);
+            match cf_client.get_bucket_website(&bucket.name).await {
+                Ok(website_config)
+                    if website_config.index_document.is_some()
+                        || website_config.error_document.is_some() =>
+                {
+                    print_website_config(&website_config, &bucket.name, config)
+                }
+                Ok(_) | Err(r2pilot_core::Error::NotFound(_)) => {
+                    println!("  Static hosting not enabled")
+                }
+                Err(e) => println!("  ⚠️  Couldn't read website configuration: {}", e),
+            }
+        }
+
+        return Ok(());
+    }
 
-    let cf_client = CloudflareClient::new(api_token, config.cloudflare.account_id.clone());
-    let bucket_name = bucket.unwrap_or(&config.r2.default_bucket);
+    let bucket_name = &ctx.resolve_bucket(bucket).await?;
 
     match action {
         "enable" => {
             let index_suffix = index.unwrap_or("index.html");
             let error_key = error.unwrap_or("404.html");
 
-            println!("Enabling static hosting for '{}'...", bucket_name);
+            status!("Enabling static hosting for '{}'...", bucket_name);
 
             let website_config = WebsiteConfiguration {
                 index_document: Some(IndexDocument {
@@ -1014,43 +3937,32 @@ pub async fn handle_website(
                 .put_bucket_website(bucket_name, &website_config)
                 .await?;
 
-            println!("  ✅ Static hosting enabled");
-            println!();
-            println!("  Your bucket is now publicly accessible at:");
-            println!("  https://{}.{}", bucket_name, config.cloudflare.account_id);
-            println!();
-            println!("  Index document: {}", index_suffix);
-            println!("  Error document: {}", error_key);
+            status!("  ✅ Static hosting enabled");
+            status!();
+            status!("  Your bucket is now publicly accessible at:");
+            status!("  https://{}.{}", bucket_name, config.cloudflare.account_id);
+            status!();
+            status!("  Index document: {}", index_suffix);
+            status!("  Error document: {}", error_key);
 
             Ok(())
         }
         "disable" => {
-            println!("Disabling static hosting for '{}'...", bucket_name);
+            status!("Disabling static hosting for '{}'...", bucket_name);
 
             cf_client.delete_bucket_website(bucket_name).await?;
 
-            println!("  ✅ Static hosting disabled");
+            status!("  ✅ Static hosting disabled");
 
             Ok(())
         }
         "get" => {
-            println!("Getting website configuration for '{}'...", bucket_name);
+            status!("Getting website configuration for '{}'...", bucket_name);
 
             let website_config = cf_client.get_bucket_website(bucket_name).await?;
 
-            println!();
-            println!("Website Configuration:");
-            if let Some(index) = &website_config.index_document {
-                println!("  Index Document: {}", index.suffix);
-            }
-            if let Some(error) = &website_config.error_document {
-                println!("  Error Document: {}", error.key);
-            }
-            println!();
-            println!(
-                "  Public URL: https://{}.{}",
-                bucket_name, config.cloudflare.account_id
-            );
+            status!();
+            print_website_config(&website_config, bucket_name, config);
 
             Ok(())
         }
@@ -1061,3 +3973,440 @@ pub async fn handle_website(
         }
     }
 }
+
+/// Print a website configuration as shown by `website get`
+fn print_website_config(
+    website_config: &r2pilot_core::WebsiteConfiguration,
+    bucket_name: &str,
+    config: &ConfigFile,
+) {
+    println!("Website Configuration:");
+    if let Some(index) = &website_config.index_document {
+        println!("  Index Document: {}", index.suffix);
+    }
+    if let Some(error) = &website_config.error_document {
+        println!("  Error Document: {}", error.key);
+    }
+    println!();
+    println!(
+        "  Public URL: https://{}.{}",
+        bucket_name, config.cloudflare.account_id
+    );
+}
+
+/// Handle the bucket audit
+pub async fn handle_audit(ctx: &Context, concurrency: Option<usize>, output: &str) -> Result<()> {
+    let config = ctx.config()?;
+    let cf_client = ctx.cloudflare()?;
+    let concurrency = concurrency.unwrap_or_else(|| {
+        config
+            .advanced
+            .as_ref()
+            .map(|a| a.max_concurrent_uploads)
+            .unwrap_or(5)
+    });
+
+    let buckets: Vec<String> = cf_client
+        .list_buckets()
+        .await?
+        .into_iter()
+        .map(|b| b.name)
+        .collect();
+    if output != "json" {
+        status!("Auditing {} bucket(s)...", buckets.len());
+    }
+
+    let audits = r2pilot_core::audit_buckets(cf_client, &buckets, concurrency).await;
+
+    if output == "json" {
+        let rows: Vec<serde_json::Value> = audits
+            .iter()
+            .map(|audit| {
+                let mut row = serde_json::to_value(audit).unwrap_or_default();
+                row["public"] = audit.is_public().into();
+                row["findings"] = audit.findings().into();
+                row
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    if audits.is_empty() {
+        status!("  No buckets found");
+        return Ok(());
+    }
+
+    #[derive(Tabled)]
+    struct AuditRow {
+        bucket: String,
+        public: String,
+        website: String,
+        lifecycle: String,
+        cors: String,
+        findings: String,
+    }
+
+    let flag = |value: Option<bool>, yes: &str, no: &str| match value {
+        Some(true) => yes.to_string(),
+        Some(false) => no.to_string(),
+        None => "?".to_string(),
+    };
+    let rows: Vec<AuditRow> = audits
+        .iter()
+        .map(|audit| AuditRow {
+            bucket: audit.bucket.clone(),
+            public: match &audit.public_domains {
+                Some(domains) if domains.is_empty() => "no".to_string(),
+                Some(domains) => domains.join(", "),
+                None => "?".to_string(),
+            },
+            website: flag(audit.website, "enabled", "-"),
+            lifecycle: match audit.lifecycle_rules {
+                Some(0) => "none".to_string(),
+                Some(rules) => format!("{} rule(s)", rules),
+                None => "?".to_string(),
+            },
+            cors: flag(audit.cors_any_origin, "any origin (*)", "restricted"),
+            findings: match audit.findings() {
+                findings if findings.is_empty() => "✅ ok".to_string(),
+                findings => format!("⚠️  {}", findings.join(", ")),
+            },
+        })
+        .collect();
+
+    status!();
+    println!("{}", Table::new(rows));
+
+    let count = |check: fn(&r2pilot_core::BucketAudit) -> bool| {
+        audits.iter().filter(|audit| check(audit)).count()
+    };
+    status!(
+        "  {} publicly exposed, {} with CORS open to any origin, {} without lifecycle rules",
+        count(|a| a.is_public()),
+        count(|a| a.cors_any_origin == Some(true)),
+        count(|a| a.lifecycle_rules == Some(0)),
+    );
+
+    let errors: Vec<String> = audits
+        .iter()
+        .flat_map(|audit| {
+            audit
+                .errors
+                .iter()
+                .map(move |e| format!("{}: {}", audit.bucket, e))
+        })
+        .collect();
+    if !errors.is_empty() {
+        println!();
+        for error in &errors {
+            println!("  ⚠️  {}", error);
+        }
+        println!("  \"?\" marks settings that couldn't be read");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_expires_at() {
+        let now = chrono::Utc::now();
+        let at = response_expires_at(now, std::time::Duration::from_secs(3600)).unwrap();
+        assert_eq!(at - now, chrono::TimeDelta::hours(1));
+
+        // parse_age accepts up to u64 seconds, far beyond what a date can hold
+        let age = r2pilot_core::parse_age("100000000w").unwrap();
+        let error = response_expires_at(now, age).unwrap_err();
+        assert!(matches!(error, r2pilot_core::Error::InvalidInput(_)));
+        assert!(response_expires_at(now, std::time::Duration::MAX).is_err());
+    }
+
+    #[test]
+    fn test_check_rm_threshold() {
+        assert!(check_rm_threshold(0, 100).is_ok());
+        assert!(check_rm_threshold(100, 100).is_ok());
+
+        let error = check_rm_threshold(101, 100).unwrap_err().to_string();
+        assert!(error.contains("101 objects"), "{}", error);
+        assert!(error.contains("--yes"), "{}", error);
+        assert!(check_rm_threshold(1, 0).is_err());
+    }
+
+    #[derive(serde::Serialize)]
+    struct Row {
+        key: String,
+        size: Option<u64>,
+    }
+
+    impl ListingRow for Row {
+        const COLUMNS: &'static [&'static str] = &["key", "size"];
+    }
+
+    fn row(key: &str, size: Option<u64>) -> Row {
+        Row {
+            key: key.to_string(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_render_csv_quoting() {
+        let rows = [
+            row("plain.txt", Some(3)),
+            row("a,b.txt", Some(1)),
+            row("say \"hi\".txt", None),
+            row("two\r\nlines", Some(0)),
+            row("cr\ronly", Some(0)),
+        ];
+
+        assert_eq!(
+            render_csv(&rows).unwrap(),
+            "key,size\r\n\
+             plain.txt,3\r\n\
+             \"a,b.txt\",1\r\n\
+             \"say \"\"hi\"\".txt\",\r\n\
+             \"two\r\nlines\",0\r\n\
+             \"cr\ronly\",0\r\n"
+        );
+        assert_eq!(render_csv::<Row>(&[]).unwrap(), "key,size\r\n");
+    }
+
+    fn mock_config() -> ConfigFile {
+        crate::wizard::build_config(
+            "account".to_string(),
+            "https://account.r2.cloudflarestorage.com".to_string(),
+            None,
+            (Some("token".to_string()), None, None),
+            "mock-bucket".to_string(),
+        )
+    }
+
+    fn yes() -> FileOptions {
+        FileOptions {
+            yes: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_files_upload_download_round_trip() {
+        let mock = r2pilot_core::MockR2::new("mock-bucket");
+        let config = mock_config();
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("report.txt");
+        std::fs::write(&source, b"quarterly numbers").unwrap();
+        let source = source.to_str().unwrap();
+
+        run_files(
+            &mock,
+            &config,
+            "upload",
+            Some(source),
+            Some("docs/report.txt"),
+            None,
+            FileOptions::default(),
+        )
+        .await
+        .unwrap();
+        let object = mock.get("docs/report.txt").unwrap();
+        assert_eq!(object.body, b"quarterly numbers");
+        assert_eq!(object.content_type, "text/plain");
+
+        let dest = dir.path().join("copy.txt");
+        let dest = dest.to_str().unwrap();
+        run_files(
+            &mock,
+            &config,
+            "download",
+            Some(dest),
+            Some("docs/report.txt"),
+            None,
+            FileOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read(dest).unwrap(), b"quarterly numbers");
+    }
+
+    #[tokio::test]
+    async fn test_files_upload_no_clobber_keeps_existing() {
+        let mock = r2pilot_core::MockR2::new("mock-bucket");
+        mock.insert("report.txt", b"remote".to_vec(), "text/plain");
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("report.txt");
+        std::fs::write(&source, b"local").unwrap();
+
+        let options = FileOptions {
+            no_clobber: true,
+            ..Default::default()
+        };
+        run_files(
+            &mock,
+            &mock_config(),
+            "upload",
+            source.to_str(),
+            Some("report.txt"),
+            None,
+            options,
+        )
+        .await
+        .unwrap();
+        assert_eq!(mock.get("report.txt").unwrap().body, b"remote");
+    }
+
+    #[tokio::test]
+    async fn test_files_rm_deletes_matching_keys() {
+        let mock = r2pilot_core::MockR2::new("mock-bucket");
+        for key in ["logs/a.log", "logs/b.log", "logs/keep.txt", "data/c.log"] {
+            mock.insert(key, vec![0; 4], "text/plain");
+        }
+
+        run_files(
+            &mock,
+            &mock_config(),
+            "rm",
+            None,
+            Some("logs/*.log"),
+            None,
+            yes(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(mock.keys(), ["data/c.log", "logs/keep.txt"]);
+
+        // A plain prefix removes everything under it
+        run_files(
+            &mock,
+            &mock_config(),
+            "rm",
+            None,
+            Some("logs/"),
+            None,
+            yes(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(mock.keys(), ["data/c.log"]);
+    }
+
+    #[tokio::test]
+    async fn test_files_rm_over_threshold_deletes_nothing() {
+        let mock = r2pilot_core::MockR2::new("mock-bucket");
+        for i in 0..3 {
+            mock.insert(&format!("tmp/{}", i), vec![0; 4], "text/plain");
+        }
+        let mut config = mock_config();
+        config.advanced = Some(r2pilot_core::config::AdvancedConfig {
+            rm_confirm_threshold: 2,
+            ..Default::default()
+        });
+
+        let error = run_files(
+            &mock,
+            &config,
+            "rm",
+            None,
+            Some("tmp/"),
+            None,
+            FileOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("--yes"), "{}", error);
+        assert_eq!(mock.keys().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_files_delete_keys_file() {
+        let mock = r2pilot_core::MockR2::new("mock-bucket");
+        for key in ["a.txt", "b c.txt", "d.txt"] {
+            mock.insert(key, vec![0; 4], "text/plain");
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let keys_file = dir.path().join("keys.txt");
+        std::fs::write(&keys_file, "a.txt\nb c.txt\n").unwrap();
+
+        let options = FileOptions {
+            keys_file: Some(keys_file.to_str().unwrap().to_string()),
+            yes: true,
+            ..Default::default()
+        };
+        run_files(&mock, &mock_config(), "delete", None, None, None, options)
+            .await
+            .unwrap();
+        assert_eq!(mock.keys(), ["d.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_files_mv_prefix() {
+        let mock = r2pilot_core::MockR2::new("mock-bucket");
+        for key in ["old/a.txt", "old/sub/b.txt", "older/c.txt"] {
+            mock.insert(key, key.as_bytes().to_vec(), "text/plain");
+        }
+
+        run_files(
+            &mock,
+            &mock_config(),
+            "mv-prefix",
+            None,
+            Some("new/"),
+            Some("old/"),
+            yes(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(mock.keys(), ["new/a.txt", "new/sub/b.txt", "older/c.txt"]);
+        assert_eq!(mock.get("new/sub/b.txt").unwrap().body, b"old/sub/b.txt");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain.txt"), "'plain.txt'");
+        assert_eq!(shell_quote("my file.txt"), "'my file.txt'");
+        assert_eq!(shell_quote("$HOME `id`"), "'$HOME `id`'");
+        assert_eq!(shell_quote("it's.txt"), "'it'\\''s.txt'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_curl_command() {
+        let url = "https://example.com/b/k?X-Amz-Signature=abc&X-Amz-Expires=60";
+        assert_eq!(
+            curl_command(PresignedMethod::Get, url, "docs/it's $1.pdf", &[]),
+            format!("curl -o 'it'\\''s $1.pdf' '{}'", url)
+        );
+        assert_eq!(
+            curl_command(
+                PresignedMethod::Put,
+                url,
+                "upload/my file.txt",
+                &[("Content-Type", "text/plain")]
+            ),
+            format!(
+                "curl -X PUT -H 'Content-Type: text/plain' --upload-file 'my file.txt' '{}'",
+                url
+            )
+        );
+        assert_eq!(
+            curl_command(PresignedMethod::Head, url, "a/b", &[]),
+            format!("curl -I '{}'", url)
+        );
+    }
+
+    #[test]
+    fn test_curl_command_folder_key() {
+        let url = "https://example.com/b/k";
+        assert_eq!(
+            curl_command(PresignedMethod::Get, url, "photos/2024/", &[]),
+            format!("curl -o '2024' '{}'", url)
+        );
+        assert_eq!(
+            curl_command(PresignedMethod::Get, url, "/", &[]),
+            format!("curl -o 'download' '{}'", url)
+        );
+    }
+}
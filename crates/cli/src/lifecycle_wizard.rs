@@ -1,8 +1,11 @@
 //! Interactive wizard for Lifecycle rules configuration
 
 use anyhow::Result;
-use dialoguer::{theme::ColorfulTheme, Confirm, Input};
-use r2pilot_core::{LifecycleConfiguration, LifecycleExpiration, LifecycleFilter, LifecycleRule};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use r2pilot_core::{
+    LifecycleConfiguration, LifecycleExpiration, LifecycleFilter, LifecycleRule,
+    NoncurrentVersionExpiration,
+};
 
 /// Run the interactive Lifecycle configuration wizard
 pub async fn run_lifecycle_wizard() -> Result<LifecycleConfiguration> {
@@ -37,21 +40,65 @@ pub async fn run_lifecycle_wizard() -> Result<LifecycleConfiguration> {
             },
         };
 
-        // Expiration by days
+        // Expiration by days or on a date
         let use_expiration = Confirm::with_theme(&theme)
             .with_prompt("Add expiration to this rule?")
             .default(true)
             .interact()?;
 
         let expiration = if use_expiration {
-            let days_input: String = Input::with_theme(&theme)
-                .with_prompt("Delete objects after how many days?")
-                .default("30".to_string())
+            let kinds = vec!["After N days", "On a specific date"];
+            let kind = Select::with_theme(&theme)
+                .with_prompt("When should objects expire?")
+                .items(&kinds)
+                .default(0)
                 .interact()?;
 
-            Some(LifecycleExpiration {
-                days: Some(days_input.parse().unwrap_or(30)),
-            })
+            let expiration = if kind == 0 {
+                let days_input: String = Input::with_theme(&theme)
+                    .with_prompt("Delete objects after how many days?")
+                    .default("30".to_string())
+                    .interact()?;
+
+                LifecycleExpiration::after_days(days_input.parse().unwrap_or(30))
+            } else {
+                let date: String = Input::with_theme(&theme)
+                    .with_prompt("Delete objects on which date? (YYYY-MM-DD)")
+                    .validate_with(|input: &String| -> std::result::Result<(), String> {
+                        LifecycleExpiration::on_date(input.as_str())
+                            .validate()
+                            .map_err(|e| e.to_string())
+                    })
+                    .interact()?;
+
+                LifecycleExpiration::on_date(date)
+            };
+
+            Some(expiration)
+        } else {
+            None
+        };
+
+        // Noncurrent versions (versioned buckets)
+        let expire_noncurrent = Confirm::with_theme(&theme)
+            .with_prompt("Delete noncurrent versions? (versioned buckets)")
+            .default(false)
+            .interact()?;
+
+        let noncurrent_version_expiration = if expire_noncurrent {
+            let noncurrent_days: u32 = Input::with_theme(&theme)
+                .with_prompt("Delete versions how many days after they become noncurrent?")
+                .default(30)
+                .validate_with(|days: &u32| -> std::result::Result<(), String> {
+                    NoncurrentVersionExpiration {
+                        noncurrent_days: *days,
+                    }
+                    .validate()
+                    .map_err(|e| e.to_string())
+                })
+                .interact()?;
+
+            Some(NoncurrentVersionExpiration { noncurrent_days })
         } else {
             None
         };
@@ -71,6 +118,8 @@ pub async fn run_lifecycle_wizard() -> Result<LifecycleConfiguration> {
                 "Disabled".to_string()
             },
             expiration,
+            noncurrent_version_expiration,
+            abort_incomplete_multipart_upload: None,
         };
 
         rules.push(rule);
@@ -108,6 +157,7 @@ pub async fn load_lifecycle_from_file(file_path: &str) -> Result<LifecycleConfig
 
     let config: LifecycleConfiguration = serde_json::from_str(&content)
         .map_err(|e| anyhow::anyhow!("Failed to parse Lifecycle config: {}", e))?;
+    config.validate()?;
 
     Ok(config)
 }
@@ -2,6 +2,7 @@ use anyhow::Result;
 use clap::{CommandFactory, Parser};
 use color_eyre::config::HookBuilder;
 
+mod context;
 mod cors_wizard;
 mod handlers;
 mod lifecycle_wizard;
@@ -14,6 +15,26 @@ mod wizard;
 #[command(version = "0.1.0")]
 #[command(about = "Rust CLI to manage Cloudflare R2 from your terminal", long_about = None)]
 struct Cli {
+    /// Override the configured S3 endpoint (e.g. http://localhost:9000)
+    #[arg(long, global = true, value_name = "URL", env = "R2PILOT_ENDPOINT")]
+    endpoint: Option<String>,
+
+    /// Show timestamps in UTC instead of the local timezone
+    #[arg(long, global = true)]
+    utc: bool,
+
+    /// Cap upload bandwidth, e.g. 5MB/s (overrides advanced.max_upload_rate)
+    #[arg(long, global = true, value_name = "RATE", value_parser = parse_max_rate)]
+    max_rate: Option<String>,
+
+    /// Print only results (URLs, listings, JSON) and errors, for scripts
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Print how long each kind of S3 request took to stderr after the command
+    #[arg(long, global = true)]
+    timings: bool,
+
     /// Subcommand to execute
     #[command(subcommand)]
     command: Commands,
@@ -21,8 +42,30 @@ struct Cli {
 
 #[derive(clap::Subcommand, Debug)]
 enum Commands {
-    /// Initial configuration (interactive wizard)
-    Init,
+    /// Initial configuration (interactive wizard, or flags only for CI)
+    Init {
+        /// Cloudflare account ID
+        #[arg(long)]
+        account_id: Option<String>,
+        /// API token
+        #[arg(long, env = "R2PILOT_API_TOKEN", hide_env_values = true, conflicts_with_all = ["access_key_id", "secret_access_key"])]
+        api_token: Option<String>,
+        /// R2 access key ID (with --secret-access-key)
+        #[arg(long, env = "R2PILOT_ACCESS_KEY_ID", hide_env_values = true)]
+        access_key_id: Option<String>,
+        /// R2 secret access key (with --access-key-id)
+        #[arg(long, env = "R2PILOT_SECRET_ACCESS_KEY", hide_env_values = true)]
+        secret_access_key: Option<String>,
+        /// Jurisdiction (eu, fedramp)
+        #[arg(long, value_parser = parse_jurisdiction)]
+        jurisdiction: Option<String>,
+        /// Default bucket
+        #[arg(long)]
+        bucket: Option<String>,
+        /// Don't ask for confirmation; overwrite an existing configuration
+        #[arg(short, long)]
+        yes: bool,
+    },
 
     /// Configuration management
     Config {
@@ -83,59 +126,217 @@ enum Commands {
         #[command(subcommand)]
         action: WebsiteAction,
     },
+
+    /// Review every bucket's public access, CORS and lifecycle settings
+    Audit {
+        /// Buckets checked at once (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
 enum ConfigAction {
     /// Show current configuration
-    Show,
+    Show {
+        /// Print the full account ID
+        #[arg(long)]
+        reveal: bool,
+        /// Also print secrets in plain text (asks for confirmation)
+        #[arg(long)]
+        reveal_secrets: bool,
+    },
     /// Edit configuration in $EDITOR
     Edit,
     /// Validate credentials
     Validate,
+    /// Print the configuration for backup (secrets left out by default)
+    Export {
+        /// Output format (toml, json)
+        #[arg(short, long, default_value = "toml")]
+        format: String,
+        /// Include the API token and access keys
+        #[arg(long)]
+        with_secrets: bool,
+    },
+    /// Validate an exported configuration and install it
+    Import {
+        /// Exported configuration file
+        file: String,
+        /// File format (toml, json; default: from the extension)
+        #[arg(short, long)]
+        format: Option<String>,
+        /// Replace the current configuration without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
 enum TokenAction {
     /// List API tokens
-    List,
-    /// Create a new token
-    Create,
+    List {
+        /// Output format (table, json, csv)
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json", "csv"])]
+        output: String,
+    },
+    /// Create a new token (interactive unless flags are given)
+    Create {
+        /// Token name
+        #[arg(long)]
+        name: Option<String>,
+        /// Access level (read, edit)
+        #[arg(long)]
+        permission: Option<r2pilot_core::TokenPermission>,
+        /// Restrict use to these IPs/CIDRs (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        ip: Option<Vec<String>>,
+        /// Expire the token after this many days
+        #[arg(long)]
+        expires_days: Option<u32>,
+        /// Print the created token as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Revoke a token
     Revoke { token_id: String },
+    /// Check a token's status, expiry and R2 access (the configured token by default)
+    Verify {
+        /// Token ID to check instead of the configured token
+        token_id: Option<String>,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
 enum BucketAction {
     /// List buckets
-    List,
+    List {
+        /// Output format (table, json, csv)
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json", "csv"])]
+        output: String,
+    },
     /// Create a bucket
-    Create { name: String },
+    Create {
+        name: String,
+        /// Also apply a public-read CORS configuration (GET/HEAD from any origin)
+        #[arg(long)]
+        with_cors: bool,
+        /// Also enable static website hosting (index.html / 404.html)
+        #[arg(long)]
+        with_website: bool,
+        /// Keep the bucket's data in a jurisdiction (eu, fedramp; uses the configured one)
+        #[arg(long, value_parser = parse_jurisdiction)]
+        jurisdiction: Option<String>,
+        /// Succeed without changes if the bucket already exists
+        #[arg(long)]
+        if_not_exists: bool,
+    },
     /// Delete a bucket
     Delete { name: String },
+    /// "Rename" a bucket: create the new one, copy every object, delete the old one
+    Rename {
+        name: String,
+        new_name: String,
+        /// Objects copied at once (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+        /// Delete the old bucket without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
     /// Bucket information
     Info { name: String },
-    /// List bucket contents
-    Ls { name: Option<String> },
+    /// List bucket contents, with the total object count and size
+    Ls {
+        name: Option<String>,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
+    },
+    /// Show or edit a bucket's local description and tags (stored in the config file)
+    Tag {
+        name: String,
+        /// Set the description shown by `buckets list` ("" removes it)
+        #[arg(long)]
+        description: Option<String>,
+        /// Add or overwrite a tag (repeatable)
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = r2pilot_core::parse_tag)]
+        set_tags: Vec<(String, String)>,
+        /// Remove a tag by key (repeatable)
+        #[arg(long = "remove", value_name = "KEY")]
+        remove_tags: Vec<String>,
+        /// Remove the description and all tags
+        #[arg(long)]
+        clear: bool,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
 enum FileAction {
     /// Upload a file
     Upload {
-        /// Local file to upload
+        /// Local file to upload ("-" streams from stdin, a quoted glob uploads every match)
         file: String,
-        /// R2 key (destination)
-        key: String,
+        /// R2 key (destination prefix for a glob)
+        #[arg(
+            required_unless_present = "content_addressed",
+            conflicts_with = "content_addressed"
+        )]
+        key: Option<String>,
+        /// Use the file's SHA-256 as its key, skipping the upload if that key exists
+        #[arg(long)]
+        content_addressed: bool,
+        /// Where content-addressed objects land (key: <prefix>/<sha256>)
+        #[arg(long, value_name = "PREFIX", requires = "content_addressed")]
+        prefix: Option<String>,
         /// Target bucket (uses default bucket)
         #[arg(short, long)]
         bucket: Option<String>,
-        /// Show progress bar
+        /// Show a progress bar (multipart uploads)
         #[arg(short, long)]
         progress: bool,
         /// Force multipart upload
         #[arg(long)]
         multipart: bool,
+        /// Compress before upload and set Content-Encoding (gzip, zstd)
+        #[arg(long, value_name = "ALGO")]
+        compress: Option<String>,
+        /// Skip the upload if the key already exists
+        #[arg(long)]
+        no_clobber: bool,
+        /// Declared stdin size in bytes (sent as Content-Length; unknown sizes use multipart)
+        #[arg(long, value_name = "BYTES")]
+        content_length: Option<u64>,
+        /// Parallel transfers for this run (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+        /// Content-Disposition for uploaded objects (overrides upload.default_content_disposition)
+        #[arg(long, value_name = "VALUE", value_parser = parse_content_disposition)]
+        content_disposition: Option<String>,
+        /// Only overwrite if the object's current ETag matches
+        #[arg(long, value_name = "ETAG", conflicts_with = "if_none_match")]
+        if_match: Option<String>,
+        /// Only upload if no object matches ("*" creates only if absent)
+        #[arg(long, value_name = "ETAG")]
+        if_none_match: Option<String>,
+        /// Skip files a previous interrupted glob upload already completed
+        #[arg(long)]
+        resume: bool,
+        /// Print the multipart plan (part size, part count, concurrency) without uploading
+        #[arg(long)]
+        plan_only: bool,
+        /// Extra header stored with the object (repeatable), e.g. 'Cache-Control: max-age=60' or 'x-amz-meta-owner: ops'
+        #[arg(long = "header", value_name = "NAME: VALUE", value_parser = r2pilot_core::parse_header)]
+        headers: Vec<(String, String)>,
+        /// Follow symlinks (files and directories) instead of skipping them (glob uploads)
+        #[arg(long)]
+        follow_symlinks: bool,
     },
     /// Download a file
     Download {
@@ -146,14 +347,53 @@ enum FileAction {
         /// Source bucket (uses default bucket)
         #[arg(short, long)]
         bucket: Option<String>,
+        /// Skip the download if the local file already exists
+        #[arg(long)]
+        no_clobber: bool,
+        /// Parallel transfers for this run (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+        /// Decode a gzip or zstd Content-Encoding instead of saving the stored bytes
+        #[arg(long)]
+        decompress: bool,
+        /// Serve the file from the local download cache while its ETag is unchanged
+        #[arg(long, conflicts_with = "decompress")]
+        cache: bool,
     },
     /// Delete a file
     Delete {
+        /// R2 key
+        #[arg(required_unless_present = "keys_file", conflicts_with = "keys_file")]
+        key: Option<String>,
+        /// Delete every key listed in a file (one per line, "#" starts a comment)
+        #[arg(long, value_name = "FILE")]
+        keys_file: Option<String>,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Parallel transfers for this run (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+        /// Skip the confirmation (--keys-file)
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show an object's metadata
+    Stat {
         /// R2 key
         key: String,
         /// Target bucket (uses default bucket)
         #[arg(short, long)]
         bucket: Option<String>,
+        /// Poll until the object exists (backs off exponentially)
+        #[arg(long)]
+        wait: bool,
+        /// How long --wait polls before giving up (e.g. 30s, 5m)
+        #[arg(long, value_name = "DURATION", default_value = "30s", value_parser = r2pilot_core::parse_age, requires = "wait")]
+        timeout: std::time::Duration,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
     },
     /// List files
     Ls {
@@ -162,6 +402,195 @@ enum FileAction {
         /// Target bucket (uses default bucket)
         #[arg(short, long)]
         bucket: Option<String>,
+        /// Only objects at least this large (e.g. 500KB, 10MB)
+        #[arg(long, value_name = "SIZE", value_parser = r2pilot_core::parse_size)]
+        min_size: Option<u64>,
+        /// Only objects at most this large (e.g. 500KB, 10MB)
+        #[arg(long, value_name = "SIZE", value_parser = r2pilot_core::parse_size)]
+        max_size: Option<u64>,
+        /// Only objects last modified more than this long ago (e.g. 30d, 12h)
+        #[arg(long, value_name = "AGE", value_parser = r2pilot_core::parse_age)]
+        older_than: Option<std::time::Duration>,
+        /// Only objects last modified less than this long ago (e.g. 30d, 12h)
+        #[arg(long, value_name = "AGE", value_parser = r2pilot_core::parse_age)]
+        newer_than: Option<std::time::Duration>,
+        /// Also show each object's last-modified time
+        #[arg(short, long)]
+        long: bool,
+        /// List every key under the prefix instead of one folder level
+        #[arg(short, long)]
+        recursive: bool,
+        /// Also show each object's Content-Type (one HEAD request per object; implies --long)
+        #[arg(long)]
+        with_content_type: bool,
+        /// Stop listing after the first N keys (folders count as keys); filters apply to those only
+        #[arg(long, value_name = "N", value_parser = parse_max_keys)]
+        max_keys: Option<usize>,
+        /// Start listing after this key: the cursor printed by the previous page
+        #[arg(long, value_name = "KEY")]
+        after: Option<String>,
+        /// Output format (table, json, csv)
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json", "csv"])]
+        output: String,
+    },
+    /// Find objects whose keys match a glob pattern (e.g. "logs/2023/*.gz")
+    Find {
+        /// Glob pattern ("*" stays within a path segment, "**" crosses "/")
+        pattern: String,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
+    },
+    /// Delete every object matching a glob (or everything under a "prefix/")
+    Rm {
+        /// Glob pattern such as 'tmp/**' or 'logs/*.gz' (quote it); "prefix/" means "prefix/**"
+        pattern: String,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Skip the confirmation (required above rm_confirm_threshold matches)
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Copy an object to another key, in the same bucket or another one
+    Copy {
+        /// Source R2 key
+        source: String,
+        /// Destination R2 key
+        dest: String,
+        /// Source bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Destination bucket (defaults to the source bucket)
+        #[arg(long, value_name = "BUCKET")]
+        dst_bucket: Option<String>,
+        /// Content-Type of the copy (other headers and metadata are kept)
+        #[arg(long)]
+        content_type: Option<String>,
+    },
+    /// Move every object under a prefix to a new prefix (folder rename)
+    MvPrefix {
+        /// Source prefix (e.g. old/)
+        from: String,
+        /// Destination prefix (e.g. new/)
+        to: String,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Skip confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Parallel transfers for this run (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+    },
+    /// Show what sync would upload or delete, without changing anything
+    Diff {
+        /// Local directory
+        dir: String,
+        /// Remote prefix (default: bucket root)
+        prefix: Option<String>,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Compare same-size files by MD5 against the remote ETag
+        #[arg(long)]
+        checksum: bool,
+        /// Also list remote objects missing locally
+        #[arg(long)]
+        delete: bool,
+        /// Follow symlinks (files and directories) instead of skipping them
+        #[arg(long)]
+        follow_symlinks: bool,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
+    },
+    /// Upload new and changed files from a local directory
+    Sync {
+        /// Local directory
+        dir: String,
+        /// Destination prefix (default: bucket root)
+        prefix: Option<String>,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Compare same-size files by MD5 against the remote ETag
+        #[arg(long)]
+        checksum: bool,
+        /// Delete remote objects under the prefix that are missing locally
+        #[arg(long)]
+        delete: bool,
+        /// Skip the deletion confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Parallel transfers for this run (overrides max_concurrent_uploads)
+        #[arg(long, value_name = "N", value_parser = parse_concurrency)]
+        concurrency: Option<usize>,
+        /// Content-Disposition for uploaded objects (overrides upload.default_content_disposition)
+        #[arg(long, value_name = "VALUE", value_parser = parse_content_disposition)]
+        content_disposition: Option<String>,
+        /// Correct the Content-Type of unchanged objects in place (no re-upload)
+        #[arg(long)]
+        fix_content_type: bool,
+        /// Follow symlinks (files and directories) instead of skipping them
+        #[arg(long)]
+        follow_symlinks: bool,
+    },
+    /// Move an object to another storage class (copies it onto itself)
+    SetClass {
+        /// R2 key
+        key: String,
+        /// Storage class (STANDARD, STANDARD_IA)
+        class: String,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+    },
+    /// Change an object's headers and metadata (rewrites it in place, no re-upload)
+    SetMeta {
+        /// R2 key
+        key: String,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// New Content-Type
+        #[arg(long)]
+        content_type: Option<String>,
+        /// New Cache-Control
+        #[arg(long, value_name = "VALUE")]
+        cache_control: Option<String>,
+        /// New Content-Disposition
+        #[arg(long, value_name = "VALUE", value_parser = parse_content_disposition)]
+        content_disposition: Option<String>,
+        /// User metadata to add or replace (repeatable), e.g. owner=ops
+        #[arg(long = "metadata", value_name = "NAME=VALUE", value_parser = r2pilot_core::parse_metadata_entry)]
+        metadata: Vec<(String, String)>,
+    },
+    /// Undelete an object on a versioned bucket by removing its delete marker
+    Restore {
+        /// R2 key
+        key: String,
+        /// Target bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+    },
+    /// Generate a shareable download link and copy it to the clipboard
+    Share {
+        /// R2 key
+        key: String,
+        /// Expiration, e.g. 3600, 30m, 2h or 7d (uses default expiration)
+        #[arg(short, long, value_name = "DURATION", value_parser = parse_expires)]
+        expires: Option<u64>,
+        /// Source bucket (uses default bucket)
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Point the link at a custom domain instead of the R2 endpoint
+        #[arg(long, value_name = "HOST")]
+        custom_host: Option<String>,
     },
 }
 
@@ -171,11 +600,14 @@ enum UrlAction {
     Generate {
         /// R2 key
         key: String,
-        /// HTTP method (get, put, delete)
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// HTTP method (get, put, delete, head)
         #[arg(short, long, default_value = "get")]
         method: String,
-        /// Expiration in seconds (default: 7200)
-        #[arg(short, long, default_value = "7200")]
+        /// Expiration, e.g. 3600, 30m, 2h or 7d (at most 7 days)
+        #[arg(short, long, value_name = "DURATION", default_value = "2h", value_parser = parse_expires)]
         expires: u64,
         /// Content type (for PUT requests)
         #[arg(long)]
@@ -183,6 +615,26 @@ enum UrlAction {
         /// Output format (table, json)
         #[arg(short, long, default_value = "table")]
         output: String,
+        /// Also print the required headers and a curl command to test the URL
+        #[arg(long)]
+        curl: bool,
+        /// Point the URL at a custom domain instead of the R2 endpoint (still signed for the endpoint)
+        #[arg(long, value_name = "HOST")]
+        custom_host: Option<String>,
+        /// Cache-Control of the response, e.g. 'max-age=3600' (GET only, signed into the URL)
+        #[arg(long, value_name = "VALUE")]
+        response_cache_control: Option<String>,
+        /// Expires header of the response, this far from now, e.g. 1h or 30d (GET only)
+        #[arg(long, value_name = "AGE", value_parser = r2pilot_core::parse_age)]
+        response_expires: Option<std::time::Duration>,
+    },
+    /// Inspect a presigned URL (method, bucket, key, expiry)
+    Inspect {
+        /// Presigned URL
+        url: String,
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        output: String,
     },
 }
 
@@ -190,6 +642,8 @@ enum UrlAction {
 enum DoctorAction {
     /// Check installation
     Check,
+    /// Repair common configuration issues (asks before each fix)
+    Fix,
     /// Test R2 connection
     TestConnection,
 }
@@ -197,8 +651,18 @@ enum DoctorAction {
 #[derive(clap::Subcommand, Debug)]
 enum CorsAction {
     /// Get CORS configuration
-    Get { name: Option<String> },
-    /// Set CORS configuration (interactive or JSON)
+    Get {
+        /// Bucket name (same as --bucket)
+        #[arg(conflicts_with = "bucket")]
+        name: Option<String>,
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Show every bucket in the account
+        #[arg(long, conflicts_with_all = ["name", "bucket"])]
+        all: bool,
+    },
+    /// Set CORS configuration (interactive, JSON or a preset)
     Set {
         /// Bucket name
         #[arg(short, long)]
@@ -209,6 +673,12 @@ enum CorsAction {
         /// Interactive mode
         #[arg(short, long)]
         interactive: bool,
+        /// Apply a preset rule set (public-read, web-app, all)
+        #[arg(long, conflicts_with_all = ["file", "interactive"])]
+        preset: Option<r2pilot_core::CorsPreset>,
+        /// Origin allowed by the web-app preset (prompted if omitted)
+        #[arg(long, requires = "preset")]
+        origin: Option<String>,
     },
     /// Delete CORS configuration
     Delete {
@@ -216,13 +686,63 @@ enum CorsAction {
         #[arg(short, long)]
         bucket: Option<String>,
     },
+    /// Add a CORS rule, keeping existing rules
+    Add {
+        /// Rule ID (used by `cors rm`)
+        #[arg(long)]
+        id: String,
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// JSON file with a single CORS rule
+        #[arg(short, long)]
+        file: Option<String>,
+        /// Interactive mode
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    /// Remove a CORS rule by ID, keeping the others
+    Rm {
+        /// Rule ID
+        id: String,
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+    },
+    /// Print the JSON schema (with an example) for `cors set --file`
+    Schema,
+    /// Check whether the current rules allow a browser preflight
+    Test {
+        /// Request origin (e.g. https://app.example.com)
+        #[arg(long)]
+        origin: String,
+        /// Request method (GET, PUT, POST, DELETE, HEAD)
+        #[arg(long)]
+        method: String,
+        /// Request header the browser would send (repeatable)
+        #[arg(long = "header", value_name = "NAME")]
+        headers: Vec<String>,
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
 enum LifecycleAction {
     /// Get lifecycle rules
-    Get { name: Option<String> },
-    /// Set lifecycle rules (interactive or JSON)
+    Get {
+        /// Bucket name (same as --bucket)
+        #[arg(conflicts_with = "bucket")]
+        name: Option<String>,
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Show every bucket in the account
+        #[arg(long, conflicts_with_all = ["name", "bucket"])]
+        all: bool,
+    },
+    /// Set lifecycle rules (interactive, JSON or a preset)
     Set {
         /// Bucket name
         #[arg(short, long)]
@@ -233,6 +753,12 @@ enum LifecycleAction {
         /// Interactive mode
         #[arg(short, long)]
         interactive: bool,
+        /// Apply a preset rule set (expire-logs-30d, cleanup-incomplete-uploads, tmp-7d)
+        #[arg(long, conflicts_with_all = ["file", "interactive"])]
+        preset: Option<r2pilot_core::LifecyclePreset>,
+        /// Override the preset's day count
+        #[arg(long, requires = "preset")]
+        days: Option<u32>,
     },
     /// Delete lifecycle rules
     Delete {
@@ -240,6 +766,8 @@ enum LifecycleAction {
         #[arg(short, long)]
         bucket: Option<String>,
     },
+    /// Print the JSON schema (with an example) for `lifecycle set --file`
+    Schema,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -263,7 +791,17 @@ enum WebsiteAction {
         bucket: Option<String>,
     },
     /// Get website configuration
-    Get { name: Option<String> },
+    Get {
+        /// Bucket name (same as --bucket)
+        #[arg(conflicts_with = "bucket")]
+        name: Option<String>,
+        /// Bucket name
+        #[arg(short, long)]
+        bucket: Option<String>,
+        /// Show every bucket in the account
+        #[arg(long, conflicts_with_all = ["name", "bucket"])]
+        all: bool,
+    },
 }
 
 #[tokio::main]
@@ -273,102 +811,665 @@ async fn main() -> Result<()> {
         eprintln!("Warning: Failed to install error handler: {}", e);
     }
 
+    // Logging is off unless RUST_LOG is set (e.g. RUST_LOG=debug)
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("off")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
     // Parse CLI arguments
     let cli = Cli::parse();
 
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(exit_code(&e));
+    }
+
+    Ok(())
+}
+
+/// Parse a `--jurisdiction` value
+fn parse_jurisdiction(value: &str) -> std::result::Result<String, String> {
+    r2pilot_core::validate_jurisdiction(value)
+        .map(|()| value.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Parse a `--concurrency` value (at least 1)
+fn parse_concurrency(value: &str) -> std::result::Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("concurrency must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Check a `--max-keys` value
+fn parse_max_keys(value: &str) -> std::result::Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("max-keys must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Check a `--max-rate` value, keeping it as given for the configuration
+fn parse_max_rate(value: &str) -> std::result::Result<String, String> {
+    r2pilot_core::parse_rate(value)
+        .map(|_| value.trim().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Parse an `--expires` duration into seconds (1 second to 7 days)
+fn parse_expires(value: &str) -> std::result::Result<u64, String> {
+    let seconds = r2pilot_core::parse_duration(value)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+    match seconds {
+        0 => Err("expiration must be at least 1 second".to_string()),
+        s if s > 604800 => Err("expiration cannot exceed 7 days (604800 seconds)".to_string()),
+        s => Ok(s),
+    }
+}
+
+/// Parse a `--content-disposition` value (must be a well-formed header value)
+fn parse_content_disposition(value: &str) -> std::result::Result<String, String> {
+    r2pilot_core::validate_content_disposition(value)
+        .map(|()| value.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Process exit codes, by error category
+mod exit_codes {
+    pub const GENERIC: i32 = 1;
+    pub const CONFIG: i32 = 2;
+    pub const AUTH: i32 = 3;
+    pub const NOT_FOUND: i32 = 4;
+    pub const NETWORK: i32 = 5;
+    pub const PRECONDITION_FAILED: i32 = 6;
+    pub const ALREADY_EXISTS: i32 = 7;
+    pub const CANCELLED: i32 = 130;
+}
+
+/// Map an error to its exit code from the first `r2pilot_core::Error` in its chain
+fn exit_code(err: &anyhow::Error) -> i32 {
+    use r2pilot_core::Error;
+
+    let Some(core_err) = err.chain().find_map(|e| e.downcast_ref::<Error>()) else {
+        return exit_codes::GENERIC;
+    };
+
+    match core_err {
+        Error::Config(_)
+        | Error::ConfigNotFound(_)
+        | Error::InvalidConfig(_)
+        | Error::Serialization(_)
+        | Error::Deserialization(_) => exit_codes::CONFIG,
+        Error::Authentication(_) | Error::PermissionDenied(_) => exit_codes::AUTH,
+        Error::NotFound(_) => exit_codes::NOT_FOUND,
+        Error::Network(_) | Error::Timeout | Error::Transient(_) | Error::HttpClient(_) => {
+            exit_codes::NETWORK
+        }
+        Error::PreconditionFailed(_) => exit_codes::PRECONDITION_FAILED,
+        Error::AlreadyExists(_) => exit_codes::ALREADY_EXISTS,
+        Error::Cancelled => exit_codes::CANCELLED,
+        _ => exit_codes::GENERIC,
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    handlers::set_display_utc(cli.utc);
+    if cli.quiet {
+        handlers::set_verbosity(handlers::Verbosity::Quiet);
+    }
+
+    if let Some(endpoint) = &cli.endpoint {
+        r2pilot_core::validate_endpoint(endpoint)?;
+    }
+    let timings = cli
+        .timings
+        .then(|| std::sync::Arc::new(r2pilot_core::Timings::new()));
+    let mut ctx = context::Context::new(context::ConfigOverrides {
+        endpoint: cli.endpoint,
+        max_rate: cli.max_rate,
+    });
+    if let Some(timings) = &timings {
+        ctx = ctx.with_timings(timings.clone());
+    }
+    let ctx = &ctx;
+
     // Execute command
-    match cli.command {
-        Commands::Init => handlers::handle_init().await,
+    let result = match cli.command {
+        Commands::Init {
+            account_id,
+            api_token,
+            access_key_id,
+            secret_access_key,
+            jurisdiction,
+            bucket,
+            yes,
+        } => {
+            handlers::handle_init(wizard::InitOptions {
+                account_id,
+                api_token,
+                access_key_id,
+                secret_access_key,
+                jurisdiction,
+                bucket,
+                yes,
+            })
+            .await
+        }
         Commands::Config { action } => {
-            let action_str = match action {
-                ConfigAction::Show => "show",
-                ConfigAction::Edit => "edit",
-                ConfigAction::Validate => "validate",
+            let (action_str, options) = match action {
+                ConfigAction::Show {
+                    reveal,
+                    reveal_secrets,
+                } => (
+                    "show",
+                    handlers::ConfigOptions {
+                        reveal,
+                        reveal_secrets,
+                        ..Default::default()
+                    },
+                ),
+                ConfigAction::Edit => ("edit", Default::default()),
+                ConfigAction::Validate => ("validate", Default::default()),
+                ConfigAction::Export {
+                    format,
+                    with_secrets,
+                } => (
+                    "export",
+                    handlers::ConfigOptions {
+                        format: Some(format),
+                        with_secrets,
+                        ..Default::default()
+                    },
+                ),
+                ConfigAction::Import { file, format, yes } => (
+                    "import",
+                    handlers::ConfigOptions {
+                        file: Some(file),
+                        format,
+                        yes,
+                        ..Default::default()
+                    },
+                ),
             };
-            handlers::handle_config(action_str).await
+            handlers::handle_config(ctx, action_str, options).await
         }
         Commands::Tokens { action } => {
-            let (action_str, token_id) = match action {
-                TokenAction::List => ("list", None),
-                TokenAction::Create => ("create", None),
-                TokenAction::Revoke { token_id } => ("revoke", Some(token_id)),
+            let (action_str, token_id, options) = match action {
+                TokenAction::List { output } => (
+                    "list",
+                    None,
+                    handlers::TokenOptions {
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                TokenAction::Create {
+                    name,
+                    permission,
+                    ip,
+                    expires_days,
+                    json,
+                } => (
+                    "create",
+                    None,
+                    handlers::TokenOptions {
+                        name,
+                        permission,
+                        ip,
+                        expires_days,
+                        json,
+                        ..Default::default()
+                    },
+                ),
+                TokenAction::Revoke { token_id } => ("revoke", Some(token_id), Default::default()),
+                TokenAction::Verify { token_id, output } => (
+                    "verify",
+                    token_id,
+                    handlers::TokenOptions {
+                        output,
+                        ..Default::default()
+                    },
+                ),
             };
-            handlers::handle_tokens(action_str, token_id.as_deref()).await
+            handlers::handle_tokens(ctx, action_str, token_id.as_deref(), options).await
         }
         Commands::Buckets { action } => {
-            let (action_str, name) = match action {
-                BucketAction::List => ("list", None),
-                BucketAction::Create { name } => ("create", Some(name)),
-                BucketAction::Delete { name } => ("delete", Some(name)),
-                BucketAction::Info { name } => ("info", Some(name)),
-                BucketAction::Ls { name } => ("ls", name),
+            let (action_str, name, options) = match action {
+                BucketAction::List { output } => (
+                    "list",
+                    None,
+                    handlers::BucketOptions {
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                BucketAction::Create {
+                    name,
+                    with_cors,
+                    with_website,
+                    jurisdiction,
+                    if_not_exists,
+                } => (
+                    "create",
+                    Some(name),
+                    handlers::BucketOptions {
+                        with_cors,
+                        with_website,
+                        jurisdiction,
+                        if_not_exists,
+                        ..Default::default()
+                    },
+                ),
+                BucketAction::Delete { name } => ("delete", Some(name), Default::default()),
+                BucketAction::Rename {
+                    name,
+                    new_name,
+                    concurrency,
+                    yes,
+                } => (
+                    "rename",
+                    Some(name),
+                    handlers::BucketOptions {
+                        new_name: Some(new_name),
+                        concurrency,
+                        yes,
+                        ..Default::default()
+                    },
+                ),
+                BucketAction::Info { name } => ("info", Some(name), Default::default()),
+                BucketAction::Ls { name, output } => (
+                    "ls",
+                    name,
+                    handlers::BucketOptions {
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                BucketAction::Tag {
+                    name,
+                    description,
+                    set_tags,
+                    remove_tags,
+                    clear,
+                } => (
+                    "tag",
+                    Some(name),
+                    handlers::BucketOptions {
+                        description,
+                        set_tags,
+                        remove_tags,
+                        clear,
+                        ..Default::default()
+                    },
+                ),
             };
-            handlers::handle_buckets(action_str, name.as_deref()).await
+            handlers::handle_buckets(ctx, action_str, name.as_deref(), options).await
         }
         Commands::Files { action } => {
-            let (action_str, file, key, bucket, prefix, progress, multipart) = match action {
+            let (action_str, file, key, bucket, prefix, options) = match action {
                 FileAction::Upload {
                     file,
                     key,
+                    content_addressed,
+                    prefix,
                     bucket,
                     progress,
                     multipart,
+                    compress,
+                    no_clobber,
+                    content_length,
+                    concurrency,
+                    content_disposition,
+                    if_match,
+                    if_none_match,
+                    resume,
+                    plan_only,
+                    headers,
+                    follow_symlinks,
                 } => (
                     "upload",
                     Some(file),
-                    Some(key),
+                    key,
                     bucket,
-                    None,
-                    progress,
-                    multipart,
+                    prefix,
+                    handlers::FileOptions {
+                        content_addressed,
+                        progress,
+                        multipart,
+                        compress,
+                        no_clobber,
+                        content_length,
+                        concurrency,
+                        content_disposition,
+                        if_match,
+                        if_none_match,
+                        resume,
+                        plan_only,
+                        headers,
+                        follow_symlinks,
+                        ..Default::default()
+                    },
                 ),
-                FileAction::Download { key, dest, bucket } => (
+                FileAction::Download {
+                    key,
+                    dest,
+                    bucket,
+                    no_clobber,
+                    concurrency,
+                    decompress,
+                    cache,
+                } => (
                     "download",
                     Some(dest),
                     Some(key),
                     bucket,
                     None,
-                    false,
-                    false,
+                    handlers::FileOptions {
+                        no_clobber,
+                        concurrency,
+                        decompress,
+                        cache,
+                        ..Default::default()
+                    },
                 ),
-                FileAction::Delete { key, bucket } => {
-                    ("delete", None, Some(key), bucket, None, false, false)
-                }
-                FileAction::Ls { prefix, bucket } => {
-                    ("ls", None, None, bucket, prefix, false, false)
+                FileAction::Delete {
+                    key,
+                    keys_file,
+                    bucket,
+                    concurrency,
+                    yes,
+                } => (
+                    "delete",
+                    None,
+                    key,
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        concurrency,
+                        keys_file,
+                        yes,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Stat {
+                    key,
+                    bucket,
+                    wait,
+                    timeout,
+                    output,
+                } => (
+                    "stat",
+                    None,
+                    Some(key),
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        wait: wait.then_some(timeout),
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Ls {
+                    prefix,
+                    bucket,
+                    min_size,
+                    max_size,
+                    older_than,
+                    newer_than,
+                    long,
+                    recursive,
+                    with_content_type,
+                    max_keys,
+                    after,
+                    output,
+                } => (
+                    "ls",
+                    None,
+                    None,
+                    bucket,
+                    prefix,
+                    handlers::FileOptions {
+                        filter: r2pilot_core::ObjectFilter {
+                            min_size,
+                            max_size,
+                            older_than,
+                            newer_than,
+                        },
+                        long,
+                        recursive,
+                        with_content_type,
+                        max_keys,
+                        after,
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Find {
+                    pattern,
+                    bucket,
+                    output,
+                } => (
+                    "find",
+                    None,
+                    Some(pattern),
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Rm {
+                    pattern,
+                    bucket,
+                    yes,
+                } => (
+                    "rm",
+                    None,
+                    Some(pattern),
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        yes,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::SetClass { key, class, bucket } => (
+                    "set-class",
+                    None,
+                    Some(key),
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        storage_class: Some(class),
+                        ..Default::default()
+                    },
+                ),
+                FileAction::SetMeta {
+                    key,
+                    bucket,
+                    content_type,
+                    cache_control,
+                    content_disposition,
+                    metadata,
+                } => (
+                    "set-meta",
+                    None,
+                    Some(key),
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        content_type,
+                        cache_control,
+                        content_disposition,
+                        metadata,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Restore { key, bucket } => {
+                    ("restore", None, Some(key), bucket, None, Default::default())
                 }
+                FileAction::Share {
+                    key,
+                    expires,
+                    bucket,
+                    custom_host,
+                } => (
+                    "share",
+                    None,
+                    Some(key),
+                    bucket,
+                    None,
+                    handlers::FileOptions {
+                        expires,
+                        custom_host,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Diff {
+                    dir,
+                    prefix,
+                    bucket,
+                    checksum,
+                    delete,
+                    follow_symlinks,
+                    output,
+                } => (
+                    "diff",
+                    Some(dir),
+                    None,
+                    bucket,
+                    prefix,
+                    handlers::FileOptions {
+                        checksum,
+                        delete,
+                        follow_symlinks,
+                        output,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Sync {
+                    dir,
+                    prefix,
+                    bucket,
+                    checksum,
+                    delete,
+                    yes,
+                    concurrency,
+                    content_disposition,
+                    fix_content_type,
+                    follow_symlinks,
+                } => (
+                    "sync",
+                    Some(dir),
+                    None,
+                    bucket,
+                    prefix,
+                    handlers::FileOptions {
+                        checksum,
+                        delete,
+                        yes,
+                        concurrency,
+                        content_disposition,
+                        fix_content_type,
+                        follow_symlinks,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::Copy {
+                    source,
+                    dest,
+                    bucket,
+                    dst_bucket,
+                    content_type,
+                } => (
+                    "copy",
+                    None,
+                    Some(dest),
+                    bucket,
+                    Some(source),
+                    handlers::FileOptions {
+                        dst_bucket,
+                        content_type,
+                        ..Default::default()
+                    },
+                ),
+                FileAction::MvPrefix {
+                    from,
+                    to,
+                    bucket,
+                    yes,
+                    concurrency,
+                } => (
+                    "mv-prefix",
+                    None,
+                    Some(to),
+                    bucket,
+                    Some(from),
+                    handlers::FileOptions {
+                        yes,
+                        concurrency,
+                        ..Default::default()
+                    },
+                ),
             };
             handlers::handle_files(
+                ctx,
                 action_str,
                 file.as_deref(),
                 key.as_deref(),
                 bucket.as_deref(),
                 prefix.as_deref(),
-                progress,
-                multipart,
+                options,
             )
             .await
         }
         Commands::Urls { action } => {
-            let (action_str, key, method, expires, content_type_string, output) = match action {
+            let (action_str, key, options) = match action {
                 UrlAction::Generate {
                     key,
+                    bucket,
                     method,
                     expires,
                     content_type,
                     output,
-                } => ("generate", Some(key), method, expires, content_type, output),
+                    curl,
+                    custom_host,
+                    response_cache_control,
+                    response_expires,
+                } => (
+                    "generate",
+                    key,
+                    handlers::UrlOptions {
+                        bucket,
+                        method,
+                        expires,
+                        content_type,
+                        output,
+                        curl,
+                        custom_host,
+                        response_cache_control,
+                        response_expires,
+                    },
+                ),
+                UrlAction::Inspect { url, output } => (
+                    "inspect",
+                    url,
+                    handlers::UrlOptions {
+                        output,
+                        ..Default::default()
+                    },
+                ),
             };
-            let content_type_ref = content_type_string.as_deref();
-            handlers::handle_urls(
-                action_str,
-                key.as_deref(),
-                &method,
-                expires,
-                content_type_ref,
-                &output,
-            )
-            .await
+            handlers::handle_urls(ctx, action_str, Some(&key), options).await
         }
         Commands::Completion { shell } => {
             handlers::handle_completion(&shell, &mut Cli::command()).await
@@ -376,52 +1477,202 @@ async fn main() -> Result<()> {
         Commands::Doctor { action } => {
             let action_str = match action {
                 DoctorAction::Check => "check",
+                DoctorAction::Fix => "fix",
                 DoctorAction::TestConnection => "test-connection",
             };
-            handlers::handle_doctor(action_str).await
+            handlers::handle_doctor(ctx, action_str).await
         }
         Commands::Cors { action } => {
-            let (action_str, bucket, file, interactive) = match action {
-                CorsAction::Get { name } => ("get", name, None, false),
+            let (action_str, bucket, options) = match action {
+                CorsAction::Get { name, bucket, all } => (
+                    "get",
+                    bucket.or(name),
+                    handlers::CorsOptions {
+                        all,
+                        ..Default::default()
+                    },
+                ),
                 CorsAction::Set {
                     bucket,
                     file,
                     interactive,
-                } => ("set", bucket, file, interactive),
-                CorsAction::Delete { bucket } => ("delete", bucket, None, false),
+                    preset,
+                    origin,
+                } => (
+                    "set",
+                    bucket,
+                    handlers::CorsOptions {
+                        file,
+                        interactive,
+                        preset,
+                        origin,
+                        ..Default::default()
+                    },
+                ),
+                CorsAction::Delete { bucket } => ("delete", bucket, Default::default()),
+                CorsAction::Add {
+                    id,
+                    bucket,
+                    file,
+                    interactive,
+                } => (
+                    "add",
+                    bucket,
+                    handlers::CorsOptions {
+                        file,
+                        interactive,
+                        rule_id: Some(id),
+                        ..Default::default()
+                    },
+                ),
+                CorsAction::Rm { id, bucket } => (
+                    "rm",
+                    bucket,
+                    handlers::CorsOptions {
+                        rule_id: Some(id),
+                        ..Default::default()
+                    },
+                ),
+                CorsAction::Schema => ("schema", None, Default::default()),
+                CorsAction::Test {
+                    origin,
+                    method,
+                    headers,
+                    bucket,
+                } => (
+                    "test",
+                    bucket,
+                    handlers::CorsOptions {
+                        preflight: Some(handlers::CorsPreflight {
+                            origin,
+                            method,
+                            headers,
+                        }),
+                        ..Default::default()
+                    },
+                ),
             };
-            handlers::handle_cors(action_str, bucket.as_deref(), file.as_deref(), interactive).await
+            handlers::handle_cors(ctx, action_str, bucket.as_deref(), options).await
         }
         Commands::Lifecycle { action } => {
-            let (action_str, bucket, file, interactive) = match action {
-                LifecycleAction::Get { name } => ("get", name, None, false),
+            let (action_str, bucket, options) = match action {
+                LifecycleAction::Get { name, bucket, all } => (
+                    "get",
+                    bucket.or(name),
+                    handlers::LifecycleOptions {
+                        all,
+                        ..Default::default()
+                    },
+                ),
                 LifecycleAction::Set {
                     bucket,
                     file,
                     interactive,
-                } => ("set", bucket, file, interactive),
-                LifecycleAction::Delete { bucket } => ("delete", bucket, None, false),
+                    preset,
+                    days,
+                } => (
+                    "set",
+                    bucket,
+                    handlers::LifecycleOptions {
+                        file,
+                        interactive,
+                        preset,
+                        days,
+                        ..Default::default()
+                    },
+                ),
+                LifecycleAction::Delete { bucket } => ("delete", bucket, Default::default()),
+                LifecycleAction::Schema => ("schema", None, Default::default()),
             };
-            handlers::handle_lifecycle(action_str, bucket.as_deref(), file.as_deref(), interactive)
-                .await
+            handlers::handle_lifecycle(ctx, action_str, bucket.as_deref(), options).await
         }
         Commands::Website { action } => {
-            let (action_str, bucket, index, error) = match action {
+            let (action_str, bucket, index, error, all) = match action {
                 WebsiteAction::Enable {
                     bucket,
                     index,
                     error,
-                } => ("enable", bucket, index, error),
-                WebsiteAction::Disable { bucket } => ("disable", bucket, None, None),
-                WebsiteAction::Get { name } => ("get", name, None, None),
+                } => ("enable", bucket, index, error, false),
+                WebsiteAction::Disable { bucket } => ("disable", bucket, None, None, false),
+                WebsiteAction::Get { name, bucket, all } => {
+                    ("get", bucket.or(name), None, None, all)
+                }
             };
             handlers::handle_website(
+                ctx,
                 action_str,
                 bucket.as_deref(),
                 index.as_deref(),
                 error.as_deref(),
+                all,
             )
             .await
         }
+        Commands::Audit {
+            concurrency,
+            output,
+        } => handlers::handle_audit(ctx, concurrency, &output).await,
+    };
+
+    // Also after a failure: a slow phase is often why it failed
+    if let Some(timings) = &timings {
+        handlers::print_timings(timings);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files_about(name: &str) -> String {
+        Cli::command()
+            .find_subcommand("files")
+            .and_then(|files| files.find_subcommand(name))
+            .and_then(|command| command.get_about())
+            .map(|about| about.to_string())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_files_rm_and_find_help() {
+        assert!(files_about("rm").starts_with("Delete every object matching a glob"));
+        assert!(files_about("find").starts_with("Find objects whose keys match"));
+    }
+
+    #[test]
+    fn test_csv_output_values() {
+        for args in [
+            ["r2pilot", "buckets", "list", "-o", "csv"].as_slice(),
+            &["r2pilot", "tokens", "list", "--output", "json"],
+            &["r2pilot", "files", "ls", "-o", "table"],
+        ] {
+            assert!(Cli::try_parse_from(args).is_ok(), "{:?}", args);
+        }
+
+        // A typo is rejected instead of falling back to the table
+        let error = Cli::try_parse_from(["r2pilot", "buckets", "list", "-o", "cvs"]).unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        use r2pilot_core::Error;
+
+        let code = |e: Error| exit_code(&anyhow::Error::from(e));
+        assert_eq!(code(Error::AlreadyExists("Bucket 'a'".into())), 7);
+        assert_eq!(code(Error::PreconditionFailed("etag".into())), 6);
+        assert_eq!(code(Error::NotFound("key".into())), 4);
+        assert_eq!(code(Error::Cancelled), 130);
+        assert_eq!(code(Error::InvalidInput("x".into())), 1);
+
+        let wrapped = anyhow::Error::from(Error::AlreadyExists("Bucket 'a'".into()))
+            .context("Could not create bucket");
+        assert_eq!(exit_code(&wrapped), 7);
+        assert_eq!(exit_code(&anyhow::anyhow!("plain")), 1);
+
+        // Missing configuration is a configuration error
+        let missing = anyhow::Error::from(context::missing_access_keys("account"));
+        assert_eq!(exit_code(&missing), 2);
     }
 }
@@ -3,10 +3,149 @@
 use anyhow::Result;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password, Select};
 use indicatif::{ProgressBar, ProgressStyle};
-use r2pilot_core::{save_config, CloudflareConfig, ConfigFile, R2Config};
+use r2pilot_core::{
+    config_exists, get_config_path, is_valid_bucket_name, r2_endpoint, save_config,
+    validate_config, CloudflareConfig, ConfigFile, R2Config, CONFIG_VERSION, JURISDICTIONS,
+};
+use std::io::IsTerminal;
 
-/// Run the interactive setup wizard
-pub async fn run_init_wizard() -> Result<()> {
+/// Values for `init` given on the command line instead of prompted
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    pub account_id: Option<String>,
+    pub api_token: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub jurisdiction: Option<String>,
+    pub bucket: Option<String>,
+    /// Skip the confirmation (and allow overwriting an existing config)
+    pub yes: bool,
+}
+
+impl InitOptions {
+    /// Whether every required value was given, so no prompt is needed
+    fn is_complete(&self) -> bool {
+        let has_auth = self.api_token.is_some()
+            || (self.access_key_id.is_some() && self.secret_access_key.is_some());
+        self.account_id.is_some() && has_auth && self.bucket.is_some()
+    }
+
+    /// Flags still needed to run without prompts
+    fn missing_flags(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.account_id.is_none() {
+            missing.push("--account-id");
+        }
+        if self.api_token.is_none() {
+            match (&self.access_key_id, &self.secret_access_key) {
+                (None, None) => {
+                    missing.push("--api-token (or --access-key-id/--secret-access-key)")
+                }
+                (Some(_), None) => missing.push("--secret-access-key"),
+                (None, Some(_)) => missing.push("--access-key-id"),
+                (Some(_), Some(_)) => {}
+            }
+        }
+        if self.bucket.is_none() {
+            missing.push("--bucket");
+        }
+        missing
+    }
+}
+
+/// Run the setup: without prompts when all flags are given, otherwise the interactive wizard
+pub async fn run_init(options: InitOptions) -> Result<()> {
+    if options.is_complete() {
+        return run_non_interactive(options);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Missing {} (no terminal to prompt for them)",
+            options.missing_flags().join(", ")
+        ));
+    }
+
+    run_init_wizard(options).await
+}
+
+/// Build and save the configuration from flags alone
+fn run_non_interactive(options: InitOptions) -> Result<()> {
+    let account_id = options.account_id.unwrap_or_default();
+    let bucket = options.bucket.unwrap_or_default();
+
+    if config_exists() && !options.yes {
+        return Err(anyhow::anyhow!(
+            "A configuration already exists at {} (pass --yes to overwrite it)",
+            get_config_path()?.display()
+        ));
+    }
+    if !is_valid_bucket_name(&bucket) {
+        return Err(anyhow::anyhow!(
+            "Invalid bucket name '{}' (3-63 characters: lowercase letters, digits and hyphens, starting and ending with a letter or digit)",
+            bucket
+        ));
+    }
+
+    let (api_token, access_key_id, secret_access_key) = match options.api_token {
+        Some(token) => (Some(token), None, None),
+        None => (None, options.access_key_id, options.secret_access_key),
+    };
+    let endpoint = r2_endpoint(&account_id, options.jurisdiction.as_deref());
+    let config = build_config(
+        account_id,
+        endpoint,
+        options.jurisdiction,
+        (api_token, access_key_id, secret_access_key),
+        bucket,
+    );
+
+    validate_config(&config)?;
+    save_config(&config)?;
+
+    println!("✅ Configuration saved to {}", get_config_path()?.display());
+    Ok(())
+}
+
+/// Assemble a fresh configuration file
+pub(crate) fn build_config(
+    account_id: String,
+    endpoint: String,
+    jurisdiction: Option<String>,
+    (api_token, access_key_id, secret_access_key): (Option<String>, Option<String>, Option<String>),
+    default_bucket: String,
+) -> ConfigFile {
+    ConfigFile {
+        version: CONFIG_VERSION,
+        cloudflare: CloudflareConfig {
+            account_id,
+            endpoint,
+            api_token,
+            access_key_id,
+            secret_access_key,
+            jurisdiction,
+            force_path_style: None,
+            user_agent: None,
+            ca_bundle: None,
+            danger_accept_invalid_certs: None,
+            api_token_file: None,
+        },
+        r2: R2Config {
+            default_bucket,
+            region: "auto".to_string(),
+            default_expiration: 7200,
+        },
+        advanced: None,
+        logging: None,
+        output: None,
+        upload: None,
+        deploy: None,
+        buckets: Default::default(),
+    }
+}
+
+/// Run the interactive setup wizard, prompting for anything not given as a flag
+async fn run_init_wizard(options: InitOptions) -> Result<()> {
     println!("🚀 Welcome to r2pilot setup!\n");
 
     println!("This wizard will guide you through the configuration process.");
@@ -16,21 +155,42 @@ pub async fn run_init_wizard() -> Result<()> {
     println!("  3. Your R2 bucket name\n");
 
     // Step 1: Account ID
-    let account_id = prompt_account_id()?;
+    let account_id = match options.account_id {
+        Some(account_id) => account_id,
+        None => prompt_account_id()?,
+    };
 
     // Step 2: Choose auth method
-    let (api_token, access_key_id, secret_access_key) = prompt_auth_method()?;
+    let (api_token, access_key_id, secret_access_key) = match (
+        options.api_token,
+        options.access_key_id,
+        options.secret_access_key,
+    ) {
+        (Some(token), _, _) => (Some(token), None, None),
+        (None, Some(key), Some(secret)) => (None, Some(key), Some(secret)),
+        _ => prompt_auth_method()?,
+    };
 
-    // Step 3: Build endpoint from account_id
-    let endpoint = format!("https://{}.r2.cloudflarestorage.com", account_id);
+    // Step 3: Build endpoint from account_id (and jurisdiction, if any)
+    let jurisdiction = match options.jurisdiction {
+        Some(jurisdiction) => Some(jurisdiction),
+        None => prompt_jurisdiction()?,
+    };
+    let endpoint = r2_endpoint(&account_id, jurisdiction.as_deref());
 
     // Step 4: Default bucket
-    let default_bucket = prompt_bucket_name()?;
+    let default_bucket = match options.bucket {
+        Some(bucket) => bucket,
+        None => prompt_bucket_name()?,
+    };
 
     // Summary
     println!("\n📋 Configuration summary:");
     println!("  Account ID: {}", account_id);
     println!("  Endpoint: {}", endpoint);
+    if let Some(jurisdiction) = &jurisdiction {
+        println!("  Jurisdiction: {}", jurisdiction);
+    }
     println!("  Bucket: {}", default_bucket);
     println!(
         "  Auth: {}",
@@ -42,10 +202,11 @@ pub async fn run_init_wizard() -> Result<()> {
     );
 
     // Confirmation
-    let confirm = Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("Save this configuration?")
-        .default(false)
-        .interact()?;
+    let confirm = options.yes
+        || Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Save this configuration?")
+            .default(false)
+            .interact()?;
 
     if !confirm {
         println!("❌ Configuration cancelled");
@@ -53,23 +214,14 @@ pub async fn run_init_wizard() -> Result<()> {
     }
 
     // Create config
-    let config = ConfigFile {
-        cloudflare: CloudflareConfig {
-            account_id: account_id.clone(),
-            endpoint: endpoint.clone(),
-            api_token: api_token.clone(),
-            access_key_id: access_key_id.clone(),
-            secret_access_key: secret_access_key.clone(),
-        },
-        r2: R2Config {
-            default_bucket: default_bucket.clone(),
-            region: "auto".to_string(),
-            default_expiration: 7200,
-        },
-        advanced: None,
-        logging: None,
-        output: None,
-    };
+    let config = build_config(
+        account_id,
+        endpoint,
+        jurisdiction,
+        (api_token, access_key_id, secret_access_key),
+        default_bucket,
+    );
+    validate_config(&config)?;
 
     // Save config
     let pb = ProgressBar::new(2);
@@ -179,6 +331,24 @@ fn prompt_auth_method() -> Result<(Option<String>, Option<String>, Option<String
     }
 }
 
+/// Prompt for the R2 jurisdiction (default: none)
+fn prompt_jurisdiction() -> Result<Option<String>> {
+    let mut choices = vec!["None (default)"];
+    choices.extend_from_slice(JURISDICTIONS);
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Jurisdiction")
+        .items(&choices)
+        .default(0)
+        .interact()
+        .map_err(|e| anyhow::anyhow!("Failed to select jurisdiction: {}", e))?;
+
+    Ok(match selection {
+        0 => None,
+        i => Some(choices[i].to_string()),
+    })
+}
+
 /// Prompt for default bucket name
 fn prompt_bucket_name() -> Result<String> {
     Input::with_theme(&ColorfulTheme::default())
@@ -0,0 +1,59 @@
+//! Serial vs concurrent listing of a bucket with many folders
+//!
+//! Every page of 1000 entries waits a simulated round trip, so the gain of
+//! fanning out on common prefixes shows without a real bucket:
+//!
+//! ```sh
+//! cargo bench -p r2pilot-core --features mock
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use r2pilot_core::{list_objects_concurrent, MockR2};
+use std::time::Duration;
+
+/// Folders at the bucket root, each listed separately when concurrent
+const FOLDERS: usize = 32;
+const OBJECTS_PER_FOLDER: usize = 1000;
+/// Simulated latency of one listing page
+const ROUND_TRIP: Duration = Duration::from_millis(20);
+
+fn bucket() -> MockR2 {
+    let mock = MockR2::new("bench-bucket");
+    for folder in 0..FOLDERS {
+        for object in 0..OBJECTS_PER_FOLDER {
+            mock.insert(
+                &format!("folder-{:02}/object-{:04}.bin", folder, object),
+                vec![0; 16],
+                "application/octet-stream",
+            );
+        }
+    }
+    mock.set_list_latency(ROUND_TRIP);
+    mock
+}
+
+fn bench_list_concurrent(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mock = bucket();
+
+    let mut group = c.benchmark_group("list_objects_concurrent");
+    group.sample_size(10);
+    for concurrency in [1, 4, 16] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                b.to_async(&runtime).iter(|| async {
+                    let objects = list_objects_concurrent(&mock, None, concurrency)
+                        .await
+                        .unwrap();
+                    assert_eq!(objects.len(), FOLDERS * OBJECTS_PER_FOLDER);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_list_concurrent);
+criterion_main!(benches);
@@ -0,0 +1,241 @@
+//! Account-wide review of bucket exposure and cost settings
+
+use crate::cloudflare::{
+    BucketCorsConfig, CloudflareClient, CustomDomain, LifecycleConfiguration, ManagedDomain,
+    WebsiteConfiguration,
+};
+use crate::error::{Error, Result};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+
+/// Settings of one bucket relevant to exposure and cost
+///
+/// A setting that couldn't be read is `None` and its error is kept in
+/// `errors`, so one failing bucket or endpoint doesn't hide the others.
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketAudit {
+    pub bucket: String,
+    /// Hostnames serving the bucket publicly (enabled r2.dev and custom domains)
+    pub public_domains: Option<Vec<String>>,
+    /// Static website hosting is configured
+    pub website: Option<bool>,
+    /// Number of enabled lifecycle rules
+    pub lifecycle_rules: Option<usize>,
+    /// A CORS rule allows any origin (`*`)
+    pub cors_any_origin: Option<bool>,
+    /// Checks that failed, as "<setting>: <error>"
+    pub errors: Vec<String>,
+}
+
+impl BucketAudit {
+    /// Build the audit of `bucket` from the responses of each settings endpoint
+    ///
+    /// A 404 means "not configured" (the API's answer for a bucket without
+    /// CORS, lifecycle or website settings) rather than a failure.
+    pub fn from_settings(
+        bucket: &str,
+        cors: Result<BucketCorsConfig>,
+        lifecycle: Result<LifecycleConfiguration>,
+        website: Result<WebsiteConfiguration>,
+        managed_domain: Result<ManagedDomain>,
+        custom_domains: Result<Vec<CustomDomain>>,
+    ) -> Self {
+        let mut errors = Vec::new();
+
+        let cors_any_origin = read_setting("cors", cors, &mut errors, false, |cors| {
+            cors.rules
+                .iter()
+                .any(|rule| rule.allowed_origins.iter().any(|o| o == "*"))
+        });
+        let lifecycle_rules = read_setting("lifecycle", lifecycle, &mut errors, 0, |lifecycle| {
+            lifecycle
+                .rules
+                .iter()
+                .filter(|rule| rule.status == "Enabled")
+                .count()
+        });
+        let website = read_setting("website", website, &mut errors, false, |website| {
+            website.index_document.is_some() || website.error_document.is_some()
+        });
+        let managed = read_setting("r2.dev domain", managed_domain, &mut errors, None, |d| {
+            d.enabled.then_some(d.domain)
+        });
+        let custom = read_setting(
+            "custom domains",
+            custom_domains,
+            &mut errors,
+            Vec::new(),
+            |d| {
+                d.into_iter()
+                    .filter(|d| d.enabled)
+                    .map(|d| d.domain)
+                    .collect()
+            },
+        );
+        let public_domains = match (managed, custom) {
+            (Some(managed), Some(custom)) => Some(managed.into_iter().chain(custom).collect()),
+            _ => None,
+        };
+
+        Self {
+            bucket: bucket.to_string(),
+            public_domains,
+            website,
+            lifecycle_rules,
+            cors_any_origin,
+            errors,
+        }
+    }
+
+    /// Readable anywhere: a public domain or static website hosting
+    pub fn is_public(&self) -> bool {
+        self.public_domains.as_ref().is_some_and(|d| !d.is_empty()) || self.website == Some(true)
+    }
+
+    /// What deserves attention, most serious first
+    pub fn findings(&self) -> Vec<&'static str> {
+        let mut findings = Vec::new();
+        if self.is_public() {
+            findings.push("publicly exposed");
+        }
+        if self.cors_any_origin == Some(true) {
+            findings.push("CORS allows any origin");
+        }
+        if self.lifecycle_rules == Some(0) {
+            findings.push("no lifecycle rules");
+        }
+        findings
+    }
+}
+
+/// What a setting says, `default` when it isn't configured, `None` when it failed
+fn read_setting<T, V>(
+    setting: &str,
+    result: Result<T>,
+    errors: &mut Vec<String>,
+    default: V,
+    read: impl FnOnce(T) -> V,
+) -> Option<V> {
+    match result {
+        Ok(value) => Some(read(value)),
+        Err(Error::NotFound(_)) => Some(default),
+        Err(e) => {
+            errors.push(format!("{}: {}", setting, e));
+            None
+        }
+    }
+}
+
+/// Audit every bucket in `buckets`, at most `concurrency` at a time
+///
+/// Each bucket's settings are fetched concurrently too. Results keep the
+/// order of `buckets`.
+pub async fn audit_buckets(
+    client: &CloudflareClient,
+    buckets: &[String],
+    concurrency: usize,
+) -> Vec<BucketAudit> {
+    stream::iter(buckets)
+        .map(|bucket| async move {
+            let (cors, lifecycle, website, managed_domain, custom_domains) = tokio::join!(
+                client.get_bucket_cors(bucket),
+                client.get_bucket_lifecycle(bucket),
+                client.get_bucket_website(bucket),
+                client.get_bucket_managed_domain(bucket),
+                client.list_bucket_custom_domains(bucket),
+            );
+            BucketAudit::from_settings(
+                bucket,
+                cors,
+                lifecycle,
+                website,
+                managed_domain,
+                custom_domains,
+            )
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloudflare::{CorsPreset, IndexDocument, LifecyclePreset};
+
+    fn not_found<T>() -> Result<T> {
+        Err(Error::NotFound("Resource not found".to_string()))
+    }
+
+    #[test]
+    fn test_audit_exposed_bucket() {
+        let managed = ManagedDomain {
+            domain: "pub-0123.r2.dev".to_string(),
+            enabled: true,
+        };
+        let custom = vec![
+            CustomDomain {
+                domain: "cdn.example.com".to_string(),
+                enabled: true,
+            },
+            CustomDomain {
+                domain: "old.example.com".to_string(),
+                enabled: false,
+            },
+        ];
+        let audit = BucketAudit::from_settings(
+            "assets",
+            CorsPreset::All.config(None),
+            not_found(),
+            Ok(WebsiteConfiguration::default()),
+            Ok(managed),
+            Ok(custom),
+        );
+
+        assert_eq!(
+            audit.public_domains.as_deref(),
+            Some(&["pub-0123.r2.dev".to_string(), "cdn.example.com".to_string()][..])
+        );
+        assert_eq!(audit.website, Some(false));
+        assert_eq!(audit.lifecycle_rules, Some(0));
+        assert_eq!(audit.cors_any_origin, Some(true));
+        assert!(audit.errors.is_empty());
+        assert_eq!(
+            audit.findings(),
+            vec![
+                "publicly exposed",
+                "CORS allows any origin",
+                "no lifecycle rules"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit_keeps_going_on_errors() {
+        let website = WebsiteConfiguration {
+            index_document: Some(IndexDocument {
+                suffix: "index.html".to_string(),
+            }),
+            error_document: None,
+        };
+        let audit = BucketAudit::from_settings(
+            "site",
+            not_found(),
+            LifecyclePreset::ExpireTmp.config(None),
+            Ok(website),
+            Err(Error::PermissionDenied(
+                "Insufficient permissions".to_string(),
+            )),
+            Ok(Vec::new()),
+        );
+
+        // The website alone makes it public; the domains are unknown
+        assert!(audit.public_domains.is_none());
+        assert_eq!(audit.website, Some(true));
+        assert_eq!(audit.lifecycle_rules, Some(1));
+        assert_eq!(audit.cors_any_origin, Some(false));
+        assert_eq!(audit.errors.len(), 1);
+        assert!(audit.errors[0].starts_with("r2.dev domain: Permission denied"));
+        assert_eq!(audit.findings(), vec!["publicly exposed"]);
+    }
+}
@@ -0,0 +1,509 @@
+//! Batch operations over many objects
+
+use crate::client::{MultipartUploadInfo, ObjectInfo, ObjectMetadata};
+use crate::error::{Error, Result};
+use crate::operations::{list_objects_concurrent, R2Operations};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::HashMap;
+
+/// Result of moving a single object
+#[derive(Debug)]
+pub enum MoveStatus {
+    /// Copied to the destination and original deleted
+    Moved,
+    /// Copy failed, the original is untouched
+    CopyFailed(Error),
+    /// Copied, but the original could not be deleted (object now exists twice)
+    DeleteFailed(Error),
+}
+
+/// Outcome of moving one object
+#[derive(Debug)]
+pub struct MoveOutcome {
+    pub source: String,
+    pub dest: String,
+    pub status: MoveStatus,
+}
+
+/// Map keys under `from` to the same relative keys under `to`
+///
+/// Keys that don't start with `from` are skipped.
+pub fn plan_prefix_move(keys: &[String], from: &str, to: &str) -> Vec<(String, String)> {
+    keys.iter()
+        .filter_map(|key| {
+            key.strip_prefix(from)
+                .map(|rest| (key.clone(), format!("{}{}", to, rest)))
+        })
+        .collect()
+}
+
+/// Move every object under `from` to `to` (copy then delete), concurrently
+///
+/// Returns one outcome per object; a failure on one object doesn't stop the others.
+pub async fn move_prefix(
+    ops: &dyn R2Operations,
+    from: &str,
+    to: &str,
+    concurrency: usize,
+) -> Result<Vec<MoveOutcome>> {
+    if from.is_empty() {
+        return Err(Error::InvalidInput(
+            "Source prefix cannot be empty".to_string(),
+        ));
+    }
+    if from == to {
+        return Err(Error::InvalidInput(
+            "Source and destination prefixes are identical".to_string(),
+        ));
+    }
+
+    let keys: Vec<String> = list_objects_concurrent(ops, Some(from), concurrency)
+        .await?
+        .into_iter()
+        .map(|o| o.key)
+        .collect();
+
+    Ok(move_objects(ops, plan_prefix_move(&keys, from, to), concurrency).await)
+}
+
+/// Move a set of (source, dest) pairs, concurrently
+pub async fn move_objects(
+    ops: &dyn R2Operations,
+    pairs: Vec<(String, String)>,
+    concurrency: usize,
+) -> Vec<MoveOutcome> {
+    stream::iter(pairs)
+        .map(|(source, dest)| async move {
+            let status = match ops.copy_object(&source, &dest).await {
+                Err(e) => MoveStatus::CopyFailed(e),
+                Ok(()) => match ops.delete_object(&source).await {
+                    Ok(()) => MoveStatus::Moved,
+                    Err(e) => MoveStatus::DeleteFailed(e),
+                },
+            };
+            MoveOutcome {
+                source,
+                dest,
+                status,
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// What [`copy_bucket`] copied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketCopy {
+    pub objects: usize,
+    pub bytes: u64,
+    /// Every key copied, so the source can be emptied of exactly these
+    pub keys: Vec<String>,
+}
+
+/// Copy every object of `source` to the bucket of `dest`, server-side
+///
+/// Copies run at most `concurrency` at a time through `copy_object_to`, so
+/// each object keeps its Content-Type and metadata. The first failing copy
+/// stops the others and is returned; objects already copied stay in `dest`.
+/// Once done, `dest` is listed and must hold every source key at the same size.
+pub async fn copy_bucket(
+    source: &dyn R2Operations,
+    dest: &dyn R2Operations,
+    concurrency: usize,
+) -> Result<BucketCopy> {
+    let objects = list_objects_concurrent(source, None, concurrency).await?;
+    let dest_bucket = dest.bucket();
+
+    stream::iter(&objects)
+        .map(Ok)
+        .try_for_each_concurrent(concurrency.max(1), |object| async move {
+            source
+                .copy_object_to(&object.key, dest_bucket, &object.key, None)
+                .await
+                .map_err(|e| Error::R2Operation(format!("Copying '{}': {}", object.key, e)))
+        })
+        .await?;
+
+    let copied = list_objects_concurrent(dest, None, concurrency).await?;
+    verify_bucket_copy(&objects, &copied)
+}
+
+/// Check that `dest` holds every object of `source` at the same size
+fn verify_bucket_copy(source: &[ObjectInfo], dest: &[ObjectInfo]) -> Result<BucketCopy> {
+    let sizes: HashMap<&str, i64> = dest.iter().map(|o| (o.key.as_str(), o.size)).collect();
+
+    let missing = source
+        .iter()
+        .filter(|o| sizes.get(o.key.as_str()) != Some(&o.size))
+        .count();
+    if missing > 0 {
+        return Err(Error::R2Operation(format!(
+            "{} of {} object(s) missing or different in the destination",
+            missing,
+            source.len()
+        )));
+    }
+
+    Ok(BucketCopy {
+        objects: source.len(),
+        bytes: source.iter().map(|o| o.size.max(0) as u64).sum(),
+        keys: source.iter().map(|o| o.key.clone()).collect(),
+    })
+}
+
+/// HEAD every key, at most `concurrency` at a time
+///
+/// Results come back in the order of `keys`, one per key, so a missing or
+/// unreadable object doesn't hide the others.
+pub async fn head_objects(
+    ops: &dyn R2Operations,
+    keys: Vec<String>,
+    concurrency: usize,
+) -> Vec<(String, Result<ObjectMetadata>)> {
+    stream::iter(keys)
+        .map(|key| async move {
+            let result = ops.head_object(&key).await;
+            (key, result)
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Total size of the parts already uploaded to `uploads`, listed at most
+/// `concurrency` at a time
+///
+/// Uploads completed or aborted since they were listed count as empty.
+pub async fn multipart_uploads_size(
+    ops: &dyn R2Operations,
+    uploads: &[MultipartUploadInfo],
+    concurrency: usize,
+) -> Result<u64> {
+    let sizes: Vec<Result<u64>> = stream::iter(uploads)
+        .map(|upload| async move {
+            match ops
+                .multipart_upload_size(&upload.key, &upload.upload_id)
+                .await
+            {
+                Err(Error::NotFound(_)) => Ok(0),
+                result => result,
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    sizes.into_iter().sum()
+}
+
+/// Keys listed one per line, e.g. in a `--keys-file`
+///
+/// Only the line terminator (`\n` or `\r\n`) is stripped, since spaces can be
+/// part of a key; empty lines and lines starting with `#` are skipped, and
+/// repeated keys are kept once.
+pub fn parse_key_list(content: &str) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    content
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|key| seen.insert(*key))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ClientOptions, R2Client};
+    use crate::mock::MockR2;
+    use crate::test_support::MockServer;
+
+    #[test]
+    fn test_parse_key_list() {
+        let content = "# stale exports\nexports/a.csv\r\n\nexports/b.csv\n# done\nexports/a.csv\n";
+        assert_eq!(
+            parse_key_list(content),
+            vec!["exports/a.csv", "exports/b.csv"]
+        );
+        // Leading and trailing spaces belong to the key
+        assert_eq!(
+            parse_key_list(" padded key \r\nnotes/ draft.txt\n"),
+            vec![" padded key ", "notes/ draft.txt"]
+        );
+        assert!(parse_key_list("\n# nothing\n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_uploads_size() {
+        let mock = MockR2::new("test-bucket");
+        assert!(mock.list_multipart_uploads().await.unwrap().is_empty());
+
+        let big = mock
+            .create_multipart_upload("backups/big.tar", "application/x-tar", None)
+            .await
+            .unwrap();
+        mock.upload_part("backups/big.tar", &big, 1, vec![0; 100])
+            .await
+            .unwrap();
+        mock.upload_part("backups/big.tar", &big, 2, vec![0; 50])
+            .await
+            .unwrap();
+        mock.create_multipart_upload("empty.bin", "application/octet-stream", None)
+            .await
+            .unwrap();
+
+        let uploads = mock.list_multipart_uploads().await.unwrap();
+        let keys: Vec<&str> = uploads.iter().map(|u| u.key.as_str()).collect();
+        assert_eq!(keys, vec!["backups/big.tar", "empty.bin"]);
+        assert!(uploads.iter().all(|u| u.initiated.is_some()));
+        assert_eq!(
+            multipart_uploads_size(&mock, &uploads, 4).await.unwrap(),
+            150
+        );
+
+        // An upload aborted after the listing no longer counts
+        mock.abort_multipart_upload("backups/big.tar", &big)
+            .await
+            .unwrap();
+        assert_eq!(multipart_uploads_size(&mock, &uploads, 4).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_head_objects_keeps_order() {
+        let mock = MockR2::new("test-bucket");
+        mock.insert("site/index.html", vec![1], "text/html");
+        mock.insert("site/app.js", vec![2], "application/javascript");
+
+        let keys = ["site/index.html", "site/gone.css", "site/app.js"].map(str::to_string);
+        let results = head_objects(&mock, keys.to_vec(), 2).await;
+
+        let summary: Vec<(&str, Option<&str>)> = results
+            .iter()
+            .map(|(key, result)| {
+                let content_type = result.as_ref().ok().map(|m| m.content_type.as_str());
+                (key.as_str(), content_type)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("site/index.html", Some("text/html")),
+                ("site/gone.css", None),
+                ("site/app.js", Some("application/javascript")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_try_delete_objects_reports_each_key() {
+        let mock = MockR2::new("test-bucket");
+        mock.insert("a.txt", vec![1], "text/plain");
+        mock.insert("b.txt", vec![2], "text/plain");
+
+        let outcomes = mock
+            .try_delete_objects(vec!["a.txt".to_string(), "missing.txt".to_string()])
+            .await
+            .unwrap();
+
+        let keys: Vec<&str> = outcomes.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, vec!["a.txt", "missing.txt"]);
+        assert!(outcomes.iter().all(|o| o.result.is_ok()));
+        assert_eq!(mock.keys(), vec!["b.txt"]);
+    }
+
+    #[test]
+    fn test_plan_prefix_move() {
+        let keys = vec![
+            "old/a.txt".to_string(),
+            "old/sub/b.txt".to_string(),
+            "other/c.txt".to_string(),
+        ];
+
+        let plan = plan_prefix_move(&keys, "old/", "new/");
+
+        assert_eq!(
+            plan,
+            vec![
+                ("old/a.txt".to_string(), "new/a.txt".to_string()),
+                ("old/sub/b.txt".to_string(), "new/sub/b.txt".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_prefix() {
+        let mock = MockR2::new("test-bucket");
+        mock.insert("old/a.txt", b"a".to_vec(), "text/plain");
+        mock.insert("old/sub/b.txt", b"b".to_vec(), "text/plain");
+        mock.insert("keep/c.txt", b"c".to_vec(), "text/plain");
+
+        let outcomes = move_prefix(&mock, "old/", "new/", 4).await.unwrap();
+
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes
+            .iter()
+            .all(|o| matches!(o.status, MoveStatus::Moved)));
+        assert_eq!(
+            mock.keys(),
+            vec!["keep/c.txt", "new/a.txt", "new/sub/b.txt"]
+        );
+        assert_eq!(mock.get("new/sub/b.txt").unwrap().body, b"b");
+    }
+
+    #[tokio::test]
+    async fn test_move_prefix_rejects_same_prefix() {
+        let mock = MockR2::new("test-bucket");
+
+        assert!(move_prefix(&mock, "a/", "a/", 1).await.is_err());
+        assert!(move_prefix(&mock, "", "b/", 1).await.is_err());
+    }
+
+    fn object(key: &str, size: i64) -> ObjectInfo {
+        ObjectInfo {
+            key: key.to_string(),
+            size,
+            last_modified: None,
+            etag: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_verify_bucket_copy() {
+        let source = vec![object("a.txt", 3), object("dir/b.bin", 10)];
+
+        let copy = verify_bucket_copy(&source, &[object("dir/b.bin", 10), object("a.txt", 3)]);
+        assert_eq!(
+            copy.unwrap(),
+            BucketCopy {
+                objects: 2,
+                bytes: 13,
+                keys: vec!["a.txt".to_string(), "dir/b.bin".to_string()],
+            }
+        );
+
+        // A missing object or a size mismatch fails the check
+        assert!(verify_bucket_copy(&source, &[object("a.txt", 3)]).is_err());
+        assert!(
+            verify_bucket_copy(&source, &[object("a.txt", 3), object("dir/b.bin", 9)]).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_bucket_stops_on_copy_failure() {
+        let source = MockR2::new("old-bucket");
+        source.insert("a.txt", b"a".to_vec(), "text/plain");
+        let dest = MockR2::new("new-bucket");
+
+        // The mock can't copy across buckets
+        let err = copy_bucket(&source, &dest, 4).await.unwrap_err();
+        assert!(err.to_string().contains("a.txt"));
+        assert_eq!(source.keys(), vec!["a.txt"]);
+        assert!(dest.keys().is_empty());
+
+        // An empty bucket copies nothing and verifies
+        let empty = MockR2::new("empty");
+        assert_eq!(copy_bucket(&empty, &dest, 4).await.unwrap().objects, 0);
+    }
+
+    /// S3 client for `bucket` on a local mock server
+    async fn s3_client(server: &MockServer, bucket: &str) -> R2Client {
+        R2Client::new_with_options(
+            server.url(),
+            "key".to_string(),
+            "secret".to_string(),
+            bucket.to_string(),
+            ClientOptions {
+                force_path_style: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    /// A ListObjectsV2 response holding `objects` (key, size)
+    fn listing(objects: &[(&str, i64)]) -> (u16, String) {
+        let contents: String = objects
+            .iter()
+            .map(|(key, size)| {
+                format!(
+                    "<Contents><Key>{}</Key><Size>{}</Size><ETag>\"e\"</ETag></Contents>",
+                    key, size
+                )
+            })
+            .collect();
+        (
+            200,
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><Name>b</Name>{}<IsTruncated>false</IsTruncated></ListBucketResult>",
+                contents
+            ),
+        )
+    }
+
+    fn copied() -> (u16, String) {
+        (
+            200,
+            "<CopyObjectResult><ETag>\"e\"</ETag></CopyObjectResult>".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_copy_bucket_copies_every_object() {
+        let objects = [("a.txt", 3), ("dir/b.bin", 10)];
+        let source_server = MockServer::start(vec![listing(&objects), copied(), copied()]).await;
+        let dest_server = MockServer::start(vec![listing(&objects)]).await;
+        let source = s3_client(&source_server, "old-bucket").await;
+        let dest = s3_client(&dest_server, "new-bucket").await;
+
+        let copy = copy_bucket(&source, &dest, 1).await.unwrap();
+        assert_eq!(
+            copy,
+            BucketCopy {
+                objects: 2,
+                bytes: 13,
+                keys: vec!["a.txt".to_string(), "dir/b.bin".to_string()],
+            }
+        );
+
+        // Both copies go to the new bucket, from the old one
+        let requests = source_server.requests();
+        assert_eq!(requests.len(), 3);
+        for (request, key) in requests[1..].iter().zip(["a.txt", "dir/b.bin"]) {
+            assert_eq!(request.method, "PUT");
+            assert!(request.path.starts_with(&format!("/new-bucket/{}", key)));
+            assert!(request
+                .headers
+                .contains(&format!("x-amz-copy-source: old-bucket/{}", key)));
+        }
+        assert_eq!(dest_server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_copy_bucket_fails_on_short_destination() {
+        let source_server = MockServer::start(vec![
+            listing(&[("a.txt", 3), ("dir/b.bin", 10)]),
+            copied(),
+            copied(),
+        ])
+        .await;
+        let dest_server = MockServer::start(vec![listing(&[("a.txt", 3)])]).await;
+        let source = s3_client(&source_server, "old-bucket").await;
+        let dest = s3_client(&dest_server, "new-bucket").await;
+
+        let err = copy_bucket(&source, &dest, 1).await.unwrap_err();
+        assert!(err.to_string().contains("1 of 2 object(s)"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_move_objects_reports_copy_failure() {
+        let mock = MockR2::new("test-bucket");
+
+        let outcomes =
+            move_objects(&mock, vec![("missing".to_string(), "dest".to_string())], 1).await;
+
+        assert!(matches!(outcomes[0].status, MoveStatus::CopyFailed(_)));
+        assert!(mock.keys().is_empty());
+    }
+}
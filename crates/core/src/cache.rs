@@ -0,0 +1,285 @@
+//! Local cache of downloaded objects, keyed by bucket, key and ETag
+
+use crate::config::get_config_dir;
+use crate::error::{Error, Result};
+use crate::operations::{download_file_verified, R2Operations};
+use md5::{Digest, Md5};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Default size cap of the download cache in MB
+pub const DEFAULT_CACHE_MAX_SIZE_MB: u64 = 1024;
+
+/// Where a cached download came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// The cached copy matched the object's current ETag
+    Hit,
+    /// The object was downloaded (not cached yet, or changed since)
+    Miss,
+}
+
+/// Downloaded objects stored as `<dir>/<md5(bucket/key)>/<etag>`
+///
+/// Only the latest version of each key is kept. Once the cache grows past
+/// `max_size` bytes, the least recently used entries (by file modification
+/// time, refreshed on every hit) are removed.
+#[derive(Debug, Clone)]
+pub struct DownloadCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl DownloadCache {
+    pub fn new(dir: impl Into<PathBuf>, max_size: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size,
+        }
+    }
+
+    /// The default location, `cache/` under the config directory
+    pub fn default_dir() -> Result<PathBuf> {
+        Ok(get_config_dir()?.join("cache"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn object_dir(&self, bucket: &str, key: &str) -> PathBuf {
+        let digest = Md5::digest(format!("{}/{}", bucket, key).as_bytes());
+        let name: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        self.dir.join(name)
+    }
+
+    /// Path of the entry for one version of an object
+    pub fn entry_path(&self, bucket: &str, key: &str, etag: &str) -> PathBuf {
+        let etag: String = etag
+            .trim_matches('"')
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.object_dir(bucket, key).join(etag)
+    }
+
+    /// The cached copy of this version, marked as recently used
+    pub fn get(&self, bucket: &str, key: &str, etag: &str) -> Option<PathBuf> {
+        let path = self.entry_path(bucket, key, etag);
+        let file = std::fs::File::open(&path).ok()?;
+        if let Err(e) = file.set_modified(SystemTime::now()) {
+            tracing::debug!(path = %path.display(), error = %e, "failed to refresh cache entry");
+        }
+        Some(path)
+    }
+
+    /// Store a copy of `src` as this version, replacing older ones, then evict
+    pub fn insert(&self, bucket: &str, key: &str, etag: &str, src: &Path) -> Result<()> {
+        let object_dir = self.object_dir(bucket, key);
+        if object_dir.exists() {
+            std::fs::remove_dir_all(&object_dir).map_err(Error::Io)?;
+        }
+        std::fs::create_dir_all(&object_dir).map_err(Error::Io)?;
+
+        // Copy then rename, so a reader never sees a partial entry
+        let path = self.entry_path(bucket, key, etag);
+        let partial = path.with_extension("partial");
+        std::fs::copy(src, &partial).map_err(Error::Io)?;
+        std::fs::rename(&partial, &path).map_err(Error::Io)?;
+
+        self.evict()?;
+        Ok(())
+    }
+
+    /// Total size of the cached files in bytes
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Remove least recently used entries until the cache fits its cap
+    ///
+    /// Returns the number of bytes freed.
+    pub fn evict(&self) -> Result<u64> {
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(used, _, _)| *used);
+
+        let mut freed = 0;
+        for (_, size, path) in entries {
+            if total <= self.max_size {
+                break;
+            }
+            std::fs::remove_file(&path).map_err(Error::Io)?;
+            if let Some(parent) = path.parent() {
+                // Only succeeds once the key has no entry left
+                let _ = std::fs::remove_dir(parent);
+            }
+            total -= size;
+            freed += size;
+        }
+
+        Ok(freed)
+    }
+
+    /// Every entry as (last used, size, path)
+    fn entries(&self) -> Result<Vec<(SystemTime, u64, PathBuf)>> {
+        let mut entries = Vec::new();
+        let dirs = match std::fs::read_dir(&self.dir) {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        for object_dir in dirs {
+            let object_dir = object_dir.map_err(Error::Io)?;
+            if !object_dir.file_type().map_err(Error::Io)?.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(object_dir.path()).map_err(Error::Io)? {
+                let entry = entry.map_err(Error::Io)?;
+                let metadata = entry.metadata().map_err(Error::Io)?;
+                if metadata.is_file() {
+                    let used = metadata.modified().map_err(Error::Io)?;
+                    entries.push((used, metadata.len(), entry.path()));
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Download `key` to `dest_path`, from the cache when its ETag is unchanged
+    ///
+    /// The ETag is checked with a HEAD request first. On a miss the object is
+    /// downloaded like [`download_file_verified`] and cached, unless it
+    /// changed while downloading (the ETag is checked again afterwards).
+    pub async fn download<O: R2Operations + ?Sized>(
+        &self,
+        ops: &O,
+        key: &str,
+        dest_path: &Path,
+        max_retries: u32,
+        retry_delay: Duration,
+    ) -> Result<(u64, CacheStatus)> {
+        let bucket = ops.bucket();
+        let metadata = ops.head_object(key).await?;
+        let expected = metadata.size.max(0) as u64;
+
+        if let Some(cached) = self.get(bucket, key, &metadata.etag) {
+            let size = std::fs::metadata(&cached).map_err(Error::Io)?.len();
+            if size == expected {
+                if let Some(parent) = dest_path.parent() {
+                    tokio::fs::create_dir_all(parent).await.map_err(Error::Io)?;
+                }
+                tokio::fs::copy(&cached, dest_path)
+                    .await
+                    .map_err(Error::Io)?;
+                return Ok((size, CacheStatus::Hit));
+            }
+        }
+
+        let size = download_file_verified(ops, key, dest_path, max_retries, retry_delay).await?;
+
+        if ops.head_object(key).await?.etag == metadata.etag {
+            if let Err(e) = self.insert(bucket, key, &metadata.etag, dest_path) {
+                tracing::warn!(key, error = %e, "failed to store download in cache");
+            }
+        }
+
+        Ok((size, CacheStatus::Miss))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockR2;
+
+    fn set_used(path: &Path, secs_ago: u64) {
+        let file = std::fs::File::open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(secs_ago))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_cache_hit_and_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path().join("cache"), 1024 * 1024);
+        let dest = dir.path().join("out/a.txt");
+
+        let mock = MockR2::new("test-bucket");
+        mock.upload_bytes("a.txt", b"first".to_vec(), "text/plain")
+            .await
+            .unwrap();
+
+        let (size, status) = cache
+            .download(&mock, "a.txt", &dest, 0, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!((size, status), (5, CacheStatus::Miss));
+        assert_eq!(cache.size().unwrap(), 5);
+
+        std::fs::remove_file(&dest).unwrap();
+        let (_, status) = cache
+            .download(&mock, "a.txt", &dest, 0, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(status, CacheStatus::Hit);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"first");
+
+        // A new ETag is a miss and replaces the old entry
+        mock.upload_bytes("a.txt", b"second!".to_vec(), "text/plain")
+            .await
+            .unwrap();
+        let (_, status) = cache
+            .download(&mock, "a.txt", &dest, 0, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(status, CacheStatus::Miss);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"second!");
+        assert_eq!(cache.size().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_download_cache_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::write(&src, [0u8; 10]).unwrap();
+        let cache = DownloadCache::new(dir.path().join("cache"), 25);
+
+        cache.insert("b", "old", "\"e1\"", &src).unwrap();
+        cache.insert("b", "used", "\"e2\"", &src).unwrap();
+        set_used(&cache.entry_path("b", "old", "\"e1\""), 200);
+        set_used(&cache.entry_path("b", "used", "\"e2\""), 100);
+
+        // A hit makes "used" the most recent entry
+        assert!(cache.get("b", "used", "\"e2\"").is_some());
+        assert!(cache.get("b", "used", "\"other\"").is_none());
+
+        cache.insert("b", "new", "\"e3\"", &src).unwrap();
+
+        assert!(cache.get("b", "old", "\"e1\"").is_none());
+        assert!(cache.get("b", "used", "\"e2\"").is_some());
+        assert!(cache.get("b", "new", "\"e3\"").is_some());
+        assert_eq!(cache.size().unwrap(), 20);
+        assert_eq!(cache.evict().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_download_cache_entry_path() {
+        let cache = DownloadCache::new("/tmp/cache", 0);
+        let path = cache.entry_path("bucket", "dir/file.txt", "\"abc123-4\"");
+        assert!(path.ends_with("abc123-4"));
+        assert_ne!(
+            cache.entry_path("bucket", "a", "e").parent(),
+            cache.entry_path("other", "a", "e").parent()
+        );
+        assert!(DownloadCache::new("/tmp/cache", 0).size().is_ok());
+    }
+}
@@ -1,15 +1,36 @@
 //! R2 Client implementation using AWS S3 SDK
 
 use crate::error::{Error, Result};
+use crate::operations::R2Operations;
+use crate::presigned::ResponseHeaders;
+use crate::rate::{RateLimiter, ThrottledBody};
+use crate::timings::Timings;
+use aws_sdk_s3::config::SharedHttpClient;
 use aws_sdk_s3::{
-    config::{Credentials, Region},
+    config::{
+        interceptors::BeforeTransmitInterceptorContextMut, BehaviorVersion, ConfigBag, Credentials,
+        Intercept, Region, RuntimeComponents,
+    },
+    error::BoxError,
+    presigning::PresigningConfig,
     primitives::ByteStream,
     Client,
 };
+use aws_smithy_http_client::tls;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use futures::stream::{self, Stream, TryStreamExt};
+use http_body::{Frame, SizeHint};
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
-use std::time::Duration;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
 
 // === Multipart Upload Types ===
 
@@ -20,6 +41,28 @@ pub struct MultipartUploadConfig {
     pub chunk_size: usize,
     /// Maximum concurrent uploads (default: 5)
     pub concurrent_parts: usize,
+    /// Retries for a part failing with a transient error (default: 3)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each attempt (default: 1s)
+    pub retry_delay: Duration,
+    /// Cancelling aborts the upload and returns `Error::Cancelled`
+    pub cancel: CancellationToken,
+    /// `Content-Disposition` stored with the object
+    pub content_disposition: Option<String>,
+    /// Receives an event once the upload starts and after each completed part
+    ///
+    /// Events are sent with `try_send`, so a full channel drops them rather
+    /// than slowing the upload; each event carries cumulative totals.
+    pub progress: Option<tokio::sync::mpsc::Sender<MultipartUploadProgress>>,
+}
+
+impl MultipartUploadConfig {
+    /// Send a progress event, building it only when someone is listening
+    pub(crate) fn report(&self, event: impl FnOnce() -> MultipartUploadProgress) {
+        if let Some(progress) = &self.progress {
+            let _ = progress.try_send(event());
+        }
+    }
 }
 
 impl Default for MultipartUploadConfig {
@@ -27,6 +70,11 @@ impl Default for MultipartUploadConfig {
         Self {
             chunk_size: 100 * 1024 * 1024, // 100MB
             concurrent_parts: 5,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            cancel: CancellationToken::new(),
+            content_disposition: None,
+            progress: None,
         }
     }
 }
@@ -35,9 +83,11 @@ impl Default for MultipartUploadConfig {
 #[derive(Debug, Clone)]
 pub struct MultipartUploadProgress {
     pub upload_id: String,
+    /// `0` when the size is unknown (streamed input)
     pub total_bytes: u64,
     pub uploaded_bytes: u64,
     pub completed_parts: usize,
+    /// `0` when the size is unknown (streamed input)
     pub total_parts: usize,
 }
 
@@ -48,10 +98,452 @@ pub struct CompletedPart {
     pub etag: String,
 }
 
+// === Upload Options ===
+
+/// Compression applied to an object body before upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Value stored in the object's `Content-Encoding` header
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Compress a buffer in memory
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Zstd => Ok(zstd::encode_all(data, 0)?),
+        }
+    }
+
+    /// Compression named by a `Content-Encoding` header, if any
+    ///
+    /// An empty value or `identity` means no compression; any other encoding
+    /// than gzip or zstd is an error, since it can't be decoded.
+    pub fn from_content_encoding(value: &str) -> Result<Option<Self>> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Compression::Gzip)),
+            "zstd" => Ok(Some(Compression::Zstd)),
+            other => Err(Error::InvalidInput(format!(
+                "Unsupported Content-Encoding: {} (only gzip and zstd can be decompressed)",
+                other
+            ))),
+        }
+    }
+
+    /// Stream-decompress `reader` into `writer`, returning the decoded size
+    pub fn decompress_to(
+        &self,
+        reader: impl std::io::Read,
+        writer: &mut impl std::io::Write,
+    ) -> Result<u64> {
+        let decoded = match self {
+            Compression::Gzip => {
+                std::io::copy(&mut flate2::read::MultiGzDecoder::new(reader), writer)
+            }
+            Compression::Zstd => {
+                std::io::copy(&mut zstd::stream::read::Decoder::new(reader)?, writer)
+            }
+        };
+        decoded.map_err(|e| Error::InvalidInput(format!("Invalid {} data: {}", self, e)))
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.content_encoding())
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(Error::InvalidInput(format!(
+                "Unsupported compression: {} (expected gzip or zstd)",
+                s
+            ))),
+        }
+    }
+}
+
+/// R2 storage class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageClass {
+    Standard,
+    /// Cheaper storage with a retrieval fee and a 30-day minimum
+    InfrequentAccess,
+}
+
+impl StorageClass {
+    /// Value sent in the `x-amz-storage-class` header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageClass::Standard => "STANDARD",
+            StorageClass::InfrequentAccess => "STANDARD_IA",
+        }
+    }
+}
+
+impl std::fmt::Display for StorageClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for StorageClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "standard" => Ok(StorageClass::Standard),
+            "standard_ia" | "infrequent_access" | "ia" => Ok(StorageClass::InfrequentAccess),
+            _ => Err(Error::InvalidInput(format!(
+                "Unsupported storage class: {} (expected STANDARD or STANDARD_IA)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Extra options for a single-request upload
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    /// Compress the body and set `Content-Encoding` (content type is kept as-is)
+    pub compression: Option<Compression>,
+    /// `Content-Disposition` stored with the object (e.g. `attachment`)
+    pub content_disposition: Option<String>,
+    /// Only overwrite if the current ETag matches (`If-Match`)
+    pub if_match: Option<String>,
+    /// Only write if no object matches (`If-None-Match`, usually `*`)
+    pub if_none_match: Option<String>,
+    /// `Content-Type` stored instead of the one passed to the upload
+    pub content_type: Option<String>,
+    /// `Content-Encoding` of an already encoded body (not with `compression`)
+    pub content_encoding: Option<String>,
+    /// `Cache-Control` stored with the object
+    pub cache_control: Option<String>,
+    /// `Content-Language` stored with the object
+    pub content_language: Option<String>,
+    /// User metadata, sent as `x-amz-meta-<name>` headers
+    pub metadata: HashMap<String, String>,
+    /// Per-file headers for multi-file uploads (`[deploy.rules]`), ignored by
+    /// single-file uploads
+    pub deploy_rules: crate::sync::DeployRules,
+}
+
+impl UploadOptions {
+    /// Set the option matching an HTTP header, as given to `--header`
+    ///
+    /// `x-amz-meta-*` headers become user metadata. Headers the SDK has no
+    /// setter for are rejected.
+    pub fn set_header(&mut self, name: &str, value: &str) -> Result<()> {
+        let lower = name.to_ascii_lowercase();
+
+        if let Some(meta) = lower.strip_prefix("x-amz-meta-") {
+            if meta.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "Header '{}' has no metadata name",
+                    name
+                )));
+            }
+            self.metadata.insert(meta.to_string(), value.to_string());
+            return Ok(());
+        }
+
+        let field = match lower.as_str() {
+            "content-type" => &mut self.content_type,
+            "content-encoding" => &mut self.content_encoding,
+            "cache-control" => &mut self.cache_control,
+            "content-language" => &mut self.content_language,
+            "content-disposition" => {
+                validate_content_disposition(value)?;
+                &mut self.content_disposition
+            }
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Header '{}' can't be set on uploads (supported: Cache-Control, \
+                     Content-Disposition, Content-Encoding, Content-Language, Content-Type, \
+                     x-amz-meta-*)",
+                    name
+                )))
+            }
+        };
+        *field = Some(value.to_string());
+        Ok(())
+    }
+
+    /// `Content-Encoding` to store: the compression's, or the one set explicitly
+    pub(crate) fn effective_content_encoding(&self) -> Result<Option<String>> {
+        match (self.compression, &self.content_encoding) {
+            (Some(_), Some(_)) => Err(Error::InvalidInput(
+                "A Content-Encoding header can't be combined with compression".to_string(),
+            )),
+            (Some(compression), None) => Ok(Some(compression.content_encoding().to_string())),
+            (None, encoding) => Ok(encoding.clone()),
+        }
+    }
+}
+
+/// Headers and user metadata changed by [`R2Client::update_metadata`]
+///
+/// Unset headers keep their current value. `metadata` entries are added to
+/// the object's user metadata, replacing values of the same name.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataUpdate {
+    pub content_type: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_language: Option<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl MetadataUpdate {
+    /// Whether the update changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.content_type.is_none()
+            && self.cache_control.is_none()
+            && self.content_disposition.is_none()
+            && self.content_encoding.is_none()
+            && self.content_language.is_none()
+            && self.metadata.is_empty()
+    }
+}
+
+/// Parse a `--metadata` value, `name=value`
+pub fn parse_metadata_entry(input: &str) -> Result<(String, String)> {
+    match input.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_ascii_lowercase(), value.to_string()))
+        }
+        _ => Err(Error::InvalidInput(format!(
+            "Invalid metadata '{}' (expected 'name=value')",
+            input
+        ))),
+    }
+}
+
+/// Parse a `Name: Value` HTTP header
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    let (name, value) = input.split_once(':').ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Invalid header '{}' (expected 'Name: Value')",
+            input
+        ))
+    })?;
+
+    let name = name.trim();
+    let value = value.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+    {
+        return Err(Error::InvalidInput(format!(
+            "Invalid header name in '{}'",
+            input
+        )));
+    }
+    if value.chars().any(|c| c.is_control()) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid header value in '{}' (control characters are not allowed)",
+            input
+        )));
+    }
+
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Check that a value is a well-formed `Content-Disposition` header
+///
+/// Accepts a disposition type (`inline`, `attachment`, ...) followed by
+/// optional `; name=value` parameters, where values are tokens or quoted strings.
+pub fn validate_content_disposition(value: &str) -> Result<()> {
+    fn is_token(s: &str) -> bool {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+    }
+
+    fn is_param_value(s: &str) -> bool {
+        match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Some(quoted) => {
+                // Inner quotes must be escaped with a backslash
+                let mut escaped = false;
+                quoted.chars().all(|c| {
+                    let ok = escaped || c != '"';
+                    escaped = !escaped && c == '\\';
+                    ok
+                }) && !escaped
+            }
+            None => is_token(s),
+        }
+    }
+
+    let invalid = |reason: &str| {
+        Err(Error::InvalidInput(format!(
+            "Invalid Content-Disposition '{}': {}",
+            value, reason
+        )))
+    };
+
+    if value.chars().any(|c| c.is_control()) {
+        return invalid("control characters are not allowed");
+    }
+
+    let mut parts = value.split(';').map(str::trim);
+    let disposition_type = parts.next().unwrap_or_default();
+    if !is_token(disposition_type) {
+        return invalid("expected a type such as inline or attachment");
+    }
+
+    for param in parts {
+        let Some((name, param_value)) = param.split_once('=') else {
+            return invalid("parameters must be name=value");
+        };
+        if !is_token(name.trim()) || !is_param_value(param_value.trim()) {
+            return invalid("malformed parameter");
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a file is already in a compressed format (images, video, archives...)
+///
+/// Compressing these again wastes CPU for little or no size reduction.
+pub fn is_compressed_format(path: &Path) -> bool {
+    const COMPRESSED_EXTENSIONS: &[&str] = &[
+        "jpg", "jpeg", "png", "gif", "webp", "avif", "mp4", "mov", "mkv", "webm", "mp3", "ogg",
+        "zip", "gz", "tgz", "zst", "bz2", "xz", "7z", "rar", "br", "woff", "woff2",
+    ];
+
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| COMPRESSED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Extra settings for building an `R2Client`
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// Address buckets as `<endpoint>/<bucket>` instead of `<bucket>.<endpoint>`
+    pub force_path_style: bool,
+    /// `User-Agent` header (defaults to [`DEFAULT_USER_AGENT`])
+    pub user_agent: Option<String>,
+    /// Upload bandwidth cap in bytes per second, shared by all uploads
+    pub max_rate: Option<u64>,
+    /// Extra trusted root certificates (PEM, see [`load_ca_bundle`])
+    pub ca_bundle: Option<Vec<u8>>,
+    /// Where request durations are recorded, shared with the caller
+    pub timings: Option<Arc<Timings>>,
+}
+
+/// TLS settings for the Cloudflare API client
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// Extra trusted root certificates (PEM, see [`load_ca_bundle`])
+    pub ca_bundle: Option<Vec<u8>>,
+    /// Skip certificate verification entirely (development only)
+    pub danger_accept_invalid_certs: bool,
+}
+
+/// Read a PEM CA bundle, checking that it holds at least one certificate
+///
+/// The certificates are trusted in addition to the system roots, e.g. for a
+/// self-hosted S3 gateway or a TLS-intercepting proxy.
+pub fn load_ca_bundle(path: &Path) -> Result<Vec<u8>> {
+    let pem = std::fs::read(path).map_err(|e| {
+        Error::InvalidConfig(format!("Cannot read ca_bundle '{}': {}", path.display(), e))
+    })?;
+    let certs = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+        Error::InvalidConfig(format!("Invalid ca_bundle '{}': {}", path.display(), e))
+    })?;
+    if certs.is_empty() {
+        return Err(Error::InvalidConfig(format!(
+            "ca_bundle '{}' contains no PEM certificate",
+            path.display()
+        )));
+    }
+    Ok(pem)
+}
+
+/// HTTPS client for the S3 SDK trusting `ca_bundle` on top of the system roots
+fn https_client_with_ca(ca_bundle: &[u8]) -> Result<SharedHttpClient> {
+    let trust_store = tls::TrustStore::default().with_pem_certificate(ca_bundle);
+    let tls_context = tls::TlsContext::builder()
+        .with_trust_store(trust_store)
+        .build()
+        .map_err(|e| Error::InvalidConfig(format!("Invalid ca_bundle: {}", e)))?;
+
+    Ok(aws_smithy_http_client::Builder::new()
+        .tls_provider(tls::Provider::Rustls(
+            tls::rustls_provider::CryptoMode::AwsLc,
+        ))
+        .tls_context(tls_context)
+        .build_https())
+}
+
+/// `User-Agent` sent with every request unless overridden
+pub const DEFAULT_USER_AGENT: &str = concat!("r2pilot/", env!("CARGO_PKG_VERSION"));
+
+/// Pick the configured `User-Agent` or the default, rejecting values that aren't valid headers
+pub(crate) fn resolve_user_agent(user_agent: Option<&str>) -> Result<String> {
+    let user_agent = user_agent.unwrap_or(DEFAULT_USER_AGENT);
+    reqwest::header::HeaderValue::from_str(user_agent)
+        .map_err(|_| Error::InvalidConfig(format!("Invalid user_agent '{}'", user_agent)))?;
+    Ok(user_agent.to_string())
+}
+
+/// Replaces the SDK's generated `User-Agent` header
+#[derive(Debug)]
+struct UserAgentInterceptor(String);
+
+impl Intercept for UserAgentInterceptor {
+    fn name(&self) -> &'static str {
+        "UserAgentInterceptor"
+    }
+
+    fn modify_before_transmit(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> std::result::Result<(), BoxError> {
+        context
+            .request_mut()
+            .headers_mut()
+            .try_insert("user-agent", self.0.clone())?;
+        Ok(())
+    }
+}
+
 /// R2 client for managing Cloudflare R2 storage
 pub struct R2Client {
     client: Client,
     bucket: String,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    timings: Option<Arc<Timings>>,
     #[allow(dead_code)]
     endpoint: String,
     #[allow(dead_code)]
@@ -67,17 +559,42 @@ impl R2Client {
         access_key_id: String,
         secret_access_key: String,
         bucket: String,
+    ) -> Result<Self> {
+        Self::new_with_options(
+            endpoint,
+            access_key_id,
+            secret_access_key,
+            bucket,
+            ClientOptions::default(),
+        )
+        .await
+    }
+
+    /// Create a new R2 client with extra settings (path-style addressing, ...)
+    pub async fn new_with_options(
+        endpoint: String,
+        access_key_id: String,
+        secret_access_key: String,
+        bucket: String,
+        options: ClientOptions,
     ) -> Result<Self> {
         let endpoint_clone = endpoint.clone();
+        let user_agent = resolve_user_agent(options.user_agent.as_deref())?;
         // Create credentials
         let credentials =
             Credentials::new(&access_key_id, &secret_access_key, None, None, "r2pilot");
 
         // Build AWS config for R2 (S3-compatible)
-        let config_builder = aws_sdk_s3::Config::builder()
+        let mut config_builder = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
             .endpoint_url(&endpoint)
             .region(Region::new("auto".to_string()))
-            .credentials_provider(credentials);
+            .credentials_provider(credentials)
+            .force_path_style(options.force_path_style)
+            .interceptor(UserAgentInterceptor(user_agent));
+        if let Some(ca_bundle) = &options.ca_bundle {
+            config_builder = config_builder.http_client(https_client_with_ca(ca_bundle)?);
+        }
 
         let config = config_builder.build();
 
@@ -86,14 +603,62 @@ impl R2Client {
         Ok(Self {
             client,
             bucket,
+            rate_limiter: options
+                .max_rate
+                .map(|rate| Arc::new(RateLimiter::new(rate))),
+            timings: options.timings,
             endpoint: endpoint_clone,
             access_key_id,
             secret_access_key,
         })
     }
 
+    /// Await `request`, recording its duration under `phase` when timings are on
+    async fn timed<F: Future>(&self, phase: &'static str, bytes: u64, request: F) -> F::Output {
+        match &self.timings {
+            Some(timings) => timings.time(phase, bytes, request).await,
+            None => request.await,
+        }
+    }
+
+    /// Record a request of `phase` started at `start`, when timings are on
+    fn record_timing(&self, phase: &'static str, start: Instant, bytes: u64) {
+        if let Some(timings) = &self.timings {
+            timings.record(phase, start, bytes);
+        }
+    }
+
+    /// Request body for `data`, paced by `max_rate` when one is set
+    ///
+    /// The throttled body stays retryable: the SDK rebuilds it from the same
+    /// bytes if the request is retried.
+    fn upload_body(&self, data: Vec<u8>) -> ByteStream {
+        match &self.rate_limiter {
+            Some(limiter) => {
+                let data = Bytes::from(data);
+                let limiter = limiter.clone();
+                ByteStream::new(SdkBody::retryable(move || {
+                    SdkBody::from_body_1_x(ThrottledBody::new(data.clone(), limiter.clone()))
+                }))
+            }
+            None => ByteStream::from(data),
+        }
+    }
+
     /// Upload a file to R2
     pub async fn upload_file(&self, key: &str, file_path: &Path, content_type: &str) -> Result<()> {
+        self.upload_file_with_options(key, file_path, content_type, &UploadOptions::default())
+            .await
+    }
+
+    /// Upload a file to R2 with extra options (compression, ...)
+    pub async fn upload_file_with_options(
+        &self,
+        key: &str,
+        file_path: &Path,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()> {
         // Read file content
         let mut file = File::open(file_path).await.map_err(Error::Io)?;
 
@@ -104,25 +669,54 @@ impl R2Client {
         file.read_to_end(&mut buffer).await.map_err(Error::Io)?;
 
         // Upload to R2
-        self.upload_bytes(key, buffer, content_type).await
+        self.upload_bytes_with_options(key, buffer, content_type, options)
+            .await
     }
 
     /// Upload bytes to R2
     pub async fn upload_bytes(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
-        self.client
+        self.upload_bytes_with_options(key, body, content_type, &UploadOptions::default())
+            .await
+    }
+
+    /// Upload bytes to R2 with extra options (compression, ...)
+    pub async fn upload_bytes_with_options(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()> {
+        let content_encoding = options.effective_content_encoding()?;
+        let body = match options.compression {
+            Some(compression) => compression.compress(&body)?,
+            None => body,
+        };
+        let size = body.len() as u64;
+
+        let request = self
+            .client
             .put_object()
             .bucket(&self.bucket)
             .key(key)
-            .body(ByteStream::from(body))
-            .content_type(content_type)
-            .send()
-            .await?;
+            .body(self.upload_body(body))
+            .content_type(options.content_type.as_deref().unwrap_or(content_type))
+            .set_content_encoding(content_encoding)
+            .set_content_disposition(options.content_disposition.clone())
+            .set_cache_control(options.cache_control.clone())
+            .set_content_language(options.content_language.clone())
+            .set_metadata((!options.metadata.is_empty()).then(|| options.metadata.clone()))
+            .set_if_match(options.if_match.clone())
+            .set_if_none_match(options.if_none_match.clone())
+            .send();
+        self.timed("upload", size, request).await?;
 
         Ok(())
     }
 
     /// Download a file from R2
     pub async fn download_file(&self, key: &str, dest_path: &Path) -> Result<()> {
+        let start = Instant::now();
         let response = self
             .client
             .get_object()
@@ -133,6 +727,7 @@ impl R2Client {
 
         let body = response.body.collect().await?.into_bytes();
         let data = body.as_ref();
+        self.record_timing("download", start, data.len() as u64);
 
         // Create parent directories if needed
         if let Some(parent) = dest_path.parent() {
@@ -145,8 +740,82 @@ impl R2Client {
         Ok(())
     }
 
+    /// Stream the object from byte `start` to its end, appending to `dest_path`
+    ///
+    /// Bytes received before a connection drop stay on disk, so the caller can
+    /// resume from the file's new length. A dropped body is reported as a
+    /// network error. With `if_match`, R2 answers 412 (`Error::PreconditionFailed`)
+    /// if the object no longer has that ETag.
+    pub async fn download_range_to_file(
+        &self,
+        key: &str,
+        start: u64,
+        dest_path: &Path,
+        if_match: Option<&str>,
+    ) -> Result<()> {
+        let start_time = Instant::now();
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_range((start > 0).then(|| format!("bytes={}-", start)))
+            .set_if_match(if_match.map(str::to_string))
+            .send()
+            .await?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dest_path)
+            .await
+            .map_err(Error::Io)?;
+
+        let mut body = response.body;
+        let mut received = 0;
+        while let Some(chunk) = body
+            .try_next()
+            .await
+            .map_err(|e| Error::Network(format!("Download of {} interrupted: {}", key, e)))?
+        {
+            file.write_all(&chunk).await.map_err(Error::Io)?;
+            received += chunk.len() as u64;
+        }
+        file.flush().await.map_err(Error::Io)?;
+        self.record_timing("download", start_time, received);
+
+        Ok(())
+    }
+
+    /// Stream an object's body as it arrives
+    ///
+    /// The download is recorded in the timings once the body was read to the end.
+    pub async fn download_stream(&self, key: &str) -> Result<crate::operations::BoxedReader> {
+        let start = Instant::now();
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await?;
+
+        let reader = response.body.into_async_read();
+        Ok(match &self.timings {
+            Some(timings) => Box::new(TimedReader {
+                reader,
+                timings: timings.clone(),
+                start,
+                received: 0,
+                done: false,
+            }),
+            None => Box::new(reader),
+        })
+    }
+
     /// Download bytes from R2
     pub async fn download_bytes(&self, key: &str) -> Result<Vec<u8>> {
+        let start = Instant::now();
         let response = self
             .client
             .get_object()
@@ -156,19 +825,149 @@ impl R2Client {
             .await?;
 
         let body = response.body.collect().await?.into_bytes();
+        self.record_timing("download", start, body.len() as u64);
 
         Ok(body.to_vec())
     }
 
-    /// List objects in the bucket
+    /// List objects in the bucket (all pages)
     pub async fn list_objects(&self, prefix: Option<&str>) -> Result<Vec<ObjectInfo>> {
-        let response = self
+        self.list_objects_stream(prefix).try_collect().await
+    }
+
+    /// Stream objects in the bucket, following continuation tokens transparently
+    ///
+    /// Pages (up to 1000 objects) are fetched lazily: the next page is only
+    /// requested once the consumer has drained the current one, so memory stays
+    /// bounded to a single page no matter how many objects match. A slow
+    /// consumer simply delays the next `ListObjectsV2` call.
+    pub fn list_objects_stream<'a>(
+        &'a self,
+        prefix: Option<&'a str>,
+    ) -> impl Stream<Item = Result<ObjectInfo>> + 'a {
+        // State: None = finished, Some(token) = next page to fetch (first page has no token)
+        stream::try_unfold(
+            Some(None),
+            move |state: Option<Option<String>>| async move {
+                let Some(token) = state else {
+                    return Ok::<_, Error>(None);
+                };
+
+                let page = self
+                    .list_objects_page(prefix, None, token, None, None)
+                    .await?;
+                let next_state = page.next_token.map(Some);
+
+                Ok(Some((
+                    stream::iter(page.objects.into_iter().map(Ok::<_, Error>)),
+                    next_state,
+                )))
+            },
+        )
+        .try_flatten()
+    }
+
+    /// List the objects directly under `prefix` and the common prefixes below it (all pages)
+    pub async fn list_objects_delimited(
+        &self,
+        prefix: Option<&str>,
+        delimiter: &str,
+    ) -> Result<(Vec<ObjectInfo>, Vec<String>)> {
+        let mut objects = Vec::new();
+        let mut common_prefixes = Vec::new();
+        let mut token = None;
+
+        loop {
+            let page = self
+                .list_objects_page(prefix, Some(delimiter), token, None, None)
+                .await?;
+            objects.extend(page.objects);
+            common_prefixes.extend(page.common_prefixes);
+
+            match page.next_token {
+                Some(next) => token = Some(next),
+                None => return Ok((objects, common_prefixes)),
+            }
+        }
+    }
+
+    /// The first `max_keys` entries of a listing after `start_after`, without fetching further pages
+    ///
+    /// Objects and common prefixes (with a `delimiter`) both count toward the
+    /// cap, as with S3's `MaxKeys`, and come back in key order. Each page asks
+    /// for no more than what is still missing. The listing's `next_after` is
+    /// set when more entries exist, to be passed back as `start_after`.
+    pub async fn list_objects_paged(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        start_after: Option<&str>,
+        max_keys: usize,
+    ) -> Result<ObjectListing> {
+        let mut objects = Vec::new();
+        let mut common_prefixes = Vec::new();
+        let mut token: Option<String> = None;
+        let mut more = false;
+
+        while objects.len() + common_prefixes.len() < max_keys {
+            let remaining = max_keys - objects.len() - common_prefixes.len();
+            // S3 ignores StartAfter once there is a continuation token
+            let after = if token.is_none() { start_after } else { None };
+            let page = self
+                .list_objects_page(
+                    prefix,
+                    delimiter,
+                    token,
+                    after,
+                    Some(remaining.min(1000) as i32),
+                )
+                .await?;
+            // After `logs/`, a delimited listing rolls `logs/...` back up into
+            // `logs/` itself: drop it here so it doesn't count toward the cap
+            let after = start_after.unwrap_or_default();
+            objects.extend(page.objects.into_iter().filter(|o| o.key.as_str() > after));
+            common_prefixes.extend(
+                page.common_prefixes
+                    .into_iter()
+                    .filter(|p| p.as_str() > after),
+            );
+
+            more = page.next_token.is_some();
+            match page.next_token {
+                Some(next) => token = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(crate::operations::page_listing(
+            objects,
+            common_prefixes,
+            start_after,
+            max_keys,
+            more,
+        ))
+    }
+
+    /// Fetch a single page of objects, with the next continuation token
+    async fn list_objects_page(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        continuation_token: Option<String>,
+        start_after: Option<&str>,
+        max_keys: Option<i32>,
+    ) -> Result<ListPage> {
+        let request = self
             .client
             .list_objects_v2()
             .bucket(&self.bucket)
             .set_prefix(prefix.map(|s| s.to_string()))
-            .send()
-            .await?;
+            .set_delimiter(delimiter.map(|s| s.to_string()))
+            .set_continuation_token(continuation_token)
+            .set_start_after(start_after.map(|s| s.to_string()))
+            .set_max_keys(max_keys)
+            .send();
+        let response = self.timed("list", 0, request).await?;
 
         let objects = response
             .contents()
@@ -176,32 +975,155 @@ impl R2Client {
             .map(|obj| ObjectInfo {
                 key: obj.key().unwrap_or("").to_string(),
                 size: obj.size().unwrap_or(0),
-                last_modified: obj.last_modified().unwrap().to_owned(),
+                last_modified: obj.last_modified().copied(),
                 etag: obj.e_tag().unwrap_or("").to_string(),
             })
             .collect();
 
-        Ok(objects)
+        let common_prefixes = response
+            .common_prefixes()
+            .iter()
+            .filter_map(|p| p.prefix().map(|p| p.to_string()))
+            .collect();
+
+        let next_token = if response.is_truncated().unwrap_or(false) {
+            response.next_continuation_token().map(|t| t.to_string())
+        } else {
+            None
+        };
+
+        Ok(ListPage {
+            objects,
+            common_prefixes,
+            next_token,
+        })
     }
 
     /// Delete an object from R2
     pub async fn delete_object(&self, key: &str) -> Result<()> {
-        self.client
+        let request = self
+            .client
             .delete_object()
             .bucket(&self.bucket)
             .key(key)
-            .send()
-            .await?;
+            .send();
+        self.timed("delete", 0, request).await?;
 
         Ok(())
     }
 
     /// Delete multiple objects
+    ///
+    /// Sends `DeleteObjects` requests of up to 1000 keys. Every batch is
+    /// attempted; the first failed request is returned, otherwise keys R2
+    /// refuses to delete are reported together at the end.
     pub async fn delete_objects(&self, keys: Vec<String>) -> Result<()> {
-        for key in keys {
-            self.delete_object(&key).await?;
+        let (failed, error) = self.delete_object_batches(&keys).await;
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            let failed: Vec<String> = failed
+                .into_iter()
+                .map(|(key, message)| format!("{} ({})", key, message))
+                .collect();
+            Err(Error::R2Operation(format!(
+                "Failed to delete {} object(s): {}",
+                failed.len(),
+                failed.join(", ")
+            )))
         }
-        Ok(())
+    }
+
+    /// Delete multiple objects, reporting the outcome of each key
+    ///
+    /// Same requests as [`Self::delete_objects`], but keys R2 refuses to
+    /// delete, and the keys of a batch whose request failed, are returned as
+    /// failed outcomes rather than as one error.
+    pub async fn try_delete_objects(&self, keys: Vec<String>) -> Result<Vec<DeleteOutcome>> {
+        let failed: HashMap<String, String> = self
+            .delete_object_batches(&keys)
+            .await
+            .0
+            .into_iter()
+            .collect();
+
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                let result = match failed.get(&key) {
+                    Some(message) => Err(Error::R2Operation(message.clone())),
+                    None => Ok(()),
+                };
+                DeleteOutcome { key, result }
+            })
+            .collect())
+    }
+
+    /// Send `DeleteObjects` batches, returning the keys not deleted with why
+    ///
+    /// A failed request doesn't stop the following batches: its keys are
+    /// reported with the error, which is also returned (the first one).
+    async fn delete_object_batches(
+        &self,
+        keys: &[String],
+    ) -> (Vec<(String, String)>, Option<Error>) {
+        let mut failed = Vec::new();
+        let mut first_error = None;
+
+        for batch in keys.chunks(DELETE_OBJECTS_BATCH_SIZE) {
+            match self.delete_object_batch(batch).await {
+                Ok(refused) => failed.extend(refused),
+                Err(e) => {
+                    let message = e.to_string();
+                    failed.extend(batch.iter().map(|key| (key.clone(), message.clone())));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        (failed, first_error)
+    }
+
+    /// Send one `DeleteObjects` request, returning the keys R2 refused with its message
+    async fn delete_object_batch(&self, batch: &[String]) -> Result<Vec<(String, String)>> {
+        use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+
+        let objects = batch
+            .iter()
+            .map(|key| ObjectIdentifier::builder().key(key).build())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::InvalidInput(e.to_string()))?;
+        let delete = Delete::builder()
+            .set_objects(Some(objects))
+            .quiet(true)
+            .build()
+            .map_err(|e| Error::InvalidInput(e.to_string()))?;
+
+        let request = self
+            .client
+            .delete_objects()
+            .bucket(&self.bucket)
+            .delete(delete)
+            .send();
+        let response = self.timed("delete", 0, request).await?;
+
+        Ok(response
+            .errors()
+            .iter()
+            .map(|e| {
+                (
+                    e.key().unwrap_or_default().to_string(),
+                    e.message()
+                        .or(e.code())
+                        .unwrap_or("unknown error")
+                        .to_string(),
+                )
+            })
+            .collect())
     }
 
     /// Check if an object exists
@@ -215,65 +1137,315 @@ impl R2Client {
             .await
         {
             Ok(_) => Ok(true),
-            Err(e) => {
-                // Check if it's a "not found" error
-                let err_str = e.to_string().to_lowercase();
-                if err_str.contains("nosuchkey")
-                    || err_str.contains("not found")
-                    || err_str.contains("404")
-                {
-                    Ok(false)
-                } else {
-                    Err(Error::R2Operation(e.to_string()))
-                }
-            }
+            Err(e) => match Error::from(e) {
+                Error::NotFound(_) => Ok(false),
+                e => Err(e),
+            },
         }
     }
 
     /// Get object metadata
     pub async fn head_object(&self, key: &str) -> Result<ObjectMetadata> {
-        let response = self
+        let request = self
             .client
             .head_object()
             .bucket(&self.bucket)
             .key(key)
-            .send()
-            .await?;
+            .send();
+        let response = self.timed("head", 0, request).await?;
 
         Ok(ObjectMetadata {
             key: key.to_string(),
             size: response.content_length().unwrap_or(0),
             content_type: response.content_type().unwrap_or("").to_string(),
-            last_modified: response.last_modified().unwrap().to_owned(),
+            content_encoding: response.content_encoding().map(|e| e.to_string()),
+            content_disposition: response.content_disposition().map(|d| d.to_string()),
+            last_modified: response.last_modified().copied(),
             etag: response.e_tag().unwrap_or("").to_string(),
+            // The header is only sent for classes other than STANDARD
+            storage_class: match response.storage_class() {
+                Some(aws_sdk_s3::types::StorageClass::StandardIa) => StorageClass::InfrequentAccess,
+                _ => StorageClass::Standard,
+            },
         })
     }
 
     /// Copy an object within R2
     pub async fn copy_object(&self, source_key: &str, dest_key: &str) -> Result<()> {
-        self.client
+        self.copy_object_to(source_key, &self.bucket, dest_key, None)
+            .await
+    }
+
+    /// Copy an object to `dest_key` in `dest_bucket`, which may be this bucket
+    ///
+    /// The copy keeps the source's metadata, unless a `content_type` replaces
+    /// it: the other headers, storage class and user metadata are then
+    /// carried over like [`Self::set_content_type`] does.
+    pub async fn copy_object_to(
+        &self,
+        source_key: &str,
+        dest_bucket: &str,
+        dest_key: &str,
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        if let Some(content_type) = content_type {
+            let update = MetadataUpdate {
+                content_type: Some(content_type.to_string()),
+                ..Default::default()
+            };
+            return self
+                .copy_replacing(source_key, dest_bucket, dest_key, &update, None)
+                .await;
+        }
+
+        let request = self
+            .client
             .copy_object()
+            .bucket(dest_bucket)
+            .key(dest_key)
+            .copy_source(copy_source(&self.bucket, source_key))
+            .send();
+        self.timed("copy", 0, request).await?;
+
+        Ok(())
+    }
+
+    /// Presigned GET URL for `key`, signed by the SDK with this client's credentials
+    ///
+    /// This is the SDK's own presigner (`presigned()` with a
+    /// `PresigningConfig`), scoped to this client's bucket. The standalone
+    /// [`crate::presigned::generate_presigned_url_signed`] signs the same way
+    /// without a client, from the `CloudflareConfig` alone, and for every
+    /// method. The expiry is at most 7 days.
+    ///
+    /// `response` headers become `response-*` query parameters, covered by
+    /// the signature like the rest of the query string.
+    pub async fn presign_get(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        response: &ResponseHeaders,
+    ) -> Result<String> {
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_response_cache_control(response.cache_control.clone())
+            .set_response_expires(response.expires.map(|at| SystemTime::from(at).into()))
+            .presigned(presigning_config(expires_in)?)
+            .await
+            .map_err(|e| Error::PresignedUrlConfig(e.to_string()))?;
+
+        Ok(request.uri().to_string())
+    }
+
+    /// Presigned PUT URL for `key`, signed like [`Self::presign_get`]
+    ///
+    /// With a `content_type`, the Content-Type header is part of the signature,
+    /// so the upload must send exactly that value.
+    pub async fn presign_put(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        content_type: Option<&str>,
+    ) -> Result<String> {
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_content_type(content_type.map(str::to_string))
+            .presigned(presigning_config(expires_in)?)
+            .await
+            .map_err(|e| Error::PresignedUrlConfig(e.to_string()))?;
+
+        Ok(request.uri().to_string())
+    }
+
+    /// Replace an object's Content-Type with a self-copy
+    pub async fn set_content_type(&self, key: &str, content_type: &str) -> Result<()> {
+        let update = MetadataUpdate {
+            content_type: Some(content_type.to_string()),
+            ..Default::default()
+        };
+        self.update_metadata(key, &update).await
+    }
+
+    /// Change an object's headers and user metadata with a self-copy
+    ///
+    /// R2 has no metadata-only update: the object is rewritten server-side
+    /// (`MetadataDirective=REPLACE`), so its body is never downloaded but its
+    /// Last-Modified changes. The body being the same, a single-part object
+    /// keeps its ETag. The copy only happens if the object still has the
+    /// ETag read beforehand.
+    pub async fn update_metadata(&self, key: &str, update: &MetadataUpdate) -> Result<()> {
+        self.copy_replacing(key, &self.bucket, key, update, None)
+            .await
+    }
+
+    /// Move an object to another storage class with a self-copy
+    ///
+    /// This is how a transition is done by hand; the body, Content-Type and
+    /// other metadata stay as they are.
+    pub async fn change_storage_class(&self, key: &str, class: StorageClass) -> Result<()> {
+        self.copy_replacing(
+            key,
+            &self.bucket,
+            key,
+            &MetadataUpdate::default(),
+            Some(class),
+        )
+        .await
+    }
+
+    /// Copy an object (onto itself or elsewhere), replacing some of its metadata and/or storage class
+    ///
+    /// Replacing metadata drops every header not sent again, so the source's
+    /// content, cache, encoding, disposition and language headers, its storage
+    /// class and its user metadata are read first and carried over where
+    /// `update` leaves them unset.
+    async fn copy_replacing(
+        &self,
+        source_key: &str,
+        dest_bucket: &str,
+        dest_key: &str,
+        update: &MetadataUpdate,
+        storage_class: Option<StorageClass>,
+    ) -> Result<()> {
+        let current = self
+            .client
+            .head_object()
             .bucket(&self.bucket)
+            .key(source_key)
+            .send()
+            .await?;
+
+        let storage_class = match storage_class {
+            Some(class) => Some(aws_sdk_s3::types::StorageClass::from(class.as_str())),
+            None => current.storage_class().cloned(),
+        };
+
+        let replace = |new: &Option<String>, current: Option<&str>| {
+            new.as_deref().or(current).map(str::to_string)
+        };
+        let mut metadata = current.metadata().cloned().unwrap_or_default();
+        metadata.extend(update.metadata.clone());
+
+        self.client
+            .copy_object()
+            .bucket(dest_bucket)
             .key(dest_key)
-            .copy_source(format!("{}/{}", self.bucket, source_key))
+            .copy_source(copy_source(&self.bucket, source_key))
+            .set_copy_source_if_match(current.e_tag().map(str::to_string))
+            .metadata_directive(aws_sdk_s3::types::MetadataDirective::Replace)
+            .set_content_type(replace(&update.content_type, current.content_type()))
+            .set_storage_class(storage_class)
+            .set_cache_control(replace(&update.cache_control, current.cache_control()))
+            .set_content_encoding(replace(
+                &update.content_encoding,
+                current.content_encoding(),
+            ))
+            .set_content_disposition(replace(
+                &update.content_disposition,
+                current.content_disposition(),
+            ))
+            .set_content_language(replace(
+                &update.content_language,
+                current.content_language(),
+            ))
+            .set_metadata((!metadata.is_empty()).then_some(metadata))
             .send()
             .await?;
 
         Ok(())
     }
 
+    /// Undelete an object on a versioned bucket by removing its latest delete marker
+    ///
+    /// Returns the version ID of the removed marker. Fails with
+    /// `Error::BucketSettings` if versioning isn't enabled (or supported) on the
+    /// bucket, and `Error::NotFound` if the key's latest version isn't a delete marker.
+    pub async fn restore_object(&self, key: &str) -> Result<String> {
+        let versioning = self
+            .client
+            .get_bucket_versioning()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::BucketSettings(format!(
+                    "Cannot read versioning status of '{}' (the endpoint may not support versioning): {}",
+                    self.bucket,
+                    Error::from(e)
+                ))
+            })?;
+        if versioning.status().is_none() {
+            return Err(Error::BucketSettings(format!(
+                "Versioning is not enabled on bucket '{}'",
+                self.bucket
+            )));
+        }
+
+        let mut key_marker = None;
+        let mut version_marker = None;
+        loop {
+            let response = self
+                .client
+                .list_object_versions()
+                .bucket(&self.bucket)
+                .prefix(key)
+                .set_key_marker(key_marker)
+                .set_version_id_marker(version_marker)
+                .send()
+                .await?;
+
+            let marker = response
+                .delete_markers()
+                .iter()
+                .find(|m| m.key() == Some(key) && m.is_latest() == Some(true))
+                .and_then(|m| m.version_id());
+            if let Some(version_id) = marker {
+                let version_id = version_id.to_string();
+                self.client
+                    .delete_object()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .version_id(&version_id)
+                    .send()
+                    .await?;
+                return Ok(version_id);
+            }
+
+            if !response.is_truncated().unwrap_or(false) {
+                return Err(Error::NotFound(format!(
+                    "No delete marker for '{}' (the object is not deleted)",
+                    key
+                )));
+            }
+            key_marker = response.next_key_marker().map(|s| s.to_string());
+            version_marker = response.next_version_id_marker().map(|s| s.to_string());
+        }
+    }
+
     // === Multipart Upload Operations ===
 
     /// Initiate a multipart upload
-    pub async fn create_multipart_upload(&self, key: &str, content_type: &str) -> Result<String> {
-        let response = self
+    pub async fn create_multipart_upload(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: Option<&str>,
+    ) -> Result<String> {
+        let request = self
             .client
             .create_multipart_upload()
             .bucket(&self.bucket)
             .key(key)
             .content_type(content_type)
-            .send()
-            .await?;
+            .set_content_disposition(content_disposition.map(str::to_string))
+            .send();
+        let response = self.timed("create", 0, request).await?;
 
         response
             .upload_id()
@@ -289,16 +1461,17 @@ impl R2Client {
         part_number: i32,
         body: Vec<u8>,
     ) -> Result<CompletedPart> {
-        let response = self
+        let size = body.len() as u64;
+        let request = self
             .client
             .upload_part()
             .bucket(&self.bucket)
             .key(key)
             .upload_id(upload_id)
             .part_number(part_number)
-            .body(ByteStream::from(body))
-            .send()
-            .await?;
+            .body(self.upload_body(body))
+            .send();
+        let response = self.timed("part", size, request).await?;
 
         let etag = response
             .e_tag()
@@ -331,14 +1504,15 @@ impl R2Client {
             .set_parts(Some(aws_parts))
             .build();
 
-        self.client
+        let request = self
+            .client
             .complete_multipart_upload()
             .bucket(&self.bucket)
             .key(key)
             .upload_id(upload_id)
             .multipart_upload(multipart_upload)
-            .send()
-            .await?;
+            .send();
+        self.timed("complete", 0, request).await?;
 
         Ok(())
     }
@@ -356,6 +1530,158 @@ impl R2Client {
         Ok(())
     }
 
+    /// [`Self::abort_multipart_upload`] as a future that doesn't borrow the client
+    pub fn detached_abort(
+        &self,
+        key: &str,
+        upload_id: &str,
+    ) -> futures::future::BoxFuture<'static, Result<()>> {
+        let request = self
+            .client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id);
+
+        Box::pin(async move {
+            request.send().await?;
+            Ok(())
+        })
+    }
+
+    /// List the multipart uploads started but neither completed nor aborted
+    pub async fn list_multipart_uploads(&self) -> Result<Vec<MultipartUploadInfo>> {
+        let mut uploads = Vec::new();
+        let mut key_marker: Option<String> = None;
+        let mut upload_id_marker: Option<String> = None;
+
+        loop {
+            let response = self
+                .client
+                .list_multipart_uploads()
+                .bucket(&self.bucket)
+                .set_key_marker(key_marker.take())
+                .set_upload_id_marker(upload_id_marker.take())
+                .send()
+                .await?;
+
+            uploads.extend(response.uploads().iter().map(|upload| MultipartUploadInfo {
+                key: upload.key().unwrap_or_default().to_string(),
+                upload_id: upload.upload_id().unwrap_or_default().to_string(),
+                initiated: upload.initiated().copied(),
+            }));
+
+            key_marker = response.next_key_marker().map(str::to_string);
+            upload_id_marker = response.next_upload_id_marker().map(str::to_string);
+            if response.is_truncated() != Some(true) || key_marker.is_none() {
+                break;
+            }
+        }
+
+        Ok(uploads)
+    }
+
+    /// Total size of the parts uploaded so far to a multipart upload
+    pub async fn multipart_upload_size(&self, key: &str, upload_id: &str) -> Result<u64> {
+        let mut total = 0u64;
+        let mut part_number_marker: Option<String> = None;
+
+        loop {
+            let response = self
+                .client
+                .list_parts()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .set_part_number_marker(part_number_marker.take())
+                .send()
+                .await?;
+
+            total += response
+                .parts()
+                .iter()
+                .map(|part| part.size().unwrap_or(0).max(0) as u64)
+                .sum::<u64>();
+
+            part_number_marker = response.next_part_number_marker().map(str::to_string);
+            if response.is_truncated() != Some(true) || part_number_marker.is_none() {
+                break;
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Upload from a reader without buffering the whole body
+    ///
+    /// With a known `content_length` the body is streamed in a single `PutObject`
+    /// carrying that `Content-Length`; if the reader yields more or fewer bytes the
+    /// request is aborted before it completes, so no object is created. With an
+    /// unknown length the reader is uploaded in `config.chunk_size` parts instead.
+    pub async fn upload_stream<R>(
+        &self,
+        key: &str,
+        reader: R,
+        content_type: &str,
+        content_length: Option<u64>,
+        config: MultipartUploadConfig,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let Some(content_length) = content_length else {
+            return self
+                .upload_reader_multipart(key, reader, content_type, config)
+                .await;
+        };
+
+        let (tx, rx) = tokio::sync::mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        let body = ChannelBody {
+            rx,
+            remaining: content_length,
+        };
+
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .set_content_disposition(config.content_disposition.clone())
+            .content_length(content_length as i64)
+            .body(ByteStream::from_body_1_x(body))
+            .send();
+
+        // Feed the body while the request is in flight
+        let limiter = self.rate_limiter.as_deref();
+        let (sent, pumped) = tokio::join!(
+            self.timed("upload", content_length, request),
+            pump_exact(reader, content_length, tx, limiter)
+        );
+
+        // A length mismatch is the root cause of the failed request, report it first
+        pumped?;
+        sent?;
+
+        Ok(())
+    }
+
+    /// Upload a reader of unknown length in parts, without staging it to disk
+    ///
+    /// See [`R2Operations::upload_reader_multipart`].
+    pub async fn upload_reader_multipart<R>(
+        &self,
+        key: &str,
+        reader: R,
+        content_type: &str,
+        config: MultipartUploadConfig,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        crate::operations::upload_reader_in_parts(self, key, reader, content_type, config).await
+    }
+
     /// Upload a file using multipart upload
     pub async fn upload_file_multipart(
         &self,
@@ -364,100 +1690,249 @@ impl R2Client {
         content_type: &str,
         config: MultipartUploadConfig,
     ) -> Result<()> {
-        use tokio::io::AsyncReadExt;
-
-        // Open file and get size
-        let file = File::open(file_path).await.map_err(Error::Io)?;
-        let metadata = file.metadata().await.map_err(Error::Io)?;
-        let file_size = metadata.len();
+        R2Operations::upload_file_multipart(self, key, file_path, content_type, config).await
+    }
 
-        // Calculate number of parts
-        let _total_parts = (file_size as usize).div_ceil(config.chunk_size);
+    /// Presigned GET URL for an object, like [`Self::presign_get`] without response headers
+    pub async fn generate_presigned_url(&self, key: &str, expires_in: Duration) -> Result<String> {
+        self.presign_get(key, expires_in, &ResponseHeaders::default())
+            .await
+    }
 
-        // Initiate multipart upload
-        let upload_id = self.create_multipart_upload(key, content_type).await?;
+    /// Get the bucket name
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+}
 
-        // Read and upload parts
-        let mut parts = Vec::new();
-        let mut current_part = 0;
+// === Streaming Upload ===
 
-        // Reopen file for reading chunks
-        let mut file = File::open(file_path).await.map_err(Error::Io)?;
+/// Chunks buffered between the reader and the request body
+const STREAM_CHANNEL_CAPACITY: usize = 4;
 
-        loop {
-            current_part += 1;
+/// Size of each chunk read from a streamed reader
+const STREAM_READ_SIZE: usize = 64 * 1024;
 
-            // Read chunk
-            let mut buffer = vec![0u8; config.chunk_size.min(100 * 1024 * 1024)];
-            let n = file.read(&mut buffer).await.map_err(Error::Io)?;
+/// Request body fed through a channel, advertising an exact size
+struct ChannelBody {
+    rx: tokio::sync::mpsc::Receiver<std::io::Result<Bytes>>,
+    remaining: u64,
+}
 
-            if n == 0 {
-                break;
+impl http_body::Body for ChannelBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Bytes>, Self::Error>>> {
+        match self.rx.poll_recv(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.remaining = self.remaining.saturating_sub(chunk.len() as u64);
+                Poll::Ready(Some(Ok(Frame::data(chunk))))
             }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
 
-            buffer.truncate(n);
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
 
-            // Upload part
-            match self
-                .upload_part(key, &upload_id, current_part, buffer)
-                .await
-            {
-                Ok(part) => parts.push(part),
-                Err(e) => {
-                    // Abort on error
-                    let _ = self.abort_multipart_upload(key, &upload_id).await;
-                    return Err(e);
-                }
+/// Reader recording a `download` in the timings when it reaches the end
+struct TimedReader<R> {
+    reader: R,
+    timings: Arc<Timings>,
+    start: Instant,
+    received: u64,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for TimedReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.reader).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            let n = (buf.filled().len() - before) as u64;
+            self.received += n;
+            if n == 0 && !self.done {
+                self.done = true;
+                self.timings.record("download", self.start, self.received);
             }
         }
+        result
+    }
+}
+
+/// Send exactly `expected` bytes from `reader` into `tx`
+///
+/// On a length mismatch an error is sent down the channel (failing the request
+/// body before its end) and returned.
+async fn pump_exact<R>(
+    mut reader: R,
+    expected: u64,
+    tx: tokio::sync::mpsc::Sender<std::io::Result<Bytes>>,
+    limiter: Option<&RateLimiter>,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut sent = 0u64;
+
+    loop {
+        let mut buffer = vec![0u8; STREAM_READ_SIZE];
+        let n = reader.read(&mut buffer).await.map_err(Error::Io)?;
+
+        let mismatch = if n == 0 && sent < expected {
+            Some(format!(
+                "stream ended after {} bytes, {} declared",
+                sent, expected
+            ))
+        } else if sent + n as u64 > expected {
+            Some(format!(
+                "stream is longer than the {} declared bytes",
+                expected
+            ))
+        } else {
+            None
+        };
+
+        if let Some(message) = mismatch {
+            let _ = tx
+                .send(Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    message.clone(),
+                )))
+                .await;
+            return Err(Error::InvalidInput(format!(
+                "Content-Length mismatch: {}",
+                message
+            )));
+        }
 
-        // Complete multipart upload
-        self.complete_multipart_upload(key, &upload_id, parts)
-            .await?;
+        if n == 0 {
+            return Ok(());
+        }
 
-        Ok(())
-    }
+        buffer.truncate(n);
+        sent += n as u64;
+        if let Some(limiter) = limiter {
+            limiter.acquire(n as u64).await;
+        }
 
-    /// Generate a presigned URL for an object
-    ///
-    /// Note: For Cloudflare R2, presigned URLs require additional setup.
-    /// This method returns a direct URL. For presigned URLs, ensure your bucket
-    /// has the proper CORS configuration and consider using R2's built-in
-    /// URL signing features.
-    pub async fn generate_presigned_url(&self, key: &str, expires_in: Duration) -> Result<String> {
-        use http::Uri;
-        use std::time::{SystemTime, UNIX_EPOCH};
+        // The request was dropped (e.g. it failed), its own error is reported
+        if tx.send(Ok(Bytes::from(buffer))).await.is_err() {
+            return Ok(());
+        }
+    }
+}
 
-        // Parse endpoint to extract host
-        let endpoint_uri: Uri = self
-            .endpoint
-            .parse()
-            .map_err(|e| Error::InvalidConfig(format!("Invalid endpoint URL: {}", e)))?;
+/// Characters escaped in a copy source: all but unreserved ones and `/`
+const COPY_SOURCE_ENCODE_SET: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+
+/// `x-amz-copy-source` value for `key` in `bucket`
+///
+/// The header is URL-decoded by the server, so keys with spaces, `+`, `%`,
+/// `?` or non-ASCII characters must be percent-encoded.
+fn copy_source(bucket: &str, key: &str) -> String {
+    format!(
+        "{}/{}",
+        bucket,
+        percent_encoding::utf8_percent_encode(key, COPY_SOURCE_ENCODE_SET)
+    )
+}
 
-        let host = endpoint_uri
-            .host()
-            .ok_or_else(|| Error::InvalidConfig("Endpoint has no host".to_string()))?;
+/// Read up to `size` bytes, short only at end of input
+pub(crate) async fn read_chunk<R>(reader: &mut R, size: usize) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut chunk = Vec::with_capacity(size);
+    reader
+        .take(size as u64)
+        .read_to_end(&mut chunk)
+        .await
+        .map_err(Error::Io)?;
+    Ok(chunk)
+}
 
-        // Build the object URL
-        let url = format!("https://{}/{}/{}", host, self.bucket, key);
+/// Maximum keys per `DeleteObjects` request
+const DELETE_OBJECTS_BATCH_SIZE: usize = 1000;
 
-        // For R2, we'll return a simple URL structure
-        // In production, you'd use AWS SDK's presigning features or implement
-        // AWS Signature Version 4 manually
-        let expires_timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| Error::R2Operation(format!("Time error: {}", e)))?
-            .as_secs()
-            + expires_in.as_secs();
+/// One `ListObjectsV2` response
+struct ListPage {
+    objects: Vec<ObjectInfo>,
+    common_prefixes: Vec<String>,
+    next_token: Option<String>,
+}
 
-        // Build presigned URL parameters (simplified - needs full AWS SigV4 implementation)
-        // For now, return the direct URL with expiration info
-        Ok(format!("{}?expires={}", url, expires_timestamp))
+/// Smallest part S3 accepts in a multipart upload (all parts but the last)
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Largest part S3 accepts in a multipart upload
+pub const MAX_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Most parts a multipart upload may have
+pub const MAX_PARTS: u64 = 10_000;
+
+/// Part size for a multipart upload of `file_size` bytes
+///
+/// Starts from the configured `chunk_size` and adapts it: parts are shrunk so
+/// every one of the `concurrency` workers gets one, grown so the upload stays
+/// within 10,000 parts, and kept between 5MB and 5GB. The result is rounded up
+/// to a whole MB.
+pub fn multipart_chunk_size(file_size: u64, chunk_size: usize, concurrency: usize) -> usize {
+    const MB: u64 = 1024 * 1024;
+    let min = MIN_PART_SIZE as u64;
+
+    let mut chunk = (chunk_size as u64).max(min);
+    let concurrency = concurrency.max(1) as u64;
+    if file_size.div_ceil(chunk) < concurrency {
+        chunk = file_size.div_ceil(concurrency).max(min);
     }
+    chunk = chunk.max(file_size.div_ceil(MAX_PARTS));
 
-    /// Get the bucket name
-    pub fn bucket(&self) -> &str {
-        &self.bucket
+    (chunk.div_ceil(MB) * MB).min(MAX_PART_SIZE) as usize
+}
+
+/// How a multipart upload splits a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultipartPlan {
+    pub file_size: u64,
+    /// Size of every part but the last
+    pub chunk_size: usize,
+    pub total_parts: usize,
+    /// Parts uploaded at the same time
+    pub concurrency: usize,
+}
+
+/// Plan the multipart upload of `file_size` bytes with `config`
+///
+/// The part size comes from [`multipart_chunk_size`]; concurrency is capped
+/// at the number of parts.
+pub fn plan_multipart(file_size: u64, config: &MultipartUploadConfig) -> MultipartPlan {
+    let concurrency = config.concurrent_parts.max(1);
+    let chunk_size = multipart_chunk_size(file_size, config.chunk_size, concurrency);
+    let total_parts = file_size.div_ceil(chunk_size as u64) as usize;
+
+    MultipartPlan {
+        file_size,
+        chunk_size,
+        total_parts,
+        concurrency: concurrency.min(total_parts).max(1),
     }
 }
 
@@ -471,18 +1946,54 @@ pub fn requires_multipart_upload(file_size: u64) -> bool {
 pub struct ObjectInfo {
     pub key: String,
     pub size: i64,
-    pub last_modified: aws_smithy_types::DateTime,
+    /// Absent when the listing omits it (some S3-compatible servers do)
+    pub last_modified: Option<aws_smithy_types::DateTime>,
     pub etag: String,
 }
 
+/// One page of a listing, see [`R2Client::list_objects_paged`]
+#[derive(Debug, Clone, Default)]
+pub struct ObjectListing {
+    pub objects: Vec<ObjectInfo>,
+    /// Folders, when listing with a delimiter
+    pub common_prefixes: Vec<String>,
+    /// Last key of this page when more entries follow, the `start_after` of the next page
+    pub next_after: Option<String>,
+}
+
+/// A multipart upload that was started but not completed or aborted yet
+#[derive(Debug, Clone)]
+pub struct MultipartUploadInfo {
+    pub key: String,
+    pub upload_id: String,
+    pub initiated: Option<aws_smithy_types::DateTime>,
+}
+
 /// Object metadata
 #[derive(Debug, Clone)]
 pub struct ObjectMetadata {
     pub key: String,
     pub size: i64,
     pub content_type: String,
-    pub last_modified: aws_smithy_types::DateTime,
+    pub content_encoding: Option<String>,
+    pub content_disposition: Option<String>,
+    /// Absent when the response omits the Last-Modified header
+    pub last_modified: Option<aws_smithy_types::DateTime>,
     pub etag: String,
+    /// Reading an Infrequent Access object incurs a retrieval fee
+    pub storage_class: StorageClass,
+}
+
+/// Outcome of deleting one key in a batch
+#[derive(Debug)]
+pub struct DeleteOutcome {
+    pub key: String,
+    pub result: Result<()>,
+}
+
+/// SDK presigning settings for a URL valid for `expires_in`
+fn presigning_config(expires_in: Duration) -> Result<PresigningConfig> {
+    PresigningConfig::expires_in(expires_in).map_err(|e| Error::PresignedUrlConfig(e.to_string()))
 }
 
 #[cfg(test)]
@@ -495,7 +2006,7 @@ mod tests {
         let info = ObjectInfo {
             key: "test/file.txt".to_string(),
             size: 1024,
-            last_modified: aws_smithy_types::DateTime::from(SystemTime::UNIX_EPOCH),
+            last_modified: Some(aws_smithy_types::DateTime::from(SystemTime::UNIX_EPOCH)),
             etag: "abc123".to_string(),
         };
 
@@ -503,6 +2014,475 @@ mod tests {
         assert_eq!(info.size, 1024);
     }
 
+    #[test]
+    fn test_object_without_last_modified() {
+        let info = ObjectInfo {
+            key: "test/file.txt".to_string(),
+            size: 1024,
+            last_modified: None,
+            etag: "abc123".to_string(),
+        };
+        let metadata = ObjectMetadata {
+            key: "test/file.txt".to_string(),
+            size: 1024,
+            content_type: "text/plain".to_string(),
+            content_encoding: None,
+            content_disposition: None,
+            last_modified: None,
+            etag: "abc123".to_string(),
+            storage_class: StorageClass::Standard,
+        };
+
+        assert!(info.last_modified.is_none());
+        assert!(metadata.last_modified.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_s3_requests_send_user_agent() {
+        let (url, request) = crate::test_support::capture_request().await;
+        let client = crate::test_support::path_style_client(
+            &url,
+            ClientOptions {
+                user_agent: Some("deploy-bot/2.0".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let _ = client.list_objects(None).await;
+        assert!(request
+            .await
+            .unwrap()
+            .contains("user-agent: deploy-bot/2.0"));
+    }
+
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----\n\
+MIIBizCCATGgAwIBAgIUD9ABG6Z3NKQZvxe1KA1bdH2GpGEwCgYIKoZIzj0EAwIw\n\
+GjEYMBYGA1UEAwwPcjJwaWxvdCB0ZXN0IENBMCAXDTI2MTAxNjE5MDA1NloYDzIx\n\
+MjYwOTIyMTkwMDU2WjAaMRgwFgYDVQQDDA9yMnBpbG90IHRlc3QgQ0EwWTATBgcq\n\
+hkjOPQIBBggqhkjOPQMBBwNCAARL69BGI+AWbHoOZbLvvXNPeKz9SMmGyf0BkCuB\n\
+7/Dq/91Hqnjeea9bm5BEb1lIKOMLkQvrE2muAijFCv8iU/APo1MwUTAdBgNVHQ4E\n\
+FgQUr+wL0/SoNNWiPzPiHZYe1TmTruowHwYDVR0jBBgwFoAUr+wL0/SoNNWiPzPi\n\
+HZYe1TmTruowDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiBnf9Et\n\
+SLgZSb2yfDx15ulC+qxljz3Zw2g5l71hwzyl8gIhAJgzM3UwrgIKhLFan0Y2orNu\n\
+P80Y3qNGV+xZu8g776/n\n\
+-----END CERTIFICATE-----";
+
+    #[tokio::test]
+    async fn test_ca_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+
+        assert!(load_ca_bundle(&path).is_err());
+        std::fs::write(&path, "not a certificate").unwrap();
+        assert!(load_ca_bundle(&path).is_err());
+        std::fs::write(&path, TEST_CA).unwrap();
+        let ca_bundle = load_ca_bundle(&path).unwrap();
+
+        // Plain HTTP endpoints keep working with the custom connector
+        let (url, request) = crate::test_support::capture_request().await;
+        let client = crate::test_support::path_style_client(
+            &url,
+            ClientOptions {
+                ca_bundle: Some(ca_bundle.clone()),
+                ..Default::default()
+            },
+        )
+        .await;
+        let _ = client.list_objects(None).await;
+        assert!(request.await.unwrap().starts_with("get /bucket/"));
+
+        let tls = TlsOptions {
+            ca_bundle: Some(ca_bundle),
+            danger_accept_invalid_certs: false,
+        };
+        assert!(crate::CloudflareClient::new_with_tls(
+            "token".to_string(),
+            "account".to_string(),
+            None,
+            &tls
+        )
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_upload_maps_412() {
+        let (url, request) = crate::test_support::respond_once("412 Precondition Failed").await;
+        let client = crate::test_support::path_style_client(&url, ClientOptions::default()).await;
+
+        let options = UploadOptions {
+            if_none_match: Some("*".to_string()),
+            ..Default::default()
+        };
+        let result = client
+            .upload_bytes_with_options("a.txt", b"x".to_vec(), "text/plain", &options)
+            .await;
+
+        assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+        assert!(request.await.unwrap().contains("if-none-match: *"));
+    }
+
+    #[tokio::test]
+    async fn test_download_range_pinned_to_etag() {
+        let (url, request) = crate::test_support::respond_once("412 Precondition Failed").await;
+        let client = crate::test_support::path_style_client(&url, ClientOptions::default()).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = client
+            .download_range_to_file("a.bin", 100, &dir.path().join("a.bin"), Some("\"abc\""))
+            .await;
+
+        assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+        let request = request.await.unwrap();
+        assert!(request.contains("range: bytes=100-"), "{}", request);
+        assert!(request.contains("if-match: \"abc\""), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn test_upload_stream_sends_content_disposition() {
+        let (url, request) = crate::test_support::capture_request().await;
+        let client = crate::test_support::path_style_client(&url, ClientOptions::default()).await;
+
+        let config = MultipartUploadConfig {
+            content_disposition: Some("attachment".to_string()),
+            ..Default::default()
+        };
+        client
+            .upload_stream("a.txt", &b"hello"[..], "text/plain", Some(5), config)
+            .await
+            .unwrap();
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("put /bucket/a.txt"), "{}", request);
+        assert!(
+            request.contains("content-disposition: attachment"),
+            "{}",
+            request
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_objects_batches_by_1000() {
+        let deleted = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><DeleteResult></DeleteResult>";
+        let server = crate::test_support::MockServer::start(vec![
+            (200, deleted.to_string()),
+            (200, deleted.to_string()),
+        ])
+        .await;
+        let client =
+            crate::test_support::path_style_client(&server.url(), ClientOptions::default()).await;
+
+        let keys: Vec<String> = (0..DELETE_OBJECTS_BATCH_SIZE + 1)
+            .map(|i| format!("k{:05}", i))
+            .collect();
+        client.delete_objects(keys).await.unwrap();
+
+        let requests = server.requests();
+        let counts: Vec<usize> = requests
+            .iter()
+            .map(|r| r.body.matches("<Key>").count())
+            .collect();
+        assert_eq!(counts, vec![DELETE_OBJECTS_BATCH_SIZE, 1]);
+        assert!(requests[1].body.contains("<Key>k01000</Key>"));
+    }
+
+    #[tokio::test]
+    async fn test_try_delete_objects_keeps_going_after_failed_batch() {
+        let rejected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>InvalidRequest</Code><Message>batch rejected</Message></Error>";
+        let deleted = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><DeleteResult></DeleteResult>";
+        let server = crate::test_support::MockServer::start(vec![
+            (400, rejected.to_string()),
+            (200, deleted.to_string()),
+        ])
+        .await;
+        let client =
+            crate::test_support::path_style_client(&server.url(), ClientOptions::default()).await;
+
+        let keys: Vec<String> = (0..DELETE_OBJECTS_BATCH_SIZE + 2)
+            .map(|i| format!("k{:05}", i))
+            .collect();
+        let outcomes = client.try_delete_objects(keys).await.unwrap();
+
+        // The second batch is still sent, and only the first one's keys fail
+        assert_eq!(server.requests().len(), 2);
+        let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+        assert_eq!(failed, DELETE_OBJECTS_BATCH_SIZE);
+        assert!(outcomes[DELETE_OBJECTS_BATCH_SIZE..]
+            .iter()
+            .all(|o| o.result.is_ok()));
+        assert!(matches!(outcomes[0].result, Err(Error::R2Operation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_max_rate_paces_uploads() {
+        let (url, request) = crate::test_support::capture_request().await;
+        let client = crate::test_support::path_style_client(
+            &url,
+            ClientOptions {
+                max_rate: Some(64 * 1024),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        // One second's worth goes out at once, the remaining 32 KiB take ~0.5s
+        let start = std::time::Instant::now();
+        client
+            .upload_bytes("a.bin", vec![0u8; 96 * 1024], "application/octet-stream")
+            .await
+            .unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(400));
+        assert!(request.await.unwrap().starts_with("put /bucket/a.bin"));
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_paged_stops_paginating() {
+        let server = crate::test_support::MockServer::start(vec![
+            crate::test_support::list_page(&["a", "b"], &[], Some("t1")),
+            crate::test_support::list_page(&["c"], &[], Some("t2")),
+        ])
+        .await;
+        let client =
+            crate::test_support::path_style_client(&server.url(), ClientOptions::default()).await;
+
+        let page = client
+            .list_objects_paged(None, None, Some("0"), 3)
+            .await
+            .unwrap();
+        let keys: Vec<&str> = page.objects.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert!(page.common_prefixes.is_empty());
+        // The server said more keys follow
+        assert_eq!(page.next_after.as_deref(), Some("c"));
+
+        // The second page only asks for what is missing, and there is no third
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(
+            requests[0].path.contains("max-keys=3"),
+            "{}",
+            requests[0].path
+        );
+        assert!(requests[0].path.contains("start-after=0"));
+        assert!(!requests[1].path.contains("start-after"));
+        assert!(
+            requests[1].path.contains("max-keys=1"),
+            "{}",
+            requests[1].path
+        );
+        assert!(requests[1].path.contains("continuation-token=t1"));
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_paged_skips_start_after_folder() {
+        // R2 returns the `logs/` folder again, then what follows it
+        let server = crate::test_support::MockServer::start(vec![
+            crate::test_support::list_page(&[], &["logs/"], Some("t1")),
+            crate::test_support::list_page(&["readme.txt"], &[], Some("t2")),
+        ])
+        .await;
+        let client =
+            crate::test_support::path_style_client(&server.url(), ClientOptions::default()).await;
+
+        let page = client
+            .list_objects_paged(None, Some("/"), Some("logs/"), 1)
+            .await
+            .unwrap();
+        let keys: Vec<&str> = page.objects.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, vec!["readme.txt"]);
+        assert!(page.common_prefixes.is_empty());
+        assert_eq!(page.next_after.as_deref(), Some("readme.txt"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].path.contains("continuation-token=t1"));
+    }
+
+    #[tokio::test]
+    async fn test_presign_signs_with_sigv4() {
+        let client = crate::test_support::path_style_client(
+            "https://account.r2.cloudflarestorage.com",
+            ClientOptions::default(),
+        )
+        .await;
+
+        let url = client
+            .presign_get(
+                "docs/a file.txt",
+                Duration::from_secs(3600),
+                &ResponseHeaders::default(),
+            )
+            .await
+            .unwrap();
+        assert!(
+            url.starts_with("https://account.r2.cloudflarestorage.com/bucket/docs/a%20file.txt?")
+        );
+        assert!(url.contains("X-Amz-Algorithm=AWS4-HMAC-SHA256"));
+        assert!(url.contains("X-Amz-Expires=3600"));
+        assert!(url.contains("X-Amz-Signature="));
+        assert!(!url.contains("response-"));
+
+        // Response headers are signed query parameters
+        let response = ResponseHeaders {
+            cache_control: Some("public, max-age=3600".to_string()),
+            expires: chrono::DateTime::from_timestamp(1_767_225_600, 0),
+        };
+        let url = client
+            .presign_get("a.txt", Duration::from_secs(3600), &response)
+            .await
+            .unwrap();
+        assert!(
+            url.contains("response-cache-control=public%2C%20max-age%3D3600"),
+            "{}",
+            url
+        );
+        assert!(
+            url.contains("response-expires=Thu%2C%2001%20Jan%202026%2000%3A00%3A00%20GMT"),
+            "{}",
+            url
+        );
+
+        let url = client
+            .presign_put("a.json", Duration::from_secs(60), Some("application/json"))
+            .await
+            .unwrap();
+        assert!(url.contains("X-Amz-SignedHeaders=content-type%3Bhost"));
+
+        // The client-level helper is signed too, not just an expiry
+        let url = client
+            .generate_presigned_url("a.txt", Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(url.contains("X-Amz-Signature="));
+        assert!(!url.contains("expires="));
+
+        let too_long = client
+            .presign_get(
+                "a.txt",
+                Duration::from_secs(8 * 24 * 3600),
+                &ResponseHeaders::default(),
+            )
+            .await;
+        assert!(matches!(too_long, Err(Error::PresignedUrlConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata_replaces_in_place() {
+        let server = crate::test_support::MockServer::start(vec![
+            (200, String::new()),
+            (
+                200,
+                "<CopyObjectResult><ETag>\"abc\"</ETag></CopyObjectResult>".to_string(),
+            ),
+        ])
+        .await;
+        let client =
+            crate::test_support::path_style_client(&server.url(), ClientOptions::default()).await;
+
+        let update = MetadataUpdate {
+            cache_control: Some("max-age=60".to_string()),
+            metadata: HashMap::from([("owner".to_string(), "ops".to_string())]),
+            ..Default::default()
+        };
+        client.update_metadata("a.txt", &update).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "HEAD");
+        let copy = &requests[1];
+        assert_eq!(
+            (copy.method.as_str(), copy.path.as_str()),
+            ("PUT", "/bucket/a.txt?x-id=CopyObject")
+        );
+        assert!(copy.headers.contains("x-amz-copy-source: bucket/a.txt"));
+        assert!(copy.headers.contains("x-amz-metadata-directive: replace"));
+        assert!(copy.headers.contains("cache-control: max-age=60"));
+        assert!(copy.headers.contains("x-amz-meta-owner: ops"));
+        // Headers left unset are carried over from the HEAD response
+        assert!(copy.headers.contains("content-type: application/json"));
+    }
+
+    #[tokio::test]
+    async fn test_timings_recorded_per_phase() {
+        let server = crate::test_support::MockServer::start(vec![
+            (200, String::new()),
+            (200, String::new()),
+            (200, String::new()),
+            (200, String::new()),
+            (200, "world!".to_string()),
+        ])
+        .await;
+        let timings = Arc::new(Timings::new());
+        let client = crate::test_support::path_style_client(
+            &server.url(),
+            ClientOptions {
+                timings: Some(timings.clone()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        client
+            .upload_bytes("a.txt", b"hello".to_vec(), "text/plain")
+            .await
+            .unwrap();
+        client
+            .upload_stream(
+                "b.txt",
+                &b"hello"[..],
+                "text/plain",
+                Some(5),
+                MultipartUploadConfig::default(),
+            )
+            .await
+            .unwrap();
+        client.head_object("a.txt").await.unwrap();
+        client.head_object("a.txt").await.unwrap();
+        let mut body = Vec::new();
+        client
+            .download_stream("a.txt")
+            .await
+            .unwrap()
+            .read_to_end(&mut body)
+            .await
+            .unwrap();
+
+        let summary: Vec<(&str, usize, u64)> = timings
+            .summary()
+            .iter()
+            .map(|p| (p.phase, p.requests, p.bytes))
+            .collect();
+        // Streamed transfers count the bytes they moved
+        assert_eq!(
+            summary,
+            vec![("upload", 2, 10), ("head", 2, 0), ("download", 1, 6)]
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_entry() {
+        assert_eq!(
+            parse_metadata_entry("Owner=ops=team").unwrap(),
+            ("owner".to_string(), "ops=team".to_string())
+        );
+        assert_eq!(
+            parse_metadata_entry("empty=").unwrap(),
+            ("empty".to_string(), String::new())
+        );
+        assert!(parse_metadata_entry("owner").is_err());
+        assert!(parse_metadata_entry("=ops").is_err());
+    }
+
+    #[test]
+    fn test_copy_source_encoding() {
+        assert_eq!(copy_source("bucket", "dir/a.txt"), "bucket/dir/a.txt");
+        assert_eq!(
+            copy_source("bucket", "dir/a b+c%d?.txt"),
+            "bucket/dir/a%20b%2Bc%25d%3F.txt"
+        );
+        assert_eq!(copy_source("bucket", "été/~x"), "bucket/%C3%A9t%C3%A9/~x");
+    }
+
     #[test]
     fn test_requires_multipart_upload() {
         // Test with small file (should not require multipart)
@@ -520,6 +2500,8 @@ mod tests {
         let config = MultipartUploadConfig::default();
         assert_eq!(config.chunk_size, 100 * 1024 * 1024); // 100MB
         assert_eq!(config.concurrent_parts, 5);
+        assert_eq!(config.max_retries, 3);
+        assert_eq!(config.retry_delay, Duration::from_secs(1));
     }
 
     #[test]
@@ -527,6 +2509,7 @@ mod tests {
         let config = MultipartUploadConfig {
             chunk_size: 50 * 1024 * 1024, // 50MB
             concurrent_parts: 3,
+            ..Default::default()
         };
         assert_eq!(config.chunk_size, 50 * 1024 * 1024);
         assert_eq!(config.concurrent_parts, 3);
@@ -553,6 +2536,203 @@ mod tests {
         assert_eq!(percentage, 50.0);
     }
 
+    #[test]
+    fn test_compression_from_str() {
+        assert_eq!("gzip".parse::<Compression>().unwrap(), Compression::Gzip);
+        assert_eq!("ZSTD".parse::<Compression>().unwrap(), Compression::Zstd);
+        assert!("brotli".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    fn test_multipart_chunk_size() {
+        const MB: u64 = 1024 * 1024;
+        const GB: u64 = 1024 * MB;
+
+        // Enough parts for every worker, but never below 5MB
+        assert_eq!(
+            multipart_chunk_size(500 * MB, 100 * MB as usize, 5),
+            100 * MB as usize
+        );
+        assert_eq!(
+            multipart_chunk_size(200 * MB, 100 * MB as usize, 8),
+            25 * MB as usize
+        );
+        assert_eq!(
+            multipart_chunk_size(12 * MB, 100 * MB as usize, 8),
+            MIN_PART_SIZE
+        );
+        assert_eq!(multipart_chunk_size(50 * MB, 1000, 1), MIN_PART_SIZE);
+
+        // Grown to stay within 10,000 parts
+        let chunk = multipart_chunk_size(2000 * GB, 100 * MB as usize, 5);
+        assert!((2000 * GB).div_ceil(chunk as u64) <= MAX_PARTS);
+        assert_eq!(chunk as u64 % MB, 0);
+
+        // Capped at 5GB per part
+        assert_eq!(
+            multipart_chunk_size(u64::MAX / 2, 100 * MB as usize, 5) as u64,
+            MAX_PART_SIZE
+        );
+    }
+
+    #[test]
+    fn test_plan_multipart() {
+        const MB: u64 = 1024 * 1024;
+        let config = MultipartUploadConfig {
+            chunk_size: 100 * MB as usize,
+            concurrent_parts: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            plan_multipart(1000 * MB, &config),
+            MultipartPlan {
+                file_size: 1000 * MB,
+                chunk_size: 100 * MB as usize,
+                total_parts: 10,
+                concurrency: 5,
+            }
+        );
+
+        // Shrunk so every worker gets a part
+        let plan = plan_multipart(250 * MB, &config);
+        assert_eq!((plan.chunk_size, plan.total_parts), (50 * MB as usize, 5));
+
+        // Too small for one part per worker: fewer workers
+        let plan = plan_multipart(12 * MB, &config);
+        assert_eq!((plan.chunk_size, plan.total_parts), (MIN_PART_SIZE, 3));
+        assert_eq!(plan.concurrency, 3);
+    }
+
+    #[test]
+    fn test_storage_class_from_str() {
+        assert_eq!(
+            "standard".parse::<StorageClass>().unwrap(),
+            StorageClass::Standard
+        );
+        for input in ["STANDARD_IA", "standard-ia", "infrequent-access", "IA"] {
+            assert_eq!(
+                input.parse::<StorageClass>().unwrap(),
+                StorageClass::InfrequentAccess
+            );
+        }
+        assert_eq!(StorageClass::InfrequentAccess.to_string(), "STANDARD_IA");
+        assert!("GLACIER".parse::<StorageClass>().is_err());
+    }
+
+    #[test]
+    fn test_compression_gzip_roundtrip() {
+        use std::io::Read;
+
+        let data = b"hello hello hello hello hello".repeat(10);
+        let compressed = Compression::Gzip.compress(&data).unwrap();
+        assert!(compressed.len() < data.len());
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_compression_from_content_encoding() {
+        let parse = Compression::from_content_encoding;
+        assert_eq!(parse("gzip").unwrap(), Some(Compression::Gzip));
+        assert_eq!(parse(" X-GZIP ").unwrap(), Some(Compression::Gzip));
+        assert_eq!(parse("zstd").unwrap(), Some(Compression::Zstd));
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(parse("identity").unwrap(), None);
+        assert!(parse("br").is_err());
+        assert!(parse("gzip, br").is_err());
+
+        let data = b"decompress me ".repeat(100);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let compressed = compression.compress(&data).unwrap();
+            let mut out = Vec::new();
+            let size = compression
+                .decompress_to(compressed.as_slice(), &mut out)
+                .unwrap();
+            assert_eq!(size, data.len() as u64);
+            assert_eq!(out, data);
+            assert!(compression
+                .decompress_to(&b"plain"[..], &mut Vec::new())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_compression_zstd_roundtrip() {
+        let data = b"hello hello hello hello hello".repeat(10);
+        let compressed = Compression::Zstd.compress(&data).unwrap();
+        assert_eq!(Compression::Zstd.content_encoding(), "zstd");
+        assert_eq!(zstd::decode_all(compressed.as_slice()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_is_compressed_format() {
+        assert!(is_compressed_format(Path::new("photo.JPG")));
+        assert!(is_compressed_format(Path::new("archive.zip")));
+        assert!(!is_compressed_format(Path::new("app.js")));
+        assert!(!is_compressed_format(Path::new("README")));
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Cache-Control: max-age=60").unwrap(),
+            ("Cache-Control".to_string(), "max-age=60".to_string())
+        );
+        assert_eq!(
+            parse_header("x-amz-meta-empty:").unwrap(),
+            ("x-amz-meta-empty".to_string(), String::new())
+        );
+        assert!(parse_header("Cache-Control").is_err());
+        assert!(parse_header(": value").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+        assert!(parse_header("X-Test: a\r\nX-Evil: 1").is_err());
+    }
+
+    #[test]
+    fn test_upload_options_set_header() {
+        let mut options = UploadOptions::default();
+        options.set_header("content-language", "fr").unwrap();
+        options
+            .set_header("Content-Disposition", "attachment")
+            .unwrap();
+        options.set_header("X-Amz-Meta-Owner", "ops").unwrap();
+
+        assert_eq!(options.content_language.as_deref(), Some("fr"));
+        assert_eq!(options.content_disposition.as_deref(), Some("attachment"));
+        assert_eq!(
+            options.metadata.get("owner").map(String::as_str),
+            Some("ops")
+        );
+
+        assert!(options
+            .set_header("Content-Disposition", "attach ment")
+            .is_err());
+        assert!(options.set_header("x-amz-meta-", "x").is_err());
+        let err = options.set_header("Expires", "0").unwrap_err();
+        assert!(err.to_string().contains("can't be set on uploads"));
+    }
+
+    #[test]
+    fn test_validate_content_disposition() {
+        assert!(validate_content_disposition("attachment").is_ok());
+        assert!(validate_content_disposition("inline").is_ok());
+        assert!(validate_content_disposition("attachment; filename=report.pdf").is_ok());
+        assert!(
+            validate_content_disposition("attachment; filename=\"my \\\"q\\\" file.pdf\"").is_ok()
+        );
+
+        assert!(validate_content_disposition("").is_err());
+        assert!(validate_content_disposition("attach ment").is_err());
+        assert!(validate_content_disposition("attachment; filename").is_err());
+        assert!(validate_content_disposition("attachment; filename=\"a\"b\"").is_err());
+        assert!(validate_content_disposition("attachment\r\nX-Evil: 1").is_err());
+    }
+
     #[test]
     fn test_completed_part() {
         let part = CompletedPart {
@@ -563,4 +2743,51 @@ mod tests {
         assert_eq!(part.part_number, 1);
         assert_eq!(part.etag, "test-etag");
     }
+
+    async fn pump_collect(data: &[u8], expected: u64) -> (Result<()>, Vec<u8>, bool) {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+        let result = pump_exact(data, expected, tx, None).await;
+
+        let mut received = Vec::new();
+        let mut body_failed = false;
+        while let Some(chunk) = rx.recv().await {
+            match chunk {
+                Ok(bytes) => received.extend_from_slice(&bytes),
+                Err(_) => body_failed = true,
+            }
+        }
+        (result, received, body_failed)
+    }
+
+    #[tokio::test]
+    async fn test_pump_exact_length() {
+        let data = vec![7u8; STREAM_READ_SIZE * 2 + 10];
+        let (result, received, body_failed) = pump_collect(&data, data.len() as u64).await;
+
+        assert!(result.is_ok());
+        assert!(!body_failed);
+        assert_eq!(received, data);
+    }
+
+    #[tokio::test]
+    async fn test_pump_exact_length_mismatch() {
+        let (result, _, body_failed) = pump_collect(b"short", 10).await;
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert!(body_failed);
+
+        let (result, received, body_failed) = pump_collect(b"too long", 3).await;
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert!(body_failed);
+        assert!(received.len() <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_read_chunk() {
+        let mut reader: &[u8] = b"abcdefg";
+
+        assert_eq!(read_chunk(&mut reader, 3).await.unwrap(), b"abc");
+        assert_eq!(read_chunk(&mut reader, 3).await.unwrap(), b"def");
+        assert_eq!(read_chunk(&mut reader, 3).await.unwrap(), b"g");
+        assert!(read_chunk(&mut reader, 3).await.unwrap().is_empty());
+    }
 }
@@ -1,26 +1,109 @@
 //! Cloudflare API client for managing R2 and API tokens
 
+use crate::client::{resolve_user_agent, TlsOptions};
+use crate::config::validate_jurisdiction;
 use crate::error::{Error, Result};
-use reqwest::{Client, Response};
+use reqwest::{Client, Method, RequestBuilder, Response};
+use schemars::JsonSchema;
+use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
 
 /// Cloudflare API client
+#[derive(Clone)]
 pub struct CloudflareClient {
     api_token: String,
     account_id: String,
     http_client: Client,
     base_url: String,
+    /// Sent as `cf-r2-jurisdiction` with every bucket-scoped request
+    jurisdiction: Option<String>,
 }
 
 impl CloudflareClient {
     /// Create a new Cloudflare client
     pub fn new(api_token: String, account_id: String) -> Self {
-        Self {
+        Self::new_with_user_agent(api_token, account_id, None)
+            .expect("default user agent is a valid header")
+    }
+
+    /// Create a new Cloudflare client sending a custom `User-Agent`
+    pub fn new_with_user_agent(
+        api_token: String,
+        account_id: String,
+        user_agent: Option<&str>,
+    ) -> Result<Self> {
+        Self::new_with_tls(api_token, account_id, user_agent, &TlsOptions::default())
+    }
+
+    /// Create a new Cloudflare client with a custom `User-Agent` and TLS settings
+    pub fn new_with_tls(
+        api_token: String,
+        account_id: String,
+        user_agent: Option<&str>,
+        tls: &TlsOptions,
+    ) -> Result<Self> {
+        let mut builder = Client::builder().user_agent(resolve_user_agent(user_agent)?);
+        if let Some(ca_bundle) = &tls.ca_bundle {
+            for cert in reqwest::Certificate::from_pem_bundle(ca_bundle)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if tls.danger_accept_invalid_certs {
+            tracing::warn!("TLS certificate verification is disabled for the Cloudflare API");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        let http_client = builder.build()?;
+
+        Ok(Self {
             api_token,
             account_id,
-            http_client: Client::new(),
+            http_client,
             base_url: "https://api.cloudflare.com/client/v4".to_string(),
+            jurisdiction: None,
+        })
+    }
+
+    /// Send requests to another API root (e.g. a proxy or a local test server)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Address buckets in `jurisdiction` (`eu`, `fedramp`)
+    ///
+    /// R2 only finds a jurisdictional bucket when the request names its
+    /// jurisdiction, so every bucket-scoped call sends it; `None` targets the
+    /// default jurisdiction.
+    pub fn with_jurisdiction(mut self, jurisdiction: Option<&str>) -> Result<Self> {
+        if let Some(jurisdiction) = jurisdiction {
+            validate_jurisdiction(jurisdiction)?;
+        }
+        self.jurisdiction = jurisdiction.map(str::to_string);
+        Ok(self)
+    }
+
+    /// The jurisdiction bucket-scoped requests are sent to
+    pub fn jurisdiction(&self) -> Option<&str> {
+        self.jurisdiction.as_deref()
+    }
+
+    /// Request to `/r2/buckets/{bucket}{path}`, in the client's jurisdiction
+    fn bucket_request(&self, method: Method, bucket: &str, path: &str) -> RequestBuilder {
+        let mut request = self
+            .http_client
+            .request(
+                method,
+                format!(
+                    "{}/accounts/{}/r2/buckets/{}{}",
+                    self.base_url, self.account_id, bucket, path
+                ),
+            )
+            .header("Authorization", format!("Bearer {}", self.api_token))
+            .header("Content-Type", "application/json");
+        if let Some(jurisdiction) = &self.jurisdiction {
+            request = request.header("cf-r2-jurisdiction", jurisdiction);
         }
+        request
     }
 
     /// List all API tokens
@@ -36,6 +119,32 @@ impl CloudflareClient {
         self.handle_response(response).await
     }
 
+    /// Get one API token, including its permissions
+    pub async fn get_token(&self, token_id: &str) -> Result<ApiToken> {
+        let response = self
+            .http_client
+            .get(format!("{}/user/tokens/{}", self.base_url, token_id))
+            .header("Authorization", format!("Bearer {}", self.api_token))
+            .header("Content-Type", "application/json")
+            .send()
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Verify the token this client authenticates with
+    pub async fn verify_token(&self) -> Result<TokenVerification> {
+        let response = self
+            .http_client
+            .get(format!("{}/user/tokens/verify", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_token))
+            .header("Content-Type", "application/json")
+            .send()
+            .await?;
+
+        self.handle_response(response).await
+    }
+
     /// Create a new API token
     pub async fn create_token(&self, params: CreateTokenParams) -> Result<ApiToken> {
         let response = self
@@ -60,12 +169,20 @@ impl CloudflareClient {
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
     /// List all R2 buckets
     pub async fn list_buckets(&self) -> Result<Vec<R2Bucket>> {
+        /// The API wraps the list in `{"buckets": [...]}`
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BucketList {
+            Wrapped { buckets: Vec<R2Bucket> },
+            Plain(Vec<R2Bucket>),
+        }
+
         let response = self
             .http_client
             .get(format!(
@@ -77,26 +194,22 @@ impl CloudflareClient {
             .send()
             .await?;
 
-        self.handle_response(response).await
+        Ok(match self.handle_response(response).await? {
+            BucketList::Wrapped { buckets } | BucketList::Plain(buckets) => buckets,
+        })
     }
 
     /// Get details of a specific bucket
     pub async fn get_bucket(&self, name: &str) -> Result<R2Bucket> {
-        let response = self
-            .http_client
-            .get(format!(
-                "{}/accounts/{}/r2/buckets/{}",
-                self.base_url, self.account_id, name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
-            .send()
-            .await?;
+        let response = self.bucket_request(Method::GET, name, "").send().await?;
 
         self.handle_response(response).await
     }
 
     /// Create a new R2 bucket
+    ///
+    /// `location` is a placement hint; the client's jurisdiction (see
+    /// [`CloudflareClient::with_jurisdiction`]) pins the bucket's data to that region.
     pub async fn create_bucket(&self, name: &str, location: &str) -> Result<R2Bucket> {
         let body = serde_json::json!({
             "name": name,
@@ -105,35 +218,33 @@ impl CloudflareClient {
             }
         });
 
-        let response = self
+        let mut request = self
             .http_client
             .post(format!(
                 "{}/accounts/{}/r2/buckets",
                 self.base_url, self.account_id
             ))
             .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .header("Content-Type", "application/json");
+        if let Some(jurisdiction) = &self.jurisdiction {
+            request = request.header("cf-r2-jurisdiction", jurisdiction);
+        }
+
+        let response = request.json(&body).send().await?;
+
+        // 409 (API error code 10004): the name is taken, usually by our own bucket
+        if response.status().as_u16() == 409 {
+            return Err(Error::AlreadyExists(format!("Bucket '{}'", name)));
+        }
 
         self.handle_response(response).await
     }
 
     /// Delete an R2 bucket
     pub async fn delete_bucket(&self, name: &str) -> Result<()> {
-        let response = self
-            .http_client
-            .delete(format!(
-                "{}/accounts/{}/r2/buckets/{}",
-                self.base_url, self.account_id, name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
-            .send()
-            .await?;
+        let response = self.bucket_request(Method::DELETE, name, "").send().await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
@@ -142,13 +253,7 @@ impl CloudflareClient {
     /// Get CORS configuration for a bucket
     pub async fn get_bucket_cors(&self, bucket_name: &str) -> Result<BucketCorsConfig> {
         let response = self
-            .http_client
-            .get(format!(
-                "{}/accounts/{}/r2/buckets/{}/cors",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::GET, bucket_name, "/cors")
             .send()
             .await?;
 
@@ -161,51 +266,65 @@ impl CloudflareClient {
         bucket_name: &str,
         config: &BucketCorsConfig,
     ) -> Result<()> {
+        let mut config = config.clone();
+        config.normalize()?;
+
         let response = self
-            .http_client
-            .put(format!(
-                "{}/accounts/{}/r2/buckets/{}/cors",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
-            .json(config)
+            .bucket_request(Method::PUT, bucket_name, "/cors")
+            .json(&config)
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
     /// Delete CORS configuration for a bucket
     pub async fn delete_bucket_cors(&self, bucket_name: &str) -> Result<()> {
         let response = self
-            .http_client
-            .delete(format!(
-                "{}/accounts/{}/r2/buckets/{}/cors",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::DELETE, bucket_name, "/cors")
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
+    /// Add a CORS rule, keeping the bucket's existing rules
+    pub async fn add_cors_rule(&self, bucket_name: &str, rule: CorsRule) -> Result<()> {
+        let mut config = match self.get_bucket_cors(bucket_name).await {
+            Ok(config) => config,
+            Err(Error::NotFound(_)) => BucketCorsConfig { rules: Vec::new() },
+            Err(e) => return Err(e),
+        };
+
+        config.add_rule(rule)?;
+        self.put_bucket_cors(bucket_name, &config).await
+    }
+
+    /// Remove a CORS rule by ID, keeping the other rules
+    ///
+    /// Deletes the whole CORS configuration when the last rule is removed.
+    pub async fn remove_cors_rule(&self, bucket_name: &str, id: &str) -> Result<()> {
+        let mut config = self.get_bucket_cors(bucket_name).await?;
+
+        if config.remove_rule(id).is_none() {
+            return Err(Error::NotFound(format!("CORS rule '{}'", id)));
+        }
+
+        if config.rules.is_empty() {
+            self.delete_bucket_cors(bucket_name).await
+        } else {
+            self.put_bucket_cors(bucket_name, &config).await
+        }
+    }
+
     // === Lifecycle Rules ===
 
     /// Get lifecycle rules for a bucket
     pub async fn get_bucket_lifecycle(&self, bucket_name: &str) -> Result<LifecycleConfiguration> {
         let response = self
-            .http_client
-            .get(format!(
-                "{}/accounts/{}/r2/buckets/{}/lifecycle",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::GET, bucket_name, "/lifecycle")
             .send()
             .await?;
 
@@ -218,36 +337,26 @@ impl CloudflareClient {
         bucket_name: &str,
         config: &LifecycleConfiguration,
     ) -> Result<()> {
+        config.validate()?;
+
         let response = self
-            .http_client
-            .put(format!(
-                "{}/accounts/{}/r2/buckets/{}/lifecycle",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::PUT, bucket_name, "/lifecycle")
             .json(config)
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
     /// Delete lifecycle rules for a bucket
     pub async fn delete_bucket_lifecycle(&self, bucket_name: &str) -> Result<()> {
         let response = self
-            .http_client
-            .delete(format!(
-                "{}/accounts/{}/r2/buckets/{}/lifecycle",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::DELETE, bucket_name, "/lifecycle")
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
@@ -260,51 +369,54 @@ impl CloudflareClient {
         config: &WebsiteConfiguration,
     ) -> Result<()> {
         let response = self
-            .http_client
-            .put(format!(
-                "{}/accounts/{}/r2/buckets/{}/website",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::PUT, bucket_name, "/website")
             .json(config)
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
     /// Get website configuration for a bucket
     pub async fn get_bucket_website(&self, bucket_name: &str) -> Result<WebsiteConfiguration> {
         let response = self
-            .http_client
-            .get(format!(
-                "{}/accounts/{}/r2/buckets/{}/website",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::GET, bucket_name, "/website")
+            .send()
+            .await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Get the bucket's r2.dev public URL and whether it is enabled
+    pub async fn get_bucket_managed_domain(&self, bucket_name: &str) -> Result<ManagedDomain> {
+        let response = self
+            .bucket_request(Method::GET, bucket_name, "/domains/managed")
             .send()
             .await?;
 
         self.handle_response(response).await
     }
 
+    /// List the custom domains connected to a bucket
+    pub async fn list_bucket_custom_domains(&self, bucket_name: &str) -> Result<Vec<CustomDomain>> {
+        let response = self
+            .bucket_request(Method::GET, bucket_name, "/domains/custom")
+            .send()
+            .await?;
+
+        let list: CustomDomainList = self.handle_response(response).await?;
+        Ok(list.domains)
+    }
+
     /// Disable static hosting for a bucket
     pub async fn delete_bucket_website(&self, bucket_name: &str) -> Result<()> {
         let response = self
-            .http_client
-            .delete(format!(
-                "{}/accounts/{}/r2/buckets/{}/website",
-                self.base_url, self.account_id, bucket_name
-            ))
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
+            .bucket_request(Method::DELETE, bucket_name, "/website")
             .send()
             .await?;
 
-        self.handle_response::<()>(response).await?;
+        self.handle_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
@@ -313,35 +425,81 @@ impl CloudflareClient {
         let status = response.status();
 
         if status.is_success() {
-            let cloudflare_response: CloudflareResponse<T> = response.json().await?;
+            // Failed responses usually carry `"result": null`, so the
+            // envelope is checked before decoding the result itself
+            let cloudflare_response: CloudflareResponse<Option<serde_json::Value>> =
+                response.json().await?;
             if cloudflare_response.success {
-                Ok(cloudflare_response.result)
+                // Some DELETE endpoints answer `"result": null` (or omit it);
+                // that's only an error when a value was expected
+                match cloudflare_response.result {
+                    Some(result) if !result.is_null() => Ok(serde_json::from_value(result)?),
+                    _ => serde_json::from_value(serde_json::Value::Null).map_err(|_| {
+                        Error::CloudflareApi(
+                            "The API reported success but returned no result".to_string(),
+                        )
+                    }),
+                }
             } else {
-                let errors = cloudflare_response
-                    .errors
-                    .into_iter()
-                    .map(|e| e.message)
-                    .collect::<Vec<_>>()
-                    .join("; ");
-                Err(Error::CloudflareApi(errors))
+                Err(Error::CloudflareApi(join_errors(
+                    &cloudflare_response.errors,
+                )))
             }
-        } else if status.as_u16() == 401 {
-            Err(Error::Authentication("Invalid API token".to_string()))
-        } else if status.as_u16() == 403 {
-            Err(Error::PermissionDenied(
-                "Insufficient permissions".to_string(),
-            ))
-        } else if status.as_u16() == 404 {
-            Err(Error::NotFound("Resource not found".to_string()))
         } else {
-            let error_text = response.text().await.unwrap_or_default();
-            Err(Error::CloudflareApi(format!(
-                "HTTP {}: {}",
-                status.as_u16(),
-                error_text
-            )))
+            let body = response.text().await.unwrap_or_default();
+            let detail = error_body_detail(&body);
+            let with_detail = |summary: &str| match &detail {
+                Some(detail) => format!("{}: {}", summary, detail),
+                None => summary.to_string(),
+            };
+
+            Err(match status.as_u16() {
+                401 => Error::Authentication(with_detail("Invalid API token")),
+                403 => Error::PermissionDenied(with_detail("Insufficient permissions")),
+                404 => Error::NotFound(with_detail("Resource not found")),
+                code if code == 429 || status.is_server_error() => {
+                    Error::Transient(with_detail(&format!("HTTP {}", code)))
+                }
+                code => Error::CloudflareApi(with_detail(&format!("HTTP {}", code))),
+            })
+        }
+    }
+}
+
+/// Longest raw error body kept in a message, in characters
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+/// The messages of an API envelope's `errors`, joined
+fn join_errors(errors: &[CloudflareError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// What went wrong according to the body of a non-2xx response
+///
+/// The API still answers most failures with its JSON envelope, whose
+/// `errors` are much more telling than the status (e.g. why a bucket name is
+/// invalid). Any other body, such as an HTML error page from the edge, is
+/// kept as trimmed text, shortened to [`MAX_ERROR_BODY_CHARS`]. `None` when
+/// there is nothing to report.
+fn error_body_detail(body: &str) -> Option<String> {
+    if let Ok(envelope) = serde_json::from_str::<CloudflareResponse<Option<IgnoredAny>>>(body) {
+        if !envelope.errors.is_empty() {
+            return Some(join_errors(&envelope.errors));
         }
     }
+
+    let text = body.trim();
+    if text.is_empty() {
+        return None;
+    }
+    match text.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((end, _)) => Some(format!("{}...", &text[..end])),
+        None => Some(text.to_string()),
+    }
 }
 
 /// Cloudflare API response wrapper
@@ -352,6 +510,7 @@ struct CloudflareResponse<T> {
     errors: Vec<CloudflareError>,
     #[allow(dead_code)]
     messages: Vec<CloudflareMessage>,
+    /// Missing only works for an `Option`, which treats it as null
     result: T,
 }
 
@@ -380,7 +539,92 @@ pub struct ApiToken {
     pub issued_on: String,
     pub modified_on: String,
     pub expires_on: Option<String>,
+    #[serde(default)]
     pub permissions: Vec<Permission>,
+    /// Token secret, only returned when the token is created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl ApiToken {
+    /// Status and validity window of this token
+    pub fn verification(&self) -> TokenVerification {
+        TokenVerification {
+            id: self.id.clone(),
+            status: self.status.clone(),
+            not_before: None,
+            expires_on: self.expires_on.clone(),
+        }
+    }
+
+    /// Whether any permission group grants R2 access
+    pub fn grants_r2(&self) -> bool {
+        self.permissions.iter().any(|permission| {
+            permission
+                .policy
+                .permission_groups
+                .iter()
+                .any(|group| group.name.contains("R2"))
+        })
+    }
+
+    /// Names of the permission groups granted, in order and without duplicates
+    pub fn permission_group_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for permission in &self.permissions {
+            for group in &permission.policy.permission_groups {
+                if !names.contains(&group.name.as_str()) {
+                    names.push(&group.name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Highest account-level R2 access granted, which bucket management needs
+    ///
+    /// Object-level groups ("Workers R2 Storage Bucket Item ...") only apply
+    /// to the S3 API of some buckets, so they don't count.
+    pub fn r2_access(&self) -> Option<TokenPermission> {
+        let r2_groups = || {
+            self.permission_group_names()
+                .into_iter()
+                .filter(|name| name.contains("R2") && !name.contains("Bucket Item"))
+        };
+        if r2_groups().any(|name| name.contains("Write") || name.contains("Edit")) {
+            Some(TokenPermission::Edit)
+        } else if r2_groups().any(|name| name.contains("Read")) {
+            Some(TokenPermission::Read)
+        } else {
+            None
+        }
+    }
+}
+
+/// Result of the token verify endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenVerification {
+    pub id: String,
+    pub status: String,
+    #[serde(default)]
+    pub not_before: Option<String>,
+    #[serde(default)]
+    pub expires_on: Option<String>,
+}
+
+impl TokenVerification {
+    /// Whether the token is active and inside its validity window
+    pub fn is_active(&self) -> bool {
+        let now = chrono::Utc::now();
+        let parse = |date: &Option<String>| {
+            date.as_deref()
+                .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+        };
+
+        self.status.eq_ignore_ascii_case("active")
+            && parse(&self.not_before).is_none_or(|d| d <= now)
+            && parse(&self.expires_on).is_none_or(|d| d > now)
+    }
 }
 
 /// Permission structure
@@ -425,6 +669,8 @@ pub struct CreateTokenParams {
     pub policy: TokenPolicy,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub condition: Option<TokenCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_on: Option<String>,
 }
 
 /// Token policy
@@ -459,70 +705,584 @@ pub struct R2Bucket {
     pub name: String,
     pub location: String,
     pub creation_date: String,
+    /// Where the bucket's data is pinned (`default`, `eu`, `fedramp`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jurisdiction: Option<String>,
 }
 
 // === CORS Configuration Types ===
 
 /// CORS configuration for a bucket
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(example = "cors_example")]
 pub struct BucketCorsConfig {
     pub rules: Vec<CorsRule>,
 }
 
 /// CORS rule
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CorsRule {
+    /// Optional identifier, used to edit rules individually
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Origins allowed to make requests (`*` for any)
     #[serde(rename = "allowedOrigins")]
     pub allowed_origins: Vec<String>,
+    /// HTTP methods allowed (GET, PUT, POST, DELETE, HEAD)
     #[serde(rename = "allowedMethods")]
     pub allowed_methods: Vec<String>,
-    #[serde(rename = "allowedHeaders")]
+    /// Request headers browsers may send
+    ///
+    /// `None` omits the field, which R2 treats as "CORS-safelisted headers only";
+    /// `Some(["*"])` allows any header. An explicitly empty list is sent as-is
+    /// and behaves like `None` on R2.
+    #[schemars(
+        description = "Request headers browsers may send (`*` for any). Omit to allow only CORS-safelisted headers."
+    )]
+    #[serde(
+        rename = "allowedHeaders",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub allowed_headers: Option<Vec<String>>,
+    /// How long browsers may cache the preflight response
     #[serde(rename = "maxAgeSeconds")]
     pub max_age_seconds: Option<u64>,
 }
 
+impl CorsRule {
+    /// Trim and deduplicate origins, methods and headers
+    ///
+    /// Methods are uppercased and headers compared case-insensitively. A `*`
+    /// entry makes every other origin or header redundant, so the list collapses
+    /// to `["*"]`. Fails if no origin or method is left.
+    pub fn normalize(&mut self) -> Result<()> {
+        self.allowed_origins = normalize_list(&self.allowed_origins, |o| o.to_string());
+        self.allowed_methods = normalize_list(&self.allowed_methods, |m| m.to_uppercase());
+        if let Some(headers) = &self.allowed_headers {
+            self.allowed_headers = Some(normalize_list(headers, |h| h.to_string()));
+        }
+
+        if self.allowed_origins.is_empty() {
+            return Err(Error::CorsConfig(
+                "A rule needs at least one allowed origin".to_string(),
+            ));
+        }
+        if self.allowed_methods.is_empty() {
+            return Err(Error::CorsConfig(
+                "A rule needs at least one allowed method".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether this rule allows a preflight for `origin`, `method` and request `headers`
+    ///
+    /// Origins and allowed headers may contain one `*` wildcard (e.g.
+    /// `https://*.example.com`, `x-amz-meta-*`). Without `allowed_headers`,
+    /// only CORS-safelisted headers pass.
+    pub fn matches(&self, origin: &str, method: &str, headers: &[&str]) -> bool {
+        let origin_ok = self
+            .allowed_origins
+            .iter()
+            .any(|allowed| wildcard_match(allowed, origin));
+        let method_ok = self
+            .allowed_methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method));
+        let headers_ok = headers.iter().all(|header| {
+            let header = header.trim().to_ascii_lowercase();
+            CORS_SAFELISTED_HEADERS.contains(&header.as_str())
+                || self.allowed_headers.as_ref().is_some_and(|allowed| {
+                    allowed
+                        .iter()
+                        .any(|a| wildcard_match(&a.to_ascii_lowercase(), &header))
+                })
+        });
+
+        origin_ok && method_ok && headers_ok
+    }
+}
+
+/// Request headers browsers send without needing them allowed
+const CORS_SAFELISTED_HEADERS: &[&str] = &[
+    "accept",
+    "accept-language",
+    "content-language",
+    "content-type",
+];
+
+/// Match `value` against a pattern with at most one `*` wildcard
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len()
+                && value.starts_with(prefix)
+                && value.ends_with(suffix)
+        }
+        None => pattern == value,
+    }
+}
+
+/// Trim entries, drop empty ones and case-insensitive duplicates, collapse `*`
+fn normalize_list(values: &[String], canonical: impl Fn(&str) -> String) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let values: Vec<String> = values
+        .iter()
+        .map(|v| canonical(v.trim()))
+        .filter(|v| !v.is_empty() && seen.insert(v.to_lowercase()))
+        .collect();
+
+    if values.iter().any(|v| v == "*") {
+        vec!["*".to_string()]
+    } else {
+        values
+    }
+}
+
+/// Split a comma-separated list, trimming entries and dropping empty ones
+pub fn split_comma_list(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl BucketCorsConfig {
+    /// Normalize every rule (see [`CorsRule::normalize`])
+    pub fn normalize(&mut self) -> Result<()> {
+        self.rules.iter_mut().try_for_each(CorsRule::normalize)
+    }
+
+    /// Public read access: GET and HEAD from any origin
+    pub fn public_read() -> Self {
+        Self {
+            rules: vec![CorsRule {
+                id: Some("public-read".to_string()),
+                allowed_origins: vec!["*".to_string()],
+                allowed_methods: vec!["GET".to_string(), "HEAD".to_string()],
+                allowed_headers: None,
+                max_age_seconds: Some(86400),
+            }],
+        }
+    }
+
+    /// Browser app on one origin: GET, PUT, POST and DELETE with any header
+    pub fn web_app(origin: &str) -> Result<Self> {
+        let origin = origin.trim().trim_end_matches('/');
+        if origin.is_empty() || origin == "*" {
+            return Err(Error::CorsConfig(
+                "The web-app preset needs a specific origin (e.g. https://app.example.com)"
+                    .to_string(),
+            ));
+        }
+
+        Ok(Self {
+            rules: vec![CorsRule {
+                id: Some("web-app".to_string()),
+                allowed_origins: vec![origin.to_string()],
+                allowed_methods: ["GET", "PUT", "POST", "DELETE"]
+                    .map(str::to_string)
+                    .to_vec(),
+                allowed_headers: Some(vec!["*".to_string()]),
+                max_age_seconds: Some(3600),
+            }],
+        })
+    }
+
+    /// Everything allowed: every method and header from any origin
+    pub fn allow_all() -> Self {
+        Self {
+            rules: vec![CorsRule {
+                id: Some("all".to_string()),
+                allowed_origins: vec!["*".to_string()],
+                allowed_methods: ["GET", "HEAD", "PUT", "POST", "DELETE"]
+                    .map(str::to_string)
+                    .to_vec(),
+                allowed_headers: Some(vec!["*".to_string()]),
+                max_age_seconds: Some(86400),
+            }],
+        }
+    }
+
+    /// Append a rule, rejecting an ID that is already in use
+    pub fn add_rule(&mut self, rule: CorsRule) -> Result<()> {
+        if let Some(id) = &rule.id {
+            if self.rules.iter().any(|r| r.id.as_ref() == Some(id)) {
+                return Err(Error::CorsConfig(format!(
+                    "A rule with ID '{}' already exists",
+                    id
+                )));
+            }
+        }
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// The first rule allowing a preflight for `origin`, `method` and `headers`
+    ///
+    /// Mirrors how R2 evaluates rules: in order, first match wins.
+    pub fn matches(&self, origin: &str, method: &str, headers: &[&str]) -> Option<&CorsRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(origin, method, headers))
+    }
+
+    /// Remove the rule with the given ID, returning it
+    pub fn remove_rule(&mut self, id: &str) -> Option<CorsRule> {
+        let index = self
+            .rules
+            .iter()
+            .position(|r| r.id.as_deref() == Some(id))?;
+        Some(self.rules.remove(index))
+    }
+}
+
 // === Lifecycle Configuration Types ===
 
+/// Well-known CORS rule sets for `cors set --preset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorsPreset {
+    /// See [`BucketCorsConfig::public_read`]
+    PublicRead,
+    /// See [`BucketCorsConfig::web_app`]
+    WebApp,
+    /// See [`BucketCorsConfig::allow_all`]
+    All,
+}
+
+impl CorsPreset {
+    /// Whether the preset is restricted to an origin that must be provided
+    pub fn needs_origin(self) -> bool {
+        self == CorsPreset::WebApp
+    }
+
+    /// The preset's configuration; `origin` is only used by `web-app`
+    pub fn config(self, origin: Option<&str>) -> Result<BucketCorsConfig> {
+        match self {
+            CorsPreset::PublicRead => Ok(BucketCorsConfig::public_read()),
+            CorsPreset::WebApp => BucketCorsConfig::web_app(origin.unwrap_or_default()),
+            CorsPreset::All => Ok(BucketCorsConfig::allow_all()),
+        }
+    }
+}
+
+impl std::str::FromStr for CorsPreset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "public-read" => Ok(CorsPreset::PublicRead),
+            "web-app" => Ok(CorsPreset::WebApp),
+            "all" => Ok(CorsPreset::All),
+            _ => Err(Error::InvalidInput(format!(
+                "Invalid CORS preset '{}' (expected public-read, web-app or all)",
+                s
+            ))),
+        }
+    }
+}
+
 /// Lifecycle configuration for a bucket
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(example = "lifecycle_example")]
 pub struct LifecycleConfiguration {
     pub rules: Vec<LifecycleRule>,
 }
 
 /// Lifecycle rule
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct LifecycleRule {
+    /// Unique rule identifier
     pub id: String,
     pub filter: LifecycleFilter,
+    /// "Enabled" or "Disabled"
+    #[schemars(regex(pattern = "^(Enabled|Disabled)$"))]
     pub status: String, // "Enabled" or "Disabled"
     pub expiration: Option<LifecycleExpiration>,
+    /// Delete noncurrent versions (versioned buckets)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noncurrent_version_expiration: Option<NoncurrentVersionExpiration>,
+    /// Abort multipart uploads left incomplete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_incomplete_multipart_upload: Option<AbortIncompleteMultipartUpload>,
 }
 
 /// Lifecycle filter
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct LifecycleFilter {
+    /// Only objects whose key starts with this prefix
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prefix: Option<String>,
 }
 
-/// Lifecycle expiration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Lifecycle expiration (set either `days` or `date`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LifecycleExpiration {
+    /// Days after object creation
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
     pub days: Option<u32>,
+    /// Absolute expiration date (`YYYY-MM-DD` or RFC3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
 }
 
-// === Website Configuration Types ===
+/// Expiration of noncurrent object versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NoncurrentVersionExpiration {
+    /// Days after a version becomes noncurrent
+    #[schemars(range(min = 1))]
+    pub noncurrent_days: u32,
+}
 
-/// Website configuration for static hosting
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct WebsiteConfiguration {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub index_document: Option<IndexDocument>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+impl NoncurrentVersionExpiration {
+    /// Check that `noncurrent_days` is at least 1
+    pub fn validate(&self) -> Result<()> {
+        if self.noncurrent_days == 0 {
+            return Err(Error::LifecycleRule(
+                "Noncurrent version expiration days must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Abort of multipart uploads that were never completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortIncompleteMultipartUpload {
+    /// Days after the upload was started
+    #[schemars(range(min = 1))]
+    pub days_after_initiation: u32,
+}
+
+impl AbortIncompleteMultipartUpload {
+    /// Check that `days_after_initiation` is at least 1
+    pub fn validate(&self) -> Result<()> {
+        if self.days_after_initiation == 0 {
+            return Err(Error::LifecycleRule(
+                "Incomplete upload abort days must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl LifecycleExpiration {
+    /// Expire objects a number of days after creation
+    pub fn after_days(days: u32) -> Self {
+        Self {
+            days: Some(days),
+            date: None,
+        }
+    }
+
+    /// Expire objects on a specific date
+    pub fn on_date(date: impl Into<String>) -> Self {
+        Self {
+            days: None,
+            date: Some(date.into()),
+        }
+    }
+
+    /// Check that exactly one of `days`/`date` is set and the date parses
+    pub fn validate(&self) -> Result<()> {
+        match (&self.days, &self.date) {
+            (Some(_), Some(_)) => Err(Error::LifecycleRule(
+                "Expiration cannot set both days and date".to_string(),
+            )),
+            (None, None) => Err(Error::LifecycleRule(
+                "Expiration must set either days or date".to_string(),
+            )),
+            (Some(0), None) => Err(Error::LifecycleRule(
+                "Expiration days must be at least 1".to_string(),
+            )),
+            (Some(_), None) => Ok(()),
+            (None, Some(date)) => {
+                let valid = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+                    || chrono::DateTime::parse_from_rfc3339(date).is_ok();
+                if valid {
+                    Ok(())
+                } else {
+                    Err(Error::LifecycleRule(format!(
+                        "Invalid expiration date '{}' (expected YYYY-MM-DD or RFC3339)",
+                        date
+                    )))
+                }
+            }
+        }
+    }
+}
+
+impl LifecycleConfiguration {
+    /// Validate every rule's expirations
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            let rule_error = |e: Error| Error::LifecycleRule(format!("Rule '{}': {}", rule.id, e));
+            if let Some(expiration) = &rule.expiration {
+                expiration.validate().map_err(rule_error)?;
+            }
+            if let Some(noncurrent) = &rule.noncurrent_version_expiration {
+                noncurrent.validate().map_err(rule_error)?;
+            }
+            if let Some(abort) = &rule.abort_incomplete_multipart_upload {
+                abort.validate().map_err(rule_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete objects under `logs/` after `days`
+    pub fn expire_logs(days: u32) -> Self {
+        Self::expire_prefix("expire-logs", "logs/", days)
+    }
+
+    /// Delete objects under `tmp/` after `days`
+    pub fn expire_tmp(days: u32) -> Self {
+        Self::expire_prefix("expire-tmp", "tmp/", days)
+    }
+
+    /// Abort multipart uploads still incomplete `days` after they started
+    pub fn cleanup_incomplete_uploads(days: u32) -> Self {
+        Self {
+            rules: vec![LifecycleRule {
+                id: "cleanup-incomplete-uploads".to_string(),
+                filter: LifecycleFilter::default(),
+                status: "Enabled".to_string(),
+                expiration: None,
+                noncurrent_version_expiration: None,
+                abort_incomplete_multipart_upload: Some(AbortIncompleteMultipartUpload {
+                    days_after_initiation: days,
+                }),
+            }],
+        }
+    }
+
+    fn expire_prefix(id: &str, prefix: &str, days: u32) -> Self {
+        Self {
+            rules: vec![LifecycleRule {
+                id: id.to_string(),
+                filter: LifecycleFilter {
+                    prefix: Some(prefix.to_string()),
+                },
+                status: "Enabled".to_string(),
+                expiration: Some(LifecycleExpiration::after_days(days)),
+                noncurrent_version_expiration: None,
+                abort_incomplete_multipart_upload: None,
+            }],
+        }
+    }
+}
+
+/// Well-known lifecycle rule sets for `lifecycle set --preset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecyclePreset {
+    /// See [`LifecycleConfiguration::expire_logs`]
+    ExpireLogs,
+    /// See [`LifecycleConfiguration::cleanup_incomplete_uploads`]
+    CleanupIncompleteUploads,
+    /// See [`LifecycleConfiguration::expire_tmp`]
+    ExpireTmp,
+}
+
+impl LifecyclePreset {
+    /// Day count used when none is given
+    pub fn default_days(self) -> u32 {
+        match self {
+            LifecyclePreset::ExpireLogs => 30,
+            LifecyclePreset::CleanupIncompleteUploads | LifecyclePreset::ExpireTmp => 7,
+        }
+    }
+
+    /// The preset's configuration, with `days` overriding the default
+    pub fn config(self, days: Option<u32>) -> Result<LifecycleConfiguration> {
+        let days = days.unwrap_or(self.default_days());
+        let config = match self {
+            LifecyclePreset::ExpireLogs => LifecycleConfiguration::expire_logs(days),
+            LifecyclePreset::CleanupIncompleteUploads => {
+                LifecycleConfiguration::cleanup_incomplete_uploads(days)
+            }
+            LifecyclePreset::ExpireTmp => LifecycleConfiguration::expire_tmp(days),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl std::str::FromStr for LifecyclePreset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "expire-logs-30d" => Ok(LifecyclePreset::ExpireLogs),
+            "cleanup-incomplete-uploads" => Ok(LifecyclePreset::CleanupIncompleteUploads),
+            "tmp-7d" => Ok(LifecyclePreset::ExpireTmp),
+            _ => Err(Error::InvalidInput(format!(
+                "Invalid lifecycle preset '{}' (expected expire-logs-30d, cleanup-incomplete-uploads or tmp-7d)",
+                s
+            ))),
+        }
+    }
+}
+
+// === Config File Schemas ===
+
+/// Example shown in the CORS schema
+fn cors_example() -> BucketCorsConfig {
+    BucketCorsConfig {
+        rules: vec![CorsRule {
+            id: Some("app".to_string()),
+            allowed_origins: vec!["https://app.example.com".to_string()],
+            allowed_methods: vec!["GET".to_string(), "PUT".to_string(), "HEAD".to_string()],
+            allowed_headers: Some(vec!["*".to_string()]),
+            max_age_seconds: Some(3600),
+        }],
+    }
+}
+
+/// Example shown in the lifecycle schema
+fn lifecycle_example() -> LifecycleConfiguration {
+    LifecycleConfiguration {
+        rules: vec![LifecycleRule {
+            id: "expire-logs".to_string(),
+            filter: LifecycleFilter {
+                prefix: Some("logs/".to_string()),
+            },
+            status: "Enabled".to_string(),
+            expiration: Some(LifecycleExpiration::after_days(30)),
+            noncurrent_version_expiration: None,
+            abort_incomplete_multipart_upload: None,
+        }],
+    }
+}
+
+/// JSON schema for `cors set --file` input
+pub fn cors_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(BucketCorsConfig))
+        .expect("schema serializes to JSON")
+}
+
+/// JSON schema for `lifecycle set --file` input
+pub fn lifecycle_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(LifecycleConfiguration))
+        .expect("schema serializes to JSON")
+}
+
+// === Website Configuration Types ===
+
+/// Website configuration for static hosting
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WebsiteConfiguration {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_document: Option<IndexDocument>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error_document: Option<ErrorDocument>,
 }
 
@@ -538,11 +1298,77 @@ pub struct ErrorDocument {
     pub key: String,
 }
 
-/// Builder for creating R2 tokens with edit permissions
+/// A bucket's r2.dev subdomain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagedDomain {
+    /// e.g. `pub-<id>.r2.dev`
+    pub domain: String,
+    /// Whether the bucket is publicly readable through it
+    pub enabled: bool,
+}
+
+/// A custom domain serving a bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomDomain {
+    pub domain: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomDomainList {
+    #[serde(default)]
+    domains: Vec<CustomDomain>,
+}
+
+/// Access level granted by an R2 token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenPermission {
+    /// Read-only access to R2
+    Read,
+    /// Read and write access to R2
+    #[default]
+    Edit,
+}
+
+impl TokenPermission {
+    /// Permission group granting this access level
+    pub fn permission_group(&self) -> PermissionGroup {
+        match self {
+            TokenPermission::Read => PermissionGroup {
+                id: "b4992e1108244f5d8bfbd5744320c2e1".to_string(), // R2 Read template ID
+                name: "Workers R2 Storage Read".to_string(),
+            },
+            TokenPermission::Edit => PermissionGroup {
+                id: "c4259685b71d4e928c3201fc048494ab".to_string(), // R2 Edit template ID
+                name: "Cloudflare R2 Edit".to_string(),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for TokenPermission {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "read" => Ok(TokenPermission::Read),
+            "edit" | "write" => Ok(TokenPermission::Edit),
+            _ => Err(Error::InvalidInput(format!(
+                "Invalid permission '{}' (expected read or edit)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Builder for creating R2 tokens (edit permissions unless set otherwise)
 pub struct R2TokenBuilder {
     name: String,
     account_id: String,
+    permission: TokenPermission,
     ip_whitelist: Option<Vec<String>>,
+    expires_on: Option<String>,
 }
 
 impl R2TokenBuilder {
@@ -551,28 +1377,37 @@ impl R2TokenBuilder {
         Self {
             name,
             account_id,
+            permission: TokenPermission::default(),
             ip_whitelist: None,
+            expires_on: None,
         }
     }
 
+    /// Set the access level
+    pub fn permission(mut self, permission: TokenPermission) -> Self {
+        self.permission = permission;
+        self
+    }
+
     /// Set IP whitelist
     pub fn ip_whitelist(mut self, ips: Vec<String>) -> Self {
         self.ip_whitelist = Some(ips);
         self
     }
 
+    /// Expire the token a number of days from now
+    pub fn expires_in_days(mut self, days: u32) -> Self {
+        let expires = chrono::Utc::now() + chrono::Duration::days(days as i64);
+        self.expires_on = Some(expires.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+        self
+    }
+
     /// Build the token creation parameters
     pub fn build(self) -> CreateTokenParams {
         CreateTokenParams {
             name: self.name,
             policy: TokenPolicy {
-                permission_groups: vec![
-                    // R2 Edit permission group
-                    PermissionGroup {
-                        id: "c4259685b71d4e928c3201fc048494ab".to_string(), // R2 Edit template ID
-                        name: "Cloudflare R2 Edit".to_string(),
-                    },
-                ],
+                permission_groups: vec![self.permission.permission_group()],
                 resources: Resources {
                     account: Some(AccountResources {
                         include: Some(vec![self.account_id]),
@@ -584,6 +1419,7 @@ impl R2TokenBuilder {
                     ip: Some(IpCondition { in_list: Some(ips) }),
                 }),
             }),
+            expires_on: self.expires_on,
         }
     }
 }
@@ -591,6 +1427,572 @@ impl R2TokenBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::DEFAULT_USER_AGENT;
+    use crate::test_support::{capture_request, MockServer};
+    use serde_json::json;
+
+    /// A successful API envelope around `result`
+    fn ok(result: serde_json::Value) -> (u16, String) {
+        let body = json!({ "success": true, "errors": [], "messages": [], "result": result });
+        (200, body.to_string())
+    }
+
+    /// A failed API envelope with one error
+    fn failure(status: u16, message: &str) -> (u16, String) {
+        let body = json!({
+            "success": false,
+            "errors": [{ "code": 10000, "message": message }],
+            "messages": [],
+            "result": null
+        });
+        (status, body.to_string())
+    }
+
+    fn api_client(server: &MockServer) -> CloudflareClient {
+        CloudflareClient::new("token".to_string(), "acc".to_string()).with_base_url(server.url())
+    }
+
+    #[tokio::test]
+    async fn test_api_list_buckets() {
+        let bucket = json!({ "name": "assets", "location": "WEUR", "creation_date": "2024-01-01T00:00:00Z" });
+        let server = MockServer::start(vec![
+            ok(json!({ "buckets": [bucket.clone()] })),
+            ok(json!([bucket])),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        // Both the wrapped (current API) and plain list shapes are accepted
+        for _ in 0..2 {
+            let buckets = client.list_buckets().await.unwrap();
+            assert_eq!(buckets.len(), 1);
+            assert_eq!(buckets[0].name, "assets");
+        }
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/accounts/acc/r2/buckets");
+        assert!(request.headers.contains("authorization: bearer token"));
+    }
+
+    #[tokio::test]
+    async fn test_api_bucket_endpoints() {
+        let bucket = json!({ "name": "assets", "location": "weur", "creation_date": "2024-01-01T00:00:00Z" });
+        let fetched = json!({ "name": "assets", "location": "weur", "creation_date": "2024-01-01T00:00:00Z", "jurisdiction": "default" });
+        let server = MockServer::start(vec![ok(bucket), ok(fetched), ok(json!({}))]).await;
+        let client = api_client(&server);
+
+        let created = client.create_bucket("assets", "weur").await.unwrap();
+        assert_eq!(created.location, "weur");
+        assert_eq!(created.jurisdiction, None);
+        let fetched = client.get_bucket("assets").await.unwrap();
+        assert_eq!(fetched.name, "assets");
+        assert_eq!(fetched.jurisdiction.as_deref(), Some("default"));
+        client.delete_bucket("assets").await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/accounts/acc/r2/buckets");
+        assert_eq!(
+            requests[0].json(),
+            json!({ "name": "assets", "location": { "location": "weur" } })
+        );
+        assert!(!requests[0].headers.contains("cf-r2-jurisdiction"));
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[1].path, "/accounts/acc/r2/buckets/assets");
+        assert_eq!(requests[2].method, "DELETE");
+        assert_eq!(requests[2].path, "/accounts/acc/r2/buckets/assets");
+    }
+
+    #[tokio::test]
+    async fn test_api_bucket_requests_in_jurisdiction() {
+        let bucket =
+            json!({ "name": "gdpr", "location": "weur", "creation_date": "2024-01-01T00:00:00Z" });
+        let server = MockServer::start(vec![
+            ok(bucket.clone()),
+            ok(json!(null)),
+            ok(json!(null)),
+            ok(bucket),
+            ok(json!(null)),
+        ])
+        .await;
+        let client = api_client(&server).with_jurisdiction(Some("eu")).unwrap();
+        assert_eq!(client.jurisdiction(), Some("eu"));
+
+        client.create_bucket("gdpr", "weur").await.unwrap();
+        client
+            .put_bucket_cors("gdpr", &BucketCorsConfig::public_read())
+            .await
+            .unwrap();
+        client
+            .put_bucket_website("gdpr", &WebsiteConfiguration::default())
+            .await
+            .unwrap();
+        client.get_bucket("gdpr").await.unwrap();
+        client.delete_bucket("gdpr").await.unwrap();
+
+        // Every call on the bucket names its jurisdiction, not just the create
+        let requests = server.requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests
+            .iter()
+            .all(|r| r.headers.contains("cf-r2-jurisdiction: eu")));
+
+        // Rejected before any request is sent
+        let result = api_client(&server).with_jurisdiction(Some("mars"));
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_api_null_result() {
+        let no_result = json!({ "success": true, "errors": [], "messages": [] }).to_string();
+        let server =
+            MockServer::start(vec![ok(json!(null)), (200, no_result), ok(json!(null))]).await;
+        let client = api_client(&server);
+
+        // Nothing is expected from a DELETE, so a null or missing result is fine
+        client.delete_bucket("assets").await.unwrap();
+        client.delete_bucket("assets").await.unwrap();
+
+        // A typed result can't be null
+        let error = client.get_bucket("assets").await.unwrap_err();
+        assert!(matches!(&error, Error::CloudflareApi(m) if m.contains("no result")));
+    }
+
+    #[tokio::test]
+    async fn test_api_public_domains() {
+        let managed = json!({ "bucketId": "0123", "domain": "pub-0123.r2.dev", "enabled": true });
+        let custom = json!({ "domains": [
+            { "domain": "cdn.example.com", "enabled": true, "status": { "ownership": "active", "ssl": "active" }, "minTLS": "1.2" },
+            { "domain": "old.example.com", "enabled": false }
+        ] });
+        let server = MockServer::start(vec![ok(managed), ok(custom)]).await;
+        let client = api_client(&server);
+
+        let managed = client.get_bucket_managed_domain("assets").await.unwrap();
+        assert_eq!(managed.domain, "pub-0123.r2.dev");
+        assert!(managed.enabled);
+
+        let custom = client.list_bucket_custom_domains("assets").await.unwrap();
+        assert_eq!(custom.len(), 2);
+        assert!(custom[0].enabled && !custom[1].enabled);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].path,
+            "/accounts/acc/r2/buckets/assets/domains/managed"
+        );
+        assert_eq!(
+            requests[1].path,
+            "/accounts/acc/r2/buckets/assets/domains/custom"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_create_existing_bucket() {
+        let server = MockServer::start(vec![failure(
+            409,
+            "The bucket you tried to create already exists, and you own it.",
+        )])
+        .await;
+        let client = api_client(&server);
+
+        let error = client.create_bucket("assets", "weur").await.unwrap_err();
+
+        assert!(matches!(&error, Error::AlreadyExists(m) if m == "Bucket 'assets'"));
+        assert_eq!(error.to_string(), "Bucket 'assets' already exists");
+    }
+
+    #[tokio::test]
+    async fn test_api_cors_endpoints() {
+        let rules = json!({ "rules": [{ "allowedOrigins": ["*"], "allowedMethods": ["GET"] }] });
+        let server = MockServer::start(vec![ok(rules), ok(json!(null)), ok(json!({}))]).await;
+        let client = api_client(&server);
+
+        let config = client.get_bucket_cors("assets").await.unwrap();
+        assert_eq!(config.rules[0].allowed_methods, vec!["GET"]);
+
+        let mut rule = cors_rule(Some("app"), " https://app.example.com ");
+        rule.allowed_methods = vec!["get".to_string(), "GET".to_string()];
+        client
+            .put_bucket_cors("assets", &BucketCorsConfig { rules: vec![rule] })
+            .await
+            .unwrap();
+        client.delete_bucket_cors("assets").await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/accounts/acc/r2/buckets/assets/cors");
+        assert_eq!(requests[1].method, "PUT");
+        // Rules are normalized before being sent
+        let sent = requests[1].json();
+        assert_eq!(
+            sent["rules"][0]["allowedOrigins"],
+            json!(["https://app.example.com"])
+        );
+        assert_eq!(sent["rules"][0]["allowedMethods"], json!(["GET"]));
+        assert_eq!(sent["rules"][0]["id"], "app");
+        assert_eq!(requests[2].method, "DELETE");
+    }
+
+    #[tokio::test]
+    async fn test_api_cors_rule_edits() {
+        let existing = serde_json::to_value(BucketCorsConfig {
+            rules: vec![
+                cors_rule(Some("old"), "https://old.com"),
+                cors_rule(Some("keep"), "https://keep.com"),
+            ],
+        })
+        .unwrap();
+        let server = MockServer::start(vec![
+            // add_cors_rule on a bucket without CORS: 404, then PUT
+            failure(404, "The CORS configuration does not exist."),
+            ok(json!(null)),
+            // remove_cors_rule: GET, then PUT without the rule
+            ok(existing.clone()),
+            ok(json!(null)),
+            // remove_cors_rule with an unknown ID: GET only
+            ok(existing),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        client
+            .add_cors_rule("assets", cors_rule(Some("app"), "https://app.com"))
+            .await
+            .unwrap();
+        client.remove_cors_rule("assets", "old").await.unwrap();
+        let missing = client.remove_cors_rule("assets", "nope").await;
+        assert!(matches!(missing, Err(Error::NotFound(_))));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[1].json()["rules"][0]["id"], "app");
+        assert_eq!(requests[3].method, "PUT");
+        let remaining = requests[3].json();
+        assert_eq!(remaining["rules"].as_array().unwrap().len(), 1);
+        assert_eq!(remaining["rules"][0]["id"], "keep");
+    }
+
+    #[tokio::test]
+    async fn test_api_lifecycle_endpoints() {
+        let config = LifecycleConfiguration {
+            rules: vec![LifecycleRule {
+                id: "expire-tmp".to_string(),
+                filter: LifecycleFilter {
+                    prefix: Some("tmp/".to_string()),
+                },
+                status: "Enabled".to_string(),
+                expiration: Some(LifecycleExpiration::after_days(7)),
+                noncurrent_version_expiration: None,
+                abort_incomplete_multipart_upload: None,
+            }],
+        };
+        let server = MockServer::start(vec![
+            ok(serde_json::to_value(&config).unwrap()),
+            ok(json!(null)),
+            ok(json!({})),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        let fetched = client.get_bucket_lifecycle("assets").await.unwrap();
+        assert_eq!(fetched.rules[0].id, "expire-tmp");
+        client
+            .put_bucket_lifecycle("assets", &config)
+            .await
+            .unwrap();
+        client.delete_bucket_lifecycle("assets").await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].path,
+            "/accounts/acc/r2/buckets/assets/lifecycle"
+        );
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].json()["rules"][0]["expiration"]["days"], 7);
+        assert_eq!(requests[2].method, "DELETE");
+    }
+
+    #[tokio::test]
+    async fn test_api_website_endpoints() {
+        let config = WebsiteConfiguration {
+            index_document: Some(IndexDocument {
+                suffix: "index.html".to_string(),
+            }),
+            error_document: None,
+        };
+        let server = MockServer::start(vec![
+            ok(json!(null)),
+            ok(serde_json::to_value(&config).unwrap()),
+            ok(json!({})),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        client.put_bucket_website("site", &config).await.unwrap();
+        let fetched = client.get_bucket_website("site").await.unwrap();
+        assert_eq!(fetched.index_document.unwrap().suffix, "index.html");
+        client.delete_bucket_website("site").await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/accounts/acc/r2/buckets/site/website");
+        assert_eq!(requests[0].json()["index_document"]["suffix"], "index.html");
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[2].method, "DELETE");
+    }
+
+    #[tokio::test]
+    async fn test_api_token_endpoints() {
+        let token = json!({
+            "id": "tok1",
+            "name": "ci",
+            "status": "active",
+            "issued_on": "2024-01-01T00:00:00Z",
+            "modified_on": "2024-01-01T00:00:00Z",
+            "expires_on": null,
+            "value": "secret"
+        });
+        let server = MockServer::start(vec![
+            ok(json!([token.clone()])),
+            ok(token),
+            ok(json!({ "id": "tok1" })),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        assert_eq!(client.list_tokens().await.unwrap()[0].id, "tok1");
+        let params = R2TokenBuilder::new("ci".to_string(), "acc".to_string()).build();
+        let created = client.create_token(params).await.unwrap();
+        assert_eq!(created.value.as_deref(), Some("secret"));
+        client.revoke_token("tok1").await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            (requests[0].method.as_str(), requests[0].path.as_str()),
+            ("GET", "/user/tokens")
+        );
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].json()["name"], "ci");
+        assert_eq!(
+            (requests[2].method.as_str(), requests[2].path.as_str()),
+            ("DELETE", "/user/tokens/tok1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_token_verify() {
+        let token = json!({
+            "id": "tok1",
+            "name": "ci",
+            "status": "active",
+            "issued_on": "2024-01-01T00:00:00Z",
+            "modified_on": "2024-01-01T00:00:00Z",
+            "expires_on": "2999-01-01T00:00:00Z",
+            "permissions": [{ "policy": {
+                "permission_groups": [{ "id": "g1", "name": "Workers R2 Storage Read" }],
+                "resources": {}
+            }}]
+        });
+        let server = MockServer::start(vec![
+            ok(json!({ "id": "tok1", "status": "active", "not_before": "2024-01-01T00:00:00Z" })),
+            ok(token),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        let verification = client.verify_token().await.unwrap();
+        assert_eq!(verification.id, "tok1");
+        assert!(verification.is_active());
+        let fetched = client.get_token("tok1").await.unwrap();
+        assert!(fetched.grants_r2());
+        assert!(fetched.verification().is_active());
+        assert_eq!(
+            fetched.permission_group_names(),
+            vec!["Workers R2 Storage Read"]
+        );
+        assert_eq!(fetched.r2_access(), Some(TokenPermission::Read));
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/user/tokens/verify");
+        assert_eq!(requests[1].path, "/user/tokens/tok1");
+
+        let expired = TokenVerification {
+            id: "tok2".to_string(),
+            status: "active".to_string(),
+            not_before: None,
+            expires_on: Some("2020-01-01T00:00:00Z".to_string()),
+        };
+        assert!(!expired.is_active());
+        let disabled = TokenVerification {
+            status: "disabled".to_string(),
+            expires_on: None,
+            ..expired
+        };
+        assert!(!disabled.is_active());
+    }
+
+    #[test]
+    fn test_token_r2_access() {
+        let token = |groups: &[&str]| -> ApiToken {
+            serde_json::from_value(json!({
+                "id": "tok1",
+                "name": "ci",
+                "status": "active",
+                "issued_on": "2024-01-01T00:00:00Z",
+                "modified_on": "2024-01-01T00:00:00Z",
+                "expires_on": null,
+                "permissions": groups.iter().map(|name| json!({ "policy": {
+                    "permission_groups": [{ "id": "g", "name": name }],
+                    "resources": {}
+                }})).collect::<Vec<_>>()
+            }))
+            .unwrap()
+        };
+
+        let edit = token(&["Workers R2 Storage Read", "Workers R2 Storage Write"]);
+        assert_eq!(edit.r2_access(), Some(TokenPermission::Edit));
+        let read = token(&["API Tokens Read", "Workers R2 Storage Read"]);
+        assert_eq!(read.r2_access(), Some(TokenPermission::Read));
+        assert_eq!(
+            read.permission_group_names(),
+            vec!["API Tokens Read", "Workers R2 Storage Read"]
+        );
+
+        // Object-level access doesn't allow managing buckets
+        let objects_only = token(&["Workers R2 Storage Bucket Item Write"]);
+        assert_eq!(objects_only.r2_access(), None);
+        assert!(token(&["Zone Read", "Zone Read"]).r2_access().is_none());
+        assert_eq!(
+            token(&["Zone Read", "Zone Read"]).permission_group_names(),
+            vec!["Zone Read"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_error_mapping() {
+        let server = MockServer::start(vec![
+            failure(401, "Invalid API Token"),
+            failure(403, "Forbidden"),
+            failure(404, "Not found"),
+            failure(429, "Rate limited"),
+            failure(502, "Bad gateway"),
+            failure(400, "Bad bucket name"),
+            failure(200, "Bucket already exists"),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        let mut results = Vec::new();
+        for _ in 0..7 {
+            results.push(client.list_buckets().await.unwrap_err());
+        }
+
+        assert!(matches!(results[0], Error::Authentication(_)));
+        assert!(matches!(results[1], Error::PermissionDenied(_)));
+        assert!(matches!(results[2], Error::NotFound(_)));
+        assert!(matches!(results[3], Error::Transient(_)));
+        assert!(results[3].is_transient());
+        assert!(matches!(results[4], Error::Transient(_)));
+        assert!(matches!(&results[5], Error::CloudflareApi(m) if m.contains("Bad bucket name")));
+        // success: false in a 200 response
+        assert!(matches!(&results[6], Error::CloudflareApi(m) if m == "Bucket already exists"));
+    }
+
+    #[tokio::test]
+    async fn test_api_error_bodies() {
+        let invalid_name = json!({
+            "success": false,
+            "errors": [{
+                "code": 10005,
+                "message": "The bucket name is not valid. Bucket names must be between 3 and 63 characters long."
+            }],
+            "messages": [],
+            "result": null
+        });
+        let forbidden = json!({
+            "success": false,
+            "errors": [
+                { "code": 10000, "message": "Authentication error" },
+                { "code": 9109, "message": "Unauthorized to access requested resource" }
+            ],
+            "messages": []
+        });
+        let gateway_page = format!(
+            "<!DOCTYPE html>\n<html><head><title>502 Bad Gateway</title></head>{}</html>\n",
+            "<p>cloudflare</p>".repeat(100)
+        );
+        let server = MockServer::start(vec![
+            (400, invalid_name.to_string()),
+            (403, forbidden.to_string()),
+            (502, gateway_page),
+            (404, String::new()),
+            (400, "  bucket name too long\n".to_string()),
+        ])
+        .await;
+        let client = api_client(&server);
+
+        let mut results = Vec::new();
+        for _ in 0..5 {
+            results.push(client.list_buckets().await.unwrap_err());
+        }
+
+        // The envelope's errors are used even though the status isn't 2xx
+        assert!(matches!(
+            &results[0],
+            Error::CloudflareApi(m) if m == "HTTP 400: The bucket name is not valid. Bucket names must be between 3 and 63 characters long."
+        ));
+        assert!(matches!(
+            &results[1],
+            Error::PermissionDenied(m) if m == "Insufficient permissions: Authentication error; Unauthorized to access requested resource"
+        ));
+
+        // Anything else is kept as text, shortened
+        let Error::Transient(page) = &results[2] else {
+            panic!("{:?}", results[2]);
+        };
+        assert!(page.starts_with("HTTP 502: <!DOCTYPE html>"), "{}", page);
+        assert!(page.ends_with("..."));
+        assert!(page.chars().count() < 600);
+        assert!(matches!(&results[3], Error::NotFound(m) if m == "Resource not found"));
+        assert!(
+            matches!(&results[4], Error::CloudflareApi(m) if m == "HTTP 400: bucket name too long")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_send_user_agent() {
+        let (url, request) = capture_request().await;
+        let client = CloudflareClient::new("token".to_string(), "account".to_string())
+            .with_base_url(url.clone());
+        let _ = client.list_tokens().await;
+        let head = request.await.unwrap();
+        assert!(head.contains(&format!("user-agent: {}", DEFAULT_USER_AGENT)));
+
+        let (url, request) = capture_request().await;
+        let client = CloudflareClient::new_with_user_agent(
+            "token".to_string(),
+            "account".to_string(),
+            Some("deploy-bot/2.0"),
+        )
+        .unwrap()
+        .with_base_url(url);
+        let _ = client.list_tokens().await;
+        assert!(request
+            .await
+            .unwrap()
+            .contains("user-agent: deploy-bot/2.0"));
+    }
+
+    #[test]
+    fn test_invalid_user_agent_is_rejected() {
+        let result = CloudflareClient::new_with_user_agent(
+            "token".to_string(),
+            "account".to_string(),
+            Some("bad\nagent"),
+        );
+        assert!(matches!(result, Err(Error::InvalidConfig(_))));
+    }
 
     #[test]
     fn test_r2_token_builder() {
@@ -603,6 +2005,26 @@ mod tests {
         assert_eq!(params.name, "Test Token");
         assert_eq!(params.policy.permission_groups.len(), 1);
         assert!(params.condition.is_some());
+        assert!(params.expires_on.is_none());
+    }
+
+    #[test]
+    fn test_r2_token_builder_permission_and_expiry() {
+        let params = R2TokenBuilder::new("ci".to_string(), "abc123".to_string())
+            .permission("read".parse().unwrap())
+            .expires_in_days(30)
+            .build();
+
+        assert_eq!(
+            params.policy.permission_groups[0].id,
+            TokenPermission::Read.permission_group().id
+        );
+        let expires = params.expires_on.unwrap();
+        let expires = chrono::DateTime::parse_from_rfc3339(&expires).unwrap();
+        let days = (expires.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_days();
+        assert!((29..=30).contains(&days));
+
+        assert!("admin".parse::<TokenPermission>().is_err());
     }
 
     #[test]
@@ -622,6 +2044,7 @@ mod tests {
             name: "test-bucket".to_string(),
             location: "wnam".to_string(),
             creation_date: "2024-01-01T00:00:00Z".to_string(),
+            jurisdiction: None,
         };
 
         assert_eq!(bucket.name, "test-bucket");
@@ -632,6 +2055,7 @@ mod tests {
     #[test]
     fn test_cors_rule() {
         let rule = CorsRule {
+            id: None,
             allowed_origins: vec!["https://example.com".to_string()],
             allowed_methods: vec!["GET".to_string(), "PUT".to_string(), "DELETE".to_string()],
             allowed_headers: Some(vec!["*".to_string()]),
@@ -648,6 +2072,7 @@ mod tests {
     fn test_bucket_cors_config() {
         let config = BucketCorsConfig {
             rules: vec![CorsRule {
+                id: None,
                 allowed_origins: vec!["*".to_string()],
                 allowed_methods: vec!["GET".to_string(), "HEAD".to_string()],
                 allowed_headers: None,
@@ -660,6 +2085,244 @@ mod tests {
         assert_eq!(config.rules[0].allowed_methods, vec!["GET", "HEAD"]);
     }
 
+    fn cors_rule(id: Option<&str>, origin: &str) -> CorsRule {
+        CorsRule {
+            id: id.map(str::to_string),
+            allowed_origins: vec![origin.to_string()],
+            allowed_methods: vec!["GET".to_string()],
+            allowed_headers: None,
+            max_age_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_cors_add_and_remove_rule() {
+        let mut config = BucketCorsConfig {
+            rules: vec![cors_rule(None, "https://existing.com")],
+        };
+
+        config
+            .add_rule(cors_rule(Some("app"), "https://app.example.com"))
+            .unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert!(config
+            .add_rule(cors_rule(Some("app"), "https://other.com"))
+            .is_err());
+
+        let removed = config.remove_rule("app").unwrap();
+        assert_eq!(removed.allowed_origins, vec!["https://app.example.com"]);
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(
+            config.rules[0].allowed_origins,
+            vec!["https://existing.com"]
+        );
+        assert!(config.remove_rule("app").is_none());
+    }
+
+    #[test]
+    fn test_cors_matches_exact_origin() {
+        let mut rule = cors_rule(Some("app"), "https://app.example.com");
+        rule.allowed_methods.push("PUT".to_string());
+        rule.allowed_headers = Some(vec!["X-Custom".to_string()]);
+        let config = BucketCorsConfig {
+            rules: vec![cors_rule(Some("read"), "https://other.com"), rule],
+        };
+
+        let matched = config
+            .matches(
+                "https://app.example.com",
+                "put",
+                &["x-custom", "Content-Type"],
+            )
+            .unwrap();
+        assert_eq!(matched.id.as_deref(), Some("app"));
+
+        assert!(config
+            .matches("https://app.example.com", "DELETE", &[])
+            .is_none());
+        assert!(config.matches("https://evil.com", "PUT", &[]).is_none());
+        assert!(config
+            .matches("https://app.example.com", "PUT", &["authorization"])
+            .is_none());
+        // Only safelisted headers without allowed_headers
+        assert!(config
+            .matches("https://other.com", "GET", &["x-custom"])
+            .is_none());
+        assert!(config
+            .matches("https://other.com", "GET", &["accept"])
+            .is_some());
+    }
+
+    #[test]
+    fn test_cors_matches_wildcards() {
+        let mut subdomains = cors_rule(Some("subdomains"), "https://*.example.com");
+        subdomains.allowed_headers = Some(vec!["x-amz-meta-*".to_string()]);
+        let config = BucketCorsConfig {
+            rules: vec![subdomains, BucketCorsConfig::public_read().rules.remove(0)],
+        };
+
+        let matched = config
+            .matches("https://cdn.example.com", "GET", &["X-Amz-Meta-Owner"])
+            .unwrap();
+        assert_eq!(matched.id.as_deref(), Some("subdomains"));
+
+        // Falls through to the public rule, which allows any origin
+        let matched = config.matches("https://example.org", "HEAD", &[]).unwrap();
+        assert_eq!(matched.id.as_deref(), Some("public-read"));
+
+        assert!(config.matches("https://example.org", "PUT", &[]).is_none());
+        assert!(config
+            .matches("https://cdn.example.com", "GET", &["x-other"])
+            .is_none());
+        assert!(!wildcard_match(
+            "https://*.example.com",
+            "https://example.com"
+        ));
+    }
+
+    #[test]
+    fn test_cors_public_read() {
+        let config = BucketCorsConfig::public_read();
+
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].allowed_origins, vec!["*"]);
+        assert_eq!(config.rules[0].allowed_methods, vec!["GET", "HEAD"]);
+    }
+
+    #[test]
+    fn test_cors_presets() {
+        let preset = |name: &str, origin| name.parse::<CorsPreset>().unwrap().config(origin);
+
+        let public = preset("public-read", None).unwrap();
+        assert_eq!(public.rules[0].allowed_methods, vec!["GET", "HEAD"]);
+
+        let web_app = preset("web-app", Some(" https://app.example.com/ ")).unwrap();
+        assert_eq!(
+            web_app.rules[0].allowed_origins,
+            vec!["https://app.example.com"]
+        );
+        assert_eq!(
+            web_app.rules[0].allowed_methods,
+            vec!["GET", "PUT", "POST", "DELETE"]
+        );
+        assert!(web_app
+            .matches("https://app.example.com", "PUT", &["content-type"])
+            .is_some());
+        assert!(web_app
+            .matches("https://evil.example.com", "GET", &[])
+            .is_none());
+        assert!(preset("web-app", None).is_err());
+        assert!(preset("web-app", Some("*")).is_err());
+        assert!(CorsPreset::WebApp.needs_origin());
+
+        let all = preset("ALL", None).unwrap();
+        assert!(all
+            .matches("https://any.example", "DELETE", &["x-custom"])
+            .is_some());
+        assert!(!CorsPreset::All.needs_origin());
+
+        assert!("open".parse::<CorsPreset>().is_err());
+    }
+
+    #[test]
+    fn test_cors_rule_id_serialization() {
+        let json = serde_json::to_value(cors_rule(None, "*")).unwrap();
+        assert!(json.get("id").is_none());
+
+        let rule: CorsRule = serde_json::from_str(
+            r#"{"id":"web","allowedOrigins":["*"],"allowedMethods":["GET"],"allowedHeaders":null,"maxAgeSeconds":null}"#,
+        )
+        .unwrap();
+        assert_eq!(rule.id.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn test_split_comma_list_drops_empty_entries() {
+        assert_eq!(
+            split_comma_list("https://a.com, https://b.com,"),
+            vec!["https://a.com", "https://b.com"]
+        );
+        assert_eq!(
+            split_comma_list(" Content-Type ,, Authorization , "),
+            vec!["Content-Type", "Authorization"]
+        );
+        assert!(split_comma_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_cors_rule_normalize() {
+        let mut rule = CorsRule {
+            id: None,
+            allowed_origins: vec![" https://a.com".to_string(), "https://a.com ".to_string()],
+            allowed_methods: vec!["get".to_string(), "GET".to_string(), " ".to_string()],
+            allowed_headers: Some(vec![
+                "Content-Type".to_string(),
+                "content-type".to_string(),
+                "".to_string(),
+            ]),
+            max_age_seconds: None,
+        };
+
+        rule.normalize().unwrap();
+
+        assert_eq!(rule.allowed_origins, vec!["https://a.com"]);
+        assert_eq!(rule.allowed_methods, vec!["GET"]);
+        assert_eq!(rule.allowed_headers, Some(vec!["Content-Type".to_string()]));
+    }
+
+    #[test]
+    fn test_cors_rule_normalize_wildcard_and_headers() {
+        let mut rule = CorsRule {
+            id: None,
+            allowed_origins: vec!["https://a.com".to_string(), "*".to_string()],
+            allowed_methods: vec!["GET".to_string()],
+            allowed_headers: None,
+            max_age_seconds: None,
+        };
+        rule.normalize().unwrap();
+        assert_eq!(rule.allowed_origins, vec!["*"]);
+
+        // No header restriction declared: the field is omitted, not sent as null
+        assert_eq!(rule.allowed_headers, None);
+        let json = serde_json::to_value(&rule).unwrap();
+        assert!(json.get("allowedHeaders").is_none());
+
+        // An explicitly empty list stays explicit
+        rule.allowed_headers = Some(vec![" ".to_string()]);
+        rule.normalize().unwrap();
+        assert_eq!(rule.allowed_headers, Some(vec![]));
+
+        rule.allowed_methods = vec![" ".to_string()];
+        assert!(rule.normalize().is_err());
+    }
+
+    #[test]
+    fn test_cors_schema() {
+        let schema = cors_schema();
+
+        let rule = &schema["definitions"]["CorsRule"];
+        assert!(rule["properties"]["allowedOrigins"].is_object());
+        assert!(rule["properties"]["maxAgeSeconds"].is_object());
+
+        // The embedded example is itself a valid config
+        let example = schema["examples"][0].clone();
+        let config: BucketCorsConfig = serde_json::from_value(example).unwrap();
+        assert_eq!(config.rules[0].id.as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn test_lifecycle_schema() {
+        let schema = lifecycle_schema();
+
+        let expiration = &schema["definitions"]["LifecycleExpiration"];
+        assert!(expiration["properties"]["days"].is_object());
+        assert!(expiration["properties"]["date"].is_object());
+
+        let example = schema["examples"][0].clone();
+        let config: LifecycleConfiguration = serde_json::from_value(example).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_lifecycle_filter() {
         let filter = LifecycleFilter {
@@ -671,9 +2334,50 @@ mod tests {
 
     #[test]
     fn test_lifecycle_expiration() {
-        let expiration = LifecycleExpiration { days: Some(30) };
+        let expiration = LifecycleExpiration::after_days(30);
 
         assert_eq!(expiration.days, Some(30));
+        assert_eq!(expiration.date, None);
+    }
+
+    #[test]
+    fn test_lifecycle_expiration_date_serialization() {
+        let expiration = LifecycleExpiration::on_date("2026-01-01");
+
+        let json = serde_json::to_value(&expiration).unwrap();
+        assert_eq!(json, serde_json::json!({ "date": "2026-01-01" }));
+
+        let parsed: LifecycleExpiration = serde_json::from_str(r#"{"days": 7}"#).unwrap();
+        assert_eq!(parsed.days, Some(7));
+        assert_eq!(parsed.date, None);
+    }
+
+    #[test]
+    fn test_lifecycle_expiration_validate() {
+        assert!(LifecycleExpiration::after_days(30).validate().is_ok());
+        assert!(LifecycleExpiration::on_date("2026-01-01")
+            .validate()
+            .is_ok());
+        assert!(LifecycleExpiration::on_date("2026-01-01T00:00:00Z")
+            .validate()
+            .is_ok());
+
+        assert!(LifecycleExpiration::after_days(0).validate().is_err());
+        assert!(LifecycleExpiration::on_date("next tuesday")
+            .validate()
+            .is_err());
+        assert!(LifecycleExpiration {
+            days: None,
+            date: None
+        }
+        .validate()
+        .is_err());
+        assert!(LifecycleExpiration {
+            days: Some(30),
+            date: Some("2026-01-01".to_string())
+        }
+        .validate()
+        .is_err());
     }
 
     #[test]
@@ -684,7 +2388,9 @@ mod tests {
                 prefix: Some("logs/".to_string()),
             },
             status: "Enabled".to_string(),
-            expiration: Some(LifecycleExpiration { days: Some(30) }),
+            expiration: Some(LifecycleExpiration::after_days(30)),
+            noncurrent_version_expiration: None,
+            abort_incomplete_multipart_upload: None,
         };
 
         assert_eq!(rule.id, "log-rotation");
@@ -702,12 +2408,77 @@ mod tests {
                     prefix: Some("videos/".to_string()),
                 },
                 status: "Enabled".to_string(),
-                expiration: Some(LifecycleExpiration { days: Some(90) }),
+                expiration: Some(LifecycleExpiration::after_days(90)),
+                noncurrent_version_expiration: None,
+                abort_incomplete_multipart_upload: None,
             }],
         };
 
         assert_eq!(config.rules.len(), 1);
         assert_eq!(config.rules[0].id, "delete-old-videos");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_lifecycle_noncurrent_version_expiration() {
+        let rule: LifecycleRule = serde_json::from_value(serde_json::json!({
+            "id": "prune-versions",
+            "filter": {},
+            "status": "Enabled",
+            "expiration": null,
+            "noncurrentVersionExpiration": { "noncurrentDays": 14 }
+        }))
+        .unwrap();
+        assert_eq!(
+            rule.noncurrent_version_expiration,
+            Some(NoncurrentVersionExpiration {
+                noncurrent_days: 14
+            })
+        );
+
+        let json = serde_json::to_value(&rule).unwrap();
+        assert_eq!(json["noncurrentVersionExpiration"]["noncurrentDays"], 14);
+
+        // Omitted when unset, and older configs without it still parse
+        let mut plain = rule.clone();
+        plain.noncurrent_version_expiration = None;
+        let json = serde_json::to_value(&plain).unwrap();
+        assert!(json.get("noncurrentVersionExpiration").is_none());
+        let parsed: LifecycleRule = serde_json::from_value(json).unwrap();
+        assert!(parsed.noncurrent_version_expiration.is_none());
+
+        let mut config = LifecycleConfiguration { rules: vec![rule] };
+        assert!(config.validate().is_ok());
+        config.rules[0].noncurrent_version_expiration =
+            Some(NoncurrentVersionExpiration { noncurrent_days: 0 });
+        assert!(
+            matches!(config.validate(), Err(Error::LifecycleRule(m)) if m.contains("prune-versions"))
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_presets() {
+        let preset = |name: &str, days| name.parse::<LifecyclePreset>().unwrap().config(days);
+
+        let logs = preset("expire-logs-30d", None).unwrap();
+        assert_eq!(logs.rules[0].filter.prefix.as_deref(), Some("logs/"));
+        assert_eq!(logs.rules[0].expiration.as_ref().unwrap().days, Some(30));
+
+        let tmp = preset("tmp-7d", Some(2)).unwrap();
+        assert_eq!(tmp.rules[0].filter.prefix.as_deref(), Some("tmp/"));
+        assert_eq!(tmp.rules[0].expiration.as_ref().unwrap().days, Some(2));
+
+        let cleanup = preset("cleanup-incomplete-uploads", None).unwrap();
+        assert!(cleanup.rules[0].expiration.is_none());
+        let json = serde_json::to_value(&cleanup).unwrap();
+        assert_eq!(
+            json["rules"][0]["abortIncompleteMultipartUpload"]["daysAfterInitiation"],
+            7
+        );
+
+        assert!(preset("tmp-7d", Some(0)).is_err());
+        assert!(preset("cleanup-incomplete-uploads", Some(0)).is_err());
+        assert!("expire-everything".parse::<LifecyclePreset>().is_err());
     }
 
     #[test]
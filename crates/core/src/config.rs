@@ -1,10 +1,12 @@
 //! Configuration management for r2pilot
 
+use crate::client::{load_ca_bundle, validate_content_disposition};
 use crate::error::{Error, Result};
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Configuration directory name
 const CONFIG_DIR: &str = "r2pilot";
@@ -15,14 +17,68 @@ const CONFIG_FILE: &str = "config.toml";
 /// Credentials file name (separate from config for security)
 const CREDENTIALS_FILE: &str = "credentials.toml";
 
+/// Current configuration layout version
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Environment variable naming a file that holds the API token
+pub const API_TOKEN_FILE_ENV: &str = "R2PILOT_API_TOKEN_FILE";
+
+/// Known R2 jurisdictions (data residency restricted endpoints)
+pub const JURISDICTIONS: &[&str] = &["eu", "fedramp"];
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigFile {
+    /// Layout version, upgraded by `migrate_config` on load
+    #[serde(default = "default_config_version")]
+    pub version: u32,
     pub cloudflare: CloudflareConfig,
     pub r2: R2Config,
     pub advanced: Option<AdvancedConfig>,
     pub logging: Option<LoggingConfig>,
     pub output: Option<OutputConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload: Option<UploadConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<DeployConfig>,
+    /// Local bucket annotations, keyed by bucket name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub buckets: BTreeMap<String, BucketAnnotation>,
+}
+
+/// Local description and tags for a bucket
+///
+/// R2 has no bucket tagging API, so these live only in the config file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BucketAnnotation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl BucketAnnotation {
+    /// Whether there is nothing to store
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.tags.is_empty()
+    }
+}
+
+/// Parse a `key=value` tag
+pub fn parse_tag(input: &str) -> Result<(String, String)> {
+    let (key, value) = input.split_once('=').ok_or_else(|| {
+        Error::InvalidInput(format!("Invalid tag '{}' (expected key=value)", input))
+    })?;
+
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "Invalid tag '{}' (key cannot be empty)",
+            input
+        )));
+    }
+
+    Ok((key.to_string(), value.trim().to_string()))
 }
 
 /// Cloudflare configuration
@@ -35,12 +91,111 @@ pub struct CloudflareConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_token: Option<String>,
 
+    // File holding the API token (e.g. a mounted Docker/Kubernetes secret)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token_file: Option<PathBuf>,
+
     // OR Access Keys (alternative method)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub access_key_id: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub secret_access_key: Option<String>,
+
+    // Jurisdiction for restricted endpoints (eu, fedramp)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jurisdiction: Option<String>,
+
+    // Path-style addressing for S3-compatible endpoints (MinIO, proxies)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_path_style: Option<bool>,
+
+    // User-Agent sent to the S3 and Cloudflare APIs (default: r2pilot/<version>)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
+    // Extra trusted root certificates (PEM) for self-hosted gateways and TLS proxies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+
+    // Skip TLS certificate verification of the Cloudflare API (development only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub danger_accept_invalid_certs: Option<bool>,
+}
+
+/// Secrets kept in `credentials.toml`, next to the config file
+///
+/// Values found there fill in whatever `config.toml` leaves unset, and are
+/// left out of `config.toml` when it is saved.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+}
+
+impl Credentials {
+    /// The secrets set in a `[cloudflare]` section
+    pub fn from_config(cloudflare: &CloudflareConfig) -> Self {
+        Self {
+            api_token: cloudflare.api_token.clone(),
+            access_key_id: cloudflare.access_key_id.clone(),
+            secret_access_key: cloudflare.secret_access_key.clone(),
+        }
+    }
+
+    /// The secrets written in a raw `config.toml` itself
+    pub fn inline(content: &str) -> Result<Self> {
+        let value: toml::Value = toml::from_str(content)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to parse config file: {}", e)))?;
+        let field = |key: &str| {
+            value
+                .get("cloudflare")
+                .and_then(|cloudflare| cloudflare.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+
+        Ok(Self {
+            api_token: field("api_token"),
+            access_key_id: field("access_key_id"),
+            secret_access_key: field("secret_access_key"),
+        })
+    }
+
+    /// Whether no secret is set
+    pub fn is_empty(&self) -> bool {
+        self.api_token.is_none() && self.access_key_id.is_none() && self.secret_access_key.is_none()
+    }
+
+    /// Fill in the secrets the config leaves unset
+    fn apply(&self, cloudflare: &mut CloudflareConfig) {
+        for (field, value) in [
+            (&mut cloudflare.api_token, &self.api_token),
+            (&mut cloudflare.access_key_id, &self.access_key_id),
+            (&mut cloudflare.secret_access_key, &self.secret_access_key),
+        ] {
+            if field.is_none() {
+                field.clone_from(value);
+            }
+        }
+    }
+
+    /// Drop the secrets that are stored here from the config
+    fn strip(&self, cloudflare: &mut CloudflareConfig) {
+        for (field, value) in [
+            (&mut cloudflare.api_token, &self.api_token),
+            (&mut cloudflare.access_key_id, &self.access_key_id),
+            (&mut cloudflare.secret_access_key, &self.secret_access_key),
+        ] {
+            if value.is_some() && field == value {
+                *field = None;
+            }
+        }
+    }
 }
 
 /// R2 configuration
@@ -77,6 +232,15 @@ pub struct AdvancedConfig {
     /// Multipart upload chunk size in MB (default: 100)
     #[serde(default = "default_multipart_chunk_size")]
     pub multipart_chunk_size_mb: usize,
+    /// `files rm` refuses to delete more objects than this without `--yes` (default: 100)
+    #[serde(default = "default_rm_confirm_threshold")]
+    pub rm_confirm_threshold: usize,
+    /// Size cap of the `files download --cache` directory in MB (default: 1024)
+    #[serde(default = "default_download_cache_max_size")]
+    pub download_cache_max_size_mb: u64,
+    /// Upload bandwidth cap, e.g. `5MB/s` (unlimited when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_upload_rate: Option<String>,
 }
 
 impl Default for AdvancedConfig {
@@ -87,10 +251,27 @@ impl Default for AdvancedConfig {
             retry_delay: default_retry_delay(),
             max_concurrent_uploads: default_max_concurrent(),
             multipart_chunk_size_mb: default_multipart_chunk_size(),
+            rm_confirm_threshold: default_rm_confirm_threshold(),
+            download_cache_max_size_mb: default_download_cache_max_size(),
+            max_upload_rate: None,
         }
     }
 }
 
+impl AdvancedConfig {
+    /// Size cap of the download cache in bytes
+    pub fn download_cache_max_bytes(&self) -> Result<u64> {
+        self.download_cache_max_size_mb
+            .checked_mul(1024 * 1024)
+            .ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "download_cache_max_size_mb is too large: {}",
+                    self.download_cache_max_size_mb
+                ))
+            })
+    }
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -130,7 +311,37 @@ impl Default for OutputConfig {
     }
 }
 
+/// Defaults applied to uploads unless overridden per command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UploadConfig {
+    /// `Content-Disposition` set on uploaded objects (e.g. `attachment`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_content_disposition: Option<String>,
+}
+
+/// Settings for multi-file uploads (glob uploads and `files sync`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeployConfig {
+    /// Headers per glob, matched against each file's path relative to the
+    /// uploaded directory (e.g. `"assets/**"`, `"**/*.html"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rules: BTreeMap<String, DeployRule>,
+}
+
+/// Headers set on files matching a `[deploy.rules]` glob
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
 // Default values
+fn default_config_version() -> u32 {
+    CONFIG_VERSION
+}
+
 fn default_region() -> String {
     "auto".to_string()
 }
@@ -159,6 +370,14 @@ fn default_multipart_chunk_size() -> usize {
     100 // 100MB
 }
 
+fn default_rm_confirm_threshold() -> usize {
+    100
+}
+
+fn default_download_cache_max_size() -> u64 {
+    crate::cache::DEFAULT_CACHE_MAX_SIZE_MB
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -175,6 +394,54 @@ fn default_color() -> String {
     "auto".to_string()
 }
 
+/// Build the R2 S3 endpoint for an account, optionally jurisdiction-specific
+///
+/// `https://{account}.r2.cloudflarestorage.com` or
+/// `https://{account}.{jurisdiction}.r2.cloudflarestorage.com`
+pub fn r2_endpoint(account_id: &str, jurisdiction: Option<&str>) -> String {
+    match jurisdiction {
+        Some(jurisdiction) => format!(
+            "https://{}.{}.r2.cloudflarestorage.com",
+            account_id, jurisdiction
+        ),
+        None => format!("https://{}.r2.cloudflarestorage.com", account_id),
+    }
+}
+
+/// Validate an S3 endpoint URL (http or https, with a host)
+pub fn validate_endpoint(endpoint: &str) -> Result<()> {
+    let url = reqwest::Url::parse(endpoint)
+        .map_err(|e| Error::InvalidInput(format!("Invalid endpoint URL '{}': {}", endpoint, e)))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::InvalidInput(format!(
+            "Invalid endpoint URL '{}': scheme must be http or https",
+            endpoint
+        )));
+    }
+    if url.host_str().is_none() {
+        return Err(Error::InvalidInput(format!(
+            "Invalid endpoint URL '{}': missing host",
+            endpoint
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validate a jurisdiction against the known values
+pub fn validate_jurisdiction(jurisdiction: &str) -> Result<()> {
+    if JURISDICTIONS.contains(&jurisdiction) {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Unknown jurisdiction '{}' (expected one of: {})",
+            jurisdiction,
+            JURISDICTIONS.join(", ")
+        )))
+    }
+}
+
 /// Get the configuration directory
 pub fn get_config_dir() -> Result<PathBuf> {
     let home =
@@ -211,34 +478,388 @@ pub fn load_config() -> Result<ConfigFile> {
     let content = fs::read_to_string(&config_path)
         .map_err(|e| Error::InvalidConfig(format!("Failed to read config file: {}", e)))?;
 
-    let config: ConfigFile = toml::from_str(&content)
+    let mut value: toml::Value = toml::from_str(&content)
         .map_err(|e| Error::InvalidConfig(format!("Failed to parse config file: {}", e)))?;
 
+    let from_version = config_version(&value);
+    let migrated = migrate_config(&mut value)?;
+
+    let mut config: ConfigFile = value
+        .try_into()
+        .map_err(|e| Error::InvalidConfig(format!("Failed to parse config file: {}", e)))?;
+
+    if let Some(credentials) = load_credentials()? {
+        credentials.apply(&mut config.cloudflare);
+    }
+    apply_api_token_file(&mut config.cloudflare, env_api_token_file())?;
+
+    if migrated {
+        // Keep the original around: rewriting through serde drops comments
+        let backup_path = config_path.with_extension(format!("toml.v{}.bak", from_version));
+        fs::write(&backup_path, &content)
+            .map_err(|e| Error::Config(format!("Failed to back up config file: {}", e)))?;
+        save_config(&config)?;
+
+        eprintln!(
+            "Warning: configuration upgraded from v{} to v{} (original saved to {})",
+            from_version,
+            CONFIG_VERSION,
+            backup_path.display()
+        );
+        if content.lines().any(|l| l.trim_start().starts_with('#')) {
+            eprintln!("Warning: comments in the original file were not preserved");
+        }
+    }
+
     Ok(config)
 }
 
+/// Read an API token file, trimmed of surrounding whitespace
+pub fn read_api_token_file(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        Error::InvalidConfig(format!(
+            "Cannot read api_token_file '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let token = content.trim();
+    if token.is_empty() {
+        return Err(Error::InvalidConfig(format!(
+            "api_token_file '{}' is empty",
+            path.display()
+        )));
+    }
+    Ok(token.to_string())
+}
+
+/// The file named by `R2PILOT_API_TOKEN_FILE`, if set
+fn env_api_token_file() -> Option<PathBuf> {
+    std::env::var_os(API_TOKEN_FILE_ENV)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Where the API token is read from: `api_token_file`, else `env_file`
+fn api_token_source(cloudflare: &CloudflareConfig, env_file: Option<PathBuf>) -> Option<PathBuf> {
+    cloudflare.api_token_file.clone().or(env_file)
+}
+
+/// Read the API token from its file when none is set inline
+///
+/// Precedence: `api_token` (config or `credentials.toml`), then
+/// `api_token_file`, then the file named by `R2PILOT_API_TOKEN_FILE`.
+fn apply_api_token_file(
+    cloudflare: &mut CloudflareConfig,
+    env_file: Option<PathBuf>,
+) -> Result<()> {
+    if cloudflare.api_token.is_some() {
+        return Ok(());
+    }
+    if let Some(path) = api_token_source(cloudflare, env_file) {
+        cloudflare.api_token = Some(read_api_token_file(&path)?);
+    }
+    Ok(())
+}
+
+/// Drop the API token if it is the one read from its file
+fn strip_api_token_file(cloudflare: &mut CloudflareConfig, env_file: Option<PathBuf>) {
+    let Some(path) = api_token_source(cloudflare, env_file) else {
+        return;
+    };
+    if cloudflare.api_token.is_some() && read_api_token_file(&path).ok() == cloudflare.api_token {
+        cloudflare.api_token = None;
+    }
+}
+
+/// Layout version of a raw config (files without `version` are v0)
+fn config_version(value: &toml::Value) -> u32 {
+    value
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// Upgrade a raw config to `CONFIG_VERSION`, one version at a time
+///
+/// Returns whether anything changed. Configs newer than this build are rejected.
+pub fn migrate_config(value: &mut toml::Value) -> Result<bool> {
+    let version = config_version(value);
+
+    if version > CONFIG_VERSION {
+        return Err(Error::InvalidConfig(format!(
+            "Config version {} is newer than supported version {} (upgrade r2pilot)",
+            version, CONFIG_VERSION
+        )));
+    }
+
+    let table = value
+        .as_table_mut()
+        .ok_or_else(|| Error::InvalidConfig("Config must be a table".to_string()))?;
+
+    for from in version..CONFIG_VERSION {
+        match from {
+            0 => migrate_v0_to_v1(table)?,
+            _ => unreachable!("no migration from config v{}", from),
+        }
+    }
+
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(CONFIG_VERSION as i64),
+    );
+
+    Ok(version < CONFIG_VERSION)
+}
+
+/// v0 -> v1: move bare top-level credentials and bucket into their sections,
+/// and derive a missing endpoint from the account ID
+fn migrate_v0_to_v1(table: &mut toml::Table) -> Result<()> {
+    fn section<'a>(table: &'a mut toml::Table, name: &str) -> Result<&'a mut toml::Table> {
+        table
+            .entry(name)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| Error::InvalidConfig(format!("[{}] must be a table", name)))
+    }
+
+    for key in [
+        "account_id",
+        "endpoint",
+        "api_token",
+        "access_key_id",
+        "secret_access_key",
+    ] {
+        if let Some(value) = table.remove(key) {
+            section(table, "cloudflare")?.entry(key).or_insert(value);
+        }
+    }
+
+    for key in ["default_bucket", "bucket"] {
+        if let Some(value) = table.remove(key) {
+            section(table, "r2")?
+                .entry("default_bucket")
+                .or_insert(value);
+        }
+    }
+
+    let cloudflare = section(table, "cloudflare")?;
+    if !cloudflare.contains_key("endpoint") {
+        if let Some(account_id) = cloudflare.get("account_id").and_then(|v| v.as_str()) {
+            let endpoint = r2_endpoint(
+                account_id,
+                cloudflare.get("jurisdiction").and_then(|v| v.as_str()),
+            );
+            cloudflare.insert("endpoint".to_string(), toml::Value::String(endpoint));
+        }
+    }
+
+    Ok(())
+}
+
 /// Save configuration to file
+///
+/// Secrets that come from `credentials.toml` or an API token file are not
+/// written back to the config.
 pub fn save_config(config: &ConfigFile) -> Result<()> {
     let config_path = get_config_path()?;
 
-    let content = toml::to_string_pretty(config)
+    let mut config = config.clone();
+    if let Some(credentials) = load_credentials()? {
+        credentials.strip(&mut config.cloudflare);
+    }
+    strip_api_token_file(&mut config.cloudflare, env_api_token_file());
+
+    let content = toml::to_string_pretty(&config)
         .map_err(|e| Error::InvalidConfig(format!("Failed to serialize config: {}", e)))?;
 
     fs::write(&config_path, content)
         .map_err(|e| Error::Config(format!("Failed to write config file: {}", e)))?;
 
-    // Set secure permissions on config file (read/write for owner only)
+    restrict_permissions(&config_path)
+}
+
+/// Load `credentials.toml`, if there is one
+pub fn load_credentials() -> Result<Option<Credentials>> {
+    let path = get_credentials_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| Error::InvalidConfig(format!("Failed to read credentials file: {}", e)))?;
+    let credentials = toml::from_str(&content)
+        .map_err(|e| Error::InvalidConfig(format!("Failed to parse credentials file: {}", e)))?;
+
+    Ok(Some(credentials))
+}
+
+/// Write `credentials.toml`, readable by the owner only
+pub fn save_credentials(credentials: &Credentials) -> Result<()> {
+    let path = get_credentials_path()?;
+
+    let content = toml::to_string_pretty(credentials)
+        .map_err(|e| Error::InvalidConfig(format!("Failed to serialize credentials: {}", e)))?;
+
+    fs::write(&path, content)
+        .map_err(|e| Error::Config(format!("Failed to write credentials file: {}", e)))?;
+
+    restrict_permissions(&path)
+}
+
+/// File format of an exported configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format of a file, from its extension (TOML unless `.json`)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            _ => Err(Error::InvalidInput(format!(
+                "Unsupported config format: {} (expected toml or json)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Serialize a configuration for backup or transfer to another machine
+///
+/// Secrets (API token, access keys) are left out unless `with_secrets` is set.
+pub fn export_config(
+    config: &ConfigFile,
+    format: ConfigFormat,
+    with_secrets: bool,
+) -> Result<String> {
+    let mut config = config.clone();
+    if !with_secrets {
+        Credentials::from_config(&config.cloudflare).strip(&mut config.cloudflare);
+    }
+
+    match format {
+        ConfigFormat::Toml => toml::to_string_pretty(&config)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to serialize config: {}", e))),
+        ConfigFormat::Json => serde_json::to_string_pretty(&config)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to serialize config: {}", e))),
+    }
+}
+
+/// Parse an exported configuration, upgrading an older TOML layout
+pub fn parse_config(content: &str, format: ConfigFormat) -> Result<ConfigFile> {
+    let config: ConfigFile = match format {
+        ConfigFormat::Toml => {
+            let mut value: toml::Value = toml::from_str(content)
+                .map_err(|e| Error::InvalidConfig(format!("Failed to parse config: {}", e)))?;
+            migrate_config(&mut value)?;
+            value
+                .try_into()
+                .map_err(|e| Error::InvalidConfig(format!("Failed to parse config: {}", e)))?
+        }
+        ConfigFormat::Json => serde_json::from_str(content)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to parse config: {}", e)))?,
+    };
+
+    if config.version > CONFIG_VERSION {
+        return Err(Error::InvalidConfig(format!(
+            "Config version {} is newer than supported version {} (upgrade r2pilot)",
+            config.version, CONFIG_VERSION
+        )));
+    }
+
+    Ok(config)
+}
+
+/// Validate an exported configuration and install it as the config file
+///
+/// Secrets the export leaves out are kept from the current configuration
+/// (or `credentials.toml`), so a redacted export can be imported as is.
+/// The file is written readable by the owner only.
+pub fn import_config(content: &str, format: ConfigFormat) -> Result<ConfigFile> {
+    let mut config = parse_config(content, format)?;
+
+    // An unreadable current config shouldn't block restoring a backup
+    let current = match load_config() {
+        Ok(current) => Some(Credentials::from_config(&current.cloudflare)),
+        Err(_) => load_credentials()?,
+    };
+    if let Some(credentials) = current {
+        credentials.apply(&mut config.cloudflare);
+    }
+
+    validate_config(&config)?;
+    save_config(&config)?;
+
+    Ok(config)
+}
+
+/// Set secure permissions on a file (read/write for owner only)
+pub fn restrict_permissions(path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&config_path)?.permissions();
+        let mut perms = fs::metadata(path)?.permissions();
         perms.set_mode(0o600);
-        fs::set_permissions(&config_path, perms)?;
+        fs::set_permissions(path, perms)?;
     }
+    #[cfg(not(unix))]
+    let _ = path;
 
     Ok(())
 }
 
+/// `[r2]` settings a raw config file leaves to their defaults
+///
+/// Returns the missing keys (`region`, `default_expiration`).
+pub fn missing_config_defaults(content: &str) -> Result<Vec<&'static str>> {
+    let value: toml::Value = toml::from_str(content)
+        .map_err(|e| Error::InvalidConfig(format!("Failed to parse config file: {}", e)))?;
+    let r2 = value.get("r2").and_then(|v| v.as_table());
+
+    Ok(["region", "default_expiration"]
+        .into_iter()
+        .filter(|key| !r2.is_some_and(|r2| r2.contains_key(*key)))
+        .collect())
+}
+
+/// The R2 endpoint the config should use, if its endpoint belongs to another account
+///
+/// Only R2 endpoints are considered; custom endpoints (MinIO, proxies) are left alone.
+pub fn expected_endpoint(config: &ConfigFile) -> Option<String> {
+    let mismatch = endpoint_account_mismatch(config)?;
+    if !mismatch.host.ends_with(".r2.cloudflarestorage.com") {
+        return None;
+    }
+
+    Some(r2_endpoint(
+        &config.cloudflare.account_id,
+        config.cloudflare.jurisdiction.as_deref(),
+    ))
+}
+
+/// Root of the git work tree containing `path`, if any
+pub fn find_git_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
 /// Validate configuration
 pub fn validate_config(config: &ConfigFile) -> Result<()> {
     // Validate account_id (should be 32 characters)
@@ -260,6 +881,18 @@ pub fn validate_config(config: &ConfigFile) -> Result<()> {
         ));
     }
 
+    // Validate jurisdiction
+    if let Some(jurisdiction) = &config.cloudflare.jurisdiction {
+        validate_jurisdiction(jurisdiction)?;
+    }
+
+    // A default R2 endpoint must belong to the configured account
+    if let Some(mismatch) = endpoint_account_mismatch(config) {
+        if mismatch.default_endpoint {
+            return Err(Error::InvalidInput(mismatch.message()));
+        }
+    }
+
     // Validate bucket name
     if config.r2.default_bucket.is_empty() {
         return Err(Error::InvalidInput(
@@ -275,9 +908,114 @@ pub fn validate_config(config: &ConfigFile) -> Result<()> {
         ));
     }
 
+    // Validate upload defaults
+    if let Some(disposition) = config
+        .upload
+        .as_ref()
+        .and_then(|u| u.default_content_disposition.as_deref())
+    {
+        validate_content_disposition(disposition)?;
+    }
+
+    // Validate the CA bundle loads
+    if let Some(path) = &config.cloudflare.ca_bundle {
+        load_ca_bundle(path)?;
+    }
+
+    // Validate the download cache size converts to bytes
+    if let Some(advanced) = &config.advanced {
+        advanced.download_cache_max_bytes()?;
+    }
+
+    // Validate the upload bandwidth cap
+    if let Some(rate) = config
+        .advanced
+        .as_ref()
+        .and_then(|a| a.max_upload_rate.as_deref())
+    {
+        crate::rate::parse_rate(rate)?;
+    }
+
+    // Validate deploy rules
+    if let Some(deploy) = &config.deploy {
+        crate::sync::DeployRules::new(&deploy.rules)?;
+    }
+
     Ok(())
 }
 
+/// Non-fatal configuration issues, for display after `validate_config` passes
+pub fn config_warnings(config: &ConfigFile) -> Vec<String> {
+    let mut warnings: Vec<String> = endpoint_account_mismatch(config)
+        .filter(|mismatch| !mismatch.default_endpoint)
+        .map(|mismatch| mismatch.message())
+        .into_iter()
+        .collect();
+    if config.cloudflare.danger_accept_invalid_certs == Some(true) {
+        warnings.push(
+            "danger_accept_invalid_certs is on: Cloudflare API certificates are not verified \
+             (development only)"
+                .to_string(),
+        );
+    }
+    warnings
+}
+
+/// An endpoint whose host doesn't start with `<account_id>.`
+struct EndpointAccountMismatch {
+    host: String,
+    account_id: String,
+    /// `<account>.r2.cloudflarestorage.com`, as opposed to a jurisdiction or custom endpoint
+    default_endpoint: bool,
+}
+
+impl EndpointAccountMismatch {
+    fn message(&self) -> String {
+        format!(
+            "Endpoint host '{}' does not start with the account ID '{}' (copied from another account?)",
+            self.host, self.account_id
+        )
+    }
+}
+
+/// Compare the endpoint host with the account ID
+///
+/// R2 endpoints are `https://<account_id>[.<jurisdiction>].r2.cloudflarestorage.com`;
+/// unparsable endpoints are left to other checks.
+fn endpoint_account_mismatch(config: &ConfigFile) -> Option<EndpointAccountMismatch> {
+    let url = reqwest::Url::parse(&config.cloudflare.endpoint).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let account_id = config.cloudflare.account_id.to_ascii_lowercase();
+
+    if host.starts_with(&format!("{}.", account_id)) {
+        return None;
+    }
+
+    let default_endpoint = host
+        .strip_suffix(".r2.cloudflarestorage.com")
+        .is_some_and(|label| !label.contains('.'));
+
+    Some(EndpointAccountMismatch {
+        host,
+        account_id: config.cloudflare.account_id.clone(),
+        default_endpoint,
+    })
+}
+
+/// Check whether a bucket name is valid for R2
+///
+/// 3-63 lowercase letters, digits and hyphens, starting and ending with a
+/// letter or digit.
+pub fn is_valid_bucket_name(name: &str) -> bool {
+    let edge = |c: Option<char>| c.is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    (3..=63).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && edge(name.chars().next())
+        && edge(name.chars().last())
+}
+
 /// Check if configuration exists
 pub fn config_exists() -> bool {
     get_config_path().map(|p| p.exists()).unwrap_or(false)
@@ -292,12 +1030,20 @@ mod tests {
 
     fn make_valid_config() -> ConfigFile {
         ConfigFile {
+            version: CONFIG_VERSION,
             cloudflare: CloudflareConfig {
                 account_id: "0123456789abcdef0123456789abcdef".to_string(),
                 api_token: Some("test_token".to_string()),
-                endpoint: "https://test.r2.cloudflarestorage.com".to_string(),
+                endpoint: "https://0123456789abcdef0123456789abcdef.r2.cloudflarestorage.com"
+                    .to_string(),
                 access_key_id: None,
                 secret_access_key: None,
+                jurisdiction: None,
+                force_path_style: None,
+                user_agent: None,
+                ca_bundle: None,
+                danger_accept_invalid_certs: None,
+                api_token_file: None,
             },
             r2: R2Config {
                 default_bucket: "test-bucket".to_string(),
@@ -307,6 +1053,9 @@ mod tests {
             advanced: None,
             logging: None,
             output: None,
+            upload: None,
+            deploy: None,
+            buckets: BTreeMap::new(),
         }
     }
 
@@ -362,6 +1111,169 @@ mod tests {
         assert!(validate_config(&config).is_ok());
     }
 
+    #[test]
+    fn test_validate_config_endpoint_account() {
+        let mut config = make_valid_config();
+        assert!(config_warnings(&config).is_empty());
+
+        // Default endpoint for another account: rejected
+        config.cloudflare.endpoint =
+            "https://fedcba9876543210fedcba9876543210.r2.cloudflarestorage.com".to_string();
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("0123456789abcdef0123456789abcdef"));
+
+        // Jurisdiction endpoint for another account: warning only
+        config.cloudflare.endpoint =
+            "https://fedcba9876543210fedcba9876543210.eu.r2.cloudflarestorage.com".to_string();
+        assert!(validate_config(&config).is_ok());
+        assert_eq!(config_warnings(&config).len(), 1);
+
+        // Matching jurisdiction endpoint (host case doesn't matter)
+        config.cloudflare.endpoint =
+            "https://0123456789ABCDEF0123456789ABCDEF.eu.r2.cloudflarestorage.com".to_string();
+        assert!(config_warnings(&config).is_empty());
+
+        // Custom endpoint: warning only
+        config.cloudflare.endpoint = "http://localhost:9000".to_string();
+        assert!(validate_config(&config).is_ok());
+        assert_eq!(config_warnings(&config).len(), 1);
+    }
+
+    #[test]
+    fn test_validate_config_tls() {
+        let mut config = make_valid_config();
+        config.cloudflare.ca_bundle = Some(PathBuf::from("/nonexistent/ca.pem"));
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/ca.pem"));
+
+        config.cloudflare.ca_bundle = None;
+        config.cloudflare.danger_accept_invalid_certs = Some(true);
+        assert!(validate_config(&config).is_ok());
+        assert_eq!(config_warnings(&config).len(), 1);
+    }
+
+    #[test]
+    fn test_r2_endpoint() {
+        assert_eq!(
+            r2_endpoint("abc", None),
+            "https://abc.r2.cloudflarestorage.com"
+        );
+        assert_eq!(
+            r2_endpoint("abc", Some("eu")),
+            "https://abc.eu.r2.cloudflarestorage.com"
+        );
+    }
+
+    #[test]
+    fn test_force_path_style_is_optional() {
+        let mut config = make_valid_config();
+        let content = toml::to_string(&config).unwrap();
+        assert!(!content.contains("force_path_style"));
+
+        config.cloudflare.force_path_style = Some(true);
+        let content = toml::to_string(&config).unwrap();
+        let parsed: ConfigFile = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.cloudflare.force_path_style, Some(true));
+    }
+
+    #[test]
+    fn test_validate_config_content_disposition() {
+        let mut config = make_valid_config();
+        config.upload = Some(UploadConfig {
+            default_content_disposition: Some("attachment".to_string()),
+        });
+        assert!(validate_config(&config).is_ok());
+
+        config.upload = Some(UploadConfig {
+            default_content_disposition: Some("attachment; filename=".to_string()),
+        });
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_deploy_config_from_toml() {
+        let mut config = make_valid_config();
+        config.deploy = Some(
+            toml::from_str(
+                r#"
+                [rules."assets/**"]
+                cache_control = "max-age=31536000, immutable"
+
+                [rules."**/*.html"]
+                cache_control = "no-cache"
+                content_type = "text/html; charset=utf-8"
+                "#,
+            )
+            .unwrap(),
+        );
+        assert!(validate_config(&config).is_ok());
+
+        let rules = &config.deploy.as_ref().unwrap().rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(
+            rules["assets/**"].cache_control.as_deref(),
+            Some("max-age=31536000, immutable")
+        );
+        assert!(rules["assets/**"].content_type.is_none());
+
+        config
+            .deploy
+            .as_mut()
+            .unwrap()
+            .rules
+            .insert("[".to_string(), DeployRule::default());
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_upload_config_from_toml() {
+        let upload: UploadConfig =
+            toml::from_str("default_content_disposition = \"attachment\"").unwrap();
+        assert_eq!(
+            upload.default_content_disposition.as_deref(),
+            Some("attachment")
+        );
+        assert!(toml::from_str::<UploadConfig>("")
+            .unwrap()
+            .default_content_disposition
+            .is_none());
+    }
+
+    #[test]
+    fn test_validate_endpoint() {
+        assert!(validate_endpoint("https://abc.r2.cloudflarestorage.com").is_ok());
+        assert!(validate_endpoint("http://localhost:9000").is_ok());
+        assert!(validate_endpoint("localhost:9000").is_err());
+        assert!(validate_endpoint("ftp://example.com").is_err());
+        assert!(validate_endpoint("not a url").is_err());
+    }
+
+    #[test]
+    fn test_validate_config_jurisdiction() {
+        let mut config = make_valid_config();
+        config.cloudflare.jurisdiction = Some("eu".to_string());
+        assert!(validate_config(&config).is_ok());
+
+        config.cloudflare.jurisdiction = Some("mars".to_string());
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_is_valid_bucket_name() {
+        assert!(is_valid_bucket_name("my-bucket"));
+        assert!(is_valid_bucket_name("assets-v2"));
+        assert!(is_valid_bucket_name("123"));
+        assert!(!is_valid_bucket_name(""));
+        assert!(!is_valid_bucket_name("ab"));
+        assert!(!is_valid_bucket_name("my_bucket"));
+        assert!(!is_valid_bucket_name(&"a".repeat(64)));
+        assert!(!is_valid_bucket_name("MyBucket"));
+        assert!(!is_valid_bucket_name("a.b.c"));
+        assert!(!is_valid_bucket_name("-foo"));
+        assert!(!is_valid_bucket_name("foo-"));
+        assert!(!is_valid_bucket_name("-foo-"));
+    }
+
     #[test]
     fn test_advanced_config_default() {
         let config = AdvancedConfig::default();
@@ -371,6 +1283,8 @@ mod tests {
         assert_eq!(config.retry_delay, 1000);
         assert_eq!(config.max_concurrent_uploads, 5);
         assert_eq!(config.multipart_chunk_size_mb, 100);
+        assert_eq!(config.rm_confirm_threshold, 100);
+        assert_eq!(config.download_cache_max_size_mb, 1024);
     }
 
     #[test]
@@ -381,6 +1295,9 @@ mod tests {
             retry_delay: 2000,
             max_concurrent_uploads: 10,
             multipart_chunk_size_mb: 200,
+            rm_confirm_threshold: 50,
+            download_cache_max_size_mb: 256,
+            max_upload_rate: Some("5MB/s".to_string()),
         };
 
         assert_eq!(config.timeout, 60);
@@ -388,6 +1305,26 @@ mod tests {
         assert_eq!(config.retry_delay, 2000);
         assert_eq!(config.max_concurrent_uploads, 10);
         assert_eq!(config.multipart_chunk_size_mb, 200);
+        assert_eq!(config.rm_confirm_threshold, 50);
+    }
+
+    #[test]
+    fn test_download_cache_max_bytes() {
+        let mut advanced = AdvancedConfig::default();
+        assert_eq!(
+            advanced.download_cache_max_bytes().unwrap(),
+            1024 * 1024 * 1024
+        );
+
+        advanced.download_cache_max_size_mb = u64::MAX;
+        assert!(matches!(
+            advanced.download_cache_max_bytes(),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let mut config = make_valid_config();
+        config.advanced = Some(advanced);
+        assert!(validate_config(&config).is_err());
     }
 
     #[test]
@@ -418,12 +1355,19 @@ mod tests {
     #[test]
     fn test_config_file_with_advanced_options() {
         let config = ConfigFile {
+            version: CONFIG_VERSION,
             cloudflare: CloudflareConfig {
                 account_id: "test-account".to_string(),
                 endpoint: "https://api.cloudflare.com".to_string(),
                 api_token: Some("test-token".to_string()),
                 access_key_id: None,
                 secret_access_key: None,
+                jurisdiction: None,
+                force_path_style: None,
+                user_agent: None,
+                ca_bundle: None,
+                danger_accept_invalid_certs: None,
+                api_token_file: None,
             },
             r2: R2Config {
                 default_bucket: "test-bucket".to_string(),
@@ -433,10 +1377,266 @@ mod tests {
             advanced: Some(AdvancedConfig::default()),
             logging: Some(LoggingConfig::default()),
             output: Some(OutputConfig::default()),
+            upload: None,
+            deploy: None,
+            buckets: BTreeMap::new(),
         };
 
         assert!(config.advanced.is_some());
         assert!(config.logging.is_some());
         assert!(config.output.is_some());
     }
+
+    #[test]
+    fn test_bucket_annotations_roundtrip() {
+        let mut config = make_valid_config();
+        let annotation = BucketAnnotation {
+            description: Some("Marketing assets".to_string()),
+            tags: BTreeMap::from([("team".to_string(), "growth".to_string())]),
+        };
+        config
+            .buckets
+            .insert("assets".to_string(), annotation.clone());
+
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        let parsed: ConfigFile = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.buckets.get("assets"), Some(&annotation));
+
+        // Configs without annotations don't grow a [buckets] table
+        let plain = toml::to_string_pretty(&make_valid_config()).unwrap();
+        assert!(!plain.contains("buckets"));
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(
+            parse_tag("team=growth").unwrap(),
+            ("team".to_string(), "growth".to_string())
+        );
+        assert_eq!(
+            parse_tag("note=a=b").unwrap(),
+            ("note".to_string(), "a=b".to_string())
+        );
+        assert!(parse_tag("team").is_err());
+        assert!(parse_tag("=value").is_err());
+    }
+
+    #[test]
+    fn test_migrate_v0_config() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            account_id = "0123456789abcdef0123456789abcdef"
+            access_key_id = "key"
+            secret_access_key = "secret"
+            bucket = "my-bucket"
+
+            [r2]
+            default_expiration = 3600
+            "#,
+        )
+        .unwrap();
+
+        assert!(migrate_config(&mut value).unwrap());
+
+        let config: ConfigFile = value.try_into().unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.cloudflare.access_key_id.as_deref(), Some("key"));
+        assert_eq!(
+            config.cloudflare.endpoint,
+            "https://0123456789abcdef0123456789abcdef.r2.cloudflarestorage.com"
+        );
+        assert_eq!(config.r2.default_bucket, "my-bucket");
+        assert_eq!(config.r2.default_expiration, 3600);
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_migrate_current_config_is_noop() {
+        let content = toml::to_string(&make_valid_config()).unwrap();
+        let mut value: toml::Value = toml::from_str(&content).unwrap();
+        let before = value.clone();
+
+        assert!(!migrate_config(&mut value).unwrap());
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_config() {
+        let mut value: toml::Value =
+            toml::from_str(&format!("version = {}", CONFIG_VERSION + 1)).unwrap();
+
+        assert!(migrate_config(&mut value).is_err());
+    }
+
+    #[test]
+    fn test_credentials_apply_and_strip() {
+        let credentials = Credentials {
+            access_key_id: Some("key".to_string()),
+            secret_access_key: Some("secret".to_string()),
+            ..Default::default()
+        };
+
+        // Values from config.toml win over credentials.toml
+        let mut cloudflare = make_valid_config().cloudflare;
+        cloudflare.access_key_id = Some("inline-key".to_string());
+        credentials.apply(&mut cloudflare);
+        assert_eq!(cloudflare.api_token.as_deref(), Some("test_token"));
+        assert_eq!(cloudflare.access_key_id.as_deref(), Some("inline-key"));
+        assert_eq!(cloudflare.secret_access_key.as_deref(), Some("secret"));
+
+        // Only the values identical to the stored ones are left out on save
+        credentials.strip(&mut cloudflare);
+        assert_eq!(cloudflare.api_token.as_deref(), Some("test_token"));
+        assert_eq!(cloudflare.access_key_id.as_deref(), Some("inline-key"));
+        assert_eq!(cloudflare.secret_access_key, None);
+
+        assert!(Credentials::default().is_empty());
+        assert!(!Credentials::from_config(&cloudflare).is_empty());
+
+        let inline = Credentials::inline("[cloudflare]\napi_token = \"tok\"\n").unwrap();
+        assert_eq!(inline.api_token.as_deref(), Some("tok"));
+        assert_eq!(inline.access_key_id, None);
+    }
+
+    #[test]
+    fn test_api_token_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("token");
+        let env_file = dir.path().join("env-token");
+        std::fs::write(&file, "  file-token\n").unwrap();
+        std::fs::write(&env_file, "env-token").unwrap();
+
+        // An inline token wins over both files
+        let mut cloudflare = make_valid_config().cloudflare;
+        cloudflare.api_token_file = Some(file.clone());
+        apply_api_token_file(&mut cloudflare, Some(env_file.clone())).unwrap();
+        assert_eq!(cloudflare.api_token.as_deref(), Some("test_token"));
+
+        // Then api_token_file, trimmed, then the environment's file
+        cloudflare.api_token = None;
+        apply_api_token_file(&mut cloudflare, Some(env_file.clone())).unwrap();
+        assert_eq!(cloudflare.api_token.as_deref(), Some("file-token"));
+
+        let mut from_env = make_valid_config().cloudflare;
+        from_env.api_token = None;
+        apply_api_token_file(&mut from_env, Some(env_file.clone())).unwrap();
+        assert_eq!(from_env.api_token.as_deref(), Some("env-token"));
+
+        // A token read from its file is not written back on save
+        strip_api_token_file(&mut cloudflare, None);
+        assert_eq!(cloudflare.api_token, None);
+        let mut inline = make_valid_config().cloudflare;
+        strip_api_token_file(&mut inline, Some(env_file));
+        assert_eq!(inline.api_token.as_deref(), Some("test_token"));
+
+        // A missing or empty file is a configuration error
+        std::fs::write(&file, " \n").unwrap();
+        cloudflare.api_token = None;
+        assert!(apply_api_token_file(&mut cloudflare, None).is_err());
+        cloudflare.api_token_file = Some(dir.path().join("missing"));
+        assert!(apply_api_token_file(&mut cloudflare, None).is_err());
+    }
+
+    #[test]
+    fn test_missing_config_defaults() {
+        let content = r#"
+            [cloudflare]
+            account_id = "0123456789abcdef0123456789abcdef"
+            endpoint = "https://0123456789abcdef0123456789abcdef.r2.cloudflarestorage.com"
+
+            [r2]
+            default_bucket = "test-bucket"
+            region = "auto"
+        "#;
+        assert_eq!(
+            missing_config_defaults(content).unwrap(),
+            vec!["default_expiration"]
+        );
+        assert_eq!(
+            missing_config_defaults("").unwrap(),
+            vec!["region", "default_expiration"]
+        );
+        assert!(missing_config_defaults("r2 = [").is_err());
+    }
+
+    #[test]
+    fn test_expected_endpoint() {
+        let mut config = make_valid_config();
+        assert_eq!(expected_endpoint(&config), None);
+
+        config.cloudflare.endpoint =
+            "https://ffffffffffffffffffffffffffffffff.eu.r2.cloudflarestorage.com".to_string();
+        config.cloudflare.jurisdiction = Some("eu".to_string());
+        assert_eq!(
+            expected_endpoint(&config).as_deref(),
+            Some("https://0123456789abcdef0123456789abcdef.eu.r2.cloudflarestorage.com")
+        );
+
+        // Custom endpoints are never rewritten
+        config.cloudflare.endpoint = "http://localhost:9000".to_string();
+        assert_eq!(expected_endpoint(&config), None);
+    }
+
+    #[test]
+    fn test_export_config_roundtrip() {
+        let mut config = make_valid_config();
+        config.cloudflare.access_key_id = Some("key".to_string());
+        config.cloudflare.secret_access_key = Some("secret".to_string());
+        config.cloudflare.jurisdiction = Some("eu".to_string());
+        config.advanced = Some(AdvancedConfig::default());
+        config.buckets.insert(
+            "assets".to_string(),
+            BucketAnnotation {
+                description: Some("Static files".to_string()),
+                tags: BTreeMap::new(),
+            },
+        );
+
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let exported = export_config(&config, format, false).unwrap();
+            assert!(!exported.contains("test_token"));
+            assert!(!exported.contains("secret"));
+
+            let imported = parse_config(&exported, format).unwrap();
+            assert!(imported.cloudflare.api_token.is_none());
+            assert!(imported.cloudflare.secret_access_key.is_none());
+            assert_eq!(export_config(&imported, format, false).unwrap(), exported);
+
+            let with_secrets = export_config(&config, format, true).unwrap();
+            let imported = parse_config(&with_secrets, format).unwrap();
+            assert_eq!(imported.cloudflare.api_token.as_deref(), Some("test_token"));
+            assert_eq!(
+                imported.cloudflare.secret_access_key.as_deref(),
+                Some("secret")
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_format() {
+        assert_eq!("JSON".parse::<ConfigFormat>().unwrap(), ConfigFormat::Json);
+        assert!("yaml".parse::<ConfigFormat>().is_err());
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("backup.json")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("backup.toml")),
+            ConfigFormat::Toml
+        );
+
+        let newer = r#"{"version": 99, "cloudflare": {"account_id": "a", "endpoint": "e"}, "r2": {"default_bucket": "b"}}"#;
+        assert!(parse_config(newer, ConfigFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_find_git_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("dotfiles/.config/r2pilot");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_git_root(&nested), None);
+
+        fs::create_dir(dir.path().join("dotfiles/.git")).unwrap();
+        assert_eq!(find_git_root(&nested), Some(dir.path().join("dotfiles")));
+    }
 }
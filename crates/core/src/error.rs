@@ -1,5 +1,6 @@
 //! Error types for r2pilot-core
 
+use aws_sdk_s3::error::ProvideErrorMetadata;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -73,6 +74,18 @@ pub enum Error {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    /// The resource to create already exists (e.g. a bucket name in use)
+    #[error("{0} already exists")]
+    AlreadyExists(String),
+
+    /// Conditional request rejected (412), e.g. the object changed or already exists
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    /// Transient service error (throttling or a 5xx response), safe to retry
+    #[error("Service temporarily unavailable: {0}")]
+    Transient(String),
+
     /// Timeout
     #[error("Operation timed out")]
     Timeout,
@@ -106,6 +119,16 @@ pub enum Error {
     BucketSettings(String),
 }
 
+impl Error {
+    /// Whether the error is likely to go away if the request is retried
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::Transient(_) | Error::Timeout | Error::Network(_)
+        )
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
@@ -126,13 +149,41 @@ impl From<aws_sdk_s3::Error> for Error {
     }
 }
 
+/// S3 error codes returned when the access key or secret is wrong
+const AUTH_ERROR_CODES: &[&str] = &["SignatureDoesNotMatch", "InvalidAccessKeyId"];
+
 // Generic SdkError conversion for all S3 operations
 impl<E> From<aws_sdk_s3::error::SdkError<E>> for Error
 where
-    E: std::error::Error + Send + Sync + 'static,
+    E: std::error::Error + ProvideErrorMetadata + Send + Sync + 'static,
 {
     fn from(err: aws_sdk_s3::error::SdkError<E>) -> Self {
-        Error::R2Operation(err.to_string())
+        use aws_sdk_s3::error::SdkError;
+
+        let status = err.raw_response().map(|r| r.status().as_u16());
+        let code = err.code();
+
+        match &err {
+            SdkError::TimeoutError(_) => Error::Timeout,
+            SdkError::DispatchFailure(_) => Error::Network(err.to_string()),
+            _ if status == Some(403) || code.is_some_and(|c| AUTH_ERROR_CODES.contains(&c)) => {
+                // HEAD responses have no body, so a bare 403 may carry no code
+                let detail = match (code, err.message()) {
+                    (Some(code), Some(message)) => format!("{}: {}", code, message),
+                    (Some(code), None) => code.to_string(),
+                    _ => "access denied (403)".to_string(),
+                };
+                Error::Authentication(format!(
+                    "{}. Check the R2 credentials with `r2pilot config validate`",
+                    detail
+                ))
+            }
+            _ if status == Some(404) => Error::NotFound(err.to_string()),
+            _ if status == Some(412) => Error::PreconditionFailed(err.to_string()),
+            // 429 and 5xx (e.g. 503 SlowDown) are throttling or server-side hiccups
+            _ if matches!(status, Some(429 | 500..=599)) => Error::Transient(err.to_string()),
+            _ => Error::R2Operation(err.to_string()),
+        }
     }
 }
 
@@ -224,12 +275,89 @@ mod tests {
         assert!(error.to_string().contains("Access denied"));
     }
 
+    #[test]
+    fn test_error_already_exists() {
+        let error = Error::AlreadyExists("Bucket 'assets'".to_string());
+        assert_eq!(error.to_string(), "Bucket 'assets' already exists");
+        assert!(!error.is_transient());
+    }
+
     #[test]
     fn test_error_timeout() {
         let error = Error::Timeout;
         assert!(error.to_string().contains("Operation timed out"));
     }
 
+    #[test]
+    fn test_error_is_transient() {
+        assert!(Error::Transient("503 SlowDown".to_string()).is_transient());
+        assert!(Error::Timeout.is_transient());
+        assert!(Error::Network("connection reset".to_string()).is_transient());
+        assert!(!Error::NotFound("missing".to_string()).is_transient());
+        assert!(!Error::R2Operation("AccessDenied".to_string()).is_transient());
+    }
+
+    fn service_error(status: u16, code: Option<&str>) -> Error {
+        use aws_sdk_s3::config::http::HttpResponse;
+        use aws_sdk_s3::error::{ErrorMetadata, SdkError};
+        use aws_sdk_s3::operation::get_object::GetObjectError;
+        use aws_smithy_types::body::SdkBody;
+
+        let mut meta = ErrorMetadata::builder();
+        if let Some(code) = code {
+            meta = meta.code(code).message("request rejected");
+        }
+        let raw = http::Response::builder()
+            .status(status)
+            .body(SdkBody::empty())
+            .unwrap();
+        Error::from(SdkError::service_error(
+            GetObjectError::generic(meta.build()),
+            HttpResponse::try_from(raw).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_sdk_error_auth_mapping() {
+        for code in ["SignatureDoesNotMatch", "InvalidAccessKeyId"] {
+            let error = service_error(403, Some(code));
+            assert!(matches!(error, Error::Authentication(_)), "{}", code);
+            assert!(error.to_string().contains(code));
+            assert!(error.to_string().contains("r2pilot config validate"));
+        }
+
+        // HEAD requests get a 403 without an error body
+        let error = service_error(403, None);
+        assert!(matches!(error, Error::Authentication(_)));
+        assert!(error.to_string().contains("r2pilot config validate"));
+
+        // The code alone is enough, whatever the status
+        assert!(matches!(
+            service_error(400, Some("InvalidAccessKeyId")),
+            Error::Authentication(_)
+        ));
+    }
+
+    #[test]
+    fn test_sdk_error_status_mapping() {
+        assert!(matches!(
+            service_error(404, Some("NoSuchKey")),
+            Error::NotFound(_)
+        ));
+        assert!(matches!(
+            service_error(412, Some("PreconditionFailed")),
+            Error::PreconditionFailed(_)
+        ));
+        assert!(matches!(
+            service_error(503, Some("SlowDown")),
+            Error::Transient(_)
+        ));
+        assert!(matches!(
+            service_error(400, Some("InvalidArgument")),
+            Error::R2Operation(_)
+        ));
+    }
+
     #[test]
     fn test_error_cancelled() {
         let error = Error::Cancelled;
@@ -0,0 +1,417 @@
+//! Client-side filtering of object listings
+
+use crate::client::ObjectInfo;
+use crate::error::{Error, Result};
+use crate::operations::R2Operations;
+use globset::{GlobBuilder, GlobMatcher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Size and age filters applied after listing (all set filters must match)
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFilter {
+    /// Minimum size in bytes (inclusive)
+    pub min_size: Option<u64>,
+    /// Maximum size in bytes (inclusive)
+    pub max_size: Option<u64>,
+    /// Only objects last modified more than this long ago
+    pub older_than: Option<Duration>,
+    /// Only objects last modified less than this long ago
+    pub newer_than: Option<Duration>,
+}
+
+impl ObjectFilter {
+    /// Whether no filter is set
+    pub fn is_empty(&self) -> bool {
+        self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.older_than.is_none()
+            && self.newer_than.is_none()
+    }
+
+    /// Check an object against the filters, measuring age from `now`
+    pub fn matches_at(&self, object: &ObjectInfo, now: SystemTime) -> bool {
+        let size = object.size.max(0) as u64;
+        if self.min_size.is_some_and(|min| size < min) {
+            return false;
+        }
+        if self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+
+        if self.older_than.is_none() && self.newer_than.is_none() {
+            return true;
+        }
+
+        let now_secs = now
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        // Objects without a modification date can't satisfy an age filter
+        let Some(last_modified) = object.last_modified else {
+            return false;
+        };
+        let age = now_secs.saturating_sub(last_modified.secs());
+
+        if self
+            .older_than
+            .is_some_and(|older| age < older.as_secs() as i64)
+        {
+            return false;
+        }
+        if self
+            .newer_than
+            .is_some_and(|newer| age > newer.as_secs() as i64)
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// Keep the objects matching the filters
+    pub fn apply(&self, objects: Vec<ObjectInfo>) -> Vec<ObjectInfo> {
+        if self.is_empty() {
+            return objects;
+        }
+
+        let now = SystemTime::now();
+        objects
+            .into_iter()
+            .filter(|o| self.matches_at(o, now))
+            .collect()
+    }
+}
+
+/// Glob pattern matched against object keys
+///
+/// `*` and `?` stay within one path segment; use `**` to cross `/`.
+#[derive(Debug, Clone)]
+pub struct KeyGlob {
+    pattern: String,
+    matcher: GlobMatcher,
+}
+
+impl KeyGlob {
+    /// Compile a pattern such as `logs/2023/*.gz`
+    pub fn new(pattern: &str) -> Result<Self> {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| Error::InvalidInput(format!("Invalid pattern '{}': {}", pattern, e)))?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            matcher: glob.compile_matcher(),
+        })
+    }
+
+    /// The literal part before the first wildcard, used as the listing prefix
+    pub fn prefix(&self) -> &str {
+        let end = self
+            .pattern
+            .find(['*', '?', '[', '{', '\\'])
+            .unwrap_or(self.pattern.len());
+        &self.pattern[..end]
+    }
+
+    /// The pattern as given
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether a key matches the pattern
+    pub fn is_match(&self, key: &str) -> bool {
+        self.matcher.is_match(key)
+    }
+}
+
+/// List the objects whose keys match a glob, scanning only its literal prefix
+pub async fn find_objects(ops: &dyn R2Operations, glob: &KeyGlob) -> Result<Vec<ObjectInfo>> {
+    let prefix = glob.prefix();
+    let objects = ops
+        .list_objects((!prefix.is_empty()).then_some(prefix))
+        .await?;
+
+    Ok(objects
+        .into_iter()
+        .filter(|o| glob.is_match(&o.key))
+        .collect())
+}
+
+/// Parse an age like `30d`, `12h`, `45m`, `90s` or `2w`
+///
+/// Unlike [`parse_duration`] the unit is required, so `30` isn't silently
+/// read as 30 seconds.
+pub fn parse_age(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid duration '{}' (add a unit: s, m, h, d or w)",
+            input
+        )));
+    }
+    parse_duration(input)
+}
+
+/// Parse a duration like `90s`, `30m`, `2h`, `7d` or `2w`; a bare number is seconds
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| Error::InvalidInput(format!("Invalid duration '{}'", input)))?;
+
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        _ => {
+            return Err(Error::InvalidInput(format!(
+                "Invalid duration '{}' (use a number followed by s, m, h, d or w)",
+                input
+            )))
+        }
+    };
+
+    value
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(|| Error::InvalidInput(format!("Duration '{}' is too large", input)))
+}
+
+/// Format a duration with its two largest units, e.g. `7d`, `1h 30m` or `45s`
+pub fn format_duration(duration: Duration) -> String {
+    const UNITS: [(u64, &str); 4] = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+
+    let mut remaining = duration.as_secs();
+    let parts: Vec<String> = UNITS
+        .iter()
+        .filter_map(|&(size, unit)| {
+            let count = remaining / size;
+            remaining %= size;
+            (count > 0).then(|| format!("{}{}", count, unit))
+        })
+        .take(2)
+        .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Parse a size like `1048576`, `500KB`, `10MB` or `2GB` (binary units)
+pub fn parse_size(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| Error::InvalidInput(format!("Invalid size '{}'", input)))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        "T" | "TB" => 1024 * 1024 * 1024 * 1024,
+        _ => {
+            return Err(Error::InvalidInput(format!(
+                "Invalid size '{}' (use bytes or a KB, MB, GB, TB suffix)",
+                input
+            )))
+        }
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| Error::InvalidInput(format!("Size '{}' is too large", input)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockR2;
+
+    fn object(size: i64, modified_secs: i64) -> ObjectInfo {
+        ObjectInfo {
+            key: "obj".to_string(),
+            size,
+            last_modified: Some(aws_smithy_types::DateTime::from_secs(modified_secs)),
+            etag: "\"etag\"".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_age("45m").unwrap(), Duration::from_secs(45 * 60));
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3600));
+        assert_eq!(parse_age("30d").unwrap(), Duration::from_secs(30 * 86400));
+        assert_eq!(parse_age("2w").unwrap(), Duration::from_secs(14 * 86400));
+
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+        assert!(parse_age("99999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("3600").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration(" 30m ").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604800));
+        assert_eq!(parse_duration("0s").unwrap(), Duration::ZERO);
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("-5m").is_err());
+        assert!(parse_duration("2.5h").is_err());
+        assert!(parse_duration("1h30m").is_err());
+        assert!(parse_duration("5 min").is_err());
+        assert!(parse_duration("3y").is_err());
+        // Fits in u64 as a number, overflows once converted to seconds
+        assert!(parse_duration("18446744073709551615w").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(5400)), "1h 30m");
+        assert_eq!(format_duration(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_duration(Duration::from_secs(604800)), "7d");
+        // Only the two largest units are shown
+        assert_eq!(format_duration(Duration::from_secs(90061)), "1d 1h");
+        assert_eq!(format_duration(Duration::from_secs(86461)), "1d 1m");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("500KB").unwrap(), 500 * 1024);
+        assert_eq!(parse_size("10mb").unwrap(), 10 * 1024 * 1024);
+        assert_eq!(parse_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10XB").is_err());
+    }
+
+    #[test]
+    fn test_filter_combines_with_and() {
+        let now = UNIX_EPOCH + Duration::from_secs(100 * 86400);
+        let filter = ObjectFilter {
+            min_size: Some(1000),
+            older_than: Some(Duration::from_secs(30 * 86400)),
+            ..Default::default()
+        };
+
+        // Large and old
+        assert!(filter.matches_at(&object(5000, 10 * 86400), now));
+        // Large but recent
+        assert!(!filter.matches_at(&object(5000, 90 * 86400), now));
+        // Old but small
+        assert!(!filter.matches_at(&object(10, 10 * 86400), now));
+    }
+
+    #[test]
+    fn test_filter_size_and_age_bounds() {
+        let now = UNIX_EPOCH + Duration::from_secs(100 * 86400);
+        let filter = ObjectFilter {
+            max_size: Some(100),
+            newer_than: Some(Duration::from_secs(86400)),
+            ..Default::default()
+        };
+
+        assert!(filter.matches_at(&object(100, 100 * 86400 - 3600), now));
+        assert!(!filter.matches_at(&object(101, 100 * 86400 - 3600), now));
+        assert!(!filter.matches_at(&object(50, 98 * 86400), now));
+        assert!(ObjectFilter::default().matches_at(&object(0, 0), now));
+    }
+
+    #[test]
+    fn test_filter_without_last_modified() {
+        let now = UNIX_EPOCH + Duration::from_secs(100 * 86400);
+        let mut undated = object(5000, 0);
+        undated.last_modified = None;
+
+        let by_size = ObjectFilter {
+            min_size: Some(1000),
+            ..Default::default()
+        };
+        assert!(by_size.matches_at(&undated, now));
+
+        let by_age = ObjectFilter {
+            older_than: Some(Duration::from_secs(86400)),
+            ..Default::default()
+        };
+        assert!(!by_age.matches_at(&undated, now));
+    }
+
+    #[test]
+    fn test_key_glob_prefix() {
+        assert_eq!(
+            KeyGlob::new("logs/2023/*.gz").unwrap().prefix(),
+            "logs/2023/"
+        );
+        assert_eq!(
+            KeyGlob::new("logs/app-?.txt").unwrap().prefix(),
+            "logs/app-"
+        );
+        assert_eq!(KeyGlob::new("**/*.jpg").unwrap().prefix(), "");
+        assert_eq!(KeyGlob::new("exact.txt").unwrap().prefix(), "exact.txt");
+        assert!(KeyGlob::new("logs/[").is_err());
+    }
+
+    #[test]
+    fn test_key_glob_matching() {
+        let glob = KeyGlob::new("logs/2023/*.gz").unwrap();
+        assert!(glob.is_match("logs/2023/app.gz"));
+        assert!(!glob.is_match("logs/2023/nested/app.gz"));
+        assert!(!glob.is_match("logs/2023/app.txt"));
+
+        let recursive = KeyGlob::new("logs/**/*.gz").unwrap();
+        assert!(recursive.is_match("logs/2023/nested/app.gz"));
+    }
+
+    #[test]
+    fn test_key_glob_recursive_prefix() {
+        let glob = KeyGlob::new("tmp/**").unwrap();
+        assert_eq!(glob.prefix(), "tmp/");
+        assert!(glob.is_match("tmp/a.txt"));
+        assert!(glob.is_match("tmp/nested/deep/b.txt"));
+        assert!(!glob.is_match("tmpfile.txt"));
+        assert!(!glob.is_match("other/tmp/a.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_find_objects() {
+        let mock = MockR2::new("test-bucket");
+        mock.insert("logs/2023/a.gz", b"a".to_vec(), "application/gzip");
+        mock.insert("logs/2023/b.txt", b"b".to_vec(), "text/plain");
+        mock.insert("logs/2024/c.gz", b"c".to_vec(), "application/gzip");
+
+        let glob = KeyGlob::new("logs/*/*.gz").unwrap();
+        let mut keys: Vec<String> = find_objects(&mock, &glob)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|o| o.key)
+            .collect();
+        keys.sort();
+
+        assert_eq!(keys, vec!["logs/2023/a.gz", "logs/2024/c.gz"]);
+    }
+}
@@ -3,23 +3,83 @@
 //! This library provides the core functionality for managing Cloudflare R2 storage,
 //! including configuration management, R2 client operations, and Cloudflare API integration.
 
+pub mod audit;
+pub mod batch;
+pub mod cache;
 pub mod client;
 pub mod cloudflare;
 pub mod config;
 pub mod error;
+pub mod filter;
+pub mod manifest;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod operations;
 pub mod presigned;
+pub mod rate;
+pub mod sync;
+#[cfg(test)]
+mod test_support;
+pub mod timings;
 
 // Re-export commonly used types
+pub use audit::{audit_buckets, BucketAudit};
+pub use batch::{
+    copy_bucket, head_objects, move_objects, move_prefix, multipart_uploads_size, parse_key_list,
+    plan_prefix_move, BucketCopy, MoveOutcome, MoveStatus,
+};
+pub use cache::{CacheStatus, DownloadCache, DEFAULT_CACHE_MAX_SIZE_MB};
 pub use client::{
-    requires_multipart_upload, CompletedPart, MultipartUploadConfig, MultipartUploadProgress,
-    R2Client,
+    is_compressed_format, load_ca_bundle, parse_header, parse_metadata_entry, plan_multipart,
+    requires_multipart_upload, validate_content_disposition, ClientOptions, CompletedPart,
+    Compression, DeleteOutcome, MetadataUpdate, MultipartPlan, MultipartUploadConfig,
+    MultipartUploadInfo, MultipartUploadProgress, ObjectInfo, ObjectListing, ObjectMetadata,
+    R2Client, StorageClass, TlsOptions, UploadOptions, DEFAULT_USER_AGENT, MIN_PART_SIZE,
 };
 pub use cloudflare::{
-    ApiToken, BucketCorsConfig, CloudflareClient, CorsRule, ErrorDocument, IndexDocument,
-    LifecycleConfiguration, LifecycleExpiration, LifecycleFilter, LifecycleRule, R2Bucket,
-    R2TokenBuilder, WebsiteConfiguration,
+    cors_schema, lifecycle_schema, split_comma_list, AbortIncompleteMultipartUpload, ApiToken,
+    BucketCorsConfig, CloudflareClient, CorsPreset, CorsRule, CustomDomain, ErrorDocument,
+    IndexDocument, LifecycleConfiguration, LifecycleExpiration, LifecycleFilter, LifecyclePreset,
+    LifecycleRule, ManagedDomain, NoncurrentVersionExpiration, R2Bucket, R2TokenBuilder,
+    TokenPermission, TokenVerification, WebsiteConfiguration,
+};
+pub use config::{
+    config_exists, config_warnings, expected_endpoint, export_config, find_git_root,
+    get_config_path, get_credentials_path, import_config, is_valid_bucket_name, load_config,
+    load_credentials, migrate_config, missing_config_defaults, parse_config, parse_tag,
+    r2_endpoint, read_api_token_file, restrict_permissions, save_config, save_credentials,
+    validate_config, validate_endpoint, validate_jurisdiction, API_TOKEN_FILE_ENV, CONFIG_VERSION,
+    JURISDICTIONS,
+};
+pub use config::{
+    BucketAnnotation, CloudflareConfig, Config, ConfigFile, ConfigFormat, Credentials,
+    DeployConfig, DeployRule, R2Config, UploadConfig,
 };
-pub use config::{config_exists, get_config_path, load_config, save_config, validate_config};
-pub use config::{CloudflareConfig, Config, ConfigFile, R2Config};
 pub use error::{Error, Result};
-pub use presigned::{generate_presigned_url, PresignedMethod, PresignedUrlConfig};
+pub use filter::{
+    find_objects, format_duration, parse_age, parse_duration, parse_size, KeyGlob, ObjectFilter,
+};
+pub use manifest::{
+    manifest_path, partition_resumable, upload_local_files_resumable, UploadManifest,
+};
+#[cfg(any(test, feature = "mock"))]
+pub use mock::MockR2;
+pub use operations::{
+    download_file_decompressed, download_file_verified, list_objects_concurrent, wait_for_object,
+    AbortGuard, BoxedReader, R2Operations,
+};
+pub use presigned::{
+    generate_presigned_url, generate_presigned_url_signed, parse_presigned_url,
+    rewrite_presigned_host, PresignedInfo, PresignedMethod, PresignedUrlConfig, ResponseHeaders,
+    MAX_SIGNED_EXPIRATION,
+};
+pub use rate::{parse_rate, RateLimiter};
+pub use sync::{
+    compute_sync_plan, content_addressed_key, content_type_fix, execute_sync_plan,
+    expand_local_glob, has_glob_wildcards, is_glob_pattern, is_multipart_etag, local_md5,
+    local_sha256, scan_local_dir, sync_directory, sync_key, upload_local_files, upload_reason,
+    ContentTypeFix, DeployRules, LocalFile, PlannedUpload, SyncOptions, SyncOutcome, SyncPlan,
+    SyncStatus, UploadOutcome, UploadReason,
+};
+pub use timings::{PhaseTimings, Timings};
+pub use tokio_util::sync::CancellationToken;
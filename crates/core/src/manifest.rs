@@ -0,0 +1,301 @@
+//! Resumable directory uploads
+//!
+//! A glob upload records each completed key in a manifest as it goes. If the
+//! run fails midway, re-running with `--resume` skips the files the manifest
+//! lists, as long as the remote object still has the recorded size.
+
+use crate::client::UploadOptions;
+use crate::config::get_config_dir;
+use crate::error::{Error, Result};
+use crate::operations::R2Operations;
+use crate::sync::{sync_key, upload_local_files_stream, LocalFile, UploadOutcome};
+use futures::StreamExt;
+use md5::{Digest, Md5};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+/// Keys (and sizes) a directory upload has completed, stored one per line
+///
+/// Each line is `<size>\t<key>`; appending keeps recording cheap however many
+/// files the upload has.
+#[derive(Debug)]
+pub struct UploadManifest {
+    path: PathBuf,
+    completed: HashMap<String, u64>,
+}
+
+impl UploadManifest {
+    /// Load the manifest at `path`, or start an empty one if it doesn't exist
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        // A line cut short by a crash is ignored; its file is simply uploaded again
+        let completed = content
+            .lines()
+            .filter_map(|line| {
+                let (size, key) = line.split_once('\t')?;
+                Some((key.to_string(), size.parse().ok()?))
+            })
+            .collect();
+
+        Ok(Self { path, completed })
+    }
+
+    /// Start an empty manifest at `path`, discarding any previous one
+    pub fn create(path: PathBuf) -> Result<Self> {
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(Error::Io(e)),
+        }
+        Ok(Self {
+            path,
+            completed: HashMap::new(),
+        })
+    }
+
+    /// Where the manifest is stored
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of recorded keys
+    pub fn len(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Whether no key has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+    }
+
+    /// Size recorded for a completed key
+    pub fn completed_size(&self, key: &str) -> Option<u64> {
+        self.completed.get(key).copied()
+    }
+
+    /// Record a completed upload, appending it to the file
+    pub fn record(&mut self, key: &str, size: u64) -> Result<()> {
+        if key.contains(['\n', '\r']) {
+            // Can't be stored on one line; the file is re-uploaded on resume
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(Error::Io)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(Error::Io)?;
+        writeln!(file, "{}\t{}", size, key).map_err(Error::Io)?;
+
+        self.completed.insert(key.to_string(), size);
+        Ok(())
+    }
+
+    /// Delete the manifest file once the upload has fully succeeded
+    pub fn remove(self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+}
+
+/// Manifest location for uploading `source` to `prefix` in `bucket`
+///
+/// Manifests live in `<config dir>/manifests/`, named after a hash of the
+/// three so separate uploads don't share one.
+pub fn manifest_path(bucket: &str, source: &str, prefix: &str) -> Result<PathBuf> {
+    Ok(get_config_dir()?
+        .join("manifests")
+        .join(manifest_name(bucket, source, prefix)))
+}
+
+fn manifest_name(bucket: &str, source: &str, prefix: &str) -> String {
+    let digest = Md5::digest(format!("{}\0{}\0{}", bucket, source, prefix).as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}.manifest", hex)
+}
+
+/// Split `files` into those still to upload and those a previous run completed
+///
+/// A file counts as done only if the manifest lists its key with the file's
+/// current size and the remote object exists with that same size.
+pub async fn partition_resumable(
+    ops: &dyn R2Operations,
+    files: Vec<LocalFile>,
+    prefix: &str,
+    manifest: &UploadManifest,
+) -> Result<(Vec<LocalFile>, Vec<LocalFile>)> {
+    if manifest.is_empty() {
+        return Ok((files, Vec::new()));
+    }
+
+    let remote: HashSet<(String, u64)> = ops
+        .list_objects((!prefix.is_empty()).then_some(prefix))
+        .await?
+        .into_iter()
+        .map(|o| (o.key, o.size.max(0) as u64))
+        .collect();
+
+    Ok(files.into_iter().partition(|file| {
+        let key = sync_key(prefix, &file.relative_path);
+        manifest.completed_size(&key) != Some(file.size) || !remote.contains(&(key, file.size))
+    }))
+}
+
+/// Upload files like [`crate::sync::upload_local_files`], recording each
+/// success in `manifest` as soon as it completes
+///
+/// Failing to write the manifest doesn't fail the upload; the file is just
+/// uploaded again on resume.
+pub async fn upload_local_files_resumable(
+    ops: &dyn R2Operations,
+    files: Vec<LocalFile>,
+    prefix: &str,
+    options: &UploadOptions,
+    concurrency: usize,
+    cancel: &CancellationToken,
+    manifest: &mut UploadManifest,
+) -> Vec<UploadOutcome> {
+    let mut uploads = std::pin::pin!(upload_local_files_stream(
+        ops,
+        files,
+        prefix,
+        options,
+        concurrency,
+        cancel
+    ));
+    let mut outcomes = Vec::new();
+
+    while let Some(outcome) = uploads.next().await {
+        if outcome.result.is_ok() {
+            if let Err(e) = manifest.record(&outcome.key, outcome.size) {
+                tracing::warn!(key = %outcome.key, error = %e, "failed to record upload in manifest");
+            }
+        }
+        outcomes.push(outcome);
+    }
+
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockR2;
+    use crate::sync::scan_local_dir;
+
+    fn write(dir: &Path, relative: &str, body: &[u8]) {
+        let path = dir.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/upload.manifest");
+
+        let mut manifest = UploadManifest::open(path.clone()).unwrap();
+        assert!(manifest.is_empty());
+        manifest.record("site/index.html", 120).unwrap();
+        manifest.record("site/app.js", 4096).unwrap();
+
+        // A torn last line is skipped
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"99").unwrap();
+
+        let reopened = UploadManifest::open(path.clone()).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.completed_size("site/app.js"), Some(4096));
+        assert_eq!(reopened.completed_size("site/missing"), None);
+
+        let fresh = UploadManifest::create(path.clone()).unwrap();
+        assert!(fresh.is_empty());
+        assert!(!path.exists());
+
+        reopened.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_manifest_name_depends_on_inputs() {
+        let a = manifest_name("bucket", "/srv/dist/**", "site/");
+        assert_eq!(a, manifest_name("bucket", "/srv/dist/**", "site/"));
+        assert_ne!(a, manifest_name("bucket", "/srv/dist/**", "other/"));
+        assert_ne!(a, manifest_name("other", "/srv/dist/**", "site/"));
+        assert!(a.ends_with(".manifest"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_only_verified_uploads() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "done.txt", b"done");
+        write(dir.path(), "changed.txt", b"changed locally");
+        write(dir.path(), "gone.txt", b"gone");
+        write(dir.path(), "new.txt", b"new");
+        let files = scan_local_dir(dir.path(), false).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("site/done.txt", b"done".to_vec(), "text/plain");
+        mock.insert("site/changed.txt", b"changed".to_vec(), "text/plain");
+
+        let mut manifest = UploadManifest::open(dir.path().join("m")).unwrap();
+        manifest.record("site/done.txt", 4).unwrap();
+        manifest.record("site/changed.txt", 7).unwrap();
+        // Recorded, but deleted remotely since
+        manifest.record("site/gone.txt", 4).unwrap();
+
+        let (pending, done) = partition_resumable(&mock, files, "site/", &manifest)
+            .await
+            .unwrap();
+
+        let names = |files: &[LocalFile]| -> Vec<String> {
+            files.iter().map(|f| f.relative_path.clone()).collect()
+        };
+        assert_eq!(names(&done), vec!["done.txt"]);
+        assert_eq!(names(&pending), vec!["changed.txt", "gone.txt", "new.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload_records_successes() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.txt", b"aaa");
+        write(dir.path(), "b/c.txt", b"cc");
+        let files = scan_local_dir(dir.path(), false).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let manifest_file = dir.path().join("upload.manifest");
+        let mut manifest = UploadManifest::create(manifest_file.clone()).unwrap();
+
+        let outcomes = upload_local_files_resumable(
+            &mock,
+            files,
+            "site/",
+            &UploadOptions::default(),
+            2,
+            &CancellationToken::new(),
+            &mut manifest,
+        )
+        .await;
+
+        assert!(outcomes.iter().all(|o| o.result.is_ok()));
+        let reopened = UploadManifest::open(manifest_file).unwrap();
+        assert_eq!(reopened.completed_size("site/a.txt"), Some(3));
+        assert_eq!(reopened.completed_size("site/b/c.txt"), Some(2));
+    }
+}
@@ -0,0 +1,1320 @@
+//! In-memory R2 backend for tests
+//!
+//! Available in this crate's tests and to other crates through the `mock` feature.
+
+use crate::client::{
+    CompletedPart, MetadataUpdate, MultipartUploadInfo, ObjectInfo, ObjectMetadata, StorageClass,
+    UploadOptions,
+};
+use crate::error::{Error, Result};
+use crate::operations::{group_delimited, R2Operations};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use md5::{Digest, Md5};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// An object stored in the mock bucket
+#[derive(Debug, Clone)]
+pub struct MockObject {
+    pub body: Vec<u8>,
+    pub content_type: String,
+    pub content_encoding: Option<String>,
+    pub content_disposition: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_language: Option<String>,
+    pub metadata: HashMap<String, String>,
+    pub etag: String,
+    pub last_modified: aws_smithy_types::DateTime,
+    pub storage_class: StorageClass,
+}
+
+impl MockObject {
+    fn new(body: Vec<u8>, content_type: &str, content_encoding: Option<String>) -> Self {
+        // Single-part S3 ETag: quoted hex MD5 of the body
+        Self {
+            etag: format!("\"{}\"", md5_hex(&body)),
+            body,
+            content_type: content_type.to_string(),
+            content_encoding,
+            content_disposition: None,
+            cache_control: None,
+            content_language: None,
+            metadata: HashMap::new(),
+            last_modified: aws_smithy_types::DateTime::from(SystemTime::now()),
+            storage_class: StorageClass::Standard,
+        }
+    }
+}
+
+fn md5_hex(data: &[u8]) -> String {
+    Md5::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// An in-progress multipart upload
+#[derive(Debug, Default)]
+struct MockUpload {
+    key: String,
+    content_type: String,
+    content_disposition: Option<String>,
+    initiated: Option<aws_smithy_types::DateTime>,
+    parts: BTreeMap<i32, Vec<u8>>,
+}
+
+/// In-memory implementation of `R2Operations` backed by a HashMap
+#[derive(Debug, Default)]
+pub struct MockR2 {
+    bucket: String,
+    objects: Mutex<HashMap<String, MockObject>>,
+    /// Shared so a detached abort can outlive a borrow of the mock
+    uploads: Arc<Mutex<HashMap<String, MockUpload>>>,
+    next_upload_id: Mutex<u64>,
+    failing_parts: Mutex<u32>,
+    truncated_downloads: Mutex<u32>,
+    /// Body written over the next downloaded object, once it is read
+    replace_after_download: Mutex<Option<Vec<u8>>>,
+    /// Delay added to every listing page, like a round trip to R2
+    list_latency: Mutex<std::time::Duration>,
+}
+
+impl MockR2 {
+    /// Create an empty mock bucket
+    pub fn new(bucket: &str) -> Self {
+        Self {
+            bucket: bucket.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Seed an object directly
+    pub fn insert(&self, key: &str, body: Vec<u8>, content_type: &str) {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), MockObject::new(body, content_type, None));
+    }
+
+    /// Make the next `count` part uploads fail with a transient error
+    pub fn fail_next_parts(&self, count: u32) {
+        *self.failing_parts.lock().unwrap() = count;
+    }
+
+    /// Make the next `count` downloads return only the first half of the body
+    pub fn truncate_next_downloads(&self, count: u32) {
+        *self.truncated_downloads.lock().unwrap() = count;
+    }
+
+    /// Replace the next downloaded object with `body` once it is read, as a
+    /// concurrent writer would
+    pub fn replace_after_next_download(&self, body: Vec<u8>) {
+        *self.replace_after_download.lock().unwrap() = Some(body);
+    }
+
+    /// Delay every listing by `latency` per page of 1000 entries, like the
+    /// round trips to R2 (e.g. to benchmark concurrent listings)
+    pub fn set_list_latency(&self, latency: std::time::Duration) {
+        *self.list_latency.lock().unwrap() = latency;
+    }
+
+    /// Get a stored object
+    pub fn get(&self, key: &str) -> Option<MockObject> {
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    /// All stored keys, sorted
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.objects.lock().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Number of multipart uploads neither completed nor aborted
+    pub fn pending_uploads(&self) -> usize {
+        self.uploads.lock().unwrap().len()
+    }
+
+    /// Every object under `prefix`, sorted by key
+    fn listing(&self, prefix: Option<&str>) -> Vec<ObjectInfo> {
+        let objects = self.objects.lock().unwrap();
+        let mut infos: Vec<ObjectInfo> = objects
+            .iter()
+            .filter(|(key, _)| prefix.is_none_or(|p| key.starts_with(p)))
+            .map(|(key, obj)| ObjectInfo {
+                key: key.clone(),
+                size: obj.body.len() as i64,
+                last_modified: Some(obj.last_modified),
+                etag: obj.etag.clone(),
+            })
+            .collect();
+        infos.sort_by(|a, b| a.key.cmp(&b.key));
+        infos
+    }
+
+    /// Wait the list latency once per page of 1000 entries, as R2 pages listings
+    async fn list_round_trips(&self, entries: usize) {
+        let latency = *self.list_latency.lock().unwrap();
+        if !latency.is_zero() {
+            let pages = entries.div_ceil(1000).max(1) as u32;
+            tokio::time::sleep(latency * pages).await;
+        }
+    }
+
+    fn not_found(key: &str) -> Error {
+        Error::NotFound(format!("NoSuchKey: {}", key))
+    }
+}
+
+#[async_trait]
+impl R2Operations for MockR2 {
+    fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    async fn upload_bytes_with_options(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()> {
+        let encoding = options.effective_content_encoding()?;
+        let body = match options.compression {
+            Some(compression) => compression.compress(&body)?,
+            None => body,
+        };
+
+        let content_type = options.content_type.as_deref().unwrap_or(content_type);
+        let mut object = MockObject::new(body, content_type, encoding);
+        object.content_disposition = options.content_disposition.clone();
+        object.cache_control = options.cache_control.clone();
+        object.content_language = options.content_language.clone();
+        object.metadata = options.metadata.clone();
+
+        let mut objects = self.objects.lock().unwrap();
+        let current = objects.get(key).map(|o| o.etag.trim_matches('"'));
+        if let Some(expected) = &options.if_match {
+            if current != Some(expected.trim_matches('"')) {
+                return Err(Error::PreconditionFailed(format!(
+                    "{} does not match ETag {}",
+                    key, expected
+                )));
+            }
+        }
+        if options.if_none_match.is_some() && current.is_some() {
+            return Err(Error::PreconditionFailed(format!("{} already exists", key)));
+        }
+
+        objects.insert(key.to_string(), object);
+        Ok(())
+    }
+
+    async fn download_bytes(&self, key: &str) -> Result<Vec<u8>> {
+        let mut body = self
+            .get(key)
+            .map(|o| o.body)
+            .ok_or_else(|| Self::not_found(key))?;
+
+        let mut truncated = self.truncated_downloads.lock().unwrap();
+        if *truncated > 0 {
+            *truncated -= 1;
+            body.truncate(body.len() / 2);
+        }
+        if let Some(new_body) = self.replace_after_download.lock().unwrap().take() {
+            let mut objects = self.objects.lock().unwrap();
+            if let Some(object) = objects.get_mut(key) {
+                *object = MockObject::new(new_body, &object.content_type, None);
+            }
+        }
+        Ok(body)
+    }
+
+    async fn list_objects(&self, prefix: Option<&str>) -> Result<Vec<ObjectInfo>> {
+        let objects = self.listing(prefix);
+        self.list_round_trips(objects.len()).await;
+        Ok(objects)
+    }
+
+    async fn list_objects_delimited(
+        &self,
+        prefix: Option<&str>,
+        delimiter: &str,
+    ) -> Result<(Vec<ObjectInfo>, Vec<String>)> {
+        let (objects, common_prefixes) = group_delimited(self.listing(prefix), prefix, delimiter);
+        self.list_round_trips(objects.len() + common_prefixes.len())
+            .await;
+        Ok((objects, common_prefixes))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        // S3 semantics: deleting a missing key succeeds
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn object_exists(&self, key: &str) -> Result<bool> {
+        Ok(self.objects.lock().unwrap().contains_key(key))
+    }
+
+    async fn head_object(&self, key: &str) -> Result<ObjectMetadata> {
+        let obj = self.get(key).ok_or_else(|| Self::not_found(key))?;
+        Ok(ObjectMetadata {
+            key: key.to_string(),
+            size: obj.body.len() as i64,
+            content_type: obj.content_type,
+            content_encoding: obj.content_encoding,
+            content_disposition: obj.content_disposition,
+            last_modified: Some(obj.last_modified),
+            etag: obj.etag,
+            storage_class: obj.storage_class,
+        })
+    }
+
+    async fn copy_object(&self, source_key: &str, dest_key: &str) -> Result<()> {
+        let mut objects = self.objects.lock().unwrap();
+        let obj = objects
+            .get(source_key)
+            .cloned()
+            .ok_or_else(|| Self::not_found(source_key))?;
+        objects.insert(dest_key.to_string(), obj);
+        Ok(())
+    }
+
+    async fn set_content_type(&self, key: &str, content_type: &str) -> Result<()> {
+        let mut objects = self.objects.lock().unwrap();
+        let obj = objects.get_mut(key).ok_or_else(|| Self::not_found(key))?;
+        obj.content_type = content_type.to_string();
+        obj.last_modified = aws_smithy_types::DateTime::from(SystemTime::now());
+        Ok(())
+    }
+
+    async fn update_metadata(&self, key: &str, update: &MetadataUpdate) -> Result<()> {
+        let mut objects = self.objects.lock().unwrap();
+        let obj = objects.get_mut(key).ok_or_else(|| Self::not_found(key))?;
+        if let Some(content_type) = &update.content_type {
+            obj.content_type = content_type.clone();
+        }
+        for (field, value) in [
+            (&mut obj.cache_control, &update.cache_control),
+            (&mut obj.content_disposition, &update.content_disposition),
+            (&mut obj.content_encoding, &update.content_encoding),
+            (&mut obj.content_language, &update.content_language),
+        ] {
+            if value.is_some() {
+                *field = value.clone();
+            }
+        }
+        obj.metadata.extend(update.metadata.clone());
+        obj.last_modified = aws_smithy_types::DateTime::from(SystemTime::now());
+        Ok(())
+    }
+
+    async fn change_storage_class(&self, key: &str, class: StorageClass) -> Result<()> {
+        let mut objects = self.objects.lock().unwrap();
+        let obj = objects.get_mut(key).ok_or_else(|| Self::not_found(key))?;
+        obj.storage_class = class;
+        obj.last_modified = aws_smithy_types::DateTime::from(SystemTime::now());
+        Ok(())
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: Option<&str>,
+    ) -> Result<String> {
+        let mut next = self.next_upload_id.lock().unwrap();
+        *next += 1;
+        let upload_id = format!("upload-{}", next);
+
+        self.uploads.lock().unwrap().insert(
+            upload_id.clone(),
+            MockUpload {
+                key: key.to_string(),
+                content_type: content_type.to_string(),
+                content_disposition: content_disposition.map(str::to_string),
+                initiated: Some(aws_smithy_types::DateTime::from(SystemTime::now())),
+                ..Default::default()
+            },
+        );
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        _key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<CompletedPart> {
+        {
+            let mut failing = self.failing_parts.lock().unwrap();
+            if *failing > 0 {
+                *failing -= 1;
+                return Err(Error::Transient("503 SlowDown".to_string()));
+            }
+        }
+
+        let mut uploads = self.uploads.lock().unwrap();
+        let upload = uploads
+            .get_mut(upload_id)
+            .ok_or_else(|| Error::MultipartUpload(format!("NoSuchUpload: {}", upload_id)))?;
+        upload.parts.insert(part_number, body);
+
+        Ok(CompletedPart {
+            part_number,
+            etag: format!("\"part-{}\"", part_number),
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        _key: &str,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> Result<()> {
+        let upload = self
+            .uploads
+            .lock()
+            .unwrap()
+            .remove(upload_id)
+            .ok_or_else(|| Error::MultipartUpload(format!("NoSuchUpload: {}", upload_id)))?;
+
+        if parts.is_empty() {
+            return Err(Error::MultipartUpload(
+                "MalformedXML: at least one part is required".to_string(),
+            ));
+        }
+
+        let mut body = Vec::new();
+        for part in &parts {
+            let data = upload.parts.get(&part.part_number).ok_or_else(|| {
+                Error::MultipartUpload(format!("InvalidPart: {}", part.part_number))
+            })?;
+            body.extend_from_slice(data);
+        }
+
+        // Multipart S3 ETag: MD5 of the part MD5s, suffixed with the part count
+        let part_digests: Vec<u8> = parts
+            .iter()
+            .flat_map(|p| Md5::digest(&upload.parts[&p.part_number]).to_vec())
+            .collect();
+        let mut object = MockObject::new(body, &upload.content_type, None);
+        object.content_disposition = upload.content_disposition;
+        object.etag = format!("\"{}-{}\"", md5_hex(&part_digests), parts.len());
+
+        self.objects.lock().unwrap().insert(upload.key, object);
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(&self, _key: &str, upload_id: &str) -> Result<()> {
+        self.uploads.lock().unwrap().remove(upload_id);
+        Ok(())
+    }
+
+    async fn list_multipart_uploads(&self) -> Result<Vec<MultipartUploadInfo>> {
+        let mut uploads: Vec<MultipartUploadInfo> = self
+            .uploads
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(upload_id, upload)| MultipartUploadInfo {
+                key: upload.key.clone(),
+                upload_id: upload_id.clone(),
+                initiated: upload.initiated,
+            })
+            .collect();
+        uploads.sort_by(|a, b| (&a.key, &a.upload_id).cmp(&(&b.key, &b.upload_id)));
+        Ok(uploads)
+    }
+
+    async fn multipart_upload_size(&self, _key: &str, upload_id: &str) -> Result<u64> {
+        let uploads = self.uploads.lock().unwrap();
+        // R2 answers an unknown upload ID with a 404 NoSuchUpload
+        let upload = uploads
+            .get(upload_id)
+            .ok_or_else(|| Error::NotFound(format!("NoSuchUpload: {}", upload_id)))?;
+        Ok(upload.parts.values().map(|part| part.len() as u64).sum())
+    }
+
+    fn detached_abort(
+        &self,
+        _key: &str,
+        upload_id: &str,
+    ) -> Option<BoxFuture<'static, Result<()>>> {
+        let uploads = Arc::clone(&self.uploads);
+        let upload_id = upload_id.to_string();
+        Some(Box::pin(async move {
+            uploads.lock().unwrap().remove(&upload_id);
+            Ok(())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{Compression, MultipartUploadConfig, MIN_PART_SIZE};
+    use crate::operations::{
+        download_file_decompressed, download_file_verified, list_objects_concurrent,
+        wait_for_object, AbortGuard,
+    };
+    use std::time::Duration;
+
+    /// Multipart test files: two full parts and a half part
+    const FILE_SIZE: usize = 2 * MIN_PART_SIZE + MIN_PART_SIZE / 2;
+    const PART: u64 = MIN_PART_SIZE as u64;
+
+    #[tokio::test]
+    async fn test_mock_upload_download_roundtrip() {
+        let mock = MockR2::new("test-bucket");
+        let ops: &dyn R2Operations = &mock;
+
+        ops.upload_bytes("a.txt", b"hello".to_vec(), "text/plain")
+            .await
+            .unwrap();
+
+        assert_eq!(ops.bucket(), "test-bucket");
+        assert!(ops.object_exists("a.txt").await.unwrap());
+        assert_eq!(ops.download_bytes("a.txt").await.unwrap(), b"hello");
+
+        let meta = ops.head_object("a.txt").await.unwrap();
+        assert_eq!(meta.size, 5);
+        assert_eq!(meta.content_type, "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_mock_list_with_prefix_and_delete() {
+        let mock = MockR2::new("test-bucket");
+        mock.insert("logs/1.log", vec![1], "text/plain");
+        mock.insert("logs/2.log", vec![2, 2], "text/plain");
+        mock.insert("img/a.png", vec![3], "image/png");
+
+        let logs = mock.list_objects(Some("logs/")).await.unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].size, 2);
+
+        mock.delete_objects(vec!["logs/1.log".to_string(), "logs/2.log".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(mock.keys(), vec!["img/a.png"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_delimited() {
+        let mock = MockR2::new("test-bucket");
+        for key in ["a.txt", "logs/1.txt", "logs/2024/2.txt", "media/x.png"] {
+            mock.insert(key, b"x".to_vec(), "text/plain");
+        }
+
+        let (objects, prefixes) = mock.list_objects_delimited(None, "/").await.unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].key, "a.txt");
+        assert_eq!(prefixes, vec!["logs/", "media/"]);
+
+        let (objects, prefixes) = mock
+            .list_objects_delimited(Some("logs/"), "/")
+            .await
+            .unwrap();
+        assert_eq!(objects[0].key, "logs/1.txt");
+        assert_eq!(prefixes, vec!["logs/2024/"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_paged() {
+        let mock = MockR2::new("test-bucket");
+        for key in ["a.txt", "logs/1.txt", "logs/2.txt", "media/x.png", "z.txt"] {
+            mock.insert(key, b"x".to_vec(), "text/plain");
+        }
+
+        let page = mock.list_objects_paged(None, None, None, 2).await.unwrap();
+        let keys: Vec<&str> = page.objects.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, vec!["a.txt", "logs/1.txt"]);
+        assert!(page.common_prefixes.is_empty());
+        assert_eq!(page.next_after.as_deref(), Some("logs/1.txt"));
+
+        // The cursor picks up where the previous page stopped
+        let page = mock
+            .list_objects_paged(None, None, Some("logs/1.txt"), 2)
+            .await
+            .unwrap();
+        let keys: Vec<&str> = page.objects.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, vec!["logs/2.txt", "media/x.png"]);
+        let page = mock
+            .list_objects_paged(None, None, Some("media/x.png"), 2)
+            .await
+            .unwrap();
+        assert_eq!(page.objects.len(), 1);
+        assert!(page.next_after.is_none());
+
+        // Common prefixes count toward the cap, in key order with the objects,
+        // and a folder used as a cursor isn't listed again
+        let page = mock
+            .list_objects_paged(None, Some("/"), None, 2)
+            .await
+            .unwrap();
+        assert_eq!(page.objects[0].key, "a.txt");
+        assert_eq!(page.common_prefixes, vec!["logs/"]);
+        assert_eq!(page.next_after.as_deref(), Some("logs/"));
+        let page = mock
+            .list_objects_paged(None, Some("/"), Some("logs/"), 2)
+            .await
+            .unwrap();
+        assert_eq!(page.common_prefixes, vec!["media/"]);
+        assert_eq!(page.objects[0].key, "z.txt");
+        assert!(page.next_after.is_none());
+
+        let page = mock
+            .list_objects_paged(Some("logs/"), None, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(page.objects.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_concurrent_matches_serial() {
+        let mock = MockR2::new("test-bucket");
+        for key in [
+            "root.txt",
+            "data/a/1.bin",
+            "data/a/2.bin",
+            "data/b/3.bin",
+            "data/top.bin",
+            "data-other/4.bin",
+            "logs/5.txt",
+        ] {
+            mock.insert(key, b"x".to_vec(), "application/octet-stream");
+        }
+
+        for prefix in [None, Some("data"), Some("data/"), Some("missing/")] {
+            let serial = mock.list_objects(prefix).await.unwrap();
+            for concurrency in [1, 4] {
+                let concurrent = list_objects_concurrent(&mock, prefix, concurrency)
+                    .await
+                    .unwrap();
+                let keys = |objects: &[ObjectInfo]| {
+                    objects.iter().map(|o| o.key.clone()).collect::<Vec<_>>()
+                };
+                assert_eq!(keys(&concurrent), keys(&serial), "prefix {:?}", prefix);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_upload() {
+        let mock = MockR2::new("test-bucket");
+        let create_only = UploadOptions {
+            if_none_match: Some("*".to_string()),
+            ..Default::default()
+        };
+
+        mock.upload_bytes_with_options("a.txt", b"v1".to_vec(), "text/plain", &create_only)
+            .await
+            .unwrap();
+        let result = mock
+            .upload_bytes_with_options("a.txt", b"v2".to_vec(), "text/plain", &create_only)
+            .await;
+        assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+
+        let etag = mock.head_object("a.txt").await.unwrap().etag;
+        let stale = UploadOptions {
+            if_match: Some("\"0123\"".to_string()),
+            ..Default::default()
+        };
+        let result = mock
+            .upload_bytes_with_options("a.txt", b"v2".to_vec(), "text/plain", &stale)
+            .await;
+        assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+        assert_eq!(mock.get("a.txt").unwrap().body, b"v1");
+
+        // Unquoted ETags are accepted too
+        let current = UploadOptions {
+            if_match: Some(etag.trim_matches('"').to_string()),
+            ..Default::default()
+        };
+        mock.upload_bytes_with_options("a.txt", b"v2".to_vec(), "text/plain", &current)
+            .await
+            .unwrap();
+        assert_eq!(mock.get("a.txt").unwrap().body, b"v2");
+    }
+
+    #[tokio::test]
+    async fn test_mock_missing_object() {
+        let mock = MockR2::new("test-bucket");
+
+        assert!(!mock.object_exists("nope").await.unwrap());
+        assert!(matches!(
+            mock.head_object("nope").await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_compressed_upload_sets_encoding() {
+        let mock = MockR2::new("test-bucket");
+        let options = UploadOptions {
+            compression: Some(Compression::Gzip),
+            ..Default::default()
+        };
+
+        mock.upload_bytes_with_options("app.js", b"x".repeat(100), "text/javascript", &options)
+            .await
+            .unwrap();
+
+        let meta = mock.head_object("app.js").await.unwrap();
+        assert_eq!(meta.content_encoding.as_deref(), Some("gzip"));
+        assert_eq!(meta.content_type, "text/javascript");
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_multipart_against_mock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..FILE_SIZE).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            concurrent_parts: 1,
+            ..Default::default()
+        };
+
+        mock.upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await
+            .unwrap();
+
+        let object = mock.get("data.bin").unwrap();
+        assert_eq!(object.body, data);
+        assert!(object.etag.ends_with("-3\""));
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_copy_object_to() {
+        let mock = MockR2::new("test-bucket");
+        mock.insert("docs/a b.txt", b"hello".to_vec(), "text/plain");
+
+        mock.copy_object_to(
+            "docs/a b.txt",
+            "test-bucket",
+            "copy.md",
+            Some("text/markdown"),
+        )
+        .await
+        .unwrap();
+        let copy = mock.get("copy.md").unwrap();
+        assert_eq!(copy.body, b"hello");
+        assert_eq!(copy.content_type, "text/markdown");
+        assert_eq!(mock.get("docs/a b.txt").unwrap().content_type, "text/plain");
+
+        let missing = mock
+            .copy_object_to("missing.txt", "test-bucket", "x.txt", None)
+            .await;
+        assert!(matches!(missing, Err(Error::NotFound(_))));
+        let other_bucket = mock
+            .copy_object_to("copy.md", "other-bucket", "copy.md", None)
+            .await;
+        assert!(matches!(other_bucket, Err(Error::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata() {
+        let mock = MockR2::new("test-bucket");
+        let options = UploadOptions {
+            cache_control: Some("no-cache".to_string()),
+            metadata: HashMap::from([("owner".to_string(), "ops".to_string())]),
+            ..Default::default()
+        };
+        mock.upload_bytes_with_options("a.txt", b"hello".to_vec(), "text/plain", &options)
+            .await
+            .unwrap();
+        let before = mock.get("a.txt").unwrap();
+
+        let update = MetadataUpdate {
+            content_type: Some("text/markdown".to_string()),
+            metadata: HashMap::from([("team".to_string(), "web".to_string())]),
+            ..Default::default()
+        };
+        mock.update_metadata("a.txt", &update).await.unwrap();
+
+        let after = mock.get("a.txt").unwrap();
+        assert_eq!(after.body, before.body);
+        assert_eq!(after.etag, before.etag);
+        assert_eq!(after.content_type, "text/markdown");
+        assert_eq!(after.cache_control.as_deref(), Some("no-cache"));
+        assert_eq!(after.metadata["owner"], "ops");
+        assert_eq!(after.metadata["team"], "web");
+
+        let missing = mock.update_metadata("missing.txt", &update).await;
+        assert!(matches!(missing, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_multipart_tiny_files_fall_back_to_put() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockR2::new("test-bucket");
+
+        for (name, body) in [("empty.bin", &b""[..]), ("one.bin", &b"x"[..])] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, body).unwrap();
+
+            mock.upload_file_multipart(
+                name,
+                &path,
+                "application/octet-stream",
+                MultipartUploadConfig::default(),
+            )
+            .await
+            .unwrap();
+
+            let object = mock.get(name).unwrap();
+            assert_eq!(object.body, body);
+            // A single-request ETag, not "<md5>-<parts>"
+            assert!(!object.etag.contains('-'), "{}", object.etag);
+        }
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_reader_multipart() {
+        let data: Vec<u8> = (0..FILE_SIZE).map(|i| (i % 251) as u8).collect();
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            concurrent_parts: 2,
+            ..Default::default()
+        };
+
+        // Two full parts and a short last one
+        mock.upload_reader_multipart(
+            "data.bin",
+            Box::new(std::io::Cursor::new(data.clone())),
+            "application/octet-stream",
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        let object = mock.get("data.bin").unwrap();
+        assert_eq!(object.body, data);
+        assert!(object.etag.ends_with("-3\""));
+        assert_eq!(mock.pending_uploads(), 0);
+
+        // Less than a chunk, empty input included, is a single PUT
+        mock.upload_reader_multipart(
+            "small.txt",
+            Box::new(&b"hello"[..]),
+            "text/plain",
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        let object = mock.get("small.txt").unwrap();
+        assert_eq!(object.body, b"hello");
+        assert!(!object.etag.contains('-'));
+
+        mock.upload_reader_multipart(
+            "empty.txt",
+            Box::new(tokio::io::empty()),
+            "text/plain",
+            config,
+        )
+        .await
+        .unwrap();
+        assert!(mock.get("empty.txt").unwrap().body.is_empty());
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_header_options_are_stored() {
+        let mock = MockR2::new("test-bucket");
+        let mut options = UploadOptions::default();
+        for (name, value) in [
+            ("Cache-Control", "public, max-age=31536000, immutable"),
+            ("Content-Type", "application/wasm"),
+            ("Content-Encoding", "br"),
+            ("x-amz-meta-Build", "1234"),
+        ] {
+            options.set_header(name, value).unwrap();
+        }
+
+        mock.upload_bytes_with_options("app.wasm", b"wasm".to_vec(), "text/plain", &options)
+            .await
+            .unwrap();
+
+        let object = mock.get("app.wasm").unwrap();
+        assert_eq!(
+            object.cache_control.as_deref(),
+            Some("public, max-age=31536000, immutable")
+        );
+        assert_eq!(object.content_type, "application/wasm");
+        assert_eq!(object.content_encoding.as_deref(), Some("br"));
+        assert_eq!(
+            object.metadata.get("build").map(String::as_str),
+            Some("1234")
+        );
+
+        // An explicit encoding can't describe a body we compress ourselves
+        options.compression = Some(Compression::Gzip);
+        assert!(mock
+            .upload_bytes_with_options("app.wasm", b"wasm".to_vec(), "text/plain", &options)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_content_disposition_is_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, vec![7u8; FILE_SIZE]).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let options = UploadOptions {
+            content_disposition: Some("attachment".to_string()),
+            ..Default::default()
+        };
+        mock.upload_bytes_with_options("small.txt", b"x".to_vec(), "text/plain", &options)
+            .await
+            .unwrap();
+
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            concurrent_parts: 1,
+            content_disposition: Some("attachment; filename=\"data.bin\"".to_string()),
+            ..Default::default()
+        };
+        mock.upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await
+            .unwrap();
+
+        let small = mock.head_object("small.txt").await.unwrap();
+        assert_eq!(small.content_disposition.as_deref(), Some("attachment"));
+        let large = mock.head_object("data.bin").await.unwrap();
+        assert_eq!(
+            large.content_disposition.as_deref(),
+            Some("attachment; filename=\"data.bin\"")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_multipart_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, vec![1u8; FILE_SIZE]).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            concurrent_parts: 1,
+            progress: Some(tx),
+            ..Default::default()
+        };
+
+        mock.upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push((
+                event.uploaded_bytes,
+                event.completed_parts,
+                event.total_parts,
+            ));
+            assert_eq!(event.total_bytes, FILE_SIZE as u64);
+        }
+        assert_eq!(
+            events,
+            vec![
+                (0, 0, 3),
+                (PART, 1, 3),
+                (2 * PART, 2, 3),
+                (FILE_SIZE as u64, 3, 3)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_multipart_uploads_parts_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..4 * MIN_PART_SIZE + 10)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            concurrent_parts: 3,
+            ..Default::default()
+        };
+
+        mock.upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await
+            .unwrap();
+
+        let object = mock.get("data.bin").unwrap();
+        assert_eq!(object.body, data);
+        assert!(object.etag.ends_with("-5\""));
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_multipart_retries_transient_part_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..FILE_SIZE).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            max_retries: 2,
+            retry_delay: std::time::Duration::ZERO,
+            ..Default::default()
+        };
+
+        mock.fail_next_parts(1);
+        mock.upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await
+            .unwrap();
+
+        assert_eq!(mock.get("data.bin").unwrap().body, data);
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_multipart_aborts_after_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, vec![7u8; FILE_SIZE]).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            concurrent_parts: 1,
+            max_retries: 2,
+            retry_delay: std::time::Duration::ZERO,
+            ..Default::default()
+        };
+
+        // The first part fails on every attempt
+        mock.fail_next_parts(3);
+        let result = mock
+            .upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await;
+
+        assert!(matches!(result, Err(Error::Transient(_))));
+        assert!(mock.get("data.bin").is_none());
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_abort_guard_aborts_upload() {
+        let mock = MockR2::new("test-bucket");
+
+        let kept = mock
+            .create_multipart_upload("kept.bin", "application/octet-stream", None)
+            .await
+            .unwrap();
+        AbortGuard::new(&mock, "kept.bin", &kept).defuse();
+
+        let orphan = mock
+            .create_multipart_upload("orphan.bin", "application/octet-stream", None)
+            .await
+            .unwrap();
+        drop(AbortGuard::new(&mock, "orphan.bin", &orphan));
+        assert_eq!(mock.pending_uploads(), 2);
+
+        // The abort runs on the runtime once the test yields
+        tokio::task::yield_now().await;
+        assert_eq!(mock.pending_uploads(), 1);
+        assert!(mock.uploads.lock().unwrap().contains_key(&kept));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_multipart_upload_is_aborted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, vec![7u8; FILE_SIZE]).unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            chunk_size: MIN_PART_SIZE,
+            ..Default::default()
+        };
+        config.cancel.cancel();
+
+        let result = mock
+            .upload_file_multipart("data.bin", &path, "application/octet-stream", config)
+            .await;
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(mock.get("data.bin").is_none());
+        assert_eq!(mock.pending_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_stream_checks_content_length() {
+        let mock = MockR2::new("test-bucket");
+        let config = MultipartUploadConfig {
+            content_disposition: Some("attachment".to_string()),
+            ..Default::default()
+        };
+
+        mock.upload_stream(
+            "ok.txt",
+            Box::new(&b"hello"[..]),
+            "text/plain",
+            Some(5),
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        let object = mock.get("ok.txt").unwrap();
+        assert_eq!(object.body, b"hello");
+        assert_eq!(object.content_disposition.as_deref(), Some("attachment"));
+
+        let result = mock
+            .upload_stream(
+                "bad.txt",
+                Box::new(&b"hello"[..]),
+                "text/plain",
+                Some(4),
+                config,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert!(mock.get("bad.txt").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_download_file_verified_resumes_truncated_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out/data.bin");
+        let body: Vec<u8> = (0..=255).collect();
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("data.bin", body.clone(), "application/octet-stream");
+        mock.truncate_next_downloads(1);
+
+        let size = download_file_verified(&mock, "data.bin", &dest, 3, Duration::ZERO)
+            .await
+            .unwrap();
+
+        assert_eq!(size, 256);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn test_download_file_verified_restarts_when_object_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("data.bin");
+        let new_body: Vec<u8> = (0..200).map(|i| (i % 7) as u8).collect();
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("data.bin", vec![1; 256], "application/octet-stream");
+        // The first transfer is cut short, then the object is overwritten
+        mock.truncate_next_downloads(1);
+        mock.replace_after_next_download(new_body.clone());
+
+        let size = download_file_verified(&mock, "data.bin", &dest, 3, Duration::ZERO)
+            .await
+            .unwrap();
+
+        // Nothing of the old version is kept
+        assert_eq!(size, 200);
+        assert_eq!(std::fs::read(&dest).unwrap(), new_body);
+    }
+
+    #[tokio::test]
+    async fn test_download_file_verified_fails_when_still_short() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("data.bin");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("data.bin", vec![1; 100], "application/octet-stream");
+        mock.truncate_next_downloads(10);
+
+        let result = download_file_verified(&mock, "data.bin", &dest, 2, Duration::ZERO).await;
+
+        assert!(matches!(result, Err(Error::R2Operation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_download_file_verified_keeps_existing_file_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("data.bin");
+        std::fs::write(&dest, b"previous version").unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("data.bin", vec![1; 100], "application/octet-stream");
+        mock.truncate_next_downloads(10);
+
+        let result = download_file_verified(&mock, "data.bin", &dest, 2, Duration::ZERO).await;
+        assert!(matches!(result, Err(Error::R2Operation(_))));
+
+        let result = download_file_verified(&mock, "missing.bin", &dest, 2, Duration::ZERO).await;
+        assert!(matches!(result, Err(Error::NotFound(_))));
+
+        assert_eq!(std::fs::read(&dest).unwrap(), b"previous version");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_file_decompressed() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("report.csv");
+        let body = b"id,total\n1,42\n".repeat(50);
+
+        let mock = MockR2::new("test-bucket");
+        let options = UploadOptions {
+            compression: Some(Compression::Zstd),
+            ..Default::default()
+        };
+        mock.upload_bytes_with_options("report.csv", body.clone(), "text/csv", &options)
+            .await
+            .unwrap();
+        mock.insert("plain.csv", body.clone(), "text/csv");
+
+        let (size, encoding) =
+            download_file_decompressed(&mock, "report.csv", &dest, 0, Duration::ZERO)
+                .await
+                .unwrap();
+        assert_eq!(
+            (size, encoding),
+            (body.len() as u64, Some(Compression::Zstd))
+        );
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        // Only the decoded file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A transfer cut short is restarted from scratch
+        mock.truncate_next_downloads(1);
+        let (size, _) = download_file_decompressed(&mock, "report.csv", &dest, 1, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(size, body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+        let (_, encoding) =
+            download_file_decompressed(&mock, "plain.csv", &dest, 0, Duration::ZERO)
+                .await
+                .unwrap();
+        assert_eq!(encoding, None);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn test_download_file_decompressed_keeps_existing_file_on_corrupt_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("report.csv");
+        std::fs::write(&dest, b"previous version").unwrap();
+
+        let mock = MockR2::new("test-bucket");
+        let mut options = UploadOptions::default();
+        options.set_header("Content-Encoding", "gzip").unwrap();
+        mock.upload_bytes_with_options("report.csv", b"not gzip".to_vec(), "text/csv", &options)
+            .await
+            .unwrap();
+
+        let result =
+            download_file_decompressed(&mock, "report.csv", &dest, 2, Duration::ZERO).await;
+
+        assert!(matches!(result, Err(Error::InvalidInput(m)) if m.contains("gzip")));
+        assert_eq!(std::fs::read(&dest).unwrap(), b"previous version");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_file_decompressed_rejects_unknown_encoding() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("app.wasm");
+
+        let mock = MockR2::new("test-bucket");
+        let mut options = UploadOptions::default();
+        options.set_header("Content-Encoding", "br").unwrap();
+        mock.upload_bytes_with_options("app.wasm", b"wasm".to_vec(), "application/wasm", &options)
+            .await
+            .unwrap();
+
+        let result = download_file_decompressed(&mock, "app.wasm", &dest, 0, Duration::ZERO).await;
+
+        assert!(matches!(result, Err(Error::InvalidInput(m)) if m.contains("br")));
+        assert!(!dest.exists());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_object_sees_late_write() {
+        let mock = MockR2::new("test-bucket");
+
+        let writer = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            mock.insert("report.json", b"{}".to_vec(), "application/json");
+        };
+        let (metadata, _) = tokio::join!(
+            wait_for_object(&mock, "report.json", Duration::from_secs(5)),
+            writer
+        );
+
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.key, "report.json");
+        assert_eq!(metadata.size, 2);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_object_times_out() {
+        let mock = MockR2::new("test-bucket");
+
+        let started = std::time::Instant::now();
+        let result = wait_for_object(&mock, "never.txt", Duration::from_millis(300)).await;
+
+        assert!(matches!(result, Err(Error::Timeout)));
+        assert!(started.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_restore_requires_versioning() {
+        let mock = MockR2::new("test-bucket");
+        let result = mock.restore_object("deleted.txt").await;
+        assert!(matches!(result, Err(Error::BucketSettings(_))));
+    }
+
+    #[tokio::test]
+    async fn test_change_storage_class_keeps_metadata() {
+        let mock = MockR2::new("test-bucket");
+        let options = UploadOptions {
+            content_disposition: Some("attachment".to_string()),
+            ..Default::default()
+        };
+        mock.upload_bytes_with_options("archive.csv", b"a,b".to_vec(), "text/csv", &options)
+            .await
+            .unwrap();
+        let before = mock.get("archive.csv").unwrap();
+
+        mock.change_storage_class("archive.csv", StorageClass::InfrequentAccess)
+            .await
+            .unwrap();
+
+        let after = mock.get("archive.csv").unwrap();
+        assert_eq!(after.storage_class, StorageClass::InfrequentAccess);
+        assert_eq!(
+            mock.head_object("archive.csv").await.unwrap().storage_class,
+            StorageClass::InfrequentAccess
+        );
+        assert_eq!(after.body, before.body);
+        assert_eq!(after.etag, before.etag);
+        assert_eq!(after.content_type, "text/csv");
+        assert_eq!(after.content_disposition.as_deref(), Some("attachment"));
+
+        let missing = mock
+            .change_storage_class("missing.csv", StorageClass::Standard)
+            .await;
+        assert!(matches!(missing, Err(Error::NotFound(_))));
+    }
+}
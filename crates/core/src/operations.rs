@@ -0,0 +1,1178 @@
+//! Object operations abstraction over R2
+//!
+//! `R2Operations` is implemented by `R2Client` and by the in-memory `MockR2`
+//! (test builds and the `mock` feature), so higher-level logic can run against
+//! either backend.
+
+use crate::client::{
+    plan_multipart, read_chunk, CompletedPart, Compression, DeleteOutcome, MetadataUpdate,
+    MultipartPlan, MultipartUploadConfig, MultipartUploadInfo, MultipartUploadProgress, ObjectInfo,
+    ObjectListing, ObjectMetadata, R2Client, StorageClass, UploadOptions, MAX_PARTS, MIN_PART_SIZE,
+};
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::stream::{self, FuturesUnordered, StreamExt, TryStreamExt};
+use futures::TryFutureExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+/// Boxed reader accepted by streaming uploads
+pub type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Object operations on a single bucket
+#[async_trait]
+pub trait R2Operations: Send + Sync {
+    /// Bucket the operations apply to
+    fn bucket(&self) -> &str;
+
+    /// Upload bytes with extra options (compression, ...)
+    async fn upload_bytes_with_options(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()>;
+
+    /// Download an object into memory
+    async fn download_bytes(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// List objects, optionally filtered by prefix
+    async fn list_objects(&self, prefix: Option<&str>) -> Result<Vec<ObjectInfo>>;
+
+    /// List the objects directly under `prefix` and the common prefixes below it
+    ///
+    /// Keys containing `delimiter` after the prefix are rolled up into a common
+    /// prefix (ending with the delimiter) instead of being returned. The default
+    /// implementation groups a full listing; `R2Client` asks the server to.
+    async fn list_objects_delimited(
+        &self,
+        prefix: Option<&str>,
+        delimiter: &str,
+    ) -> Result<(Vec<ObjectInfo>, Vec<String>)> {
+        Ok(group_delimited(
+            self.list_objects(prefix).await?,
+            prefix,
+            delimiter,
+        ))
+    }
+
+    /// The first `max_keys` entries after `start_after`, objects and common prefixes together
+    ///
+    /// With a `delimiter` this is [`Self::list_objects_delimited`], otherwise a
+    /// flat listing, cut after `max_keys` entries in key order. `next_after`
+    /// is the cursor of the next page, if any. The default implementation
+    /// lists everything first; `R2Client` stops paginating.
+    async fn list_objects_paged(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        start_after: Option<&str>,
+        max_keys: usize,
+    ) -> Result<ObjectListing> {
+        let (objects, common_prefixes) = match delimiter {
+            Some(delimiter) => self.list_objects_delimited(prefix, delimiter).await?,
+            None => (self.list_objects(prefix).await?, Vec::new()),
+        };
+        Ok(page_listing(
+            objects,
+            common_prefixes,
+            start_after,
+            max_keys,
+            false,
+        ))
+    }
+
+    /// Delete an object
+    async fn delete_object(&self, key: &str) -> Result<()>;
+
+    /// Check if an object exists
+    async fn object_exists(&self, key: &str) -> Result<bool>;
+
+    /// Get object metadata
+    async fn head_object(&self, key: &str) -> Result<ObjectMetadata>;
+
+    /// Copy an object within the bucket
+    async fn copy_object(&self, source_key: &str, dest_key: &str) -> Result<()>;
+
+    /// Copy an object to `dest_key` in `dest_bucket`, optionally replacing its Content-Type
+    ///
+    /// The default implementation only copies within this bucket.
+    async fn copy_object_to(
+        &self,
+        source_key: &str,
+        dest_bucket: &str,
+        dest_key: &str,
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        if dest_bucket != self.bucket() {
+            return Err(Error::InvalidInput(format!(
+                "Copying to another bucket ('{}') is not supported by this backend",
+                dest_bucket
+            )));
+        }
+
+        self.copy_object(source_key, dest_key).await?;
+        if let Some(content_type) = content_type {
+            self.set_content_type(dest_key, content_type).await?;
+        }
+        Ok(())
+    }
+
+    /// Replace an object's Content-Type in place, keeping its body and other metadata
+    async fn set_content_type(&self, key: &str, content_type: &str) -> Result<()>;
+
+    /// Change an object's headers and user metadata in place, keeping its body
+    async fn update_metadata(&self, key: &str, update: &MetadataUpdate) -> Result<()>;
+
+    /// Move an object to another storage class in place, keeping its body and metadata
+    async fn change_storage_class(&self, key: &str, class: StorageClass) -> Result<()>;
+
+    /// Initiate a multipart upload, returning its upload ID
+    async fn create_multipart_upload(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: Option<&str>,
+    ) -> Result<String>;
+
+    /// Upload a single part in a multipart upload
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<CompletedPart>;
+
+    /// Complete a multipart upload
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> Result<()>;
+
+    /// Abort a multipart upload
+    async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> Result<()>;
+
+    /// List the multipart uploads neither completed nor aborted
+    async fn list_multipart_uploads(&self) -> Result<Vec<MultipartUploadInfo>>;
+
+    /// Total size of the parts uploaded so far to a multipart upload
+    async fn multipart_upload_size(&self, key: &str, upload_id: &str) -> Result<u64>;
+
+    /// An abort of `upload_id` that doesn't borrow `self`, for [`AbortGuard`]
+    ///
+    /// Backends that can't provide one return `None` (the default); their
+    /// uploads are then only aborted on the explicitly handled error paths.
+    fn detached_abort(
+        &self,
+        _key: &str,
+        _upload_id: &str,
+    ) -> Option<BoxFuture<'static, Result<()>>> {
+        None
+    }
+
+    /// Upload bytes
+    async fn upload_bytes(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        self.upload_bytes_with_options(key, body, content_type, &UploadOptions::default())
+            .await
+    }
+
+    /// Upload a file
+    async fn upload_file(&self, key: &str, file_path: &Path, content_type: &str) -> Result<()> {
+        self.upload_file_with_options(key, file_path, content_type, &UploadOptions::default())
+            .await
+    }
+
+    /// Upload a file with extra options (compression, ...)
+    async fn upload_file_with_options(
+        &self,
+        key: &str,
+        file_path: &Path,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()> {
+        let body = tokio::fs::read(file_path).await.map_err(Error::Io)?;
+        self.upload_bytes_with_options(key, body, content_type, options)
+            .await
+    }
+
+    /// Upload from a reader, validating it against `content_length` when given
+    ///
+    /// The default implementation buffers the reader in memory; `R2Client`
+    /// streams it instead.
+    async fn upload_stream(
+        &self,
+        key: &str,
+        mut reader: BoxedReader,
+        content_type: &str,
+        content_length: Option<u64>,
+        config: MultipartUploadConfig,
+    ) -> Result<()> {
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).await.map_err(Error::Io)?;
+
+        if let Some(expected) = content_length {
+            if body.len() as u64 != expected {
+                return Err(Error::InvalidInput(format!(
+                    "Content-Length mismatch: stream has {} bytes, {} declared",
+                    body.len(),
+                    expected
+                )));
+            }
+        }
+
+        let options = UploadOptions {
+            content_disposition: config.content_disposition,
+            ..Default::default()
+        };
+        self.upload_bytes_with_options(key, body, content_type, &options)
+            .await
+    }
+
+    /// Download an object to a local file
+    async fn download_file(&self, key: &str, dest_path: &Path) -> Result<()> {
+        let data = self.download_bytes(key).await?;
+
+        // Create parent directories if needed
+        if let Some(parent) = dest_path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(Error::Io)?;
+        }
+
+        tokio::fs::write(dest_path, data).await.map_err(Error::Io)?;
+
+        Ok(())
+    }
+
+    /// Stream an object's stored bytes
+    ///
+    /// The default implementation downloads the whole object first; `R2Client`
+    /// reads the response body as it arrives.
+    async fn download_stream(&self, key: &str) -> Result<BoxedReader> {
+        Ok(Box::new(std::io::Cursor::new(
+            self.download_bytes(key).await?,
+        )))
+    }
+
+    /// Append the object's bytes from offset `start` onwards to `dest_path`
+    ///
+    /// With `if_match`, the request fails with `Error::PreconditionFailed`
+    /// unless the object still has that ETag, so bytes of another version are
+    /// never appended. The default implementation checks the ETag with a HEAD
+    /// request, then downloads the whole object and appends its tail;
+    /// `R2Client` requests only the range and streams it to disk.
+    async fn download_range_to_file(
+        &self,
+        key: &str,
+        start: u64,
+        dest_path: &Path,
+        if_match: Option<&str>,
+    ) -> Result<()> {
+        if let Some(etag) = if_match {
+            if self.head_object(key).await?.etag != etag {
+                return Err(Error::PreconditionFailed(format!(
+                    "{} no longer has ETag {}",
+                    key, etag
+                )));
+            }
+        }
+        let data = self.download_bytes(key).await?;
+        let tail = data.get(start as usize..).unwrap_or_default();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dest_path)
+            .await
+            .map_err(Error::Io)?;
+        file.write_all(tail).await.map_err(Error::Io)?;
+
+        Ok(())
+    }
+
+    /// Undelete an object by removing its latest delete marker, returning the marker's version ID
+    ///
+    /// Requires a versioned bucket; backends without versioning return
+    /// `Error::BucketSettings`.
+    async fn restore_object(&self, _key: &str) -> Result<String> {
+        Err(Error::BucketSettings(format!(
+            "Versioning is not supported on bucket '{}'",
+            self.bucket()
+        )))
+    }
+
+    /// Delete multiple objects
+    async fn delete_objects(&self, keys: Vec<String>) -> Result<()> {
+        for key in keys {
+            self.delete_object(&key).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete multiple objects, reporting the outcome of each key
+    ///
+    /// Unlike [`Self::delete_objects`], a key that can't be deleted doesn't
+    /// fail the call or stop the remaining deletes.
+    async fn try_delete_objects(&self, keys: Vec<String>) -> Result<Vec<DeleteOutcome>> {
+        let mut outcomes = Vec::with_capacity(keys.len());
+        for key in keys {
+            let result = self.delete_object(&key).await;
+            outcomes.push(DeleteOutcome { key, result });
+        }
+        Ok(outcomes)
+    }
+
+    /// Upload a file using multipart upload
+    ///
+    /// A file smaller than the minimum part size (5MB), empty files included,
+    /// is sent with a single `PutObject` instead: a one-part upload gains
+    /// nothing, and S3 rejects completing an upload without parts.
+    async fn upload_file_multipart(
+        &self,
+        key: &str,
+        file_path: &Path,
+        content_type: &str,
+        config: MultipartUploadConfig,
+    ) -> Result<()> {
+        // Open file and get size
+        let file = File::open(file_path).await.map_err(Error::Io)?;
+        let metadata = file.metadata().await.map_err(Error::Io)?;
+        let file_size = metadata.len();
+
+        if file_size < MIN_PART_SIZE as u64 {
+            tracing::info!(
+                key,
+                file_size,
+                "file below the minimum part size, uploading with a single PUT"
+            );
+            let options = UploadOptions {
+                content_disposition: config.content_disposition.clone(),
+                ..Default::default()
+            };
+            return self
+                .upload_file_with_options(key, file_path, content_type, &options)
+                .await;
+        }
+
+        // Calculate number of parts
+        let plan = plan_multipart(file_size, &config);
+        tracing::debug!(
+            key,
+            file_size,
+            chunk_size = plan.chunk_size,
+            total_parts = plan.total_parts,
+            concurrency = plan.concurrency,
+            "multipart upload plan"
+        );
+
+        multipart_upload_reader(self, key, file, content_type, plan, &config).await
+    }
+
+    /// Upload a reader of unknown length in `config.chunk_size` parts, without a temp file
+    ///
+    /// Parts are uploaded as they fill, up to `config.concurrent_parts` at
+    /// once, so at most that many chunks are held in memory. Only the last
+    /// part may be short. Input that fits in a single chunk, empty input
+    /// included, is sent with one `PutObject` instead. The chunk size is kept
+    /// between 5MB and 100MB, which caps the input at 10,000 chunks.
+    async fn upload_reader_multipart(
+        &self,
+        key: &str,
+        reader: BoxedReader,
+        content_type: &str,
+        config: MultipartUploadConfig,
+    ) -> Result<()> {
+        upload_reader_in_parts(self, key, reader, content_type, config).await
+    }
+}
+
+/// [`R2Operations::upload_reader_multipart`] for any reader
+pub(crate) async fn upload_reader_in_parts<O, R>(
+    ops: &O,
+    key: &str,
+    mut reader: R,
+    content_type: &str,
+    config: MultipartUploadConfig,
+) -> Result<()>
+where
+    O: R2Operations + ?Sized,
+    R: AsyncRead + Send + Unpin,
+{
+    let chunk_size = config.chunk_size.clamp(MIN_PART_SIZE, 100 * 1024 * 1024);
+    let first = read_chunk(&mut reader, chunk_size).await?;
+
+    if first.len() < chunk_size {
+        let options = UploadOptions {
+            content_disposition: config.content_disposition.clone(),
+            ..Default::default()
+        };
+        return ops
+            .upload_bytes_with_options(key, first, content_type, &options)
+            .await;
+    }
+
+    // The total isn't known in advance
+    let plan = MultipartPlan {
+        file_size: 0,
+        chunk_size,
+        total_parts: 0,
+        concurrency: config.concurrent_parts.max(1),
+    };
+    let reader = std::io::Cursor::new(first).chain(reader);
+    multipart_upload_reader(ops, key, reader, content_type, plan, &config).await
+}
+
+/// Multipart upload of everything `reader` yields, split as `plan` says
+///
+/// Parts are read in order and up to `plan.concurrency` of them uploaded at
+/// once; a chunk is only read once a slot is free, bounding memory use. A
+/// `plan` with a zero `file_size` stands for an unknown length, reported as
+/// zero totals in progress events. The upload is aborted on failure.
+async fn multipart_upload_reader<O, R>(
+    ops: &O,
+    key: &str,
+    mut reader: R,
+    content_type: &str,
+    plan: MultipartPlan,
+    config: &MultipartUploadConfig,
+) -> Result<()>
+where
+    O: R2Operations + ?Sized,
+    R: AsyncRead + Send + Unpin,
+{
+    let MultipartPlan {
+        file_size,
+        chunk_size,
+        total_parts,
+        concurrency,
+    } = plan;
+
+    // Initiate multipart upload
+    let upload_id = ops
+        .create_multipart_upload(key, content_type, config.content_disposition.as_deref())
+        .await?;
+    // Covers every exit this function doesn't handle: a panic, a failed
+    // completion, or the future being dropped mid-upload
+    let guard = AbortGuard::new(ops, key, &upload_id);
+
+    let progress = |uploaded_bytes, completed_parts| MultipartUploadProgress {
+        upload_id: upload_id.clone(),
+        total_bytes: file_size,
+        uploaded_bytes,
+        completed_parts,
+        total_parts,
+    };
+    config.report(|| progress(0, 0));
+
+    let mut in_flight = FuturesUnordered::new();
+    let mut part_number = 0;
+    let mut reading = true;
+    let mut parts = Vec::new();
+    let mut uploaded_bytes = 0u64;
+
+    let result = 'upload: loop {
+        while reading && in_flight.len() < concurrency {
+            // A single read may return less than a full part
+            let buffer = match read_chunk(&mut reader, chunk_size).await {
+                Ok(buffer) => buffer,
+                Err(e) => break 'upload Err(e),
+            };
+            if buffer.is_empty() {
+                reading = false;
+                break;
+            }
+            if part_number as u64 >= MAX_PARTS {
+                break 'upload Err(Error::InvalidInput(format!(
+                    "Input is larger than {} parts of {} bytes (raise the chunk size)",
+                    MAX_PARTS, chunk_size
+                )));
+            }
+
+            part_number += 1;
+            let n = buffer.len() as u64;
+            in_flight.push(
+                upload_part_with_retry(ops, key, &upload_id, part_number, buffer, config)
+                    .map_ok(move |part| (part, n)),
+            );
+        }
+
+        match in_flight.next().await {
+            Some(Ok((part, n))) => {
+                parts.push(part);
+                uploaded_bytes += n;
+                config.report(|| progress(uploaded_bytes, parts.len()));
+            }
+            Some(Err(e)) => break Err(e),
+            None => break Ok(()),
+        }
+    };
+    // Stop the parts still in flight before aborting
+    drop(in_flight);
+
+    if let Err(e) = result {
+        guard.defuse();
+        let _ = ops.abort_multipart_upload(key, &upload_id).await;
+        return Err(e);
+    }
+
+    // Complete multipart upload (parts may have finished out of order)
+    parts.sort_by_key(|part| part.part_number);
+    ops.complete_multipart_upload(key, &upload_id, parts)
+        .await?;
+    guard.defuse();
+
+    Ok(())
+}
+
+/// Aborts a multipart upload when dropped, unless [`AbortGuard::defuse`]d
+///
+/// The abort is spawned on the current Tokio runtime, so it also runs when
+/// the upload future is dropped or panics. Nothing happens if the backend
+/// has no [`R2Operations::detached_abort`] or no runtime is running.
+#[must_use = "dropping the guard aborts the upload right away"]
+pub struct AbortGuard {
+    key: String,
+    upload_id: String,
+    abort: Option<BoxFuture<'static, Result<()>>>,
+}
+
+impl AbortGuard {
+    pub fn new<O: R2Operations + ?Sized>(ops: &O, key: &str, upload_id: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            abort: ops.detached_abort(key, upload_id),
+        }
+    }
+
+    /// Keep the upload: it completed, or the caller aborts it itself
+    pub fn defuse(mut self) {
+        self.abort = None;
+    }
+}
+
+impl Drop for AbortGuard {
+    fn drop(&mut self) {
+        let Some(abort) = self.abort.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::warn!(key = %self.key, upload_id = %self.upload_id, "no runtime to abort multipart upload");
+            return;
+        };
+
+        let (key, upload_id) = (self.key.clone(), self.upload_id.clone());
+        tracing::debug!(%key, %upload_id, "aborting orphaned multipart upload");
+        runtime.spawn(async move {
+            if let Err(e) = abort.await {
+                tracing::warn!(%key, %upload_id, error = %e, "failed to abort multipart upload");
+            }
+        });
+    }
+}
+
+/// List every object under `prefix`, fanning out over its top-level "directories"
+///
+/// A delimited listing splits the prefix on `/`, then each common prefix is
+/// listed concurrently (at most `concurrency` at once) instead of paging
+/// through the whole prefix serially. Results are sorted by key, like a plain
+/// listing. With `concurrency` of 1 this is just `list_objects`.
+pub async fn list_objects_concurrent<O: R2Operations + ?Sized>(
+    ops: &O,
+    prefix: Option<&str>,
+    concurrency: usize,
+) -> Result<Vec<ObjectInfo>> {
+    if concurrency <= 1 {
+        return ops.list_objects(prefix).await;
+    }
+
+    let (mut objects, common_prefixes) = ops.list_objects_delimited(prefix, "/").await?;
+    if common_prefixes.is_empty() {
+        return Ok(objects);
+    }
+
+    let nested: Vec<Vec<ObjectInfo>> = stream::iter(common_prefixes)
+        .map(|common| async move { ops.list_objects(Some(&common)).await })
+        .buffer_unordered(concurrency)
+        .try_collect()
+        .await?;
+
+    objects.extend(nested.into_iter().flatten());
+    objects.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(objects)
+}
+
+/// Upload one part, retrying transient failures with exponential backoff
+///
+/// Re-sending a part is safe: a later upload of the same part number replaces
+/// the earlier one. Non-transient errors are returned immediately, and
+/// `Error::Cancelled` as soon as `config.cancel` fires.
+pub(crate) async fn upload_part_with_retry<O: R2Operations + ?Sized>(
+    ops: &O,
+    key: &str,
+    upload_id: &str,
+    part_number: i32,
+    mut body: Vec<u8>,
+    config: &MultipartUploadConfig,
+) -> Result<CompletedPart> {
+    let mut attempt = 0;
+    loop {
+        // Keep a copy around only while another attempt is still possible
+        let part_body = if attempt < config.max_retries {
+            body.clone()
+        } else {
+            std::mem::take(&mut body)
+        };
+
+        let result = tokio::select! {
+            biased;
+            _ = config.cancel.cancelled() => return Err(Error::Cancelled),
+            result = ops.upload_part(key, upload_id, part_number, part_body) => result,
+        };
+
+        match result {
+            Err(e) if e.is_transient() && attempt < config.max_retries => {
+                let delay = config.retry_delay * 2u32.pow(attempt.min(6));
+                tokio::select! {
+                    biased;
+                    _ = config.cancel.cancelled() => return Err(Error::Cancelled),
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Roll the keys of a full listing under `prefix` up to the first `delimiter`
+///
+/// Keys without the delimiter after the prefix are returned as objects, the
+/// others as sorted, deduplicated common prefixes ending with the delimiter.
+pub(crate) fn group_delimited(
+    listing: Vec<ObjectInfo>,
+    prefix: Option<&str>,
+    delimiter: &str,
+) -> (Vec<ObjectInfo>, Vec<String>) {
+    let base = prefix.unwrap_or("");
+    let mut objects = Vec::new();
+    let mut common_prefixes = std::collections::BTreeSet::new();
+
+    for object in listing {
+        let rest = &object.key[base.len()..];
+        match rest.find(delimiter) {
+            Some(end) => {
+                common_prefixes
+                    .insert(object.key[..base.len() + end + delimiter.len()].to_string());
+            }
+            None => objects.push(object),
+        }
+    }
+
+    (objects, common_prefixes.into_iter().collect())
+}
+
+/// Keep the first `max_keys` entries after `start_after`, objects and prefixes together in key order
+///
+/// `more` says entries exist beyond the ones given; the page then gets a
+/// `next_after` cursor, as it does when some of them are cut. Prefixes up to
+/// `start_after` are dropped: listing with a delimiter after a folder's own
+/// prefix still rolls the keys below it up into that folder.
+pub(crate) fn page_listing(
+    mut objects: Vec<ObjectInfo>,
+    mut common_prefixes: Vec<String>,
+    start_after: Option<&str>,
+    max_keys: usize,
+    more: bool,
+) -> ObjectListing {
+    if let Some(after) = start_after {
+        objects.retain(|o| o.key.as_str() > after);
+        common_prefixes.retain(|p| p.as_str() > after);
+    }
+
+    let mut keys: Vec<&str> = objects
+        .iter()
+        .map(|o| o.key.as_str())
+        .chain(common_prefixes.iter().map(String::as_str))
+        .collect();
+    keys.sort_unstable();
+    let cut = keys.len() > max_keys;
+    keys.truncate(max_keys);
+    let last = keys.last().map(|k| k.to_string());
+
+    if cut {
+        match &last {
+            Some(last) => {
+                objects.retain(|o| o.key <= *last);
+                common_prefixes.retain(|p| p <= last);
+            }
+            None => {
+                objects.clear();
+                common_prefixes.clear();
+            }
+        }
+    }
+
+    ObjectListing {
+        objects,
+        common_prefixes,
+        next_after: if cut || more { last } else { None },
+    }
+}
+
+/// Download an object to a file and check its size against `head_object`
+///
+/// The object is downloaded into a temporary file next to `dest_path`, which
+/// replaces `dest_path` only once its size matches the object's: a failed
+/// download leaves an existing file untouched. A transfer that ends short (or
+/// fails with a transient error) is resumed from the bytes already received
+/// with a ranged request, up to `max_retries` times with exponential backoff.
+/// Every request is pinned to the ETag seen by `head_object`; if the object
+/// was replaced in between, the download restarts from zero with the new
+/// version. Otherwise `Error::R2Operation` is returned.
+pub async fn download_file_verified<O: R2Operations + ?Sized>(
+    ops: &O,
+    key: &str,
+    dest_path: &Path,
+    max_retries: u32,
+    retry_delay: Duration,
+) -> Result<u64> {
+    let metadata = ops.head_object(key).await?;
+    if metadata.storage_class == StorageClass::InfrequentAccess {
+        tracing::info!(
+            key,
+            size = metadata.size,
+            "downloading an Infrequent Access object (billed as a data retrieval)"
+        );
+    }
+
+    if let Some(parent) = dest_path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(Error::Io)?;
+    }
+    let temp_path = part_path(dest_path);
+
+    match download_resumable(ops, key, metadata, &temp_path, max_retries, retry_delay).await {
+        Ok(size) => {
+            tokio::fs::rename(&temp_path, dest_path)
+                .await
+                .map_err(Error::Io)?;
+            Ok(size)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Download `key` into `path` from scratch, resuming short transfers
+///
+/// Returns the size once `path` holds the whole object described by `metadata`.
+async fn download_resumable<O: R2Operations + ?Sized>(
+    ops: &O,
+    key: &str,
+    mut metadata: ObjectMetadata,
+    path: &Path,
+    max_retries: u32,
+    retry_delay: Duration,
+) -> Result<u64> {
+    File::create(path).await.map_err(Error::Io)?;
+
+    let mut attempt = 0;
+    loop {
+        let expected = metadata.size.max(0) as u64;
+        let start = local_size(path).await?;
+        match ops
+            .download_range_to_file(key, start, path, Some(&metadata.etag))
+            .await
+        {
+            Ok(()) => {}
+            Err(Error::PreconditionFailed(_)) if attempt < max_retries => {
+                // The object changed: the bytes on disk belong to the old version
+                tracing::info!(key, "object changed during download, restarting");
+                metadata = ops.head_object(key).await?;
+                File::create(path).await.map_err(Error::Io)?;
+                attempt += 1;
+                continue;
+            }
+            Err(e) if !e.is_transient() => return Err(e),
+            Err(_) => {}
+        }
+
+        let written = local_size(path).await?;
+        if written == expected {
+            return Ok(written);
+        }
+        if written > expected {
+            return Err(Error::R2Operation(format!(
+                "Downloaded {} bytes but {} is {} bytes (changed during download?)",
+                written, key, expected
+            )));
+        }
+        if attempt >= max_retries {
+            return Err(Error::R2Operation(format!(
+                "Incomplete download of {}: got {} of {} bytes after {} retries",
+                key, written, expected, max_retries
+            )));
+        }
+
+        tokio::time::sleep(retry_delay * 2u32.pow(attempt.min(6))).await;
+        attempt += 1;
+    }
+}
+
+/// Temporary file next to `dest_path` that a download is written to
+fn part_path(dest_path: &Path) -> PathBuf {
+    let mut name = dest_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".r2pilot-part");
+    dest_path.with_file_name(name)
+}
+
+/// Download an object like [`download_file_verified`], decoding its `Content-Encoding`
+///
+/// The body is streamed through the decoder into a temporary file next to
+/// `dest_path`, which replaces `dest_path` only once the whole object was
+/// received and decoded: corrupt data leaves an existing file untouched. A
+/// transfer cut short is restarted, up to `max_retries` times with
+/// exponential backoff. An object without an encoding is kept as-is; one
+/// other than gzip or zstd is an error and nothing is downloaded. Returns the
+/// decoded size and the encoding that was removed.
+pub async fn download_file_decompressed<O: R2Operations + ?Sized>(
+    ops: &O,
+    key: &str,
+    dest_path: &Path,
+    max_retries: u32,
+    retry_delay: Duration,
+) -> Result<(u64, Option<Compression>)> {
+    let metadata = ops.head_object(key).await?;
+    let encoding = metadata.content_encoding.as_deref().unwrap_or("");
+    let Some(compression) = Compression::from_content_encoding(encoding)? else {
+        let size = download_file_verified(ops, key, dest_path, max_retries, retry_delay).await?;
+        return Ok((size, None));
+    };
+    let expected = metadata.size.max(0) as u64;
+
+    if let Some(parent) = dest_path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(Error::Io)?;
+    }
+    let temp_path = part_path(dest_path);
+
+    let mut attempt = 0;
+    let decoded = loop {
+        match decode_to_file(ops, key, compression, expected, &temp_path).await {
+            Err(e) if e.is_transient() && attempt < max_retries => {
+                tokio::time::sleep(retry_delay * 2u32.pow(attempt.min(6))).await;
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+
+    match decoded {
+        Ok(size) => {
+            tokio::fs::rename(&temp_path, dest_path)
+                .await
+                .map_err(Error::Io)?;
+            Ok((size, Some(compression)))
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Stream `key` through the decoder into `path`, returning the decoded size
+///
+/// A body that fails or ends before `expected` bytes is a (transient)
+/// network error; undecodable data is `Error::InvalidInput`.
+async fn decode_to_file<O: R2Operations + ?Sized>(
+    ops: &O,
+    key: &str,
+    compression: Compression,
+    expected: u64,
+    path: &Path,
+) -> Result<u64> {
+    let mut body = BlockingReader {
+        reader: ops.download_stream(key).await?,
+        handle: tokio::runtime::Handle::current(),
+        received: 0,
+        error: None,
+    };
+    let (key, path) = (key.to_string(), path.to_path_buf());
+
+    tokio::task::spawn_blocking(move || {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path).map_err(Error::Io)?);
+        let decoded = compression.decompress_to(&mut body, &mut writer);
+        // Decoders may stop at the end of their data: count what follows too
+        if body.error.is_none() {
+            let _ = std::io::copy(&mut body, &mut std::io::sink());
+        }
+
+        if let Some(e) = body.error {
+            return Err(Error::Network(format!(
+                "Download of {} interrupted: {}",
+                key, e
+            )));
+        }
+        if body.received != expected {
+            return Err(Error::Network(format!(
+                "Incomplete download of {}: got {} of {} bytes",
+                key, body.received, expected
+            )));
+        }
+        let size = decoded?;
+        std::io::Write::flush(&mut writer).map_err(Error::Io)?;
+        Ok(size)
+    })
+    .await
+    .map_err(|e| Error::R2Operation(format!("Decompression task failed: {}", e)))?
+}
+
+/// Blocking `Read` over a download stream, for decoders on a blocking thread
+///
+/// Counts the bytes received and keeps the stream's own error, so a dropped
+/// connection isn't mistaken for corrupt data.
+struct BlockingReader {
+    reader: BoxedReader,
+    handle: tokio::runtime::Handle,
+    received: u64,
+    error: Option<std::io::Error>,
+}
+
+impl std::io::Read for BlockingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.handle.block_on(self.reader.read(buf)) {
+            Ok(n) => {
+                self.received += n as u64;
+                Ok(n)
+            }
+            Err(e) => {
+                let kind = e.kind();
+                let message = e.to_string();
+                self.error = Some(e);
+                Err(std::io::Error::new(kind, message))
+            }
+        }
+    }
+}
+
+/// First delay between `wait_for_object` polls, doubled after each miss
+const WAIT_INITIAL_DELAY: Duration = Duration::from_millis(200);
+/// Longest delay between `wait_for_object` polls
+const WAIT_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Poll `head_object` until the object appears, for at most `timeout`
+///
+/// Polls back off exponentially from 200ms up to 5s. Returns the object's
+/// metadata once it exists and `Error::Timeout` if it never shows up; errors
+/// other than `NotFound` are returned immediately.
+pub async fn wait_for_object<O: R2Operations + ?Sized>(
+    ops: &O,
+    key: &str,
+    timeout: Duration,
+) -> Result<ObjectMetadata> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut delay = WAIT_INITIAL_DELAY;
+
+    loop {
+        match ops.head_object(key).await {
+            Ok(metadata) => return Ok(metadata),
+            Err(Error::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::Timeout);
+        }
+
+        tokio::time::sleep(delay.min(remaining)).await;
+        delay = (delay * 2).min(WAIT_MAX_DELAY);
+    }
+}
+
+async fn local_size(path: &Path) -> Result<u64> {
+    Ok(tokio::fs::metadata(path).await.map_err(Error::Io)?.len())
+}
+
+#[async_trait]
+impl R2Operations for R2Client {
+    fn bucket(&self) -> &str {
+        R2Client::bucket(self)
+    }
+
+    async fn upload_bytes_with_options(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()> {
+        R2Client::upload_bytes_with_options(self, key, body, content_type, options).await
+    }
+
+    async fn upload_file_with_options(
+        &self,
+        key: &str,
+        file_path: &Path,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<()> {
+        R2Client::upload_file_with_options(self, key, file_path, content_type, options).await
+    }
+
+    async fn upload_stream(
+        &self,
+        key: &str,
+        reader: BoxedReader,
+        content_type: &str,
+        content_length: Option<u64>,
+        config: MultipartUploadConfig,
+    ) -> Result<()> {
+        R2Client::upload_stream(self, key, reader, content_type, content_length, config).await
+    }
+
+    async fn download_bytes(&self, key: &str) -> Result<Vec<u8>> {
+        R2Client::download_bytes(self, key).await
+    }
+
+    async fn download_file(&self, key: &str, dest_path: &Path) -> Result<()> {
+        R2Client::download_file(self, key, dest_path).await
+    }
+
+    async fn download_range_to_file(
+        &self,
+        key: &str,
+        start: u64,
+        dest_path: &Path,
+        if_match: Option<&str>,
+    ) -> Result<()> {
+        R2Client::download_range_to_file(self, key, start, dest_path, if_match).await
+    }
+
+    async fn download_stream(&self, key: &str) -> Result<BoxedReader> {
+        R2Client::download_stream(self, key).await
+    }
+
+    async fn restore_object(&self, key: &str) -> Result<String> {
+        R2Client::restore_object(self, key).await
+    }
+
+    async fn list_objects(&self, prefix: Option<&str>) -> Result<Vec<ObjectInfo>> {
+        R2Client::list_objects(self, prefix).await
+    }
+
+    async fn delete_objects(&self, keys: Vec<String>) -> Result<()> {
+        R2Client::delete_objects(self, keys).await
+    }
+
+    async fn try_delete_objects(&self, keys: Vec<String>) -> Result<Vec<DeleteOutcome>> {
+        R2Client::try_delete_objects(self, keys).await
+    }
+
+    async fn list_objects_delimited(
+        &self,
+        prefix: Option<&str>,
+        delimiter: &str,
+    ) -> Result<(Vec<ObjectInfo>, Vec<String>)> {
+        R2Client::list_objects_delimited(self, prefix, delimiter).await
+    }
+
+    async fn list_objects_paged(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        start_after: Option<&str>,
+        max_keys: usize,
+    ) -> Result<ObjectListing> {
+        R2Client::list_objects_paged(self, prefix, delimiter, start_after, max_keys).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        R2Client::delete_object(self, key).await
+    }
+
+    async fn object_exists(&self, key: &str) -> Result<bool> {
+        R2Client::object_exists(self, key).await
+    }
+
+    async fn head_object(&self, key: &str) -> Result<ObjectMetadata> {
+        R2Client::head_object(self, key).await
+    }
+
+    async fn copy_object(&self, source_key: &str, dest_key: &str) -> Result<()> {
+        R2Client::copy_object(self, source_key, dest_key).await
+    }
+
+    async fn copy_object_to(
+        &self,
+        source_key: &str,
+        dest_bucket: &str,
+        dest_key: &str,
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        R2Client::copy_object_to(self, source_key, dest_bucket, dest_key, content_type).await
+    }
+
+    async fn set_content_type(&self, key: &str, content_type: &str) -> Result<()> {
+        R2Client::set_content_type(self, key, content_type).await
+    }
+
+    async fn update_metadata(&self, key: &str, update: &MetadataUpdate) -> Result<()> {
+        R2Client::update_metadata(self, key, update).await
+    }
+
+    async fn change_storage_class(&self, key: &str, class: StorageClass) -> Result<()> {
+        R2Client::change_storage_class(self, key, class).await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: Option<&str>,
+    ) -> Result<String> {
+        R2Client::create_multipart_upload(self, key, content_type, content_disposition).await
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<CompletedPart> {
+        R2Client::upload_part(self, key, upload_id, part_number, body).await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> Result<()> {
+        R2Client::complete_multipart_upload(self, key, upload_id, parts).await
+    }
+
+    async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> Result<()> {
+        R2Client::abort_multipart_upload(self, key, upload_id).await
+    }
+
+    async fn list_multipart_uploads(&self) -> Result<Vec<MultipartUploadInfo>> {
+        R2Client::list_multipart_uploads(self).await
+    }
+
+    async fn multipart_upload_size(&self, key: &str, upload_id: &str) -> Result<u64> {
+        R2Client::multipart_upload_size(self, key, upload_id).await
+    }
+
+    fn detached_abort(&self, key: &str, upload_id: &str) -> Option<BoxFuture<'static, Result<()>>> {
+        Some(R2Client::detached_abort(self, key, upload_id))
+    }
+}
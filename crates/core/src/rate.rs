@@ -0,0 +1,229 @@
+//! Upload bandwidth limiting
+
+use crate::error::{Error, Result};
+use crate::filter::parse_size;
+use bytes::Bytes;
+use http_body::{Frame, SizeHint};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{Instant, Sleep};
+
+/// Bytes released at a time by a throttled body
+const THROTTLE_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Parse a rate like `5MB/s`, `500KB`, `1M/s` or `1048576` into bytes per second
+pub fn parse_rate(input: &str) -> Result<u64> {
+    let trimmed = input.trim();
+    let size = trimmed
+        .strip_suffix("/s")
+        .or_else(|| trimmed.strip_suffix("/S"))
+        .unwrap_or(trimmed);
+
+    let rate = parse_size(size)
+        .map_err(|_| Error::InvalidInput(format!("Invalid rate '{}' (e.g. 5MB/s)", input)))?;
+    if rate == 0 {
+        return Err(Error::InvalidInput(
+            "Rate must be greater than zero".to_string(),
+        ));
+    }
+    Ok(rate)
+}
+
+/// Token bucket shared by every upload of a client
+///
+/// Tokens are bytes, refilled at `rate` per second up to one second's worth.
+/// A request larger than what is available is still granted, leaving the
+/// bucket in debt, so callers just wait until the debt would be repaid.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: u64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `rate` bytes per second, starting full
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate: rate.max(1),
+            state: Mutex::new(BucketState {
+                tokens: rate.max(1) as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Take `bytes` at `now`, returning how long to wait before sending them
+    fn reserve_at(&self, bytes: u64, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let rate = self.rate as f64;
+
+        let elapsed = now.saturating_duration_since(state.updated).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(rate);
+        state.updated = now;
+        state.tokens -= bytes as f64;
+
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / rate)
+        }
+    }
+
+    /// Take `bytes` now, returning how long to wait before sending them
+    pub fn reserve(&self, bytes: u64) -> Duration {
+        self.reserve_at(bytes, Instant::now())
+    }
+
+    /// Wait until `bytes` may be sent
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = self.reserve(bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// In-memory request body released at the limiter's pace
+pub(crate) struct ThrottledBody {
+    data: Bytes,
+    limiter: Arc<RateLimiter>,
+    /// Size of the next chunk and the delay before it may be sent
+    pending: Option<(usize, Pin<Box<Sleep>>)>,
+}
+
+impl ThrottledBody {
+    pub(crate) fn new(data: Bytes, limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            data,
+            limiter,
+            pending: None,
+        }
+    }
+}
+
+impl http_body::Body for ThrottledBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Bytes>, Self::Error>>> {
+        let len = match self.pending.as_mut() {
+            Some((len, delay)) => {
+                let len = *len;
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.pending = None;
+                len
+            }
+            None => {
+                if self.data.is_empty() {
+                    return Poll::Ready(None);
+                }
+                let len = self.data.len().min(THROTTLE_CHUNK_SIZE);
+                let wait = self.limiter.reserve(len as u64);
+                if !wait.is_zero() {
+                    let mut delay = Box::pin(tokio::time::sleep(wait));
+                    if delay.as_mut().poll(cx).is_pending() {
+                        self.pending = Some((len, delay));
+                        return Poll::Pending;
+                    }
+                }
+                len
+            }
+        };
+
+        let chunk = self.data.split_to(len);
+        Poll::Ready(Some(Ok(Frame::data(chunk))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.data.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body::Body;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("5MB/s").unwrap(), 5 * 1024 * 1024);
+        assert_eq!(parse_rate("500KB").unwrap(), 500 * 1024);
+        assert_eq!(parse_rate(" 1m/s ").unwrap(), 1024 * 1024);
+        assert_eq!(parse_rate("1048576").unwrap(), 1048576);
+
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("5MB/min").is_err());
+    }
+
+    #[test]
+    fn test_token_bucket_pacing() {
+        let limiter = RateLimiter::new(1000);
+        let start = Instant::now();
+        *limiter.state.lock().unwrap() = BucketState {
+            tokens: 1000.0,
+            updated: start,
+        };
+
+        // The first second's worth goes out at once
+        assert_eq!(limiter.reserve_at(1000, start), Duration::ZERO);
+
+        // Then each byte costs 1ms, accumulating while the caller waits
+        assert_eq!(limiter.reserve_at(500, start), Duration::from_millis(500));
+        assert_eq!(limiter.reserve_at(500, start), Duration::from_secs(1));
+
+        // Time pays the debt back
+        let later = start + Duration::from_secs(1);
+        assert_eq!(limiter.reserve_at(0, later), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(250, later), Duration::from_millis(250));
+
+        // An idle period refills at most one second's worth
+        let idle = later + Duration::from_secs(60);
+        assert_eq!(limiter.reserve_at(1000, idle), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(100, idle), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_throttled_body_paces_chunks() {
+        // A 10-chunk burst, then 2 more chunks at 10 chunks per second
+        let limiter = Arc::new(RateLimiter::new(THROTTLE_CHUNK_SIZE as u64 * 10));
+        let data = Bytes::from(vec![7u8; THROTTLE_CHUNK_SIZE * 12]);
+        let mut body = ThrottledBody::new(data.clone(), limiter);
+        assert_eq!(body.size_hint().exact(), Some(data.len() as u64));
+
+        let start = Instant::now();
+        let mut received = Vec::new();
+        while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await
+        {
+            received.extend_from_slice(&frame.unwrap().into_data().unwrap());
+        }
+
+        assert_eq!(received, data);
+        assert!(body.is_end_stream());
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+    }
+}
@@ -0,0 +1,1253 @@
+//! Sync a local directory to a prefix in a bucket
+
+use crate::client::{ObjectInfo, UploadOptions};
+use crate::config::DeployRule;
+use crate::error::{Error, Result};
+use crate::filter::KeyGlob;
+use crate::operations::{list_objects_concurrent, R2Operations};
+use futures::stream::{self, Stream, StreamExt};
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::io::AsyncReadExt;
+use tokio_util::sync::CancellationToken;
+use walkdir::WalkDir;
+
+/// A file found in the local directory
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    /// Path relative to the synced directory, with `/` separators
+    pub relative_path: String,
+    /// Full path on disk
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Compiled `[deploy.rules]`: headers to set per glob in multi-file uploads
+///
+/// Precedence, per header: an explicit value in the [`UploadOptions`]
+/// (`--header`) wins, then the most specific matching rule (the longest
+/// pattern, ties broken alphabetically), then the Content-Type guessed from
+/// the extension. Fields a rule leaves unset fall through to the next
+/// matching rule.
+#[derive(Debug, Clone, Default)]
+pub struct DeployRules {
+    /// Most specific pattern first
+    rules: Vec<(KeyGlob, DeployRule)>,
+}
+
+impl DeployRules {
+    /// Compile rules keyed by glob pattern
+    pub fn new(rules: &BTreeMap<String, DeployRule>) -> Result<Self> {
+        let mut compiled = rules
+            .iter()
+            .map(|(pattern, rule)| {
+                for value in [&rule.cache_control, &rule.content_type]
+                    .into_iter()
+                    .flatten()
+                {
+                    if value.trim().is_empty() || value.chars().any(|c| c.is_control()) {
+                        return Err(Error::InvalidInput(format!(
+                            "Invalid header value in deploy rule '{}'",
+                            pattern
+                        )));
+                    }
+                }
+                Ok((KeyGlob::new(pattern)?, rule.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // BTreeMap order makes ties alphabetical; the sort is stable
+        compiled.sort_by_key(|(glob, _)| std::cmp::Reverse(glob.pattern().len()));
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Headers the rules give `relative_path`
+    pub fn resolve(&self, relative_path: &str) -> DeployRule {
+        let mut resolved = DeployRule::default();
+
+        for (glob, rule) in &self.rules {
+            if !glob.is_match(relative_path) {
+                continue;
+            }
+            if resolved.cache_control.is_none() {
+                resolved.cache_control = rule.cache_control.clone();
+            }
+            if resolved.content_type.is_none() {
+                resolved.content_type = rule.content_type.clone();
+            }
+        }
+
+        resolved
+    }
+}
+
+/// Options for `sync_directory`
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Compare content (local MD5 vs ETag) when sizes match
+    pub checksum: bool,
+    /// Number of files compared/uploaded at once
+    pub concurrency: usize,
+    /// Cancelling stops starting new files; in-flight ones finish
+    pub cancel: CancellationToken,
+    /// Also delete remote objects under the prefix that have no local file
+    pub delete: bool,
+    /// Options applied to every upload (content disposition, ...)
+    pub upload: UploadOptions,
+    /// Correct the Content-Type of unchanged objects that differ from the guess
+    pub fix_content_type: bool,
+    /// Upload the targets of symlinks instead of skipping them
+    pub follow_symlinks: bool,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            checksum: false,
+            concurrency: 5,
+            cancel: CancellationToken::new(),
+            delete: false,
+            upload: UploadOptions::default(),
+            fix_content_type: false,
+            follow_symlinks: false,
+        }
+    }
+}
+
+/// Why a file is uploaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadReason {
+    /// Not present remotely
+    New,
+    /// Size differs
+    SizeChanged,
+    /// Same size, different MD5
+    ChecksumChanged,
+    /// Same size, multipart ETag, local file newer than the remote object
+    Newer,
+}
+
+impl UploadReason {
+    /// Short description for reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UploadReason::New => "new",
+            UploadReason::SizeChanged => "size changed",
+            UploadReason::ChecksumChanged => "content changed",
+            UploadReason::Newer => "newer",
+        }
+    }
+}
+
+/// A local file the sync would upload
+#[derive(Debug, Clone)]
+pub struct PlannedUpload {
+    pub key: String,
+    pub local: LocalFile,
+    pub reason: UploadReason,
+}
+
+/// An unchanged object whose stored Content-Type differs from the expected one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentTypeFix {
+    pub key: String,
+    /// Content-Type currently stored
+    pub from: String,
+    /// Content-Type guessed from the file name
+    pub to: String,
+}
+
+/// What syncing a directory would change, computed without touching the bucket
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// New and changed files
+    pub uploads: Vec<PlannedUpload>,
+    /// Keys already up to date
+    pub unchanged: Vec<String>,
+    /// Remote keys without a local file (only with `SyncOptions::delete`)
+    pub deletes: Vec<String>,
+    /// Unchanged objects to re-type in place (only with `SyncOptions::fix_content_type`)
+    pub content_type_fixes: Vec<ContentTypeFix>,
+    /// Files that couldn't be compared (e.g. unreadable for the checksum)
+    pub failed: Vec<(String, Error)>,
+}
+
+impl SyncPlan {
+    /// Whether the sync would upload or delete anything
+    pub fn has_changes(&self) -> bool {
+        !self.uploads.is_empty() || !self.deletes.is_empty() || !self.content_type_fixes.is_empty()
+    }
+}
+
+/// Result of syncing one file
+#[derive(Debug)]
+pub enum SyncStatus {
+    Uploaded(UploadReason),
+    Unchanged,
+    Deleted,
+    /// Content-Type replaced in place
+    ContentTypeFixed {
+        from: String,
+        to: String,
+    },
+    Failed(Error),
+}
+
+/// Outcome for one local file
+#[derive(Debug)]
+pub struct SyncOutcome {
+    pub key: String,
+    pub status: SyncStatus,
+}
+
+/// Outcome of uploading one file unconditionally
+#[derive(Debug)]
+pub struct UploadOutcome {
+    pub key: String,
+    pub path: PathBuf,
+    /// Local file size when the upload started
+    pub size: u64,
+    pub result: Result<()>,
+}
+
+/// Characters that make a path or key a glob
+const GLOB_WILDCARDS: [char; 4] = ['*', '?', '[', '{'];
+
+/// Whether a path or key contains glob wildcards
+pub fn has_glob_wildcards(pattern: &str) -> bool {
+    pattern.contains(GLOB_WILDCARDS)
+}
+
+/// Whether a local path is a glob to expand
+///
+/// A path that exists is taken literally, so `report[1].pdf` uploads as the
+/// file of that name instead of matching `report1.pdf`.
+pub fn is_glob_pattern(pattern: &str) -> bool {
+    has_glob_wildcards(pattern) && !Path::new(pattern).exists()
+}
+
+/// Expand a local glob such as `dist/**/*.js`
+///
+/// Files are walked from the literal directory before the first wildcard
+/// (`dist`), and their `relative_path` is relative to that directory so the
+/// structure below it is preserved. Symlinks are handled as in [`scan_local_dir`].
+pub fn expand_local_glob(pattern: &str, follow_symlinks: bool) -> Result<Vec<LocalFile>> {
+    let wildcard = pattern.find(GLOB_WILDCARDS).unwrap_or(pattern.len());
+    let (base, rest) = match pattern[..wildcard].rfind('/') {
+        Some(slash) => (&pattern[..slash], &pattern[slash + 1..]),
+        None => (".", pattern),
+    };
+    let base = if base.is_empty() { "/" } else { base };
+
+    let glob = KeyGlob::new(rest)?;
+    let dir = Path::new(base);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    Ok(scan_local_dir(dir, follow_symlinks)?
+        .into_iter()
+        .filter(|f| glob.is_match(&f.relative_path))
+        .collect())
+}
+
+/// Upload files under `prefix` (keeping their relative paths), concurrently
+///
+/// A failure on one file doesn't stop the others. Once `cancel` fires no new
+/// upload starts; the outcomes only cover files that were attempted.
+pub async fn upload_local_files(
+    ops: &dyn R2Operations,
+    files: Vec<LocalFile>,
+    prefix: &str,
+    options: &UploadOptions,
+    concurrency: usize,
+    cancel: &CancellationToken,
+) -> Vec<UploadOutcome> {
+    upload_local_files_stream(ops, files, prefix, options, concurrency, cancel)
+        .collect()
+        .await
+}
+
+/// Like [`upload_local_files`], yielding each outcome as soon as its upload ends
+pub(crate) fn upload_local_files_stream<'a>(
+    ops: &'a dyn R2Operations,
+    files: Vec<LocalFile>,
+    prefix: &'a str,
+    options: &'a UploadOptions,
+    concurrency: usize,
+    cancel: &'a CancellationToken,
+) -> impl Stream<Item = UploadOutcome> + 'a {
+    stream::iter(files)
+        .take_until(cancel.cancelled())
+        .map(move |local| async move {
+            let key = sync_key(prefix, &local.relative_path);
+            let result = upload_local_file(ops, &key, &local, options).await;
+            UploadOutcome {
+                key,
+                path: local.path,
+                size: local.size,
+                result,
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+/// Upload one local file with a content type guessed from its name
+async fn upload_local_file(
+    ops: &dyn R2Operations,
+    key: &str,
+    local: &LocalFile,
+    options: &UploadOptions,
+) -> Result<()> {
+    let rule = options.deploy_rules.resolve(&local.relative_path);
+
+    // A Content-Type in `options` still replaces this one when uploading
+    let content_type = rule.content_type.unwrap_or_else(|| {
+        mime_guess::from_path(&local.path)
+            .first_or_octet_stream()
+            .to_string()
+    });
+    let options = match rule.cache_control {
+        Some(cache_control) if options.cache_control.is_none() => Cow::Owned(UploadOptions {
+            cache_control: Some(cache_control),
+            ..options.clone()
+        }),
+        _ => Cow::Borrowed(options),
+    };
+
+    ops.upload_file_with_options(key, &local.path, &content_type, &options)
+        .await
+}
+
+/// The Content-Type to store instead of `stored` for `key`, if they disagree
+///
+/// Only the media type is compared (parameters such as `charset` are
+/// ignored), and names without a known extension are never corrected.
+pub fn content_type_fix(key: &str, stored: &str) -> Option<String> {
+    let expected = mime_guess::from_path(key).first()?;
+    let stored_essence = stored.split(';').next().unwrap_or("").trim();
+
+    (!stored_essence.eq_ignore_ascii_case(expected.essence_str())).then(|| expected.to_string())
+}
+
+/// List the regular files under `dir`
+///
+/// Symlinks are skipped unless `follow_symlinks` is set, in which case the
+/// files they point to are listed under the link's path. A directory link
+/// pointing back to one of its ancestors is reported as an error instead of
+/// being walked forever; dangling links are skipped with a warning.
+pub fn scan_local_dir(dir: &Path, follow_symlinks: bool) -> Result<Vec<LocalFile>> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(dir)
+        .follow_links(follow_symlinks)
+        .sort_by_file_name()
+    {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                if let (Some(path), Some(ancestor)) = (e.path(), e.loop_ancestor()) {
+                    return Err(Error::InvalidInput(format!(
+                        "Symlink loop: {} points back to {}",
+                        path.display(),
+                        ancestor.display()
+                    )));
+                }
+                match e.path() {
+                    Some(path) if follow_symlinks && path.is_symlink() && !path.exists() => {
+                        tracing::warn!(path = %path.display(), "skipping dangling symlink");
+                        continue;
+                    }
+                    _ => return Err(Error::Io(e.into())),
+                }
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let metadata = entry.metadata().map_err(|e| Error::Io(e.into()))?;
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .map_err(|e| Error::Other(e.to_string()))?;
+        let relative_path = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        files.push(LocalFile {
+            relative_path,
+            path: entry.path().to_path_buf(),
+            size: metadata.len(),
+            modified: metadata.modified().map_err(Error::Io)?,
+        });
+    }
+
+    Ok(files)
+}
+
+/// Remote key for a relative path under `prefix`
+pub fn sync_key(prefix: &str, relative_path: &str) -> String {
+    if prefix.is_empty() || prefix.ends_with('/') {
+        format!("{}{}", prefix, relative_path)
+    } else {
+        format!("{}/{}", prefix, relative_path)
+    }
+}
+
+/// Whether an ETag comes from a multipart upload (`"<md5>-<parts>"`), i.e. isn't a plain MD5
+pub fn is_multipart_etag(etag: &str) -> bool {
+    etag.trim_matches('"').contains('-')
+}
+
+/// Hex MD5 of a local file, as S3 computes it for single-part objects
+pub async fn local_md5(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await.map_err(Error::Io)?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = file.read(&mut buffer).await.map_err(Error::Io)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Hex SHA-256 of a local file
+pub async fn local_sha256(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await.map_err(Error::Io)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = file.read(&mut buffer).await.map_err(Error::Io)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Content-addressed key of a local file: its SHA-256 under `prefix`
+///
+/// The same content always maps to the same key, so an upload can be
+/// skipped (or retried) safely when the key already exists.
+pub async fn content_addressed_key(path: &Path, prefix: &str) -> Result<String> {
+    Ok(sync_key(prefix, &local_sha256(path).await?))
+}
+
+/// Decide whether a local file must be uploaded, `None` meaning unchanged
+///
+/// Sizes are compared first. With `checksum`, same-size files are compared by
+/// MD5 against the ETag; multipart ETags aren't plain MD5s, so those fall back
+/// to the modification time.
+pub async fn upload_reason(
+    local: &LocalFile,
+    remote: Option<&ObjectInfo>,
+    checksum: bool,
+) -> Result<Option<UploadReason>> {
+    let Some(remote) = remote else {
+        return Ok(Some(UploadReason::New));
+    };
+
+    if local.size != remote.size as u64 {
+        return Ok(Some(UploadReason::SizeChanged));
+    }
+
+    if !checksum {
+        return Ok(None);
+    }
+
+    if is_multipart_etag(&remote.etag) {
+        // Without a remote date the object can't be proven current
+        let local_secs = aws_smithy_types::DateTime::from(local.modified).secs();
+        let newer = remote
+            .last_modified
+            .is_none_or(|remote_modified| local_secs > remote_modified.secs());
+        return Ok(newer.then_some(UploadReason::Newer));
+    }
+
+    let md5 = local_md5(&local.path).await?;
+    Ok((md5 != remote.etag.trim_matches('"')).then_some(UploadReason::ChecksumChanged))
+}
+
+/// Compare `dir` with the objects under `prefix` and decide what to upload or delete
+///
+/// Listing failures abort the plan; a file that can't be compared is recorded
+/// in `failed` and the others are still planned.
+///
+/// `prefix` is treated as a folder: `site` covers `site/...` but not
+/// `site-backup/...`. Deleting requires a prefix, so a sync can never empty
+/// the whole bucket.
+pub async fn compute_sync_plan(
+    ops: &dyn R2Operations,
+    dir: &Path,
+    prefix: &str,
+    options: &SyncOptions,
+) -> Result<SyncPlan> {
+    let scope = sync_key(prefix, "");
+    if options.delete && scope.is_empty() {
+        return Err(Error::InvalidInput(
+            "Deleting during a sync requires a remote prefix (refusing to delete across the whole bucket)"
+                .to_string(),
+        ));
+    }
+
+    let local_files = scan_local_dir(dir, options.follow_symlinks)?;
+
+    let list_prefix = (!scope.is_empty()).then_some(scope.as_str());
+    let mut remote: HashMap<String, ObjectInfo> =
+        list_objects_concurrent(ops, list_prefix, options.concurrency)
+            .await?
+            .into_iter()
+            .map(|o| (o.key.clone(), o))
+            .collect();
+
+    let remote_ref = &remote;
+    let compared = stream::iter(local_files)
+        .map(|local| async move {
+            let key = sync_key(prefix, &local.relative_path);
+            let reason = upload_reason(&local, remote_ref.get(&key), options.checksum).await;
+            (key, local, reason)
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut plan = SyncPlan::default();
+    for (key, local, reason) in compared {
+        remote.remove(&key);
+        match reason {
+            Ok(Some(reason)) => plan.uploads.push(PlannedUpload { key, local, reason }),
+            Ok(None) => plan.unchanged.push(key),
+            Err(e) => plan.failed.push((key, e)),
+        }
+    }
+    if options.delete {
+        // Only ever delete inside the synced folder, whatever the listing returned
+        plan.deletes = remote
+            .into_keys()
+            .filter(|key| key.starts_with(&scope))
+            .collect();
+    }
+
+    if options.fix_content_type {
+        // Listings don't carry the Content-Type, so unchanged objects are
+        // checked one by one (only those with a recognised extension)
+        let candidates = std::mem::take(&mut plan.unchanged);
+        let scope = scope.as_str();
+        let checked = stream::iter(candidates)
+            .map(|key| async move {
+                // Deploy rules choose the Content-Type of their files
+                let relative = key.strip_prefix(scope).unwrap_or(&key);
+                if mime_guess::from_path(&key).first().is_none()
+                    || options
+                        .upload
+                        .deploy_rules
+                        .resolve(relative)
+                        .content_type
+                        .is_some()
+                {
+                    return (key, Ok(None));
+                }
+                let fix = ops.head_object(&key).await.map(|metadata| {
+                    content_type_fix(&key, &metadata.content_type).map(|to| ContentTypeFix {
+                        key: key.clone(),
+                        from: metadata.content_type,
+                        to,
+                    })
+                });
+                (key, fix)
+            })
+            .buffer_unordered(options.concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+
+        for (key, fix) in checked {
+            match fix {
+                Ok(Some(fix)) => plan.content_type_fixes.push(fix),
+                Ok(None) => plan.unchanged.push(key),
+                Err(e) => plan.failed.push((key, e)),
+            }
+        }
+    }
+
+    plan.uploads.sort_by(|a, b| a.key.cmp(&b.key));
+    plan.unchanged.sort();
+    plan.deletes.sort();
+    plan.content_type_fixes.sort_by(|a, b| a.key.cmp(&b.key));
+    plan.failed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(plan)
+}
+
+/// One change applied by `execute_sync_plan`
+enum SyncTask {
+    Upload(PlannedUpload),
+    Delete(String),
+    FixContentType(ContentTypeFix),
+}
+
+/// Carry out a plan: upload new/changed files, delete orphaned objects and
+/// correct content types, concurrently
+///
+/// A failure on one file doesn't stop the others. Once `options.cancel` fires
+/// no new upload or delete is started, so the outcomes only cover the files
+/// that were processed (plus the unchanged and uncomparable ones).
+pub async fn execute_sync_plan(
+    ops: &dyn R2Operations,
+    plan: SyncPlan,
+    options: &SyncOptions,
+) -> Vec<SyncOutcome> {
+    let mut outcomes: Vec<SyncOutcome> = plan
+        .unchanged
+        .into_iter()
+        .map(|key| SyncOutcome {
+            key,
+            status: SyncStatus::Unchanged,
+        })
+        .chain(plan.failed.into_iter().map(|(key, e)| SyncOutcome {
+            key,
+            status: SyncStatus::Failed(e),
+        }))
+        .collect();
+
+    let tasks = plan
+        .uploads
+        .into_iter()
+        .map(SyncTask::Upload)
+        .chain(plan.deletes.into_iter().map(SyncTask::Delete))
+        .chain(
+            plan.content_type_fixes
+                .into_iter()
+                .map(SyncTask::FixContentType),
+        );
+
+    let processed = stream::iter(tasks)
+        .take_until(options.cancel.cancelled())
+        .map(|task| async move {
+            match task {
+                SyncTask::Upload(upload) => {
+                    let uploaded =
+                        upload_local_file(ops, &upload.key, &upload.local, &options.upload).await;
+                    let status = match uploaded {
+                        Ok(()) => SyncStatus::Uploaded(upload.reason),
+                        Err(e) => SyncStatus::Failed(e),
+                    };
+                    SyncOutcome {
+                        key: upload.key,
+                        status,
+                    }
+                }
+                SyncTask::Delete(key) => {
+                    let status = match ops.delete_object(&key).await {
+                        Ok(()) => SyncStatus::Deleted,
+                        Err(e) => SyncStatus::Failed(e),
+                    };
+                    SyncOutcome { key, status }
+                }
+                SyncTask::FixContentType(fix) => {
+                    let status = match ops.set_content_type(&fix.key, &fix.to).await {
+                        Ok(()) => SyncStatus::ContentTypeFixed {
+                            from: fix.from,
+                            to: fix.to,
+                        },
+                        Err(e) => SyncStatus::Failed(e),
+                    };
+                    SyncOutcome {
+                        key: fix.key,
+                        status,
+                    }
+                }
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+    outcomes.extend(processed);
+
+    outcomes
+}
+
+/// Upload new and changed files from `dir` to `prefix` (and delete orphans with
+/// `options.delete`), i.e. [`compute_sync_plan`] followed by [`execute_sync_plan`]
+pub async fn sync_directory(
+    ops: &dyn R2Operations,
+    dir: &Path,
+    prefix: &str,
+    options: &SyncOptions,
+) -> Result<Vec<SyncOutcome>> {
+    let plan = compute_sync_plan(ops, dir, prefix, options).await?;
+    Ok(execute_sync_plan(ops, plan, options).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockR2;
+
+    fn write(dir: &Path, relative: &str, body: &[u8]) {
+        let path = dir.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    fn status_of<'a>(outcomes: &'a [SyncOutcome], key: &str) -> &'a SyncStatus {
+        &outcomes.iter().find(|o| o.key == key).unwrap().status
+    }
+
+    #[test]
+    fn test_sync_key() {
+        assert_eq!(sync_key("", "a/b.txt"), "a/b.txt");
+        assert_eq!(sync_key("site/", "a/b.txt"), "site/a/b.txt");
+        assert_eq!(sync_key("site", "a/b.txt"), "site/a/b.txt");
+    }
+
+    #[test]
+    fn test_is_multipart_etag() {
+        assert!(is_multipart_etag("\"d41d8cd98f00b204e9800998ecf8427e-3\""));
+        assert!(!is_multipart_etag("\"d41d8cd98f00b204e9800998ecf8427e\""));
+    }
+
+    #[tokio::test]
+    async fn test_local_md5() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "empty", b"");
+
+        assert_eq!(
+            local_md5(&dir.path().join("empty")).await.unwrap(),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_content_addressed_key() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "logo.png", b"abc");
+        write(dir.path(), "copy.png", b"abc");
+        let path = dir.path().join("logo.png");
+
+        let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(local_sha256(&path).await.unwrap(), hash);
+        assert_eq!(content_addressed_key(&path, "").await.unwrap(), hash);
+        assert_eq!(
+            content_addressed_key(&path, "assets").await.unwrap(),
+            format!("assets/{}", hash)
+        );
+        assert_eq!(
+            content_addressed_key(&dir.path().join("copy.png"), "assets/")
+                .await
+                .unwrap(),
+            format!("assets/{}", hash)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_uploads_new_and_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "index.html", b"<html>");
+        write(dir.path(), "css/app.css", b"body{}");
+        write(dir.path(), "same.txt", b"aaaa");
+        write(dir.path(), "edited.txt", b"bbbb");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("site/css/app.css", b"old".to_vec(), "text/css");
+        mock.insert("site/same.txt", b"aaaa".to_vec(), "text/plain");
+        mock.insert("site/edited.txt", b"cccc".to_vec(), "text/plain");
+
+        // Size-only: the same-size edit is missed
+        let outcomes = sync_directory(&mock, dir.path(), "site/", &SyncOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(outcomes.len(), 4);
+        assert!(matches!(
+            status_of(&outcomes, "site/index.html"),
+            SyncStatus::Uploaded(UploadReason::New)
+        ));
+        assert!(matches!(
+            status_of(&outcomes, "site/css/app.css"),
+            SyncStatus::Uploaded(UploadReason::SizeChanged)
+        ));
+        assert!(matches!(
+            status_of(&outcomes, "site/edited.txt"),
+            SyncStatus::Unchanged
+        ));
+
+        // Checksum: the same-size edit is caught
+        let options = SyncOptions {
+            checksum: true,
+            ..Default::default()
+        };
+        let outcomes = sync_directory(&mock, dir.path(), "site/", &options)
+            .await
+            .unwrap();
+        assert!(matches!(
+            status_of(&outcomes, "site/edited.txt"),
+            SyncStatus::Uploaded(UploadReason::ChecksumChanged)
+        ));
+        assert!(matches!(
+            status_of(&outcomes, "site/same.txt"),
+            SyncStatus::Unchanged
+        ));
+        assert_eq!(mock.get("site/edited.txt").unwrap().body, b"bbbb");
+    }
+
+    #[test]
+    fn test_content_type_fix() {
+        assert_eq!(
+            content_type_fix("site/app.js", "text/plain").as_deref(),
+            Some("text/javascript")
+        );
+        assert_eq!(
+            content_type_fix("logo.svg", "application/octet-stream").as_deref(),
+            Some("image/svg+xml")
+        );
+        assert_eq!(content_type_fix("app.css", "text/css; charset=utf-8"), None);
+        assert_eq!(content_type_fix("app.css", "TEXT/CSS"), None);
+        // Unknown extensions are left alone
+        assert_eq!(content_type_fix("LICENSE", "text/plain"), None);
+    }
+
+    #[tokio::test]
+    async fn test_sync_fixes_content_type_of_unchanged_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "app.js", b"let a;");
+        write(dir.path(), "app.css", b"body{}");
+        write(dir.path(), "README", b"read me");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("app.js", b"let a;".to_vec(), "text/plain");
+        mock.insert("app.css", b"body{}".to_vec(), "text/css");
+        mock.insert("README", b"read me".to_vec(), "text/plain");
+
+        // Off by default
+        let plan = compute_sync_plan(&mock, dir.path(), "", &SyncOptions::default())
+            .await
+            .unwrap();
+        assert!(plan.content_type_fixes.is_empty());
+        assert!(!plan.has_changes());
+
+        let options = SyncOptions {
+            fix_content_type: true,
+            ..Default::default()
+        };
+        let plan = compute_sync_plan(&mock, dir.path(), "", &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            plan.content_type_fixes,
+            vec![ContentTypeFix {
+                key: "app.js".to_string(),
+                from: "text/plain".to_string(),
+                to: "text/javascript".to_string(),
+            }]
+        );
+        assert_eq!(plan.unchanged, vec!["README", "app.css"]);
+
+        let outcomes = execute_sync_plan(&mock, plan, &options).await;
+        assert!(matches!(
+            status_of(&outcomes, "app.js"),
+            SyncStatus::ContentTypeFixed { from, to }
+                if from == "text/plain" && to == "text/javascript"
+        ));
+        let fixed = mock.get("app.js").unwrap();
+        assert_eq!(fixed.content_type, "text/javascript");
+        assert_eq!(fixed.body, b"let a;");
+    }
+
+    #[tokio::test]
+    async fn test_checksum_falls_back_to_mtime_for_multipart_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "big.bin", b"data");
+        let local = scan_local_dir(dir.path(), false).unwrap().remove(0);
+
+        let mut remote = ObjectInfo {
+            key: "big.bin".to_string(),
+            size: 4,
+            last_modified: Some(aws_smithy_types::DateTime::from_secs(0)),
+            etag: "\"0123456789abcdef0123456789abcdef-2\"".to_string(),
+        };
+
+        // Remote older than the local file: upload
+        assert_eq!(
+            upload_reason(&local, Some(&remote), true).await.unwrap(),
+            Some(UploadReason::Newer)
+        );
+
+        // Remote newer: keep
+        remote.last_modified = Some(aws_smithy_types::DateTime::from(
+            SystemTime::now() + std::time::Duration::from_secs(60),
+        ));
+        assert_eq!(
+            upload_reason(&local, Some(&remote), true).await.unwrap(),
+            None
+        );
+
+        // Remote date unknown: upload
+        remote.last_modified = None;
+        assert_eq!(
+            upload_reason(&local, Some(&remote), true).await.unwrap(),
+            Some(UploadReason::Newer)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_local_dir_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        write(dir.path(), "site/index.html", b"<html>");
+        write(shared.path(), "logo.png", b"png");
+        symlink(shared.path(), dir.path().join("site/assets")).unwrap();
+        symlink(
+            shared.path().join("logo.png"),
+            dir.path().join("site/favicon.png"),
+        )
+        .unwrap();
+        symlink(dir.path().join("missing"), dir.path().join("site/dangling")).unwrap();
+
+        let paths = |files: Vec<LocalFile>| -> Vec<String> {
+            files.into_iter().map(|f| f.relative_path).collect()
+        };
+
+        // Skipped by default
+        let files = scan_local_dir(dir.path(), false).unwrap();
+        assert_eq!(paths(files), vec!["site/index.html"]);
+
+        // Followed, under the link's path
+        let files = scan_local_dir(dir.path(), true).unwrap();
+        assert_eq!(
+            paths(files),
+            vec![
+                "site/assets/logo.png",
+                "site/favicon.png",
+                "site/index.html"
+            ]
+        );
+
+        // A link back to an ancestor fails instead of recursing forever
+        symlink(dir.path().join("site"), dir.path().join("site/loop")).unwrap();
+        let err = scan_local_dir(dir.path(), true).unwrap_err();
+        assert!(err.to_string().contains("Symlink loop"), "{}", err);
+        assert_eq!(scan_local_dir(dir.path(), false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_expand_local_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "dist/app.js", b"a");
+        write(dir.path(), "dist/vendor/lib.js", b"b");
+        write(dir.path(), "dist/style.css", b"c");
+
+        let pattern = format!("{}/dist/**/*.js", dir.path().display());
+        let files: Vec<String> = expand_local_glob(&pattern, false)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect();
+        assert_eq!(files, vec!["app.js", "vendor/lib.js"]);
+
+        let pattern = format!("{}/dist/*.js", dir.path().display());
+        assert_eq!(expand_local_glob(&pattern, false).unwrap().len(), 1);
+
+        let pattern = format!("{}/missing/*.js", dir.path().display());
+        assert!(expand_local_glob(&pattern, false).unwrap().is_empty());
+
+        assert!(is_glob_pattern("dist/**/*.js"));
+        assert!(!is_glob_pattern("dist/app.js"));
+    }
+
+    #[test]
+    fn test_existing_path_is_not_a_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "report[1].pdf", b"a");
+
+        let existing = format!("{}/report[1].pdf", dir.path().display());
+        assert!(has_glob_wildcards(&existing));
+        assert!(!is_glob_pattern(&existing));
+
+        let missing = format!("{}/report[2].pdf", dir.path().display());
+        assert!(is_glob_pattern(&missing));
+    }
+
+    #[tokio::test]
+    async fn test_upload_local_files_preserves_structure() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "dist/app.js", b"a");
+        write(dir.path(), "dist/vendor/lib.js", b"b");
+
+        let files =
+            expand_local_glob(&format!("{}/dist/**/*.js", dir.path().display()), false).unwrap();
+        let mock = MockR2::new("test-bucket");
+
+        let outcomes = upload_local_files(
+            &mock,
+            files,
+            "assets/",
+            &UploadOptions::default(),
+            2,
+            &CancellationToken::new(),
+        )
+        .await;
+
+        assert!(outcomes.iter().all(|o| o.result.is_ok()));
+        assert_eq!(mock.keys(), vec!["assets/app.js", "assets/vendor/lib.js"]);
+        assert_eq!(
+            mock.get("assets/app.js").unwrap().content_type,
+            "text/javascript"
+        );
+    }
+
+    fn deploy_rules(rules: &[(&str, Option<&str>, Option<&str>)]) -> DeployRules {
+        let rules = rules
+            .iter()
+            .map(|(pattern, cache_control, content_type)| {
+                (
+                    pattern.to_string(),
+                    DeployRule {
+                        cache_control: cache_control.map(str::to_string),
+                        content_type: content_type.map(str::to_string),
+                    },
+                )
+            })
+            .collect();
+        DeployRules::new(&rules).unwrap()
+    }
+
+    #[test]
+    fn test_deploy_rules_precedence() {
+        let rules = deploy_rules(&[
+            ("**", Some("max-age=300"), None),
+            ("**/*.html", Some("no-cache"), None),
+            ("assets/**", Some("max-age=31536000, immutable"), None),
+            ("feed", None, Some("application/rss+xml")),
+        ]);
+
+        let resolve = |path| rules.resolve(path).cache_control.unwrap();
+        assert_eq!(resolve("robots.txt"), "max-age=300");
+        assert_eq!(resolve("index.html"), "no-cache");
+        assert_eq!(resolve("docs/index.html"), "no-cache");
+        assert_eq!(resolve("assets/app.3f2a.js"), "max-age=31536000, immutable");
+        // Equal length: alphabetical order decides
+        assert_eq!(resolve("assets/x.html"), "no-cache");
+
+        // Unset fields fall through to less specific rules
+        let feed = rules.resolve("feed");
+        assert_eq!(feed.content_type.as_deref(), Some("application/rss+xml"));
+        assert_eq!(feed.cache_control.as_deref(), Some("max-age=300"));
+
+        assert!(DeployRules::default()
+            .resolve("a.txt")
+            .cache_control
+            .is_none());
+
+        let mut invalid = BTreeMap::new();
+        invalid.insert(
+            "*.js".to_string(),
+            DeployRule {
+                cache_control: Some("max-age=60\n".to_string()),
+                content_type: None,
+            },
+        );
+        assert!(DeployRules::new(&invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_local_files_applies_deploy_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "index.html", b"<html>");
+        write(dir.path(), "assets/app.js", b"a");
+        write(dir.path(), "feed", b"<rss>");
+
+        let mock = MockR2::new("test-bucket");
+        let options = UploadOptions {
+            deploy_rules: deploy_rules(&[
+                ("*.html", Some("no-cache"), None),
+                ("assets/**", Some("immutable"), None),
+                ("feed", None, Some("application/rss+xml")),
+            ]),
+            ..Default::default()
+        };
+
+        let files = scan_local_dir(dir.path(), false).unwrap();
+        let outcomes =
+            upload_local_files(&mock, files, "", &options, 2, &CancellationToken::new()).await;
+        assert!(outcomes.iter().all(|o| o.result.is_ok()));
+
+        let html = mock.get("index.html").unwrap();
+        assert_eq!(html.cache_control.as_deref(), Some("no-cache"));
+        assert_eq!(html.content_type, "text/html");
+        assert_eq!(
+            mock.get("assets/app.js").unwrap().cache_control.as_deref(),
+            Some("immutable")
+        );
+        let feed = mock.get("feed").unwrap();
+        assert_eq!(feed.content_type, "application/rss+xml");
+        assert!(feed.cache_control.is_none());
+
+        // An explicit header beats the rules
+        let options = UploadOptions {
+            cache_control: Some("max-age=60".to_string()),
+            ..options
+        };
+        let files = scan_local_dir(dir.path(), false).unwrap();
+        upload_local_files(&mock, files, "", &options, 2, &CancellationToken::new()).await;
+        assert_eq!(
+            mock.get("index.html").unwrap().cache_control.as_deref(),
+            Some("max-age=60")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compute_sync_plan_makes_no_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "index.html", b"<html>");
+        write(dir.path(), "same.txt", b"aaaa");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("site/same.txt", b"aaaa".to_vec(), "text/plain");
+        mock.insert("site/old.txt", b"gone".to_vec(), "text/plain");
+
+        let plan = compute_sync_plan(&mock, dir.path(), "site/", &SyncOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(plan.uploads.len(), 1);
+        assert_eq!(plan.uploads[0].key, "site/index.html");
+        assert_eq!(plan.uploads[0].reason, UploadReason::New);
+        assert_eq!(plan.unchanged, vec!["site/same.txt"]);
+        // Deletions are only planned when requested
+        assert!(plan.deletes.is_empty());
+
+        let options = SyncOptions {
+            delete: true,
+            ..Default::default()
+        };
+        let plan = compute_sync_plan(&mock, dir.path(), "site/", &options)
+            .await
+            .unwrap();
+        assert_eq!(plan.deletes, vec!["site/old.txt"]);
+        assert!(plan.has_changes());
+
+        // Planning alone leaves the bucket untouched
+        assert_eq!(mock.keys(), vec!["site/old.txt", "site/same.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_deletes_orphans_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "keep.txt", b"keep");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("site/keep.txt", b"keep".to_vec(), "text/plain");
+        mock.insert("site/old.txt", b"gone".to_vec(), "text/plain");
+
+        let options = SyncOptions {
+            delete: true,
+            ..Default::default()
+        };
+        let outcomes = sync_directory(&mock, dir.path(), "site/", &options)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            status_of(&outcomes, "site/old.txt"),
+            SyncStatus::Deleted
+        ));
+        assert_eq!(mock.keys(), vec!["site/keep.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_delete_stays_within_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "keep.txt", b"keep");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("site/keep.txt", b"keep".to_vec(), "text/plain");
+        mock.insert("site/old.txt", b"gone".to_vec(), "text/plain");
+        mock.insert("site-backup/keep.txt", b"keep".to_vec(), "text/plain");
+        mock.insert("sitemap.xml", b"<urlset/>".to_vec(), "application/xml");
+        mock.insert("other/file.txt", b"other".to_vec(), "text/plain");
+
+        let options = SyncOptions {
+            delete: true,
+            ..Default::default()
+        };
+        // No trailing slash: `site` must not match `site-backup/` or `sitemap.xml`
+        sync_directory(&mock, dir.path(), "site", &options)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            mock.keys(),
+            vec![
+                "other/file.txt",
+                "site-backup/keep.txt",
+                "site/keep.txt",
+                "sitemap.xml"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_delete_requires_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "keep.txt", b"keep");
+
+        let mock = MockR2::new("test-bucket");
+        mock.insert("unrelated.txt", b"data".to_vec(), "text/plain");
+
+        let options = SyncOptions {
+            delete: true,
+            ..Default::default()
+        };
+        let result = compute_sync_plan(&mock, dir.path(), "", &options).await;
+
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert_eq!(mock.keys(), vec!["unrelated.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_sync_starts_no_uploads() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.txt", b"a");
+        write(dir.path(), "b.txt", b"b");
+
+        let mock = MockR2::new("test-bucket");
+        let options = SyncOptions::default();
+        options.cancel.cancel();
+
+        let outcomes = sync_directory(&mock, dir.path(), "", &options)
+            .await
+            .unwrap();
+
+        assert!(outcomes.is_empty());
+        assert!(mock.keys().is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+//! Helpers shared by unit tests
+
+use crate::client::{ClientOptions, R2Client};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Accept one HTTP request on a local port and return its head (request line and headers)
+///
+/// Replies with an empty 200; callers only inspect what was sent.
+pub(crate) async fn capture_request() -> (String, JoinHandle<String>) {
+    respond_once("200 OK").await
+}
+
+/// Like [`capture_request`], replying with the given status (e.g. `412 Precondition Failed`)
+pub(crate) async fn respond_once(status: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let (head, _) = read_request(&mut socket).await;
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            status
+        );
+        let _ = socket.write_all(response.as_bytes()).await;
+        head.to_lowercase()
+    });
+
+    (url, handle)
+}
+
+/// S3 client for the bucket `bucket` at `url`, path-style as a local server needs
+pub(crate) async fn path_style_client(url: &str, options: ClientOptions) -> R2Client {
+    R2Client::new_with_options(
+        url.to_string(),
+        "key".to_string(),
+        "secret".to_string(),
+        "bucket".to_string(),
+        ClientOptions {
+            force_path_style: true,
+            ..options
+        },
+    )
+    .await
+    .unwrap()
+}
+
+/// A scripted ListObjectsV2 page holding `keys` and folder `prefixes`
+///
+/// With a `next` continuation token the page says more results follow.
+pub(crate) fn list_page(keys: &[&str], prefixes: &[&str], next: Option<&str>) -> (u16, String) {
+    let contents: String = keys
+        .iter()
+        .map(|k| format!("<Contents><Key>{}</Key><Size>1</Size></Contents>", k))
+        .collect();
+    let prefixes: String = prefixes
+        .iter()
+        .map(|p| format!("<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>", p))
+        .collect();
+    let truncated = match next {
+        Some(token) => format!(
+            "<IsTruncated>true</IsTruncated><NextContinuationToken>{}</NextContinuationToken>",
+            token
+        ),
+        None => "<IsTruncated>false</IsTruncated>".to_string(),
+    };
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><Name>bucket</Name>{}{}{}</ListBucketResult>",
+        contents, prefixes, truncated
+    );
+    (200, body)
+}
+
+/// A request received by [`MockServer`]
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// Header lines, lowercased
+    pub headers: String,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// The body parsed as JSON
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+/// Local HTTP server answering each request with the next scripted response
+///
+/// Requests are recorded so tests can check the method, path, headers and
+/// body the client sent.
+pub(crate) struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Serve `responses` (status, JSON body) in order, one per connection
+    pub async fn start(responses: Vec<(u16, String)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let (head, request_body) = read_request(&mut socket).await;
+
+                let mut lines = head.lines();
+                let mut request_line = lines.next().unwrap_or_default().split(' ');
+                recorded.lock().unwrap().push(RecordedRequest {
+                    method: request_line.next().unwrap_or_default().to_string(),
+                    path: request_line.next().unwrap_or_default().to_string(),
+                    headers: lines.collect::<Vec<_>>().join("\n").to_lowercase(),
+                    body: request_body,
+                });
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Read a request's head and body (sized by `content-length`)
+async fn read_request(socket: &mut TcpStream) -> (String, String) {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1024];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break data.len();
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let mut body = data.get(head_end + 4..).unwrap_or_default().to_vec();
+    while body.len() < content_length {
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+
+    (head, String::from_utf8_lossy(&body).to_string())
+}
@@ -0,0 +1,190 @@
+//! Durations of network requests, grouped by phase
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Durations of the requests made by a client, shared by all its requests
+///
+/// Each request is recorded under a phase (`list`, `upload`, `part`,
+/// `complete`, ...) with the bytes it sent or received, so a summary can show
+/// per-request statistics and the throughput of a phase as a whole.
+#[derive(Debug)]
+pub struct Timings {
+    started: Instant,
+    samples: Mutex<Vec<Sample>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    phase: &'static str,
+    start: Instant,
+    end: Instant,
+    bytes: u64,
+}
+
+/// Statistics of one phase, see [`Timings::summary`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseTimings {
+    pub phase: &'static str,
+    pub requests: usize,
+    /// Sum of the request durations
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// Bytes sent or received by the phase's requests
+    pub bytes: u64,
+    /// From the start of the first request to the end of the last one
+    ///
+    /// Shorter than `total` when requests ran concurrently.
+    pub wall: Duration,
+}
+
+impl PhaseTimings {
+    pub fn average(&self) -> Duration {
+        self.total / self.requests.max(1) as u32
+    }
+
+    /// Bytes per second over the phase's wall time, when it moved any data
+    pub fn throughput(&self) -> Option<f64> {
+        (self.bytes > 0 && !self.wall.is_zero())
+            .then(|| self.bytes as f64 / self.wall.as_secs_f64())
+    }
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            samples: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Time since the recorder was created
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Record a request of `phase` that started at `start` and just ended
+    pub fn record(&self, phase: &'static str, start: Instant, bytes: u64) {
+        self.record_span(phase, start, Instant::now(), bytes);
+    }
+
+    fn record_span(&self, phase: &'static str, start: Instant, end: Instant, bytes: u64) {
+        self.samples
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Sample {
+                phase,
+                start,
+                end,
+                bytes,
+            });
+    }
+
+    /// Run `request`, recording how long it took under `phase`
+    pub async fn time<T>(
+        &self,
+        phase: &'static str,
+        bytes: u64,
+        request: impl Future<Output = T>,
+    ) -> T {
+        let start = Instant::now();
+        let output = request.await;
+        self.record(phase, start, bytes);
+        output
+    }
+
+    /// Statistics per phase, in the order phases were first seen
+    pub fn summary(&self) -> Vec<PhaseTimings> {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let mut phases: Vec<(PhaseTimings, Instant, Instant)> = Vec::new();
+
+        for sample in samples.iter() {
+            let duration = sample.end.saturating_duration_since(sample.start);
+            match phases.iter_mut().find(|(p, _, _)| p.phase == sample.phase) {
+                Some((phase, first, last)) => {
+                    phase.requests += 1;
+                    phase.total += duration;
+                    phase.min = phase.min.min(duration);
+                    phase.max = phase.max.max(duration);
+                    phase.bytes += sample.bytes;
+                    *first = (*first).min(sample.start);
+                    *last = (*last).max(sample.end);
+                }
+                None => phases.push((
+                    PhaseTimings {
+                        phase: sample.phase,
+                        requests: 1,
+                        total: duration,
+                        min: duration,
+                        max: duration,
+                        bytes: sample.bytes,
+                        wall: Duration::ZERO,
+                    },
+                    sample.start,
+                    sample.end,
+                )),
+            }
+        }
+
+        phases
+            .into_iter()
+            .map(|(phase, first, last)| PhaseTimings {
+                wall: last.saturating_duration_since(first),
+                ..phase
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings_summary() {
+        let timings = Timings::new();
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        timings.record_span("list", t0, t0 + ms(40), 0);
+        // Three parts, two of them concurrent
+        timings.record_span("part", t0 + ms(50), t0 + ms(150), 1000);
+        timings.record_span("part", t0 + ms(50), t0 + ms(250), 1000);
+        timings.record_span("part", t0 + ms(150), t0 + ms(450), 1000);
+        timings.record_span("complete", t0 + ms(450), t0 + ms(470), 0);
+
+        let summary = timings.summary();
+        let phases: Vec<&str> = summary.iter().map(|p| p.phase).collect();
+        assert_eq!(phases, vec!["list", "part", "complete"]);
+
+        let parts = &summary[1];
+        assert_eq!(parts.requests, 3);
+        assert_eq!(parts.total, ms(600));
+        assert_eq!((parts.min, parts.max), (ms(100), ms(300)));
+        assert_eq!(parts.average(), ms(200));
+        assert_eq!(parts.wall, ms(400));
+        assert_eq!(parts.throughput(), Some(7500.0));
+
+        assert_eq!(summary[0].throughput(), None);
+        assert!(Timings::new().summary().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timings_time() {
+        let timings = Timings::new();
+        let value = timings.time("head", 0, async { 42 }).await;
+
+        assert_eq!(value, 42);
+        let summary = timings.summary();
+        assert_eq!(summary.len(), 1);
+        assert_eq!((summary[0].phase, summary[0].requests), ("head", 1));
+    }
+}